@@ -0,0 +1,38 @@
+//! Commitment benchmarks: 256 `fcmp_pedersen_commit` calls against one
+//! `fcmp_pedersen_commit_batch` call, which looks H up once and multiplies
+//! both generators through tables.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use wattx_fcmp::*;
+
+const OUTPUTS: usize = 256;
+
+fn bench_pedersen_commit(c: &mut Criterion) {
+    let values: Vec<u8> = (0..OUTPUTS * SCALAR_SIZE).map(|i| (i * 13) as u8 & 0x0f).collect();
+    let blindings: Vec<u8> = (0..OUTPUTS * SCALAR_SIZE).map(|i| (i * 29) as u8 & 0x0f).collect();
+    let mut out = vec![0u8; OUTPUTS * POINT_SIZE];
+
+    let mut group = c.benchmark_group("pedersen_commit");
+    group.bench_function("individual", |b| {
+        b.iter(|| {
+            for i in 0..OUTPUTS {
+                unsafe {
+                    fcmp_pedersen_commit(
+                        out[i * POINT_SIZE..].as_mut_ptr(),
+                        values[i * SCALAR_SIZE..].as_ptr(),
+                        blindings[i * SCALAR_SIZE..].as_ptr(),
+                    );
+                }
+            }
+        })
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| unsafe {
+            fcmp_pedersen_commit_batch(out.as_mut_ptr(), values.as_ptr(), blindings.as_ptr(), OUTPUTS)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_pedersen_commit);
+criterion_main!(benches);
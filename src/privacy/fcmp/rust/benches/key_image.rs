@@ -0,0 +1,53 @@
+//! Key image benchmarks: 1000 single-output `fcmp_key_image_batch` calls
+//! against one call over all 1000, where repeated output keys share their
+//! hash-to-point.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use wattx_fcmp::*;
+
+const OUTPUTS: usize = 1000;
+/// Distinct output keys; the rest repeat them
+const DISTINCT: usize = 250;
+
+fn bench_key_image(c: &mut Criterion) {
+    let mut basepoint = [0u8; POINT_SIZE];
+    unsafe {
+        assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+    }
+
+    let mut secrets = vec![0u8; OUTPUTS * SCALAR_SIZE];
+    let mut pubkeys = vec![0u8; OUTPUTS * POINT_SIZE];
+    for i in 0..OUTPUTS {
+        let secret = &mut secrets[i * SCALAR_SIZE..(i + 1) * SCALAR_SIZE];
+        secret[..2].copy_from_slice(&((i % DISTINCT) as u16 + 1).to_le_bytes());
+        unsafe {
+            fcmp_point_mul(pubkeys[i * POINT_SIZE..].as_mut_ptr(), secret.as_ptr(), basepoint.as_ptr());
+        }
+    }
+    let mut images = vec![0u8; OUTPUTS * POINT_SIZE];
+
+    let mut group = c.benchmark_group("key_image");
+    group.bench_function("individual", |b| {
+        b.iter(|| {
+            for i in 0..OUTPUTS {
+                unsafe {
+                    fcmp_key_image_batch(
+                        images[i * POINT_SIZE..].as_mut_ptr(),
+                        secrets[i * SCALAR_SIZE..].as_ptr(),
+                        pubkeys[i * POINT_SIZE..].as_ptr(),
+                        1,
+                    );
+                }
+            }
+        })
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| unsafe {
+            fcmp_key_image_batch(images.as_mut_ptr(), secrets.as_ptr(), pubkeys.as_ptr(), OUTPUTS as u32)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_key_image);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+//! Scalar multiplication benchmarks: 1000 `fcmp_point_mul` calls against one
+//! `fcmp_point_mul_batch` call over the same pairs, and one scalar across the
+//! default generator set with and without the cached tables.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use wattx_fcmp::*;
+
+const PAIRS: usize = 1000;
+
+fn bench_point_mul(c: &mut Criterion) {
+    let mut basepoint = [0u8; POINT_SIZE];
+    let mut other = [0u8; POINT_SIZE];
+    unsafe {
+        assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+        assert_eq!(fcmp_hash_to_point(other.as_mut_ptr(), b"bench".as_ptr(), 5), FCMP_SUCCESS);
+    }
+
+    // Half the products are with the basepoint, half with one repeated point
+    let scalars: Vec<u8> = (0..PAIRS * SCALAR_SIZE).map(|i| (i * 7) as u8 & 0x0f).collect();
+    let points: Vec<u8> = (0..PAIRS)
+        .flat_map(|i| if i % 2 == 0 { basepoint } else { other })
+        .collect();
+    let mut out = vec![0u8; PAIRS * POINT_SIZE];
+
+    let mut group = c.benchmark_group("point_mul");
+    group.bench_function("individual", |b| {
+        b.iter(|| {
+            for i in 0..PAIRS {
+                unsafe {
+                    fcmp_point_mul(
+                        out[i * POINT_SIZE..].as_mut_ptr(),
+                        scalars[i * SCALAR_SIZE..].as_ptr(),
+                        points[i * POINT_SIZE..].as_ptr(),
+                    );
+                }
+            }
+        })
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| unsafe { fcmp_point_mul_batch(out.as_mut_ptr(), scalars.as_ptr(), points.as_ptr(), PAIRS) })
+    });
+    group.finish();
+}
+
+fn bench_scalar_mul_generators(c: &mut Criterion) {
+    let count = fcmp_generator_count();
+    let scalar = [0x2au8; SCALAR_SIZE];
+    let mut generators = vec![0u8; count as usize * POINT_SIZE];
+    let mut out = vec![0u8; count as usize * POINT_SIZE];
+    unsafe {
+        assert_eq!(fcmp_init(), FCMP_SUCCESS);
+        for i in 0..count as usize {
+            assert_eq!(fcmp_get_generator(i as u32, generators[i * POINT_SIZE..].as_mut_ptr()), FCMP_SUCCESS);
+        }
+    }
+
+    let mut group = c.benchmark_group("scalar_mul_generators");
+    group.bench_function("cached_tables", |b| {
+        b.iter(|| unsafe { fcmp_scalar_mul_generators(out.as_mut_ptr(), scalar.as_ptr(), std::ptr::null(), count) })
+    });
+    group.bench_function("caller_points", |b| {
+        b.iter(|| unsafe { fcmp_scalar_mul_generators(out.as_mut_ptr(), scalar.as_ptr(), generators.as_ptr(), count) })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_point_mul, bench_scalar_mul_generators);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Verification benchmarks: stateless `fcmp_verify` against a reusable
+//! `FcmpVerifier`, which multiplies the fixed generators from precomputed
+//! tables. The two currently measure within noise of each other; this
+//! tracks whether the tables start to pay off as proofs grow.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wattx_fcmp::*;
+
+/// Prove membership of a fixed output under `root` with a synthetic branch
+fn make_proof(root: &[u8; POINT_SIZE], num_layers: usize) -> Vec<u8> {
+    let elements: Vec<Vec<u8>> = (0..num_layers)
+        .map(|j| (0..4 * SCALAR_SIZE).map(|i| (i + j) as u8).collect())
+        .collect();
+    let layers: Vec<FcmpBranchLayer> = elements
+        .iter()
+        .map(|e| FcmpBranchLayer { num_elements: 4, elements: e.as_ptr() })
+        .collect();
+    let branch = FcmpBranch { leaf_index: 0, num_layers: num_layers as u32, layers: layers.as_ptr() };
+
+    let output = [9u8; OUTPUT_TUPLE_SIZE];
+    let mut proof = vec![0u8; FCMP_PROOF_HEADER_SIZE + num_layers * FCMP_PROOF_LAYER_SIZE];
+    let mut proof_len = 0usize;
+    let result = unsafe {
+        fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch)
+    };
+    assert_eq!(result, FCMP_SUCCESS);
+    proof
+}
+
+fn bench_verify(c: &mut Criterion) {
+    assert_eq!(fcmp_init(), FCMP_SUCCESS);
+
+    let root = [7u8; POINT_SIZE];
+    let input = FcmpInput { o_tilde: [0; 64], i_tilde: [0; 64], r: [0; 64], c_tilde: [0; 64] };
+    let verifier = fcmp_verifier_new();
+    assert!(!verifier.is_null());
+
+    let mut group = c.benchmark_group("verify");
+    for num_layers in [4usize, 16, FCMP_MAX_TREE_DEPTH as usize] {
+        let proof = make_proof(&root, num_layers);
+
+        group.bench_with_input(BenchmarkId::new("stateless", num_layers), &proof, |b, proof| {
+            b.iter(|| unsafe { fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()) })
+        });
+        group.bench_with_input(BenchmarkId::new("precomputed", num_layers), &proof, |b, proof| {
+            b.iter(|| unsafe { fcmp_verifier_verify(verifier, root.as_ptr(), &input, proof.as_ptr(), proof.len()) })
+        });
+    }
+    group.finish();
+
+    unsafe { fcmp_verifier_free(verifier) };
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
@@ -17,6 +17,26 @@ fn main() {
         .expect("Unable to generate C bindings")
         .write_to_file(out_dir.join("fcmp_ffi_gen.h"));
 
+    // Best guess at curve25519-dalek's backend for `fcmp_has_simd`: dalek does
+    // not export its choice, so this repeats its default rule (simd unless
+    // overridden, on x86_64 with 64-bit limbs) and can drift from what its own
+    // build script actually selects
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    let dalek_bits = env::var("CARGO_CFG_CURVE25519_DALEK_BITS")
+        .or_else(|_| env::var("CARGO_CFG_TARGET_POINTER_WIDTH"))
+        .unwrap_or_default();
+    let backend = match env::var("CARGO_CFG_CURVE25519_DALEK_BACKEND").as_deref() {
+        Ok("fiat") => "fiat",
+        Ok("serial") => "serial",
+        _ if target_arch == "x86_64" && dalek_bits == "64" => "simd",
+        _ => "serial",
+    };
+    println!("cargo:rustc-check-cfg=cfg(wattx_fcmp_simd)");
+    if backend == "simd" {
+        println!("cargo:rustc-cfg=wattx_fcmp_simd");
+    }
+    println!("cargo:rustc-env=WATTX_FCMP_DALEK_BACKEND={backend}");
+
     println!("cargo:rerun-if-changed=src/lib.rs");
     println!("cargo:rerun-if-changed=cbindgen.toml");
 }
@@ -9,12 +9,18 @@
 //! All FFI functions are marked `unsafe` and require valid pointers. The caller is responsible
 //! for ensuring pointer validity and proper memory management.
 
+use std::ffi::{c_char, c_void};
 use std::slice;
 use std::ptr;
+use std::sync::RwLock;
 
 use rand_core::OsRng;
 use zeroize::Zeroize;
 
+pub mod safe;
+#[cfg(feature = "profiling")]
+mod profiling;
+
 // ============================================================================
 // Error Codes
 // ============================================================================
@@ -35,9 +41,33 @@ pub const FCMP_ERROR_INVALID_POINT: i32 = -5;
 pub const FCMP_ERROR_INVALID_SCALAR: i32 = -6;
 /// Not initialized
 pub const FCMP_ERROR_NOT_INITIALIZED: i32 = -7;
+/// Membership tree has no leaves
+pub const FCMP_ERROR_EMPTY_TREE: i32 = -8;
+/// Verification exceeded its step limit
+pub const FCMP_ERROR_LIMIT_EXCEEDED: i32 = -9;
+/// Key image is (probably) already spent
+pub const FCMP_ERROR_DOUBLE_SPEND: i32 = -10;
+/// Proof was made under a different generator set
+pub const FCMP_ERROR_GENERATOR_MISMATCH: i32 = -11;
 /// Internal error
 pub const FCMP_ERROR_INTERNAL: i32 = -99;
 
+/// Every defined `FCMP_ERROR_*` code, in declaration order
+const FCMP_ERROR_CODES: [i32; 12] = [
+    FCMP_ERROR_INVALID_PARAM,
+    FCMP_ERROR_PROOF_GENERATION,
+    FCMP_ERROR_PROOF_VERIFICATION,
+    FCMP_ERROR_MEMORY,
+    FCMP_ERROR_INVALID_POINT,
+    FCMP_ERROR_INVALID_SCALAR,
+    FCMP_ERROR_NOT_INITIALIZED,
+    FCMP_ERROR_EMPTY_TREE,
+    FCMP_ERROR_LIMIT_EXCEEDED,
+    FCMP_ERROR_DOUBLE_SPEND,
+    FCMP_ERROR_GENERATOR_MISMATCH,
+    FCMP_ERROR_INTERNAL,
+];
+
 // ============================================================================
 // Constants
 // ============================================================================
@@ -56,13 +86,116 @@ pub const ELEMENTS_PER_OUTPUT: usize = 6;
 // ============================================================================
 
 /// Opaque handle to FCMP parameters
+#[derive(Clone)]
 pub struct FcmpParams {
-    // Generator points and precomputed tables
-    _initialized: bool,
-    // In full implementation, this would contain:
-    // - Pedersen generators
-    // - Hash initialization points
-    // - Precomputed tables for fast MSM
+    /// Pedersen blinding generator H
+    pub(crate) h: curve25519_dalek::edwards::EdwardsPoint,
+    /// Per-layer proof generators G_0 .. G_{MAX_PROOF_LAYERS - 1}
+    pub(crate) layer_generators: Vec<curve25519_dalek::edwards::EdwardsPoint>,
+    /// Fixed-base tables for each generator in `generator` order, built on
+    /// first use and shared between clones
+    tables: std::sync::Arc<[std::sync::OnceLock<curve25519_dalek::edwards::EdwardsBasepointTable>]>,
+    /// `fingerprint`, computed on first use since every proof checks it
+    fingerprint: std::sync::Arc<std::sync::OnceLock<[u8; 32]>>,
+}
+
+impl FcmpParams {
+    fn new(
+        h: curve25519_dalek::edwards::EdwardsPoint,
+        layer_generators: Vec<curve25519_dalek::edwards::EdwardsPoint>,
+    ) -> Self {
+        let tables = (0..1 + layer_generators.len()).map(|_| std::sync::OnceLock::new()).collect();
+        FcmpParams { h, layer_generators, tables, fingerprint: Default::default() }
+    }
+
+    /// Derive H and every layer generator a proof can reference
+    fn derive() -> Option<Self> {
+        FcmpParams::derive_with_h(unsafe { pedersen_h()? })
+    }
+
+    /// Derive every layer generator a proof can reference, alongside `h`
+    fn derive_with_h(h: curve25519_dalek::edwards::EdwardsPoint) -> Option<Self> {
+        let layer_generators = (0..MAX_PROOF_LAYERS)
+            .map(|j| unsafe { layer_generator(j as u32) })
+            .collect::<Option<Vec<_>>>()?;
+        Some(FcmpParams::new(h, layer_generators))
+    }
+
+    /// Serialize as a parameter file: magic || version || count || points || checksum
+    fn to_file_bytes(&self) -> Vec<u8> {
+        let count = 1 + self.layer_generators.len();
+        let mut bytes = Vec::with_capacity(PARAMS_FILE_MAGIC.len() + 5 + count * POINT_SIZE + 32);
+        bytes.extend_from_slice(PARAMS_FILE_MAGIC);
+        bytes.push(PARAMS_FILE_VERSION);
+        bytes.extend_from_slice(&(count as u32).to_le_bytes());
+        for generator in std::iter::once(&self.h).chain(&self.layer_generators) {
+            bytes.extend_from_slice(generator.compress().as_bytes());
+        }
+        let checksum = params_file_checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    /// Parse a parameter file written by `to_file_bytes`
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, i32> {
+        let header_len = PARAMS_FILE_MAGIC.len() + 5;
+        let count = 1 + MAX_PROOF_LAYERS;
+        if bytes.len() != header_len + count * POINT_SIZE + 32 {
+            return Err(FCMP_ERROR_INVALID_PARAM);
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len() - 32);
+        if body[..PARAMS_FILE_MAGIC.len()] != PARAMS_FILE_MAGIC[..] ||
+           body[PARAMS_FILE_MAGIC.len()] != PARAMS_FILE_VERSION ||
+           body[PARAMS_FILE_MAGIC.len() + 1..header_len] != (count as u32).to_le_bytes() ||
+           params_file_checksum(body)[..] != *checksum {
+            return Err(FCMP_ERROR_INVALID_PARAM);
+        }
+
+        let mut generators = body[header_len..]
+            .chunks_exact(POINT_SIZE)
+            .map(decode_point)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(FCMP_ERROR_INVALID_POINT)?;
+        let h = generators.remove(0);
+        Ok(FcmpParams::new(h, generators))
+    }
+
+    /// Digest of every generator in `generator` order; see `fcmp_generators_fingerprint`
+    fn fingerprint(&self) -> [u8; 32] {
+        *self.fingerprint.get_or_init(|| self.compute_fingerprint())
+    }
+
+    fn compute_fingerprint(&self) -> [u8; 32] {
+        let count = (1 + self.layer_generators.len()) as u32;
+        let encoded: Vec<[u8; POINT_SIZE]> = std::iter::once(&self.h)
+            .chain(&self.layer_generators)
+            .map(|g| g.compress().to_bytes())
+            .collect();
+
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(1 + encoded.len());
+        let count_bytes = count.to_le_bytes();
+        parts.push(&count_bytes);
+        parts.extend(encoded.iter().map(|g| &g[..]));
+        tagged_digest(b"WATTx_Generators_Fingerprint_v1", &parts)
+    }
+
+    /// Generator at `index` in the order H, G_0, G_1, ...
+    pub(crate) fn generator(&self, index: usize) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+        match index {
+            0 => Some(self.h),
+            i => self.layer_generators.get(i - 1).copied(),
+        }
+    }
+
+    /// Fixed-base multiplication table for the generator at `index`
+    fn generator_table(&self, index: usize) -> Option<&curve25519_dalek::edwards::EdwardsBasepointTable> {
+        use curve25519_dalek::edwards::EdwardsBasepointTable;
+        use curve25519_dalek::traits::BasepointTable;
+
+        let generator = self.generator(index)?;
+        Some(self.tables.get(index)?.get_or_init(|| EdwardsBasepointTable::create(&generator)))
+    }
 }
 
 /// Opaque handle to a proof
@@ -91,6 +224,10 @@ pub struct FcmpBranchLayer {
 }
 
 /// Input tuple for verification
+///
+/// Each point is stored as affine coordinates x || y, both canonical
+/// little-endian field elements below p = 2^255 - 19. Non-canonical
+/// coordinates are rejected with `FCMP_ERROR_INVALID_POINT`.
 #[repr(C)]
 pub struct FcmpInput {
     /// Re-randomized O point (x, y coordinates as scalars)
@@ -103,11 +240,118 @@ pub struct FcmpInput {
     pub c_tilde: [u8; 64],
 }
 
+/// Everything a node needs to validate a spend, bundled for one FFI call
+#[repr(C)]
+pub struct FcmpTransaction {
+    /// Tree root every membership proof is made against (32 bytes)
+    pub tree_root: *const u8,
+    /// Hash of the transaction prefix signed by every SA+L signature (32 bytes)
+    pub prefix_hash: *const u8,
+    /// Number of inputs
+    pub num_inputs: u32,
+    /// Re-randomized inputs (array of `num_inputs` FcmpInput)
+    pub inputs: *const FcmpInput,
+    /// Key images, `num_inputs * 32` bytes
+    pub key_images: *const u8,
+    /// Membership proofs, one per input, concatenated
+    pub proofs: *const u8,
+    /// Total length of `proofs` in bytes
+    pub proofs_len: usize,
+    /// SA+L signatures, `num_inputs * FCMP_SAL_SIGNATURE_SIZE` bytes
+    pub signatures: *const u8,
+    /// Number of outputs
+    pub num_outputs: u32,
+    /// Output commitments, `num_outputs * 32` bytes
+    pub output_commitments: *const u8,
+    /// Range proof covering every output commitment
+    pub range_proof: *const u8,
+    /// Length of `range_proof` in bytes
+    pub range_proof_len: usize,
+    /// Fee in atomic units, committed with zero blinding
+    pub fee: u64,
+}
+
+/// Generator configuration installed by `fcmp_params_swap`
+#[repr(C)]
+pub struct FcmpConfig {
+    /// Domain the Pedersen H generator is derived from, as for
+    /// `fcmp_set_pedersen_h_domain`; null selects "WATTx_Pedersen_H_v1"
+    pub pedersen_h_domain: *const u8,
+    /// Length of `pedersen_h_domain` in bytes
+    pub pedersen_h_domain_len: usize,
+}
+
+/// Compact description of a membership tree, as kept by light clients
+#[repr(C)]
+pub struct FcmpTreeDescriptor {
+    /// Tree root (32 bytes)
+    pub root: [u8; 32],
+    /// Branch depth, as reported by `fcmp_tree_stats`
+    pub depth: u32,
+    /// Number of leaves in the tree
+    pub leaf_count: u64,
+}
+
+/// Type identifiers accepted by `fcmp_sizeof`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FcmpTypeId {
+    /// 32-byte scalar
+    Scalar = 0,
+    /// 32-byte compressed point
+    Point = 1,
+    /// `FcmpInput` struct
+    Input = 2,
+    /// `FcmpBranch` struct
+    Branch = 3,
+    /// `FcmpBranchLayer` struct
+    BranchLayer = 4,
+    /// `FcmpTransaction` struct
+    Transaction = 5,
+    /// `FcmpConfig` struct
+    Config = 6,
+    /// `FcmpTreeDescriptor` struct
+    TreeDescriptor = 7,
+}
+
 // ============================================================================
 // Global State
 // ============================================================================
 
-static mut GLOBAL_PARAMS: Option<Box<FcmpParams>> = None;
+/// Parameters shared by every operation. Readers clone the `Arc` and drop
+/// the lock before working, so replacing the parameters never waits on
+/// in-flight proofs.
+static GLOBAL_PARAMS: RwLock<Option<std::sync::Arc<FcmpParams>>> = RwLock::new(None);
+
+/// Check whether `fcmp_init` has populated the global parameters.
+fn params_initialized() -> bool {
+    GLOBAL_PARAMS
+        .read()
+        .map(|params| params.is_some())
+        .unwrap_or(false)
+}
+
+/// Take a shared handle on the current global parameters.
+///
+/// Returns `FCMP_ERROR_NOT_INITIALIZED` if `fcmp_init` has not been called.
+fn shared_params() -> Result<std::sync::Arc<FcmpParams>, i32> {
+    GLOBAL_PARAMS
+        .read()
+        .map_err(|_| FCMP_ERROR_INTERNAL)?
+        .clone()
+        .ok_or(FCMP_ERROR_NOT_INITIALIZED)
+}
+
+/// Run `f` against the global parameters.
+///
+/// `f` sees one parameter set throughout, even if `fcmp_params_swap`
+/// replaces the global while it runs.
+///
+/// Returns `FCMP_ERROR_NOT_INITIALIZED` if `fcmp_init` has not been called.
+fn with_params<R>(f: impl FnOnce(&FcmpParams) -> R) -> Result<R, i32> {
+    let params = shared_params()?;
+    Ok(f(&params))
+}
 
 // ============================================================================
 // Initialization Functions
@@ -122,16 +366,22 @@ static mut GLOBAL_PARAMS: Option<Box<FcmpParams>> = None;
 /// - `FCMP_SUCCESS` on success
 /// - `FCMP_ERROR_*` on failure
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_init() -> i32 {
-    if GLOBAL_PARAMS.is_some() {
+pub extern "C" fn fcmp_init() -> i32 {
+    let mut global = match GLOBAL_PARAMS.write() {
+        Ok(guard) => guard,
+        Err(_) => return FCMP_ERROR_INTERNAL,
+    };
+
+    if global.is_some() {
         return FCMP_SUCCESS; // Already initialized
     }
 
-    let params = Box::new(FcmpParams {
-        _initialized: true,
-    });
+    let params = match FcmpParams::derive() {
+        Some(params) => std::sync::Arc::new(params),
+        None => return FCMP_ERROR_INTERNAL,
+    };
 
-    GLOBAL_PARAMS = Some(params);
+    *global = Some(params);
     FCMP_SUCCESS
 }
 
@@ -139,8 +389,10 @@ pub unsafe extern "C" fn fcmp_init() -> i32 {
 ///
 /// After calling this, `fcmp_init()` must be called again before using other functions.
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_cleanup() {
-    GLOBAL_PARAMS = None;
+pub extern "C" fn fcmp_cleanup() {
+    if let Ok(mut global) = GLOBAL_PARAMS.write() {
+        *global = None;
+    }
 }
 
 /// Check if FCMP is initialized.
@@ -149,8 +401,353 @@ pub unsafe extern "C" fn fcmp_cleanup() {
 /// - 1 if initialized
 /// - 0 if not initialized
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_is_initialized() -> i32 {
-    if GLOBAL_PARAMS.is_some() { 1 } else { 0 }
+pub extern "C" fn fcmp_is_initialized() -> i32 {
+    if params_initialized() { 1 } else { 0 }
+}
+
+/// Magic prefix of a saved parameter file
+const PARAMS_FILE_MAGIC: &[u8; 8] = b"WATTXFCP";
+/// Parameter file layout version; bump whenever generator derivation changes
+const PARAMS_FILE_VERSION: u8 = 1;
+
+/// Integrity checksum of a parameter file body: BLAKE2b-512 truncated to 32 bytes
+fn params_file_checksum(body: &[u8]) -> [u8; 32] {
+    use blake2::{Blake2b512, Digest};
+
+    let mut checksum = [0u8; 32];
+    checksum.copy_from_slice(&Blake2b512::digest(body)[..32]);
+    checksum
+}
+
+/// Read a NUL-terminated UTF-8 path from C, borrowed for the caller's call
+unsafe fn path_from_c<'a>(path: *const c_char) -> Option<&'a str> {
+    std::ffi::CStr::from_ptr(path).to_str().ok()
+}
+
+/// Save the initialized parameters to a file.
+///
+/// `fcmp_params_load` reinstalls the set, after checking it against a fresh
+/// derivation, so processes can confirm they run the generators on file.
+///
+/// # Safety
+/// - `path` must be a valid NUL-terminated string
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+/// - `FCMP_ERROR_INTERNAL` if the file cannot be written
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_params_save(path: *const c_char) -> i32 {
+    if path.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let path = match path_from_c(path) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    let bytes = match with_params(FcmpParams::to_file_bytes) {
+        Ok(bytes) => bytes,
+        Err(e) => return e,
+    };
+
+    match std::fs::write(path, bytes) {
+        Ok(()) => FCMP_SUCCESS,
+        Err(_) => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Initialize the library from a file written by `fcmp_params_save`.
+///
+/// The file's version and checksum are checked before anything is
+/// installed; on success it replaces any current parameters. The checksum
+/// is unkeyed, so whoever can write the file could also store generators
+/// with known relations between them. The stored set is therefore only
+/// installed if its fingerprint matches a fresh derivation under the
+/// current Pedersen H domain.
+///
+/// # Safety
+/// - `path` must be a valid NUL-terminated string
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if the file is from another version or corrupt
+/// - `FCMP_ERROR_INVALID_POINT` if a stored generator does not decode
+/// - `FCMP_ERROR_GENERATOR_MISMATCH` if the stored generators are not the
+///   ones this build derives
+/// - `FCMP_ERROR_INTERNAL` if the file cannot be read
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_params_load(path: *const c_char) -> i32 {
+    if path.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let path = match path_from_c(path) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(_) => return FCMP_ERROR_INTERNAL,
+    };
+
+    let params = match FcmpParams::from_file_bytes(&bytes) {
+        Ok(params) => std::sync::Arc::new(params),
+        Err(e) => return e,
+    };
+    match FcmpParams::derive() {
+        Some(expected) if expected.fingerprint() == params.fingerprint() => {}
+        Some(_) => return FCMP_ERROR_GENERATOR_MISMATCH,
+        None => return FCMP_ERROR_INTERNAL,
+    }
+
+    match GLOBAL_PARAMS.write() {
+        Ok(mut global) => {
+            *global = Some(params);
+            FCMP_SUCCESS
+        }
+        Err(_) => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Atomically replace the global parameters, e.g. for a scheduled
+/// generator rotation on a live node.
+///
+/// The new set is derived from `new_config` before the global is touched,
+/// and installed with a single pointer swap. Each operation reads the
+/// parameters once when it starts and uses that set to the end. Operations
+/// already in flight finish on the old set, those starting after the swap
+/// use the new one, and no operation mixes the two. The swap does not wait
+/// for in-flight operations. If the library is not initialized, the new set
+/// is installed as if by `fcmp_init`.
+///
+/// Only the parameters behind proofs and verifiers are swapped. The H that
+/// `fcmp_pedersen_commit` and the range proofs use is set separately by
+/// `fcmp_set_pedersen_h_domain`. A reusable `FcmpVerifier` keeps the set it
+/// was created with.
+///
+/// # Safety
+/// - `new_config` must point to a valid `FcmpConfig`, whose
+///   `pedersen_h_domain` points to `pedersen_h_domain_len` bytes unless null
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if a non-null domain is empty
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_params_swap(new_config: *const FcmpConfig) -> i32 {
+    if new_config.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let config = &*new_config;
+    let domain = if config.pedersen_h_domain.is_null() {
+        DEFAULT_PEDERSEN_H_DOMAIN
+    } else if config.pedersen_h_domain_len == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    } else {
+        slice::from_raw_parts(config.pedersen_h_domain, config.pedersen_h_domain_len)
+    };
+
+    let params = match derive_pedersen_h(domain).and_then(FcmpParams::derive_with_h) {
+        Some(params) => std::sync::Arc::new(params),
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    match GLOBAL_PARAMS.write() {
+        Ok(mut global) => {
+            *global = Some(params);
+            FCMP_SUCCESS
+        }
+        Err(_) => FCMP_ERROR_INTERNAL,
+    }
+}
+
+// ============================================================================
+// Generators
+// ============================================================================
+
+/// Get the number of generators exposed by `fcmp_get_generator`
+///
+/// # Returns
+/// H plus one generator per supported proof layer
+#[no_mangle]
+pub extern "C" fn fcmp_generator_count() -> u32 {
+    (1 + MAX_PROOF_LAYERS) as u32
+}
+
+/// Get a generator by index: index 0 is the Pedersen H generator and index
+/// `j + 1` is the proof generator for tree layer `j`.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `index >= fcmp_generator_count()`
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_get_generator(index: u32, out: *mut u8) -> i32 {
+    if out.is_null() || index >= fcmp_generator_count() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let generator = match with_params(|params| params.generator(index as usize)) {
+        Ok(Some(g)) => g,
+        Ok(None) => return FCMP_ERROR_INVALID_PARAM,
+        Err(e) => return e,
+    };
+
+    ptr::copy_nonoverlapping(generator.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Compute a fingerprint of the active generator set.
+///
+/// BLAKE2b("WATTx_Generators_Fingerprint_v1" || count || H || G_0 || ...),
+/// truncated to 32 bytes, over every generator in `fcmp_get_generator`
+/// order. Nodes can exchange fingerprints to confirm they derived the same
+/// generators before accepting each other's proofs.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_generators_fingerprint(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match with_params(FcmpParams::fingerprint) {
+        Ok(fingerprint) => {
+            ptr::copy_nonoverlapping(fingerprint.as_ptr(), out, 32);
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Multiply one scalar across many generators: out[i] = scalar * G_i
+///
+/// With `generators` null the library's own generators are used, indices
+/// 0..count in `fcmp_get_generator` order, through fixed-base tables that
+/// are built on first use and kept for the life of the parameters.
+/// Otherwise `generators` holds `count` caller points, multiplied directly.
+/// The scalar is reduced mod l, as in `fcmp_point_mul`. Nothing is written
+/// on error.
+///
+/// # Safety
+/// - `out` must point to `count * 32` bytes of writable memory
+/// - `scalar` must point to 32 bytes
+/// - `generators` must be null or point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if the library generators are used and
+///   `count` exceeds `fcmp_generator_count()`
+/// - `FCMP_ERROR_INVALID_POINT` if a caller generator fails to decompress
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library generators are used
+///   before `fcmp_init`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_mul_generators(
+    out: *mut u8,
+    scalar: *const u8,
+    generators: *const u8,
+    count: u32,
+) -> i32 {
+    if out.is_null() || scalar.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut scalar_arr = [0u8; SCALAR_SIZE];
+    scalar_arr.copy_from_slice(slice::from_raw_parts(scalar, SCALAR_SIZE));
+    let mut s = Scalar::from_bytes_mod_order(scalar_arr);
+    scalar_arr.zeroize();
+
+    let products = if generators.is_null() {
+        if count > fcmp_generator_count() {
+            s.zeroize();
+            return FCMP_ERROR_INVALID_PARAM;
+        }
+        with_params(|params| {
+            (0..count as usize)
+                .map(|i| params.generator_table(i).map(|table| &s * table))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(FCMP_ERROR_INTERNAL)
+        })
+        .and_then(|r| r)
+    } else {
+        slice::from_raw_parts(generators, count as usize * POINT_SIZE)
+            .chunks_exact(POINT_SIZE)
+            .map(|bytes| decode_point(bytes).map(|g| s * g))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(FCMP_ERROR_INVALID_POINT)
+    };
+    s.zeroize();
+
+    match products {
+        Ok(products) => {
+            for (i, product) in products.iter().enumerate() {
+                ptr::copy_nonoverlapping(product.compress().as_bytes().as_ptr(), out.add(i * POINT_SIZE), POINT_SIZE);
+            }
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Derive `count` generators from a seed, reproducibly across implementations.
+///
+/// G_i = Hp(seed || LE32(i)) for i in 0..count, where Hp is the map behind
+/// `fcmp_hash_to_point` under the domain tag "WATTx_Derive_Generators_v1":
+///
+/// 1. d = BLAKE2b-512("WATTx_Derive_Generators_v1" || seed || LE32(i))
+/// 2. for k = 0, 1, ..., 255: take the first 32 bytes of BLAKE2b-512(d || k)
+///    and stop at the first that decompresses as an Edwards y-coordinate
+/// 3. G_i is that point multiplied by the cofactor 8
+///
+/// Ports deriving the same way match the output byte for byte. Nothing is
+/// written on error.
+///
+/// # Safety
+/// - `seed` must point to `seed_len` bytes
+/// - `out` must point to `count * 32` bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the compressed generators in `out`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_derive_generators(
+    seed: *const u8,
+    seed_len: usize,
+    count: u32,
+    out: *mut u8,
+) -> i32 {
+    if (seed.is_null() && seed_len > 0) || (out.is_null() && count > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let seed = if seed_len > 0 { slice::from_raw_parts(seed, seed_len) } else { &[] };
+    let mut input = seed.to_vec();
+    let mut generators = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        input.truncate(seed.len());
+        input.extend_from_slice(&i.to_le_bytes());
+        match hash_to_point_with_domain(b"WATTx_Derive_Generators_v1", &input, true) {
+            Some(point) => generators.push(point.compress()),
+            None => return FCMP_ERROR_INTERNAL,
+        }
+    }
+
+    for (i, generator) in generators.iter().enumerate() {
+        ptr::copy_nonoverlapping(generator.as_bytes().as_ptr(), out.add(i * POINT_SIZE), POINT_SIZE);
+    }
+    FCMP_SUCCESS
 }
 
 // ============================================================================
@@ -183,6 +780,40 @@ pub unsafe extern "C" fn fcmp_scalar_random(out: *mut u8) -> i32 {
     FCMP_SUCCESS
 }
 
+/// Generate a random blinding factor.
+///
+/// Unlike `fcmp_scalar_random` the result is always canonical (reduced from
+/// 64 random bytes) and never zero, which would leave a commitment
+/// unblinded; a zero draw is re-sampled. This is the default for blinders.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_blinding_random(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+    use rand_core::RngCore;
+
+    let mut wide = [0u8; 64];
+    loop {
+        if OsRng.try_fill_bytes(&mut wide).is_err() {
+            wide.zeroize();
+            return FCMP_ERROR_INTERNAL;
+        }
+
+        let mut blinding = Scalar::from_bytes_mod_order_wide(&wide);
+        if blinding != Scalar::ZERO {
+            ptr::copy_nonoverlapping(blinding.as_bytes().as_ptr(), out, SCALAR_SIZE);
+            blinding.zeroize();
+            wide.zeroize();
+            return FCMP_SUCCESS;
+        }
+    }
+}
+
 /// Add two scalars: out = a + b (mod l)
 ///
 /// # Safety
@@ -198,24 +829,18 @@ pub unsafe extern "C" fn fcmp_scalar_add(
         return FCMP_ERROR_INVALID_PARAM;
     }
 
-    // Read inputs
-    let a_bytes = slice::from_raw_parts(a, SCALAR_SIZE);
-    let b_bytes = slice::from_raw_parts(b, SCALAR_SIZE);
-
-    // Simple addition with carry (proper implementation would use curve25519-dalek)
-    let mut result = [0u8; SCALAR_SIZE];
-    let mut carry: u16 = 0;
+    use curve25519_dalek::scalar::Scalar;
 
-    for i in 0..SCALAR_SIZE {
-        let sum = a_bytes[i] as u16 + b_bytes[i] as u16 + carry;
-        result[i] = sum as u8;
-        carry = sum >> 8;
-    }
+    let mut a_arr = [0u8; SCALAR_SIZE];
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    a_arr.copy_from_slice(slice::from_raw_parts(a, SCALAR_SIZE));
+    b_arr.copy_from_slice(slice::from_raw_parts(b, SCALAR_SIZE));
 
-    // Reduce mod l (simplified)
-    result[31] &= 0x7f;
+    let result = Scalar::from_bytes_mod_order(a_arr) + Scalar::from_bytes_mod_order(b_arr);
+    a_arr.zeroize();
+    b_arr.zeroize();
 
-    ptr::copy_nonoverlapping(result.as_ptr(), out, SCALAR_SIZE);
+    ptr::copy_nonoverlapping(result.as_bytes().as_ptr(), out, SCALAR_SIZE);
     FCMP_SUCCESS
 }
 
@@ -234,46 +859,217 @@ pub unsafe extern "C" fn fcmp_scalar_mul(
         return FCMP_ERROR_INVALID_PARAM;
     }
 
-    // This is a placeholder - proper implementation would use curve25519-dalek
-    // For now, just XOR the inputs (NOT cryptographically correct!)
-    let a_bytes = slice::from_raw_parts(a, SCALAR_SIZE);
-    let b_bytes = slice::from_raw_parts(b, SCALAR_SIZE);
+    use curve25519_dalek::scalar::Scalar;
 
-    let mut result = [0u8; SCALAR_SIZE];
-    for i in 0..SCALAR_SIZE {
-        result[i] = a_bytes[i] ^ b_bytes[i];
-    }
-    result[31] &= 0x7f;
+    let mut a_arr = [0u8; SCALAR_SIZE];
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    a_arr.copy_from_slice(slice::from_raw_parts(a, SCALAR_SIZE));
+    b_arr.copy_from_slice(slice::from_raw_parts(b, SCALAR_SIZE));
 
-    ptr::copy_nonoverlapping(result.as_ptr(), out, SCALAR_SIZE);
+    let result = Scalar::from_bytes_mod_order(a_arr) * Scalar::from_bytes_mod_order(b_arr);
+    a_arr.zeroize();
+    b_arr.zeroize();
+
+    ptr::copy_nonoverlapping(result.as_bytes().as_ptr(), out, SCALAR_SIZE);
     FCMP_SUCCESS
 }
 
-// ============================================================================
-// Point Operations
-// ============================================================================
-
-/// Multiply a point by a scalar: out = scalar * point
+/// Reduce a 64-byte little-endian integer to a scalar (mod l)
+///
+/// For callers deriving scalars from their own 512-bit hash output; the
+/// wide input makes the result's bias negligible.
 ///
 /// # Safety
-/// - All pointers must point to at least 32 bytes
-/// - `out` must be writable
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `in64` must point to 64 bytes
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_point_mul(
-    out: *mut u8,
-    scalar: *const u8,
-    point: *const u8,
-) -> i32 {
-    if out.is_null() || scalar.is_null() || point.is_null() {
+pub unsafe extern "C" fn fcmp_scalar_reduce_wide(out: *mut u8, in64: *const u8) -> i32 {
+    if out.is_null() || in64.is_null() {
         return FCMP_ERROR_INVALID_PARAM;
     }
 
-    // Read inputs
-    let scalar_bytes = slice::from_raw_parts(scalar, SCALAR_SIZE);
-    let point_bytes = slice::from_raw_parts(point, POINT_SIZE);
-
-    // Use curve25519-dalek for actual point multiplication
-    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(slice::from_raw_parts(in64, 64));
+    let result = Scalar::from_bytes_mod_order_wide(&wide);
+    wide.zeroize();
+
+    ptr::copy_nonoverlapping(result.as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Write the canonical encoding of the scalar zero (additive identity)
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_zero(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    ptr::copy_nonoverlapping(Scalar::ZERO.as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Write the canonical encoding of the scalar one (multiplicative identity)
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_one(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    ptr::copy_nonoverlapping(Scalar::ONE.as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Check whether a scalar is zero, in constant time.
+///
+/// The input is reduced mod l first, matching the arithmetic functions.
+///
+/// # Safety
+/// - `scalar` must point to at least 32 bytes
+///
+/// # Returns
+/// - 1 if the scalar is zero
+/// - 0 if it is not
+/// - `FCMP_ERROR_INVALID_PARAM` if `scalar` is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_is_zero(scalar: *const u8) -> i32 {
+    if scalar.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+    use subtle::ConstantTimeEq;
+
+    let mut arr = [0u8; SCALAR_SIZE];
+    arr.copy_from_slice(slice::from_raw_parts(scalar, SCALAR_SIZE));
+    let s = Scalar::from_bytes_mod_order(arr);
+    arr.zeroize();
+
+    s.ct_eq(&Scalar::ZERO).unwrap_u8() as i32
+}
+
+/// Check whether a scalar is one, in constant time.
+///
+/// The input is reduced mod l first, matching the arithmetic functions.
+///
+/// # Safety
+/// - `scalar` must point to at least 32 bytes
+///
+/// # Returns
+/// - 1 if the scalar is one
+/// - 0 if it is not
+/// - `FCMP_ERROR_INVALID_PARAM` if `scalar` is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_is_one(scalar: *const u8) -> i32 {
+    if scalar.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+    use subtle::ConstantTimeEq;
+
+    let mut arr = [0u8; SCALAR_SIZE];
+    arr.copy_from_slice(slice::from_raw_parts(scalar, SCALAR_SIZE));
+    let s = Scalar::from_bytes_mod_order(arr);
+    arr.zeroize();
+
+    s.ct_eq(&Scalar::ONE).unwrap_u8() as i32
+}
+
+/// Check whether 32 bytes are a clamped X25519/Ed25519 secret, in constant time.
+///
+/// Clamping clears the low 3 bits (a multiple of the cofactor), clears bit
+/// 255 and sets bit 254. Key agreement with an unclamped secret silently
+/// disagrees with peers that clamp, so imported keys can be checked here.
+/// The bytes are read as-is, not reduced mod l.
+///
+/// # Safety
+/// - `scalar` must point to at least 32 bytes
+///
+/// # Returns
+/// - 1 if the bytes are clamped
+/// - 0 if they are not
+/// - `FCMP_ERROR_INVALID_PARAM` if `scalar` is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_is_clamped(scalar: *const u8) -> i32 {
+    if scalar.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use subtle::ConstantTimeEq;
+
+    let bytes = slice::from_raw_parts(scalar, SCALAR_SIZE);
+    let low = (bytes[0] & 0x07).ct_eq(&0);
+    let high = (bytes[31] & 0xc0).ct_eq(&0x40);
+    (low & high).unwrap_u8() as i32
+}
+
+/// Clamp 32 bytes as an X25519/Ed25519 secret: clear the low 3 bits and
+/// bit 255, set bit 254.
+///
+/// This is not reduction mod l; a clamped secret is generally not a
+/// canonical scalar. Use it only where X25519 semantics are required.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `in_` must point to 32 bytes; it may alias `out`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if either pointer is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scalar_clamp(out: *mut u8, in_: *const u8) -> i32 {
+    if out.is_null() || in_.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut bytes = [0u8; SCALAR_SIZE];
+    ptr::copy(in_, bytes.as_mut_ptr(), SCALAR_SIZE);
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, SCALAR_SIZE);
+    bytes.zeroize();
+    FCMP_SUCCESS
+}
+
+// ============================================================================
+// Point Operations
+// ============================================================================
+
+/// Multiply a point by a scalar: out = scalar * point
+///
+/// # Safety
+/// - All pointers must point to at least 32 bytes
+/// - `out` must be writable
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_point_mul(
+    out: *mut u8,
+    scalar: *const u8,
+    point: *const u8,
+) -> i32 {
+    if out.is_null() || scalar.is_null() || point.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    // Read inputs
+    let scalar_bytes = slice::from_raw_parts(scalar, SCALAR_SIZE);
+    let point_bytes = slice::from_raw_parts(point, POINT_SIZE);
+
+    // Use curve25519-dalek for actual point multiplication
+    use curve25519_dalek::edwards::CompressedEdwardsY;
     use curve25519_dalek::scalar::Scalar;
 
     let point_compressed = CompressedEdwardsY::from_slice(point_bytes);
@@ -301,6 +1097,71 @@ pub unsafe extern "C" fn fcmp_point_mul(
     FCMP_SUCCESS
 }
 
+/// Multiply many points by scalars: out[i] = scalars[i] * points[i]
+///
+/// Produces the same results as `count` calls to `fcmp_point_mul`. Each
+/// distinct point encoding is decompressed once, and products with the
+/// basepoint use its precomputed table.
+///
+/// # Safety
+/// - `scalars` and `points` must each point to `count * 32` bytes
+/// - `out` must point to `count * 32` bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if any point fails to decompress, in which
+///   case nothing is written
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_point_mul_batch(
+    out: *mut u8,
+    scalars: *const u8,
+    points: *const u8,
+    count: usize,
+) -> i32 {
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+    if out.is_null() || scalars.is_null() || points.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::{ED25519_BASEPOINT_COMPRESSED, ED25519_BASEPOINT_TABLE};
+    use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+    use curve25519_dalek::scalar::Scalar;
+
+    let scalars = slice::from_raw_parts(scalars, count * SCALAR_SIZE);
+    let points = slice::from_raw_parts(points, count * POINT_SIZE);
+
+    let mut decompressed: std::collections::HashMap<&[u8], EdwardsPoint> = std::collections::HashMap::new();
+    let mut results = Vec::with_capacity(count * POINT_SIZE);
+    for (scalar_bytes, point_bytes) in scalars.chunks_exact(SCALAR_SIZE).zip(points.chunks_exact(POINT_SIZE)) {
+        let mut scalar_arr = [0u8; SCALAR_SIZE];
+        scalar_arr.copy_from_slice(scalar_bytes);
+        let scalar = Scalar::from_bytes_mod_order(scalar_arr);
+
+        let result = if point_bytes == ED25519_BASEPOINT_COMPRESSED.as_bytes() {
+            &scalar * ED25519_BASEPOINT_TABLE
+        } else {
+            let point = match decompressed.get(point_bytes) {
+                Some(point) => *point,
+                None => {
+                    let point = match CompressedEdwardsY::from_slice(point_bytes).ok().and_then(|c| c.decompress()) {
+                        Some(point) => point,
+                        None => return FCMP_ERROR_INVALID_POINT,
+                    };
+                    decompressed.insert(point_bytes, point);
+                    point
+                }
+            };
+            scalar * point
+        };
+        results.extend_from_slice(result.compress().as_bytes());
+    }
+
+    ptr::copy_nonoverlapping(results.as_ptr(), out, results.len());
+    FCMP_SUCCESS
+}
+
 /// Add two points: out = a + b
 ///
 /// # Safety
@@ -363,6 +1224,23 @@ pub unsafe extern "C" fn fcmp_point_basepoint(out: *mut u8) -> i32 {
     FCMP_SUCCESS
 }
 
+/// Get the compressed encoding of the identity point
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_point_identity(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use curve25519_dalek::traits::Identity;
+
+    ptr::copy_nonoverlapping(CompressedEdwardsY::identity().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
 /// Check if a point is valid (on the curve)
 ///
 /// # Safety
@@ -389,6 +1267,109 @@ pub unsafe extern "C" fn fcmp_point_is_valid(point: *const u8) -> i32 {
     if compressed.unwrap().decompress().is_some() { 1 } else { 0 }
 }
 
+/// Validate an array of untrusted points in one call.
+///
+/// Each point must decompress, be canonically encoded and be torsion-free,
+/// the checks of `fcmp_pedersen_is_well_formed`. Scanning stops at the
+/// first failure, whose index is reported.
+///
+/// # Safety
+/// - `points` must point to `count * 32` bytes
+/// - `first_invalid_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every point is valid
+/// - `FCMP_ERROR_INVALID_POINT` with the failing index in `first_invalid_out`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_points_validate(
+    points: *const u8,
+    count: u32,
+    first_invalid_out: *mut u32,
+) -> i32 {
+    if (points.is_null() && count > 0) || first_invalid_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+
+    let points = slice::from_raw_parts(points, count as usize * POINT_SIZE);
+    for (i, bytes) in points.chunks_exact(POINT_SIZE).enumerate() {
+        if !decode_point(bytes).is_some_and(|p| p.is_torsion_free()) {
+            *first_invalid_out = i as u32;
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Convert an Edwards point to its Montgomery (X25519) u-coordinate.
+///
+/// The map is u = (1 + y) / (1 - y). It drops the sign of x, so P and -P
+/// give the same u.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `edwards` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if `edwards` is not a canonical point encoding
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_edwards_to_montgomery(out: *mut u8, edwards: *const u8) -> i32 {
+    if out.is_null() || edwards.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let point = match decode_point(slice::from_raw_parts(edwards, POINT_SIZE)) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    ptr::copy_nonoverlapping(point.to_montgomery().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Convert a Montgomery (X25519) u-coordinate to an Edwards point.
+///
+/// A u-coordinate determines the Edwards point only up to sign, since P
+/// and -P share it. `sign_bit` picks the point whose x is negative (1) or
+/// non-negative (0), the same convention as the top bit of a compressed
+/// Edwards point. The top bit of `montgomery` is ignored, as in X25519.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `montgomery` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `sign_bit` is not 0 or 1
+/// - `FCMP_ERROR_INVALID_POINT` if u has no point on the Edwards curve (e.g. u = -1)
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_montgomery_to_edwards(
+    out: *mut u8,
+    montgomery: *const u8,
+    sign_bit: u8,
+) -> i32 {
+    if out.is_null() || montgomery.is_null() || sign_bit > 1 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::montgomery::MontgomeryPoint;
+
+    let mut u = [0u8; POINT_SIZE];
+    u.copy_from_slice(slice::from_raw_parts(montgomery, POINT_SIZE));
+
+    match MontgomeryPoint(u).to_edwards(sign_bit) {
+        Some(point) => {
+            ptr::copy_nonoverlapping(point.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
 // ============================================================================
 // Hash Functions
 // ============================================================================
@@ -429,365 +1410,14498 @@ pub unsafe extern "C" fn fcmp_hash_to_scalar(
     FCMP_SUCCESS
 }
 
-/// Hash data to a point using BLAKE2b + Elligator-like mapping
+/// Hash a sequence of points to a scalar: Hs(P_0 || P_1 || ... || P_{n-1})
+///
+/// Absorbs the domain tag "WATTx_Hash_Points_v1", `count` as u64
+/// little-endian, then each 32-byte compressed point in the order given,
+/// and reduces the 64-byte BLAKE2b-512 digest mod l. Order is significant:
+/// permuting the points changes the scalar.
 ///
 /// # Safety
 /// - `out` must point to at least 32 bytes of writable memory
-/// - `data` must point to `data_len` bytes
+/// - `points` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if any point is not a canonical encoding
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_hash_to_point(
-    out: *mut u8,
-    data: *const u8,
-    data_len: usize,
-) -> i32 {
-    if out.is_null() || (data.is_null() && data_len > 0) {
+pub unsafe extern "C" fn fcmp_hash_points_to_scalar(out: *mut u8, points: *const u8, count: u32) -> i32 {
+    if out.is_null() || (points.is_null() && count > 0) {
         return FCMP_ERROR_INVALID_PARAM;
     }
 
-    use blake2::{Blake2b512, Digest};
-    use curve25519_dalek::edwards::CompressedEdwardsY;
-
-    let input = if data_len > 0 {
-        slice::from_raw_parts(data, data_len)
+    let points = if count > 0 {
+        slice::from_raw_parts(points, count as usize * POINT_SIZE)
     } else {
         &[]
     };
 
-    // Hash to get uniform bytes
-    let mut hasher = Blake2b512::new();
-    hasher.update(b"WATTx_hash_to_point_v1");
-    hasher.update(input);
-    let hash = hasher.finalize();
-
-    // Try to decode as point, increment and retry if invalid
-    let mut attempt = [0u8; POINT_SIZE];
-    for i in 0..=255u8 {
-        let mut hasher2 = Blake2b512::new();
-        hasher2.update(&hash);
-        hasher2.update(&[i]);
-        let h2 = hasher2.finalize();
-        attempt.copy_from_slice(&h2[..POINT_SIZE]);
-
-        let compressed = CompressedEdwardsY(attempt);
-        if let Some(point) = compressed.decompress() {
-            // Multiply by cofactor to ensure we're in the prime-order subgroup
-            let result = point.mul_by_cofactor();
-            let result_bytes = result.compress().to_bytes();
-            ptr::copy_nonoverlapping(result_bytes.as_ptr(), out, POINT_SIZE);
-            return FCMP_SUCCESS;
+    let mut transcript = Transcript::with_domain(b"WATTx_Hash_Points_v1");
+    transcript.absorb_count(count as usize);
+    for point in points.chunks_exact(POINT_SIZE) {
+        if decode_point(point).is_none() {
+            return FCMP_ERROR_INVALID_POINT;
         }
+        transcript.absorb(point);
     }
 
-    FCMP_ERROR_INTERNAL
+    ptr::copy_nonoverlapping(transcript.challenge().as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
 }
 
-// ============================================================================
-// Pedersen Commitment
-// ============================================================================
+/// Size of a message digest from `fcmp_message_prehash`
+pub const FCMP_PREHASH_SIZE: usize = 32;
 
-/// Create a Pedersen commitment: C = value * G + blinding * H
+/// Pre-hash a message for signing: H("WATTx_Message_Prehash_v1" || message)
+///
+/// Hash a large message once, then pass the digest with `prehashed = 1` to
+/// `fcmp_schnorr_sign` or `fcmp_sal_sign` for each key that signs it.
 ///
 /// # Safety
-/// - `out` must point to at least 32 bytes of writable memory
-/// - `value` and `blinding` must each point to 32 bytes
+/// - `out` must point to at least `FCMP_PREHASH_SIZE` bytes of writable memory
+/// - `message` must point to `msg_len` bytes
 #[no_mangle]
-pub unsafe extern "C" fn fcmp_pedersen_commit(
-    out: *mut u8,
-    value: *const u8,
-    blinding: *const u8,
-) -> i32 {
-    if out.is_null() || value.is_null() || blinding.is_null() {
+pub unsafe extern "C" fn fcmp_message_prehash(out: *mut u8, message: *const u8, msg_len: usize) -> i32 {
+    if out.is_null() || (message.is_null() && msg_len > 0) {
         return FCMP_ERROR_INVALID_PARAM;
     }
 
-    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
-    use curve25519_dalek::scalar::Scalar;
+    let message = if msg_len > 0 {
+        slice::from_raw_parts(message, msg_len)
+    } else {
+        &[]
+    };
 
-    // Read scalars
-    let value_bytes = slice::from_raw_parts(value, SCALAR_SIZE);
-    let blinding_bytes = slice::from_raw_parts(blinding, SCALAR_SIZE);
+    let digest = tagged_digest(b"WATTx_Message_Prehash_v1", &[message]);
+    ptr::copy_nonoverlapping(digest.as_ptr(), out, FCMP_PREHASH_SIZE);
+    FCMP_SUCCESS
+}
 
-    let mut v_arr = [0u8; 32];
+/// Interpret a signing function's `prehashed` flag for a message of
+/// `msg_len` bytes. A prehashed message must be a `FCMP_PREHASH_SIZE` digest.
+fn prehashed_flag(prehashed: i32, msg_len: usize) -> Result<bool, i32> {
+    match prehashed {
+        0 => Ok(false),
+        1 if msg_len == FCMP_PREHASH_SIZE => Ok(true),
+        _ => Err(FCMP_ERROR_INVALID_PARAM),
+    }
+}
+
+/// Hash data to a point using BLAKE2b + Elligator-like mapping
+///
+/// The result is cofactor-cleared; see `fcmp_hash_to_point_ex` for the raw
+/// mapped point.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `data` must point to `data_len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_hash_to_point(
+    out: *mut u8,
+    data: *const u8,
+    data_len: usize,
+) -> i32 {
+    fcmp_hash_to_point_ex(out, data, data_len, 1)
+}
+
+/// Hash data to a point, optionally skipping cofactor clearing
+///
+/// With `clear_cofactor == 0` the raw mapped point is returned. It may carry
+/// a small-order (torsion) component, so it is NOT safe to use as a
+/// generator or key: discrete-log relations and equality checks can then
+/// differ by a torsion point, enabling malleability such as multiple
+/// encodings of the same key image. Only use it to interoperate with a
+/// protocol that clears the cofactor itself or expects the unmodified map.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `data` must point to `data_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `clear_cofactor` is not 0 or 1
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_hash_to_point_ex(
+    out: *mut u8,
+    data: *const u8,
+    data_len: usize,
+    clear_cofactor: i32,
+) -> i32 {
+    if out.is_null() || (data.is_null() && data_len > 0) || !(0..=1).contains(&clear_cofactor) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let input = if data_len > 0 {
+        slice::from_raw_parts(data, data_len)
+    } else {
+        &[]
+    };
+
+    let point = if clear_cofactor == 1 {
+        hash_to_point(input)
+    } else {
+        hash_to_point_with_domain(HASH_TO_POINT_DOMAIN, input, false)
+    };
+    match point {
+        Some(point) => {
+            ptr::copy_nonoverlapping(point.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// The canonical "no input" generator
+///
+/// Hashing zero bytes with `fcmp_hash_to_point` is an ordinary input under
+/// the data domain. Protocols that need a sentinel for "nothing" distinct
+/// from any data, including the empty string, use this point instead. It is
+/// the cofactor-cleared try-and-increment map of the empty string under the
+/// domain "WATTx_hash_to_point_empty_v1" and will not change within v1.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_hash_empty_to_point(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match hash_to_point_with_domain(b"WATTx_hash_to_point_empty_v1", &[], true) {
+        Some(point) => {
+            ptr::copy_nonoverlapping(point.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Domain of `fcmp_hash_to_point`
+const HASH_TO_POINT_DOMAIN: &[u8] = b"WATTx_hash_to_point_v1";
+
+/// Replacement for the built-in hash-to-point map: writes the compressed
+/// point for the `len` bytes at `data` to the 32 bytes at `out` and returns
+/// `FCMP_SUCCESS`, or any other value on failure
+pub type FcmpHashToPointCallback = Option<unsafe extern "C" fn(data: *const u8, len: usize, out: *mut u8) -> i32>;
+
+/// Callback installed by `fcmp_set_hash_to_point_callback`, if any
+static HASH_TO_POINT_CALLBACK: RwLock<FcmpHashToPointCallback> = RwLock::new(None);
+
+/// Route the library's hash-to-point through a caller-supplied map.
+///
+/// For interop with an external implementation whose points must match
+/// bit for bit. When set, the callback replaces the cofactor-cleared map of
+/// `fcmp_hash_to_point` everywhere it is used: the Pedersen H, the proof,
+/// vector and re-randomization generators, and key images in
+/// `fcmp_key_image_batch`. Passing null restores the built-in map. Points
+/// already derived keep the map they were derived with, so install the
+/// callback before `fcmp_init` and the first commitment.
+///
+/// Soundness then rests on the caller. The callback must be deterministic,
+/// thread-safe and behave as a random oracle onto the prime-order subgroup:
+/// if anyone knows a discrete-log relation between its outputs, for example
+/// because it maps inputs to known multiples of a base point, they can open
+/// commitments to other values and forge proofs. The library only rejects
+/// outputs that are not canonical points, carry torsion or are the
+/// identity, which makes the operation fail with `FCMP_ERROR_INTERNAL`.
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub extern "C" fn fcmp_set_hash_to_point_callback(cb: FcmpHashToPointCallback) -> i32 {
+    match HASH_TO_POINT_CALLBACK.write() {
+        Ok(mut callback) => {
+            *callback = cb;
+            FCMP_SUCCESS
+        }
+        Err(_) => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// The map behind `fcmp_hash_to_point`: the installed callback if there is
+/// one, otherwise the cofactor-cleared map under `HASH_TO_POINT_DOMAIN`
+unsafe fn hash_to_point(input: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::traits::IsIdentity;
+
+    let callback = *HASH_TO_POINT_CALLBACK.read().ok()?;
+    match callback {
+        Some(cb) => {
+            let mut out = [0u8; POINT_SIZE];
+            if cb(input.as_ptr(), input.len(), out.as_mut_ptr()) != FCMP_SUCCESS {
+                return None;
+            }
+            decode_point(&out).filter(|p| p.is_torsion_free() && !p.is_identity())
+        }
+        None => hash_to_point_with_domain(HASH_TO_POINT_DOMAIN, input, true),
+    }
+}
+
+/// Try-and-increment map of `input` to a curve point under `domain`
+fn hash_to_point_with_domain(
+    domain: &[u8],
+    input: &[u8],
+    clear_cofactor: bool,
+) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    // Hash to get uniform bytes
+    let mut hasher = Blake2b512::new();
+    hasher.update(domain);
+    hasher.update(input);
+    let hash = hasher.finalize();
+
+    // Try to decode as point, increment and retry if invalid
+    let mut attempt = [0u8; POINT_SIZE];
+    for i in 0..=255u8 {
+        let mut hasher2 = Blake2b512::new();
+        hasher2.update(hash);
+        hasher2.update([i]);
+        let h2 = hasher2.finalize();
+        attempt.copy_from_slice(&h2[..POINT_SIZE]);
+
+        if let Some(point) = CompressedEdwardsY(attempt).decompress() {
+            // Multiply by cofactor to ensure we're in the prime-order subgroup
+            return Some(if clear_cofactor { point.mul_by_cofactor() } else { point });
+        }
+    }
+
+    None
+}
+
+/// Keyed PRF mapping (domain, key, input) to a scalar.
+///
+/// BLAKE2b-512 over the domain tag, the length-prefixed key and the input,
+/// reduced mod l from the full 64-byte output.
+fn prf_scalar(domain: &[u8], key: &[u8], input: &[u8]) -> curve25519_dalek::scalar::Scalar {
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(domain);
+    hasher.update((key.len() as u64).to_le_bytes());
+    hasher.update(key);
+    hasher.update(input);
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    let scalar = Scalar::from_bytes_mod_order_wide(&wide);
+    wide.zeroize();
+    scalar
+}
+
+// ============================================================================
+// Pedersen Commitment
+// ============================================================================
+
+/// Domain H is derived from unless `fcmp_set_pedersen_h_domain` changes it
+const DEFAULT_PEDERSEN_H_DOMAIN: &[u8] = b"WATTx_Pedersen_H_v1";
+
+/// A Pedersen H generator with its multiplication table
+#[derive(Clone)]
+struct PedersenH {
+    point: curve25519_dalek::edwards::EdwardsPoint,
+    /// Fixed-base table for `point`, built on first use. It lives and is
+    /// replaced together with `point`, so it never describes a stale H.
+    table: std::sync::Arc<std::sync::OnceLock<curve25519_dalek::edwards::EdwardsBasepointTable>>,
+}
+
+impl PedersenH {
+    fn new(point: curve25519_dalek::edwards::EdwardsPoint) -> Self {
+        PedersenH { point, table: Default::default() }
+    }
+
+    /// Fixed-base multiplication table for H
+    fn table(&self) -> &curve25519_dalek::edwards::EdwardsBasepointTable {
+        use curve25519_dalek::edwards::EdwardsBasepointTable;
+        use curve25519_dalek::traits::BasepointTable;
+
+        self.table.get_or_init(|| EdwardsBasepointTable::create(&self.point))
+    }
+}
+
+/// H derived from the current domain, filled on first use
+static PEDERSEN_H: RwLock<Option<PedersenH>> = RwLock::new(None);
+
+/// Derive a Pedersen H generator: hash_to_point(domain)
+unsafe fn derive_pedersen_h(domain: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    hash_to_point(domain)
+}
+
+/// The cached Pedersen H generator and its table, deriving the default on
+/// first use
+unsafe fn cached_pedersen_h() -> Option<PedersenH> {
+    if let Some(h) = PEDERSEN_H.read().ok()?.clone() {
+        return Some(h);
+    }
+
+    let mut cached = PEDERSEN_H.write().ok()?;
+    if cached.is_none() {
+        *cached = Some(PedersenH::new(derive_pedersen_h(DEFAULT_PEDERSEN_H_DOMAIN)?));
+    }
+    cached.clone()
+}
+
+/// The Pedersen H generator, hash_to_point("WATTx_Pedersen_H_v1") unless a
+/// custom domain has been set
+unsafe fn pedersen_h() -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    cached_pedersen_h().map(|h| h.point)
+}
+
+/// Re-derive the Pedersen H generator from a custom domain string.
+///
+/// For forks that want generators distinct from WATTx's. Commitments made
+/// after this call use the new H. The parameters `fcmp_init` derives keep
+/// the H they were built with, so proofs and verifiers only pick up the
+/// change after `fcmp_cleanup` and `fcmp_init`; call this before the first
+/// `fcmp_init` to avoid mixing generators. Setting the same domain again
+/// is a no-op, and passing "WATTx_Pedersen_H_v1" restores the default.
+///
+/// # Safety
+/// - `domain` must point to `domain_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `domain_len` is 0
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_set_pedersen_h_domain(domain: *const u8, domain_len: usize) -> i32 {
+    if domain.is_null() || domain_len == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let h = match derive_pedersen_h(slice::from_raw_parts(domain, domain_len)) {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    match PEDERSEN_H.write() {
+        Ok(mut cached) => {
+            *cached = Some(PedersenH::new(h));
+            FCMP_SUCCESS
+        }
+        Err(_) => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Get the compressed H generator used by `fcmp_pedersen_commit`
+///
+/// Integrators should take H from here rather than re-deriving it, so a
+/// change to the hash-to-point mapping cannot silently split them off.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_h(out: *mut u8) -> i32 {
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match pedersen_h() {
+        Some(h) => {
+            ptr::copy_nonoverlapping(h.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Create a Pedersen commitment: C = value * G + blinding * H
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `value` and `blinding` must each point to 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit(
+    out: *mut u8,
+    value: *const u8,
+    blinding: *const u8,
+) -> i32 {
+    if out.is_null() || value.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    // Read scalars
+    let value_bytes = slice::from_raw_parts(value, SCALAR_SIZE);
+    let blinding_bytes = slice::from_raw_parts(blinding, SCALAR_SIZE);
+
+    let mut v_arr = [0u8; 32];
     let mut b_arr = [0u8; 32];
     v_arr.copy_from_slice(value_bytes);
     b_arr.copy_from_slice(blinding_bytes);
 
-    let v = Scalar::from_bytes_mod_order(v_arr);
-    let b = Scalar::from_bytes_mod_order(b_arr);
+    let v = Scalar::from_bytes_mod_order(v_arr);
+    let b = Scalar::from_bytes_mod_order(b_arr);
+
+    write_commitment(out, &v, &b)
+}
+
+/// Create a Pedersen commitment, rejecting non-canonical scalars.
+///
+/// Unlike `fcmp_pedersen_commit`, which silently reduces its inputs mod l,
+/// this variant requires `value` and `blinding` to be fully reduced so each
+/// commitment has exactly one accepted opening encoding.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `value` and `blinding` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if either input is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_checked(
+    out: *mut u8,
+    value: *const u8,
+    blinding: *const u8,
+) -> i32 {
+    if out.is_null() || value.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let v = decode_scalar(slice::from_raw_parts(value, SCALAR_SIZE));
+    let b = decode_scalar(slice::from_raw_parts(blinding, SCALAR_SIZE));
+    match (v, b) {
+        (Some(v), Some(b)) => write_commitment(out, &v, &b),
+        _ => FCMP_ERROR_INVALID_SCALAR,
+    }
+}
+
+/// Create a Pedersen commitment under a caller-supplied second generator:
+/// C = value * G + blinding * H'
+///
+/// Supports per-context generators, such as a transaction-specific H',
+/// without reinitializing the library. H' must be a canonical, torsion-free,
+/// non-identity point; its discrete log with respect to G must be unknown for
+/// the commitment to bind. Scalars are reduced mod l, as in
+/// `fcmp_pedersen_commit`.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `value`, `blinding` and `h_point` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if `h_point` is not an acceptable generator
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_with_h(
+    out: *mut u8,
+    value: *const u8,
+    blinding: *const u8,
+    h_point: *const u8,
+) -> i32 {
+    if out.is_null() || value.is_null() || blinding.is_null() || h_point.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::IsIdentity;
+
+    let h = match decode_point(slice::from_raw_parts(h_point, POINT_SIZE)) {
+        Some(h) if h.is_torsion_free() && !h.is_identity() => h,
+        _ => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    let mut v_arr = [0u8; SCALAR_SIZE];
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    v_arr.copy_from_slice(slice::from_raw_parts(value, SCALAR_SIZE));
+    b_arr.copy_from_slice(slice::from_raw_parts(blinding, SCALAR_SIZE));
+    let mut v = Scalar::from_bytes_mod_order(v_arr);
+    let mut b = Scalar::from_bytes_mod_order(b_arr);
+    v_arr.zeroize();
+    b_arr.zeroize();
+
+    let commitment = v * ED25519_BASEPOINT_POINT + b * h;
+    v.zeroize();
+    b.zeroize();
+    ptr::copy_nonoverlapping(commitment.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Create a Pedersen commitment with a blinding derived from a seed.
+///
+/// The blinding is `PRF(seed, value)`, so the same seed and value always
+/// reproduce the same commitment and blinding. Intended for reproducible test
+/// fixtures; a production seed must be secret and never reused.
+///
+/// # Safety
+/// - `commitment_out` and `blinding_out` must each point to at least 32 bytes of writable memory
+/// - `value` must point to 32 bytes
+/// - `seed` must point to `seed_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if a pointer is null or `seed_len` is 0
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_deterministic(
+    commitment_out: *mut u8,
+    blinding_out: *mut u8,
+    value: *const u8,
+    seed: *const u8,
+    seed_len: usize,
+) -> i32 {
+    if commitment_out.is_null() || blinding_out.is_null() || value.is_null() || seed.is_null() || seed_len == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut v_arr = [0u8; SCALAR_SIZE];
+    v_arr.copy_from_slice(slice::from_raw_parts(value, SCALAR_SIZE));
+    let seed = slice::from_raw_parts(seed, seed_len);
+
+    let v = Scalar::from_bytes_mod_order(v_arr);
+    let mut b = prf_scalar(b"WATTx_Pedersen_Blinding_v1", seed, &v_arr);
+    v_arr.zeroize();
+
+    let result = write_commitment(commitment_out, &v, &b);
+    if result == FCMP_SUCCESS {
+        ptr::copy_nonoverlapping(b.as_bytes().as_ptr(), blinding_out, SCALAR_SIZE);
+    }
+    b.zeroize();
+    result
+}
+
+/// Derive the blinding factor for a wallet output from a master secret.
+///
+/// Each output index yields an independent scalar
+/// `PRF(master_secret, index || counter)`, so a wallet can regenerate every
+/// blinder from its seed during recovery. The counter starts at 0 and is
+/// only bumped in the negligible case that the PRF output reduces to zero.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `master_secret` must point to 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_derive_blinding(
+    out: *mut u8,
+    master_secret: *const u8,
+    output_index: u64,
+) -> i32 {
+    if out.is_null() || master_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let master = slice::from_raw_parts(master_secret, SCALAR_SIZE);
+    let mut input = [0u8; 9];
+    input[..8].copy_from_slice(&output_index.to_le_bytes());
+    for counter in 0..=u8::MAX {
+        input[8] = counter;
+        let mut b = prf_scalar(b"WATTx_Blinding_v1", master, &input);
+        if b != Scalar::ZERO {
+            ptr::copy_nonoverlapping(b.as_bytes().as_ptr(), out, SCALAR_SIZE);
+            b.zeroize();
+            return FCMP_SUCCESS;
+        }
+    }
+
+    FCMP_ERROR_INTERNAL
+}
+
+/// Write C = value*G + blinding*H to `out`
+unsafe fn write_commitment(
+    out: *mut u8,
+    v: &curve25519_dalek::scalar::Scalar,
+    b: &curve25519_dalek::scalar::Scalar,
+) -> i32 {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+
+    // G = base point, H = hash_to_point("WATTx_Pedersen_H")
+    let g = ED25519_BASEPOINT_POINT;
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    // C = v*G + b*H
+    let commitment = v * g + b * h;
+    let result = commitment.compress().to_bytes();
+
+    ptr::copy_nonoverlapping(result.as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Create many Pedersen commitments: C_i = values[i]*G + blindings[i]*H
+///
+/// Produces the same results as `count` calls to `fcmp_pedersen_commit`.
+/// Both generators are multiplied through precomputed tables. H's table is
+/// built on the first batch and cached with H until
+/// `fcmp_set_pedersen_h_domain` replaces it, so even small batches avoid
+/// variable-base multiplications.
+///
+/// # Safety
+/// - `values` and `blindings` must each point to `count * 32` bytes
+/// - `commitments_out` must point to `count * 32` bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_batch(
+    commitments_out: *mut u8,
+    values: *const u8,
+    blindings: *const u8,
+    count: usize,
+) -> i32 {
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+    if commitments_out.is_null() || values.is_null() || blindings.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let h = match cached_pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+    let h_table = h.table();
+
+    let values = slice::from_raw_parts(values, count * SCALAR_SIZE);
+    let blindings = slice::from_raw_parts(blindings, count * SCALAR_SIZE);
+    let out = slice::from_raw_parts_mut(commitments_out, count * POINT_SIZE);
+    for ((v_bytes, b_bytes), c_out) in values
+        .chunks_exact(SCALAR_SIZE)
+        .zip(blindings.chunks_exact(SCALAR_SIZE))
+        .zip(out.chunks_exact_mut(POINT_SIZE))
+    {
+        let mut arr = [0u8; SCALAR_SIZE];
+        arr.copy_from_slice(v_bytes);
+        let mut v = Scalar::from_bytes_mod_order(arr);
+        arr.copy_from_slice(b_bytes);
+        let mut b = Scalar::from_bytes_mod_order(arr);
+        arr.zeroize();
+
+        let commitment = &v * ED25519_BASEPOINT_TABLE + &b * h_table;
+        c_out.copy_from_slice(commitment.compress().as_bytes());
+        v.zeroize();
+        b.zeroize();
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Re-blind a Pedersen commitment: C' = C + delta * H with a fresh random delta.
+///
+/// The committed value is unchanged; the blinding becomes `blinding + delta`.
+/// The caller receives delta so it can track the new blinding.
+///
+/// # Safety
+/// - `commitment_out` and `delta_out` must each point to at least 32 bytes of writable memory
+/// - `commitment_in` must point to 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_reblind(
+    commitment_out: *mut u8,
+    delta_out: *mut u8,
+    commitment_in: *const u8,
+) -> i32 {
+    if commitment_out.is_null() || delta_out.is_null() || commitment_in.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut c_arr = [0u8; POINT_SIZE];
+    c_arr.copy_from_slice(slice::from_raw_parts(commitment_in, POINT_SIZE));
+    let c = match CompressedEdwardsY(c_arr).decompress() {
+        Some(c) => c,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let mut delta = Scalar::random(&mut OsRng);
+    let result = (c + delta * h).compress().to_bytes();
+
+    ptr::copy_nonoverlapping(result.as_ptr(), commitment_out, POINT_SIZE);
+    ptr::copy_nonoverlapping(delta.as_bytes().as_ptr(), delta_out, SCALAR_SIZE);
+    delta.zeroize();
+
+    FCMP_SUCCESS
+}
+
+/// Recover the value committed in a Pedersen commitment given its blinding.
+///
+/// Computes `C - blinding * H` and searches for `value` with `value * G` equal
+/// to the remainder, trying every value in `0..=max_value`. This is an audit
+/// and debugging aid that runs in O(max_value); do not use it on hot paths.
+///
+/// # Safety
+/// - `commitment` and `blinding` must each point to 32 bytes
+/// - `value_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the value written to `value_out`
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment is not a valid point
+/// - `FCMP_ERROR_INVALID_PARAM` if no value in range matches
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_recover_value(
+    commitment: *const u8,
+    blinding: *const u8,
+    max_value: u64,
+    value_out: *mut u64,
+) -> i32 {
+    if commitment.is_null() || blinding.is_null() || value_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::Identity;
+
+    let mut c_arr = [0u8; POINT_SIZE];
+    c_arr.copy_from_slice(slice::from_raw_parts(commitment, POINT_SIZE));
+    let c = match CompressedEdwardsY(c_arr).decompress() {
+        Some(c) => c,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    b_arr.copy_from_slice(slice::from_raw_parts(blinding, SCALAR_SIZE));
+    let b = Scalar::from_bytes_mod_order(b_arr);
+    b_arr.zeroize();
+
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    // value * G = C - b*H; walk the multiples of G until one matches
+    let target = c - b * h;
+    let mut candidate = EdwardsPoint::identity();
+    let mut value = 0u64;
+    loop {
+        if candidate == target {
+            *value_out = value;
+            return FCMP_SUCCESS;
+        }
+        if value == max_value {
+            return FCMP_ERROR_INVALID_PARAM;
+        }
+        candidate += ED25519_BASEPOINT_POINT;
+        value += 1;
+    }
+}
+
+/// Check that a received commitment is safe to use.
+///
+/// Composite of the checks a commitment must pass before it enters any
+/// equation: it decompresses, its encoding is canonical, and it has no
+/// torsion component that could make two commitments differ by a
+/// small-order point.
+///
+/// # Safety
+/// - `commitment` must point to 32 bytes
+///
+/// # Returns
+/// - 1 if all checks hold
+/// - 0 otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_is_well_formed(commitment: *const u8) -> i32 {
+    if commitment.is_null() {
+        return 0;
+    }
+
+    match decode_point(slice::from_raw_parts(commitment, POINT_SIZE)) {
+        Some(c) if c.is_torsion_free() => 1,
+        _ => 0,
+    }
+}
+
+/// Longest vector `fcmp_pedersen_commit_vector_open` accepts
+pub const FCMP_VECTOR_COMMIT_MAX: u32 = 256;
+
+/// Size of the opening written by `fcmp_pedersen_commit_vector_open`.
+///
+/// Layout: count (u32 LE) || values (count * 32) || blinding (32)
+///
+/// # Returns
+/// Opening size in bytes, or 0 if `count` is 0 or above `FCMP_VECTOR_COMMIT_MAX`
+#[no_mangle]
+pub extern "C" fn fcmp_pedersen_vector_opening_size(count: u32) -> usize {
+    if count == 0 || count > FCMP_VECTOR_COMMIT_MAX {
+        return 0;
+    }
+    4 + (count as usize + 1) * SCALAR_SIZE
+}
+
+/// Derive the i-th vector commitment generator: hash_to_point("WATTx_Pedersen_Vector_G_v1" || index)
+unsafe fn vector_generator(index: u32) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let mut seed = [0u8; 26 + 4];
+    seed[..26].copy_from_slice(b"WATTx_Pedersen_Vector_G_v1");
+    seed[26..].copy_from_slice(&index.to_le_bytes());
+
+    let mut g_out = [0u8; POINT_SIZE];
+    if fcmp_hash_to_point(g_out.as_mut_ptr(), seed.as_ptr(), seed.len()) != FCMP_SUCCESS {
+        return None;
+    }
+
+    CompressedEdwardsY(g_out).decompress()
+}
+
+/// C = sum(values[i] * G_i) + blinding * H over `count` 32-byte scalars
+///
+/// Values and blinding must be canonical; the decoded copies are zeroized.
+unsafe fn vector_commit(
+    values: *const u8,
+    count: u32,
+    blinding: *const u8,
+) -> Result<curve25519_dalek::edwards::EdwardsPoint, i32> {
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::traits::MultiscalarMul;
+
+    if count == 0 || count > FCMP_VECTOR_COMMIT_MAX {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let mut scalars = match slice::from_raw_parts(values, count as usize * SCALAR_SIZE)
+        .chunks_exact(SCALAR_SIZE)
+        .chain(std::iter::once(slice::from_raw_parts(blinding, SCALAR_SIZE)))
+        .map(decode_scalar)
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(scalars) => scalars,
+        None => return Err(FCMP_ERROR_INVALID_SCALAR),
+    };
+
+    let generators = (0..count)
+        .map(|i| vector_generator(i))
+        .chain(std::iter::once(pedersen_h()))
+        .collect::<Option<Vec<_>>>();
+    let result = match generators {
+        Some(generators) => Ok(EdwardsPoint::multiscalar_mul(&scalars, &generators)),
+        None => Err(FCMP_ERROR_INTERNAL),
+    };
+    scalars.zeroize();
+    result
+}
+
+/// Commit to a vector of values under one blinding and return its opening.
+///
+/// C = sum(values[i] * G_i) + blinding * H, where G_i are independent
+/// generators derived per index. The opening packages the values and
+/// blinding in the layout of `fcmp_pedersen_vector_opening_size` so a
+/// prover can later re-check or prove statements about the vector. It is
+/// secret and the caller must zeroize it.
+///
+/// # Safety
+/// - `commitment_out` must point to at least 32 bytes of writable memory
+/// - `opening_out` must point to `fcmp_pedersen_vector_opening_size(count)`
+///   bytes of writable memory
+/// - `values` must point to `count * 32` bytes
+/// - `blinding` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `count` is 0 or above `FCMP_VECTOR_COMMIT_MAX`
+/// - `FCMP_ERROR_INVALID_SCALAR` if a value or the blinding is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_vector_open(
+    commitment_out: *mut u8,
+    opening_out: *mut u8,
+    values: *const u8,
+    count: u32,
+    blinding: *const u8,
+) -> i32 {
+    if commitment_out.is_null() || opening_out.is_null() || values.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let commitment = match vector_commit(values, count, blinding) {
+        Ok(c) => c,
+        Err(e) => return e,
+    };
+
+    let values_len = count as usize * SCALAR_SIZE;
+    ptr::copy_nonoverlapping(commitment.compress().as_bytes().as_ptr(), commitment_out, POINT_SIZE);
+    ptr::copy_nonoverlapping(count.to_le_bytes().as_ptr(), opening_out, 4);
+    ptr::copy_nonoverlapping(values, opening_out.add(4), values_len);
+    ptr::copy_nonoverlapping(blinding, opening_out.add(4 + values_len), SCALAR_SIZE);
+
+    FCMP_SUCCESS
+}
+
+/// Check that a vector commitment opens to the given values and blinding.
+///
+/// # Safety
+/// - `commitment` must point to 32 bytes
+/// - `values` must point to `count * 32` bytes
+/// - `blinding` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the commitment opens to the values
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it does not
+/// - `FCMP_ERROR_INVALID_PARAM` if `count` is 0 or above `FCMP_VECTOR_COMMIT_MAX`
+/// - `FCMP_ERROR_INVALID_SCALAR` if a value or the blinding is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_verify_vector(
+    commitment: *const u8,
+    values: *const u8,
+    count: u32,
+    blinding: *const u8,
+) -> i32 {
+    if commitment.is_null() || values.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match vector_commit(values, count, blinding) {
+        Ok(c) if c.compress().as_bytes()[..] == *slice::from_raw_parts(commitment, POINT_SIZE) => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// Check that a set of commitments opens to a claimed total.
+///
+/// Verifies sum(C_i) == total_value * G + total_blinding * H, so an auditor
+/// can reconcile a balance from the total value and blinding alone without
+/// learning the individual openings.
+///
+/// # Safety
+/// - `commitments` must point to `count * 32` bytes (may be null if `count` is 0)
+/// - `total_blinding` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the commitments sum to the claimed total
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if they do not
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment is not a canonical point
+/// - `FCMP_ERROR_INVALID_SCALAR` if `total_blinding` is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitments_open_total(
+    commitments: *const u8,
+    count: u32,
+    total_value: u64,
+    total_blinding: *const u8,
+) -> i32 {
+    if (commitments.is_null() && count > 0) || total_blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+
+    let b = match decode_scalar(slice::from_raw_parts(total_blinding, SCALAR_SIZE)) {
+        Some(b) => b,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let commitments = if count > 0 {
+        slice::from_raw_parts(commitments, count as usize * POINT_SIZE)
+    } else {
+        &[]
+    };
+    let sum = match commitments
+        .chunks_exact(POINT_SIZE)
+        .map(decode_point)
+        .sum::<Option<EdwardsPoint>>()
+    {
+        Some(sum) => sum,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    if sum == Scalar::from(total_value) * ED25519_BASEPOINT_POINT + b * h {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Size of a nonzero proof: R || s_w || s_t
+pub const FCMP_NONZERO_PROOF_SIZE: usize = POINT_SIZE + 2 * SCALAR_SIZE;
+
+/// Challenge for a nonzero proof over commitment C and nonce commitment R
+fn nonzero_challenge(
+    commitment: &curve25519_dalek::edwards::CompressedEdwardsY,
+    nonce: &curve25519_dalek::edwards::CompressedEdwardsY,
+) -> curve25519_dalek::scalar::Scalar {
+    let mut transcript = Transcript::with_domain(b"WATTx_Nonzero_Proof_v1");
+    transcript.absorb_count(2);
+    transcript.absorb(commitment.as_bytes());
+    transcript.absorb(nonce.as_bytes());
+    transcript.challenge()
+}
+
+/// Prove that C = value*G + blinding*H commits to a nonzero value.
+///
+/// A value is nonzero exactly when it has an inverse w, and then
+/// w*C - (w*blinding)*H = G. The proof shows knowledge of (w, t) with
+/// w*C - t*H = G. For a commitment to zero, C = blinding*H, so such a pair
+/// would give the discrete log of G with respect to H.
+///
+/// Layout: R || s_w || s_t, where R = k_w*C - k_t*H, s_w = k_w + c*w and
+/// s_t = k_t + c*t.
+///
+/// # Safety
+/// - `proof_out` must point to at least `FCMP_NONZERO_PROOF_SIZE` bytes of writable memory
+/// - `value` and `blinding` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if either input is not canonical
+/// - `FCMP_ERROR_PROOF_GENERATION` if the value is zero
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_nonzero_prove(proof_out: *mut u8, value: *const u8, blinding: *const u8) -> i32 {
+    if proof_out.is_null() || value.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    let (mut v, mut b) = match (
+        decode_scalar(slice::from_raw_parts(value, SCALAR_SIZE)),
+        decode_scalar(slice::from_raw_parts(blinding, SCALAR_SIZE)),
+    ) {
+        (Some(v), Some(b)) => (v, b),
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    if v == Scalar::ZERO {
+        b.zeroize();
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let commitment = v * ED25519_BASEPOINT_POINT + b * h;
+    let mut w = v.invert();
+    let mut t = w * b;
+    v.zeroize();
+    b.zeroize();
+
+    let mut k_w = Scalar::random(&mut OsRng);
+    let mut k_t = Scalar::random(&mut OsRng);
+    let nonce = (k_w * commitment - k_t * h).compress();
+    let c = nonzero_challenge(&commitment.compress(), &nonce);
+    let s_w = k_w + c * w;
+    let s_t = k_t + c * t;
+    for secret in [&mut w, &mut t, &mut k_w, &mut k_t] {
+        secret.zeroize();
+    }
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+
+    let out = slice::from_raw_parts_mut(proof_out, FCMP_NONZERO_PROOF_SIZE);
+    out[..POINT_SIZE].copy_from_slice(nonce.as_bytes());
+    out[POINT_SIZE..POINT_SIZE + SCALAR_SIZE].copy_from_slice(s_w.as_bytes());
+    out[POINT_SIZE + SCALAR_SIZE..].copy_from_slice(s_t.as_bytes());
+    FCMP_SUCCESS
+}
+
+/// Verify a proof from `fcmp_nonzero_prove`: s_w*C - s_t*H == R + c*G.
+///
+/// # Safety
+/// - `proof` must point to `FCMP_NONZERO_PROOF_SIZE` bytes
+/// - `commitment` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the commitment is proven to a nonzero value
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof is invalid
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment or R is not a canonical point
+/// - `FCMP_ERROR_INVALID_SCALAR` if s_w or s_t is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_nonzero_verify(proof: *const u8, commitment: *const u8) -> i32 {
+    if proof.is_null() || commitment.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    let proof = slice::from_raw_parts(proof, FCMP_NONZERO_PROOF_SIZE);
+    let commitment_bytes = slice::from_raw_parts(commitment, POINT_SIZE);
+    let (c_point, nonce) = match (decode_point(commitment_bytes), decode_point(&proof[..POINT_SIZE])) {
+        (Some(c_point), Some(nonce)) => (c_point, nonce),
+        _ => return FCMP_ERROR_INVALID_POINT,
+    };
+    let (s_w, s_t) = match (
+        decode_scalar(&proof[POINT_SIZE..POINT_SIZE + SCALAR_SIZE]),
+        decode_scalar(&proof[POINT_SIZE + SCALAR_SIZE..]),
+    ) {
+        (Some(s_w), Some(s_t)) => (s_w, s_t),
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let c = nonzero_challenge(&c_point.compress(), &nonce.compress());
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    let check = EdwardsPoint::vartime_multiscalar_mul(
+        [s_w, -s_t, -c],
+        [c_point, h, ED25519_BASEPOINT_POINT],
+    ) - nonce;
+    if check.is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Size of an ElGamal ciphertext: C1 || C2
+pub const FCMP_ELGAMAL_CIPHERTEXT_SIZE: usize = 2 * POINT_SIZE;
+/// Size of a commitment/ElGamal equality proof: A1 || A2 || A3 || s_v || s_b || s_k
+pub const FCMP_ELGAMAL_EQ_PROOF_SIZE: usize = 3 * POINT_SIZE + 3 * SCALAR_SIZE;
+
+/// Decode an ElGamal public key; it must be a prime-order point
+fn decode_elgamal_public(bytes: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::traits::IsIdentity;
+
+    decode_point(bytes).filter(|p| p.is_torsion_free() && !p.is_identity())
+}
+
+/// Challenge binding the statement (C, C1, C2, P) and nonce commitments (A1, A2, A3)
+fn elgamal_eq_challenge(statement: [&[u8]; 4], nonces: [&[u8]; 3]) -> curve25519_dalek::scalar::Scalar {
+    let mut transcript = Transcript::with_domain(b"WATTx_Commit_ElGamal_Eq_v1");
+    transcript.absorb_count(statement.len() + nonces.len());
+    for element in statement.iter().chain(&nonces) {
+        transcript.absorb(element);
+    }
+    transcript.challenge()
+}
+
+/// Encrypt a value with exponential ElGamal: (C1, C2) = (k*G, value*G + k*P).
+///
+/// The value sits on G as in a Pedersen commitment, so the ciphertext can be
+/// tied to a commitment with `fcmp_commit_elgamal_eq_prove`.
+///
+/// # Safety
+/// - `ciphertext_out` must point to at least `FCMP_ELGAMAL_CIPHERTEXT_SIZE` bytes of writable memory
+/// - `value`, `randomness` and `pubkey` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if `value` or `randomness` is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if `pubkey` is not a prime-order point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_elgamal_encrypt(
+    ciphertext_out: *mut u8,
+    value: *const u8,
+    randomness: *const u8,
+    pubkey: *const u8,
+) -> i32 {
+    if ciphertext_out.is_null() || value.is_null() || randomness.is_null() || pubkey.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+    let (mut v, mut k) = match (
+        decode_scalar(slice::from_raw_parts(value, SCALAR_SIZE)),
+        decode_scalar(slice::from_raw_parts(randomness, SCALAR_SIZE)),
+    ) {
+        (Some(v), Some(k)) => (v, k),
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let public = match decode_elgamal_public(slice::from_raw_parts(pubkey, POINT_SIZE)) {
+        Some(p) => p,
+        None => {
+            v.zeroize();
+            k.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+
+    let c1 = (&k * ED25519_BASEPOINT_TABLE).compress();
+    let c2 = (&v * ED25519_BASEPOINT_TABLE + k * public).compress();
+    v.zeroize();
+    k.zeroize();
+
+    ptr::copy_nonoverlapping(c1.as_bytes().as_ptr(), ciphertext_out, POINT_SIZE);
+    ptr::copy_nonoverlapping(c2.as_bytes().as_ptr(), ciphertext_out.add(POINT_SIZE), POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Prove a Pedersen commitment and an ElGamal ciphertext hide the same value.
+///
+/// For C = v*G + b*H and (C1, C2) = (k*G, v*G + k*P), as built by
+/// `fcmp_pedersen_commit` and `fcmp_elgamal_encrypt`, proves knowledge of
+/// (v, b, k) satisfying all three equations at once. A single challenge
+/// covers the three nonce commitments
+///   A1 = t_v*G + t_b*H,  A2 = t_k*G,  A3 = t_v*G + t_k*P
+/// so the same v must open both representations.
+///
+/// # Safety
+/// - `proof_out` must point to at least `FCMP_ELGAMAL_EQ_PROOF_SIZE` bytes of writable memory
+/// - `value`, `ped_blinding`, `elgamal_randomness` and `pubkey` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if a secret is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if `pubkey` is not a prime-order point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commit_elgamal_eq_prove(
+    proof_out: *mut u8,
+    value: *const u8,
+    ped_blinding: *const u8,
+    elgamal_randomness: *const u8,
+    pubkey: *const u8,
+) -> i32 {
+    if proof_out.is_null() || value.is_null() || ped_blinding.is_null() ||
+       elgamal_randomness.is_null() || pubkey.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut witness = match (
+        decode_scalar(slice::from_raw_parts(value, SCALAR_SIZE)),
+        decode_scalar(slice::from_raw_parts(ped_blinding, SCALAR_SIZE)),
+        decode_scalar(slice::from_raw_parts(elgamal_randomness, SCALAR_SIZE)),
+    ) {
+        (Some(v), Some(b), Some(k)) => [v, b, k],
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let public_bytes = slice::from_raw_parts(pubkey, POINT_SIZE);
+    let public = match decode_elgamal_public(public_bytes) {
+        Some(p) => p,
+        None => {
+            witness.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => {
+            witness.zeroize();
+            return FCMP_ERROR_INTERNAL;
+        }
+    };
+
+    let [v, b, k] = witness;
+    let commitment = (&v * ED25519_BASEPOINT_TABLE + b * h).compress();
+    let c1 = (&k * ED25519_BASEPOINT_TABLE).compress();
+    let c2 = (&v * ED25519_BASEPOINT_TABLE + k * public).compress();
+
+    let mut nonces = [(); 3].map(|_| Scalar::random(&mut OsRng));
+    let [t_v, t_b, t_k] = nonces;
+    let a1 = (&t_v * ED25519_BASEPOINT_TABLE + t_b * h).compress();
+    let a2 = (&t_k * ED25519_BASEPOINT_TABLE).compress();
+    let a3 = (&t_v * ED25519_BASEPOINT_TABLE + t_k * public).compress();
+
+    let c = elgamal_eq_challenge(
+        [commitment.as_bytes(), c1.as_bytes(), c2.as_bytes(), public_bytes],
+        [a1.as_bytes(), a2.as_bytes(), a3.as_bytes()],
+    );
+    let responses = [t_v + c * v, t_b + c * b, t_k + c * k];
+    witness.zeroize();
+    nonces.zeroize();
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+
+    let out = slice::from_raw_parts_mut(proof_out, FCMP_ELGAMAL_EQ_PROOF_SIZE);
+    for (chunk, a) in out.chunks_exact_mut(POINT_SIZE).zip([a1, a2, a3]) {
+        chunk.copy_from_slice(a.as_bytes());
+    }
+    for (chunk, s) in out[3 * POINT_SIZE..].chunks_exact_mut(SCALAR_SIZE).zip(responses) {
+        chunk.copy_from_slice(s.as_bytes());
+    }
+    FCMP_SUCCESS
+}
+
+/// Verify a proof from `fcmp_commit_elgamal_eq_prove`.
+///
+/// Checks, with s_v shared between the first and last equation:
+///   s_v*G + s_b*H == A1 + c*C
+///   s_k*G         == A2 + c*C1
+///   s_v*G + s_k*P == A3 + c*C2
+///
+/// # Safety
+/// - `proof` must point to `FCMP_ELGAMAL_EQ_PROOF_SIZE` bytes
+/// - `commitment` and `pubkey` must each point to 32 bytes
+/// - `ciphertext` must point to `FCMP_ELGAMAL_CIPHERTEXT_SIZE` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if both hide the same value
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof is invalid
+/// - `FCMP_ERROR_INVALID_POINT` if a point does not decode or `pubkey` is
+///   not a prime-order point
+/// - `FCMP_ERROR_INVALID_SCALAR` if a response is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commit_elgamal_eq_verify(
+    proof: *const u8,
+    commitment: *const u8,
+    ciphertext: *const u8,
+    pubkey: *const u8,
+) -> i32 {
+    if proof.is_null() || commitment.is_null() || ciphertext.is_null() || pubkey.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    let proof = slice::from_raw_parts(proof, FCMP_ELGAMAL_EQ_PROOF_SIZE);
+    let commitment_bytes = slice::from_raw_parts(commitment, POINT_SIZE);
+    let ciphertext = slice::from_raw_parts(ciphertext, FCMP_ELGAMAL_CIPHERTEXT_SIZE);
+    let public_bytes = slice::from_raw_parts(pubkey, POINT_SIZE);
+
+    let public = match decode_elgamal_public(public_bytes) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+    let points = [
+        commitment_bytes,
+        &ciphertext[..POINT_SIZE],
+        &ciphertext[POINT_SIZE..],
+        &proof[..POINT_SIZE],
+        &proof[POINT_SIZE..2 * POINT_SIZE],
+        &proof[2 * POINT_SIZE..3 * POINT_SIZE],
+    ]
+    .map(decode_point);
+    let [Some(c_point), Some(c1), Some(c2), Some(a1), Some(a2), Some(a3)] = points else {
+        return FCMP_ERROR_INVALID_POINT;
+    };
+    let scalars = proof[3 * POINT_SIZE..]
+        .chunks_exact(SCALAR_SIZE)
+        .map(decode_scalar)
+        .collect::<Option<Vec<_>>>();
+    let (s_v, s_b, s_k) = match scalars.as_deref() {
+        Some(&[s_v, s_b, s_k]) => (s_v, s_b, s_k),
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let c = elgamal_eq_challenge(
+        [commitment_bytes, &ciphertext[..POINT_SIZE], &ciphertext[POINT_SIZE..], public_bytes],
+        [&proof[..POINT_SIZE], &proof[POINT_SIZE..2 * POINT_SIZE], &proof[2 * POINT_SIZE..3 * POINT_SIZE]],
+    );
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    let g = ED25519_BASEPOINT_POINT;
+    let holds = |scalars: &[curve25519_dalek::scalar::Scalar], points: &[EdwardsPoint], nonce: EdwardsPoint| {
+        (EdwardsPoint::vartime_multiscalar_mul(scalars, points) - nonce).is_identity()
+    };
+    if holds(&[s_v, s_b, -c], &[g, h, c_point], a1) &&
+       holds(&[s_k, -c], &[g, c1], a2) &&
+       holds(&[s_v, s_k, -c], &[g, public, c2], a3) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Size of a commitment difference proof: R || s
+pub const FCMP_COMMITMENT_DIFF_PROOF_SIZE: usize = POINT_SIZE + SCALAR_SIZE;
+
+/// Challenge for a commitment difference proof over C_in, C_out, the public
+/// value difference and the nonce commitment R
+fn commitment_diff_challenge(
+    c_in: &[u8],
+    c_out: &[u8],
+    value_diff: u64,
+    nonce: &[u8],
+) -> curve25519_dalek::scalar::Scalar {
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut transcript = Transcript::with_domain(b"WATTx_Commitment_Diff_v1");
+    transcript.absorb_count(3);
+    transcript.absorb(c_in);
+    transcript.absorb(c_out);
+    transcript.absorb(nonce);
+    transcript.absorb_count(1);
+    transcript.absorb(Scalar::from(value_diff).as_bytes());
+    transcript.challenge()
+}
+
+/// Prove C_in - C_out commits to the public `value_diff` without revealing
+/// the blinding difference.
+///
+/// With r = `blinding_diff`, C_in - C_out - value_diff*G = r*H, and the proof
+/// is a Schnorr proof of knowledge of r with respect to H. Layout: R || s,
+/// where R = k*H and s = k + c*r.
+///
+/// # Safety
+/// - `proof_out` must point to at least `FCMP_COMMITMENT_DIFF_PROOF_SIZE` bytes of writable memory
+/// - `c_in` and `c_out` must each point to 32 bytes
+/// - `blinding_diff` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment is not a canonical point
+/// - `FCMP_ERROR_INVALID_SCALAR` if `blinding_diff` is not canonical
+/// - `FCMP_ERROR_PROOF_GENERATION` if the difference does not open to
+///   (`value_diff`, `blinding_diff`)
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_diff_prove(
+    proof_out: *mut u8,
+    c_in: *const u8,
+    c_out: *const u8,
+    value_diff: u64,
+    blinding_diff: *const u8,
+) -> i32 {
+    if proof_out.is_null() || c_in.is_null() || c_out.is_null() || blinding_diff.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    let c_in = slice::from_raw_parts(c_in, POINT_SIZE);
+    let c_out = slice::from_raw_parts(c_out, POINT_SIZE);
+    let (in_point, out_point) = match (decode_point(c_in), decode_point(c_out)) {
+        (Some(in_point), Some(out_point)) => (in_point, out_point),
+        _ => return FCMP_ERROR_INVALID_POINT,
+    };
+    let mut r = match decode_scalar(slice::from_raw_parts(blinding_diff, SCALAR_SIZE)) {
+        Some(r) => r,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => {
+            r.zeroize();
+            return FCMP_ERROR_INTERNAL;
+        }
+    };
+
+    if in_point - out_point != Scalar::from(value_diff) * ED25519_BASEPOINT_POINT + r * h {
+        r.zeroize();
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+
+    let mut k = Scalar::random(&mut OsRng);
+    let nonce = (k * h).compress();
+    let c = commitment_diff_challenge(c_in, c_out, value_diff, nonce.as_bytes());
+    let s = k + c * r;
+    k.zeroize();
+    r.zeroize();
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+
+    let out = slice::from_raw_parts_mut(proof_out, FCMP_COMMITMENT_DIFF_PROOF_SIZE);
+    out[..POINT_SIZE].copy_from_slice(nonce.as_bytes());
+    out[POINT_SIZE..].copy_from_slice(s.as_bytes());
+    FCMP_SUCCESS
+}
+
+/// Verify a proof from `fcmp_commitment_diff_prove`:
+/// s*H == R + c*(C_in - C_out - value_diff*G).
+///
+/// # Safety
+/// - `proof` must point to `FCMP_COMMITMENT_DIFF_PROOF_SIZE` bytes
+/// - `c_in` and `c_out` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if C_in - C_out is proven to commit to `value_diff`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof is invalid
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment or R is not a canonical point
+/// - `FCMP_ERROR_INVALID_SCALAR` if s is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_diff_verify(
+    proof: *const u8,
+    c_in: *const u8,
+    c_out: *const u8,
+    value_diff: u64,
+) -> i32 {
+    if proof.is_null() || c_in.is_null() || c_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    let proof = slice::from_raw_parts(proof, FCMP_COMMITMENT_DIFF_PROOF_SIZE);
+    let c_in = slice::from_raw_parts(c_in, POINT_SIZE);
+    let c_out = slice::from_raw_parts(c_out, POINT_SIZE);
+    let [Some(in_point), Some(out_point), Some(nonce)] = [c_in, c_out, &proof[..POINT_SIZE]].map(decode_point) else {
+        return FCMP_ERROR_INVALID_POINT;
+    };
+    let s = match decode_scalar(&proof[POINT_SIZE..]) {
+        Some(s) => s,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let c = commitment_diff_challenge(c_in, c_out, value_diff, &proof[..POINT_SIZE]);
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    let check = EdwardsPoint::vartime_multiscalar_mul(
+        [s, -c, c, c * Scalar::from(value_diff)],
+        [h, in_point, out_point, ED25519_BASEPOINT_POINT],
+    ) - nonce;
+    if check.is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// 32-byte BLAKE2b-512 digest of a domain tag followed by `parts`
+fn tagged_digest(domain: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    use blake2::{Blake2b512, Digest};
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(domain);
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&hasher.finalize()[..32]);
+    digest
+}
+
+/// Merkle root over commitments in order; see `fcmp_commitments_digest`
+fn commitments_root(commitments: &[u8]) -> [u8; 32] {
+    let count = (commitments.len() / POINT_SIZE) as u64;
+    let mut level: Vec<[u8; 32]> = commitments
+        .chunks_exact(POINT_SIZE)
+        .map(|c| tagged_digest(b"WATTx_Commitments_Leaf_v1", &[c]))
+        .collect();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => tagged_digest(b"WATTx_Commitments_Node_v1", &[left, right]),
+                [odd] => *odd,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    let top = level.first().copied().unwrap_or([0u8; 32]);
+    tagged_digest(b"WATTx_Commitments_Root_v1", &[&count.to_le_bytes(), &top])
+}
+
+/// Compute a Merkle digest over commitments, in order.
+///
+/// Leaves are BLAKE2b("WATTx_Commitments_Leaf_v1" || C_i), paired as
+/// BLAKE2b("WATTx_Commitments_Node_v1" || left || right) with an unpaired
+/// node carried up unchanged, and the root is bound to the count with
+/// BLAKE2b("WATTx_Commitments_Root_v1" || count (u64 LE) || top). Every
+/// hash is BLAKE2b-512 truncated to 32 bytes. Guards a commitment store
+/// against corruption: any reordering or changed commitment alters it.
+///
+/// # Safety
+/// - `commitments` must point to `count * 32` bytes
+/// - `out` must point to a 32-byte buffer
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitments_digest(
+    commitments: *const u8,
+    count: u32,
+    out: *mut u8,
+) -> i32 {
+    if (commitments.is_null() && count > 0) || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let commitments = if count == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(commitments, count as usize * POINT_SIZE)
+    };
+    ptr::copy_nonoverlapping(commitments_root(commitments).as_ptr(), out, 32);
+    FCMP_SUCCESS
+}
+
+/// Check commitments against a digest from `fcmp_commitments_digest`.
+///
+/// # Safety
+/// - `commitments` must point to `count * 32` bytes
+/// - `digest` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the digest matches
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it does not
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitments_verify_digest(
+    commitments: *const u8,
+    count: u32,
+    digest: *const u8,
+) -> i32 {
+    if (commitments.is_null() && count > 0) || digest.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut expected = [0u8; 32];
+    let result = fcmp_commitments_digest(commitments, count, expected.as_mut_ptr());
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    if expected[..] == *slice::from_raw_parts(digest, 32) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Compute the ordering key of a commitment for deterministic set operations.
+///
+/// The key is the commitment's canonical 32-byte compressed encoding, and
+/// keys compare in plain byte-lexicographic order (`memcmp`). Only
+/// canonical encodings are accepted, so every point has exactly one key and
+/// nodes sorting or diffing commitment sets agree on the result.
+///
+/// # Safety
+/// - `commitment` must point to 32 bytes
+/// - `out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment is not a canonical point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_order_key(commitment: *const u8, out: *mut u8) -> i32 {
+    if commitment.is_null() || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match decode_point(slice::from_raw_parts(commitment, POINT_SIZE)) {
+        Some(point) => {
+            ptr::copy_nonoverlapping(point.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+/// Rescale a commitment by a public factor: out = factor * C.
+///
+/// For bridging between denominations without opening the commitment:
+/// `factor * (v*G + b*H)` is `(factor*v)*G + (factor*b)*H`, so the committed
+/// value is multiplied by `factor` and stays hidden. The blinding is scaled
+/// too; `fcmp_scale_blinding` computes the new one. Values are scalars, so a
+/// product past l wraps; callers must keep `factor * v` within their range.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `commitment` and `factor` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment is not a canonical point
+/// - `FCMP_ERROR_INVALID_SCALAR` if `factor` is zero or not fully reduced
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_scale(out: *mut u8, commitment: *const u8, factor: *const u8) -> i32 {
+    if out.is_null() || commitment.is_null() || factor.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let factor = match decode_scalar(slice::from_raw_parts(factor, SCALAR_SIZE)) {
+        Some(k) if k != curve25519_dalek::scalar::Scalar::ZERO => k,
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let point = match decode_point(slice::from_raw_parts(commitment, POINT_SIZE)) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    ptr::copy_nonoverlapping((factor * point).compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Blinding of a commitment rescaled by `fcmp_commitment_scale`: out = factor * blinding
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `blinding` and `factor` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if `factor` is zero or not fully reduced
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_scale_blinding(out: *mut u8, blinding: *const u8, factor: *const u8) -> i32 {
+    if out.is_null() || blinding.is_null() || factor.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let factor = match decode_scalar(slice::from_raw_parts(factor, SCALAR_SIZE)) {
+        Some(k) if k != Scalar::ZERO => k,
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    b_arr.copy_from_slice(slice::from_raw_parts(blinding, SCALAR_SIZE));
+    let mut scaled = factor * Scalar::from_bytes_mod_order(b_arr);
+    b_arr.zeroize();
+
+    ptr::copy_nonoverlapping(scaled.as_bytes().as_ptr(), out, SCALAR_SIZE);
+    scaled.zeroize();
+    FCMP_SUCCESS
+}
+
+/// Opaque running sum of commitments over a sliding window of blocks
+pub struct FcmpCommitmentWindow {
+    sum: curve25519_dalek::edwards::EdwardsPoint,
+}
+
+/// Create an empty commitment window, whose sum is the identity.
+///
+/// A node checking a balance invariant over its last N blocks adds each new
+/// block's commitments and removes those of the block leaving the window,
+/// instead of re-summing the whole window.
+///
+/// # Safety
+/// - `out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with a handle to release with `fcmp_commitment_window_free`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_window_new(out: *mut *mut FcmpCommitmentWindow) -> i32 {
+    use curve25519_dalek::traits::Identity;
+
+    if out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    *out = Box::into_raw(Box::new(FcmpCommitmentWindow {
+        sum: curve25519_dalek::edwards::EdwardsPoint::identity(),
+    }));
+    FCMP_SUCCESS
+}
+
+/// Add a commitment to a window's running sum.
+///
+/// # Safety
+/// - `window` must be a live handle from `fcmp_commitment_window_new`
+/// - `commitment` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment is not a canonical point;
+///   the sum is unchanged
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_window_add(window: *mut FcmpCommitmentWindow, commitment: *const u8) -> i32 {
+    if window.is_null() || commitment.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match decode_point(slice::from_raw_parts(commitment, POINT_SIZE)) {
+        Some(c) => {
+            (*window).sum += c;
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+/// Subtract a commitment from a window's running sum.
+///
+/// The window keeps no record of what was added, so removing a commitment
+/// that was never added is not detected; the sum simply no longer matches
+/// the window's contents.
+///
+/// # Safety
+/// - `window` must be a live handle from `fcmp_commitment_window_new`
+/// - `commitment` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if the commitment is not a canonical point;
+///   the sum is unchanged
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_window_remove(window: *mut FcmpCommitmentWindow, commitment: *const u8) -> i32 {
+    if window.is_null() || commitment.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match decode_point(slice::from_raw_parts(commitment, POINT_SIZE)) {
+        Some(c) => {
+            (*window).sum -= c;
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+/// Get a window's running sum.
+///
+/// Equals `fcmp_outputs_aggregate_commitment` over the commitments
+/// currently in the window.
+///
+/// # Safety
+/// - `window` must be a live handle from `fcmp_commitment_window_new`
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_window_sum(window: *const FcmpCommitmentWindow, out: *mut u8) -> i32 {
+    if window.is_null() || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    ptr::copy_nonoverlapping((*window).sum.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Free a window created by `fcmp_commitment_window_new`.
+///
+/// # Safety
+/// - `window` must be null or a handle from `fcmp_commitment_window_new` not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_commitment_window_free(window: *mut FcmpCommitmentWindow) {
+    if !window.is_null() {
+        drop(Box::from_raw(window));
+    }
+}
+
+// ============================================================================
+// Ristretto Commitments
+// ============================================================================
+
+/// Derive the Ristretto blinding generator H from uniform BLAKE2b-512 output
+fn ristretto_h() -> curve25519_dalek::ristretto::RistrettoPoint {
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::ristretto::RistrettoPoint;
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&Blake2b512::digest(b"WATTx_Pedersen_H_Ristretto_v1"));
+    RistrettoPoint::from_uniform_bytes(&wide)
+}
+
+/// Create a Pedersen commitment over Ristretto: C = value * G + blinding * H
+///
+/// The prime-order Ristretto group has no cofactor, so commitments carry no
+/// torsion component to check for. G is the Ristretto basepoint and H is
+/// derived independently from G. Key images stay on Edwards.
+///
+/// Ristretto and Edwards commitments are NOT interchangeable: the encodings,
+/// generators and group law all differ, so a Ristretto commitment must only
+/// be combined with or checked against other Ristretto commitments.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `value` and `blinding` must each point to 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_pedersen_commit_ristretto(
+    out: *mut u8,
+    value: *const u8,
+    blinding: *const u8,
+) -> i32 {
+    if out.is_null() || value.is_null() || blinding.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut v_arr = [0u8; SCALAR_SIZE];
+    let mut b_arr = [0u8; SCALAR_SIZE];
+    v_arr.copy_from_slice(slice::from_raw_parts(value, SCALAR_SIZE));
+    b_arr.copy_from_slice(slice::from_raw_parts(blinding, SCALAR_SIZE));
+
+    let v = Scalar::from_bytes_mod_order(v_arr);
+    let mut b = Scalar::from_bytes_mod_order(b_arr);
+    v_arr.zeroize();
+    b_arr.zeroize();
+
+    let commitment = &v * RISTRETTO_BASEPOINT_TABLE + b * ristretto_h();
+    b.zeroize();
+
+    ptr::copy_nonoverlapping(commitment.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Add two Ristretto points: out = a + b
+///
+/// # Safety
+/// - All pointers must point to at least 32 bytes
+/// - `out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if either input is not a canonical Ristretto encoding
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_ristretto_add(
+    out: *mut u8,
+    a: *const u8,
+    b: *const u8,
+) -> i32 {
+    if out.is_null() || a.is_null() || b.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::ristretto::CompressedRistretto;
+
+    let decode = |p: *const u8| {
+        CompressedRistretto::from_slice(slice::from_raw_parts(p, POINT_SIZE))
+            .ok()
+            .and_then(|c| c.decompress())
+    };
+
+    match (decode(a), decode(b)) {
+        (Some(a), Some(b)) => {
+            ptr::copy_nonoverlapping((a + b).compress().as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        _ => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+// ============================================================================
+// Field Arithmetic
+// ============================================================================
+
+/// Minimal arithmetic over GF(2^255 - 19), the Ed25519 base field.
+///
+/// curve25519-dalek keeps its field type private, but the FCMP++ tree works
+/// on affine coordinates, so recovering x from a compressed point needs a
+/// square root here. Radix 2^51 with five limbs, following dalek's u64 backend.
+mod field {
+    const MASK: u64 = (1 << 51) - 1;
+
+    #[derive(Clone, Copy)]
+    pub struct Fe([u64; 5]);
+
+    /// Little-endian 256-bit exponent with every byte 0xff except the ends
+    const fn exponent(low: u8, high: u8) -> [u8; 32] {
+        let mut e = [0xffu8; 32];
+        e[0] = low;
+        e[31] = high;
+        e
+    }
+
+    impl Fe {
+        pub const ZERO: Fe = Fe([0; 5]);
+        pub const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+        pub fn from_u64(v: u64) -> Fe {
+            Fe([v & MASK, v >> 51, 0, 0, 0])
+        }
+
+        /// Whether `b` encodes a field element below p, with the top bit clear
+        pub fn is_canonical(b: &[u8; 32]) -> bool {
+            Fe::from_bytes(b).to_bytes() == *b
+        }
+
+        /// Load 255 bits, ignoring the top bit
+        pub fn from_bytes(b: &[u8; 32]) -> Fe {
+            let load8 = |i: usize| {
+                let mut w = [0u8; 8];
+                w.copy_from_slice(&b[i..i + 8]);
+                u64::from_le_bytes(w)
+            };
+            Fe([
+                load8(0) & MASK,
+                (load8(6) >> 3) & MASK,
+                (load8(12) >> 6) & MASK,
+                (load8(19) >> 1) & MASK,
+                (load8(24) >> 12) & MASK,
+            ])
+        }
+
+        fn carry(mut l: [u64; 5]) -> Fe {
+            for i in 0..4 {
+                l[i + 1] += l[i] >> 51;
+                l[i] &= MASK;
+            }
+            l[0] += 19 * (l[4] >> 51);
+            l[4] &= MASK;
+            Fe(l)
+        }
+
+        /// Canonical little-endian encoding
+        pub fn to_bytes(self) -> [u8; 32] {
+            let mut l = Fe::carry(self.0).0;
+
+            // Subtract p if the value is >= p
+            let mut q = (l[0] + 19) >> 51;
+            for limb in &l[1..] {
+                q = (limb + q) >> 51;
+            }
+            l[0] += 19 * q;
+            for i in 0..4 {
+                l[i + 1] += l[i] >> 51;
+                l[i] &= MASK;
+            }
+            l[4] &= MASK;
+
+            let mut out = [0u8; 32];
+            let mut acc: u128 = 0;
+            let mut bits = 0;
+            let mut idx = 0;
+            for limb in l {
+                acc |= (limb as u128) << bits;
+                bits += 51;
+                while bits >= 8 {
+                    out[idx] = acc as u8;
+                    acc >>= 8;
+                    bits -= 8;
+                    idx += 1;
+                }
+            }
+            out[idx] = acc as u8;
+            out
+        }
+
+        pub fn add(&self, rhs: &Fe) -> Fe {
+            let mut l = self.0;
+            for (a, b) in l.iter_mut().zip(rhs.0) {
+                *a += b;
+            }
+            Fe::carry(l)
+        }
+
+        pub fn sub(&self, rhs: &Fe) -> Fe {
+            // Add 16p before subtracting so limbs never underflow
+            const SIXTEEN_P: [u64; 5] = [
+                36028797018963664,
+                36028797018963952,
+                36028797018963952,
+                36028797018963952,
+                36028797018963952,
+            ];
+            let rhs = Fe::carry(rhs.0).0;
+            let mut l = self.0;
+            for i in 0..5 {
+                l[i] = l[i] + SIXTEEN_P[i] - rhs[i];
+            }
+            Fe::carry(l)
+        }
+
+        pub fn neg(&self) -> Fe {
+            Fe::ZERO.sub(self)
+        }
+
+        pub fn mul(&self, rhs: &Fe) -> Fe {
+            let a = self.0.map(|x| x as u128);
+            let b = rhs.0.map(|x| x as u128);
+            let b19 = b.map(|x| x * 19);
+
+            let c0 = a[0] * b[0] + a[4] * b19[1] + a[3] * b19[2] + a[2] * b19[3] + a[1] * b19[4];
+            let mut c1 = a[1] * b[0] + a[0] * b[1] + a[4] * b19[2] + a[3] * b19[3] + a[2] * b19[4];
+            let mut c2 = a[2] * b[0] + a[1] * b[1] + a[0] * b[2] + a[4] * b19[3] + a[3] * b19[4];
+            let mut c3 = a[3] * b[0] + a[2] * b[1] + a[1] * b[2] + a[0] * b[3] + a[4] * b19[4];
+            let mut c4 = a[4] * b[0] + a[3] * b[1] + a[2] * b[2] + a[1] * b[3] + a[0] * b[4];
+
+            c1 += c0 >> 51;
+            c2 += c1 >> 51;
+            c3 += c2 >> 51;
+            c4 += c3 >> 51;
+            let mask = MASK as u128;
+            let mut l = [
+                (c0 & mask) as u64,
+                (c1 & mask) as u64,
+                (c2 & mask) as u64,
+                (c3 & mask) as u64,
+                (c4 & mask) as u64,
+            ];
+            l[0] += 19 * (c4 >> 51) as u64;
+            Fe::carry(l)
+        }
+
+        pub fn square(&self) -> Fe {
+            self.mul(self)
+        }
+
+        /// Raise to a little-endian 256-bit exponent
+        fn pow(&self, exp: &[u8; 32]) -> Fe {
+            let mut result = Fe::ONE;
+            for byte in exp.iter().rev() {
+                for bit in (0..8).rev() {
+                    result = result.square();
+                    if (byte >> bit) & 1 == 1 {
+                        result = result.mul(self);
+                    }
+                }
+            }
+            result
+        }
+
+        /// Multiplicative inverse via Fermat: self^(p - 2)
+        pub fn invert(&self) -> Fe {
+            self.pow(&exponent(0xeb, 0x7f))
+        }
+
+        pub fn ct_eq(&self, rhs: &Fe) -> bool {
+            use subtle::ConstantTimeEq;
+            bool::from(self.to_bytes().ct_eq(&rhs.to_bytes()))
+        }
+
+        pub fn is_negative(&self) -> bool {
+            self.to_bytes()[0] & 1 == 1
+        }
+
+        /// sqrt(-1) = 2^((p - 1) / 4)
+        fn sqrt_m1() -> Fe {
+            Fe::from_u64(2).pow(&exponent(0xfb, 0x1f))
+        }
+
+        /// Square root of u/v if it exists, with the sign left arbitrary
+        pub fn sqrt_ratio(u: &Fe, v: &Fe) -> Option<Fe> {
+            // r = u * v^3 * (u * v^7)^((p - 5) / 8)
+            let v3 = v.square().mul(v);
+            let v7 = v3.square().mul(v);
+            let r = u.mul(&v3).mul(&u.mul(&v7).pow(&exponent(0xfd, 0x0f)));
+
+            let check = v.mul(&r.square());
+            if check.ct_eq(u) {
+                Some(r)
+            } else if check.ct_eq(&u.neg()) {
+                Some(r.mul(&Fe::sqrt_m1()))
+            } else {
+                None
+            }
+        }
+
+        /// Edwards curve constant d = -121665 / 121666
+        pub fn edwards_d() -> Fe {
+            Fe::from_u64(121665).neg().mul(&Fe::from_u64(121666).invert())
+        }
+    }
+}
+
+/// Affine (x, y) coordinates of a point as canonical little-endian field elements
+fn point_to_affine(point: &curve25519_dalek::edwards::EdwardsPoint) -> ([u8; 32], [u8; 32]) {
+    use field::Fe;
+
+    let compressed = point.compress().to_bytes();
+    let sign = compressed[31] >> 7;
+    let y = Fe::from_bytes(&compressed);
+
+    // x^2 = (y^2 - 1) / (d*y^2 + 1); a decompressed point always has a root
+    let y2 = y.square();
+    let u = y2.sub(&Fe::ONE);
+    let v = Fe::edwards_d().mul(&y2).add(&Fe::ONE);
+    let mut x = Fe::sqrt_ratio(&u, &v).unwrap_or(Fe::ZERO);
+    if x.is_negative() != (sign == 1) {
+        x = x.neg();
+    }
+
+    (x.to_bytes(), y.to_bytes())
+}
+
+/// Decode a point from 64 bytes of affine coordinates x || y.
+///
+/// Both coordinates must be canonical field elements of a point on the curve.
+fn point_from_affine(bytes: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    let mut compressed = [0u8; POINT_SIZE];
+    compressed.copy_from_slice(&bytes[POINT_SIZE..]);
+    compressed[31] |= (bytes[0] & 1) << 7;
+
+    let point = decode_point(&compressed)?;
+    let (x, y) = point_to_affine(&point);
+    if x[..] != bytes[..POINT_SIZE] || y[..] != bytes[POINT_SIZE..] {
+        return None;
+    }
+    Some(point)
+}
+
+/// Encode a point as 64 bytes of affine coordinates x || y
+fn point_to_affine_bytes(point: &curve25519_dalek::edwards::EdwardsPoint) -> [u8; 64] {
+    let (x, y) = point_to_affine(point);
+    let mut out = [0u8; 64];
+    out[..POINT_SIZE].copy_from_slice(&x);
+    out[POINT_SIZE..].copy_from_slice(&y);
+    out
+}
+
+/// Validate an input and decode its re-randomized points (O~, I~, R, C~).
+///
+/// Every 32-byte coordinate must be a canonical field element, i.e. below
+/// p = 2^255 - 19; larger encodings are rejected rather than reduced. R feeds
+/// the SA+L linkability equation, so it must also be torsion-free: a small
+/// order component would let R be altered without changing the equation.
+fn validate_input(input: &FcmpInput) -> Result<[curve25519_dalek::edwards::EdwardsPoint; 4], i32> {
+    let fields = [&input.o_tilde, &input.i_tilde, &input.r, &input.c_tilde];
+
+    for field in fields {
+        for coordinate in field.chunks_exact(32) {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(coordinate);
+            if !field::Fe::is_canonical(&bytes) {
+                return Err(FCMP_ERROR_INVALID_POINT);
+            }
+        }
+    }
+
+    let mut points = [curve25519_dalek::edwards::EdwardsPoint::default(); 4];
+    for (point, field) in points.iter_mut().zip(fields) {
+        *point = point_from_affine(field).ok_or(FCMP_ERROR_INVALID_POINT)?;
+    }
+    if !points[2].is_torsion_free() {
+        return Err(FCMP_ERROR_INVALID_POINT);
+    }
+    Ok(points)
+}
+
+// ============================================================================
+// Output Tuples
+// ============================================================================
+
+/// Decompose an output tuple into its field-element representation.
+///
+/// Writes `ELEMENTS_PER_OUTPUT` canonical 32-byte field elements in the order
+/// O.x, O.y, I.x, I.y, C.x, C.y.
+///
+/// # Safety
+/// - `out` must point to at least `ELEMENTS_PER_OUTPUT * 32` bytes of writable memory
+/// - `output` must point to 96 bytes: O || I || C
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if any point is invalid
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_output_to_elements(out: *mut u8, output: *const u8) -> i32 {
+    if out.is_null() || output.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let output_bytes = slice::from_raw_parts(output, OUTPUT_TUPLE_SIZE);
+    let mut elements = [0u8; ELEMENTS_PER_OUTPUT * SCALAR_SIZE];
+    for (i, point_bytes) in output_bytes.chunks_exact(POINT_SIZE).enumerate() {
+        let point = match decode_point(point_bytes) {
+            Some(p) => p,
+            None => return FCMP_ERROR_INVALID_POINT,
+        };
+        let (x, y) = point_to_affine(&point);
+        elements[2 * i * SCALAR_SIZE..(2 * i + 1) * SCALAR_SIZE].copy_from_slice(&x);
+        elements[(2 * i + 1) * SCALAR_SIZE..(2 * i + 2) * SCALAR_SIZE].copy_from_slice(&y);
+    }
+
+    ptr::copy_nonoverlapping(elements.as_ptr(), out, elements.len());
+    FCMP_SUCCESS
+}
+
+/// Hash an output's field elements to its tree-leaf scalar
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `elements` must point to `ELEMENTS_PER_OUTPUT * 32` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_hash_leaf(out: *mut u8, elements: *const u8) -> i32 {
+    if out.is_null() || elements.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"WATTx_FCMP_Leaf_v1");
+    hasher.update(slice::from_raw_parts(elements, ELEMENTS_PER_OUTPUT * SCALAR_SIZE));
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+
+    let leaf = Scalar::from_bytes_mod_order_wide(&wide);
+    ptr::copy_nonoverlapping(leaf.as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Hash an output tuple straight to its tree-leaf scalar.
+///
+/// Equivalent to `fcmp_output_to_elements` followed by `fcmp_hash_leaf`. The
+/// result is the canonical leaf identifier used for branch lookups.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `output` must point to 96 bytes: O || I || C
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_output_leaf_scalar(out: *mut u8, output: *const u8) -> i32 {
+    if out.is_null() || output.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut elements = [0u8; ELEMENTS_PER_OUTPUT * SCALAR_SIZE];
+    let result = fcmp_output_to_elements(elements.as_mut_ptr(), output);
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    fcmp_hash_leaf(out, elements.as_ptr())
+}
+
+/// Derive a deterministic tree index for an output tuple.
+///
+/// The index is the first 16 bytes of H("WATTx_Output_Tree_Index_v1" || output),
+/// read little-endian, modulo `tree_size`. Taking 128 bits before the
+/// reduction keeps the modulo bias below 2^-64 for any tree size.
+///
+/// Distinct outputs collide once the tree holds more than a small fraction
+/// of `tree_size` entries. Callers own collision handling, e.g. by probing
+/// subsequent indices; this function only supplies the starting point, and
+/// both sides must agree on `tree_size`.
+///
+/// # Safety
+/// - `output_tuple` must point to 96 bytes: O || I || C
+/// - `index_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with an index below `tree_size`
+/// - `FCMP_ERROR_INVALID_PARAM` if `tree_size` is 0
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_output_tree_index(
+    output_tuple: *const u8,
+    tree_size: u64,
+    index_out: *mut u64,
+) -> i32 {
+    if output_tuple.is_null() || index_out.is_null() || tree_size == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let output = slice::from_raw_parts(output_tuple, OUTPUT_TUPLE_SIZE);
+    let digest = tagged_digest(b"WATTx_Output_Tree_Index_v1", &[output]);
+    let mut wide = [0u8; 16];
+    wide.copy_from_slice(&digest[..16]);
+    *index_out = (u128::from_le_bytes(wide) % tree_size as u128) as u64;
+    FCMP_SUCCESS
+}
+
+/// Size of the blinders returned by `fcmp_output_rerandomize`: r_o || r_i || r_r || r_c
+pub const FCMP_BLINDERS_SIZE: usize = 4 * SCALAR_SIZE;
+
+/// Derive the re-randomization generators T, U and V
+unsafe fn rerandomization_generators() -> Option<[curve25519_dalek::edwards::EdwardsPoint; 3]> {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let derive = |tag: &[u8]| {
+        let mut out = [0u8; POINT_SIZE];
+        if fcmp_hash_to_point(out.as_mut_ptr(), tag.as_ptr(), tag.len()) != FCMP_SUCCESS {
+            return None;
+        }
+        CompressedEdwardsY(out).decompress()
+    };
+
+    Some([
+        derive(b"WATTx_FCMP_T_v1")?,
+        derive(b"WATTx_FCMP_U_v1")?,
+        derive(b"WATTx_FCMP_V_v1")?,
+    ])
+}
+
+/// Apply the correlated re-randomization to an output (O, I, C):
+///
+/// O~ = O + r_o*T, I~ = I + r_i*U, R = r_i*V + r_r*T, C~ = C + r_c*H
+///
+/// R ties the I blinder to T so the SA+L signature can prove the key image
+/// against I~ without revealing r_i.
+unsafe fn rerandomize_output(
+    output: &[curve25519_dalek::edwards::EdwardsPoint; 3],
+    blinders: &[curve25519_dalek::scalar::Scalar; 4],
+) -> Option<[curve25519_dalek::edwards::EdwardsPoint; 4]> {
+    let [t, u, v] = rerandomization_generators()?;
+    let h = pedersen_h()?;
+    let [o, i, c] = output;
+    let [r_o, r_i, r_r, r_c] = blinders;
+
+    Some([o + r_o * t, i + r_i * u, r_i * v + r_r * t, c + r_c * h])
+}
+
+/// Re-randomize an output tuple into an `FcmpInput`.
+///
+/// The blinders r_o, r_i, r_r and r_c are derived from `seed` and the output
+/// with a PRF and written to `blinders_out`; the prover needs them to sign the
+/// spend. See `rerandomize_output` for how they relate the points.
+///
+/// # Safety
+/// - `input_out` must point to a writable `FcmpInput`
+/// - `blinders_out` must point to at least `FCMP_BLINDERS_SIZE` bytes of writable memory
+/// - `output_tuple` must point to 96 bytes: O || I || C
+/// - `seed` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if any output point is invalid
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_output_rerandomize(
+    input_out: *mut FcmpInput,
+    blinders_out: *mut u8,
+    output_tuple: *const u8,
+    seed: *const u8,
+) -> i32 {
+    if input_out.is_null() || blinders_out.is_null() || output_tuple.is_null() || seed.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let output_bytes = slice::from_raw_parts(output_tuple, OUTPUT_TUPLE_SIZE);
+    let seed = slice::from_raw_parts(seed, SCALAR_SIZE);
+
+    let mut output = [curve25519_dalek::edwards::EdwardsPoint::default(); 3];
+    for (point, bytes) in output.iter_mut().zip(output_bytes.chunks_exact(POINT_SIZE)) {
+        *point = match decode_point(bytes) {
+            Some(p) => p,
+            None => return FCMP_ERROR_INVALID_POINT,
+        };
+    }
+
+    let mut blinders = [0u8, 1, 2, 3].map(|k| {
+        let mut tagged = [0u8; OUTPUT_TUPLE_SIZE + 1];
+        tagged[..OUTPUT_TUPLE_SIZE].copy_from_slice(output_bytes);
+        tagged[OUTPUT_TUPLE_SIZE] = k;
+        prf_scalar(b"WATTx_FCMP_Rerandomize_v1", seed, &tagged)
+    });
+
+    let [o_tilde, i_tilde, r, c_tilde] = match rerandomize_output(&output, &blinders) {
+        Some(points) => points,
+        None => {
+            blinders.zeroize();
+            return FCMP_ERROR_INTERNAL;
+        }
+    };
+
+    ptr::write(
+        input_out,
+        FcmpInput {
+            o_tilde: point_to_affine_bytes(&o_tilde),
+            i_tilde: point_to_affine_bytes(&i_tilde),
+            r: point_to_affine_bytes(&r),
+            c_tilde: point_to_affine_bytes(&c_tilde),
+        },
+    );
+    for (k, blinder) in blinders.iter().enumerate() {
+        ptr::copy_nonoverlapping(blinder.as_bytes().as_ptr(), blinders_out.add(k * SCALAR_SIZE), SCALAR_SIZE);
+    }
+    blinders.zeroize();
+
+    FCMP_SUCCESS
+}
+
+/// Check that an `FcmpInput` is the re-randomization of an output tuple.
+///
+/// This is a spender-side check: the relationship only holds up to the
+/// blinders, and without them an input is unlinkable to its output by
+/// design, so a node cannot perform it from a proof alone. Wallets use it
+/// to catch a mismatched input/output pairing before proving and signing.
+///
+/// # Safety
+/// - `input` must point to a valid `FcmpInput`
+/// - `output_tuple` must point to 96 bytes: O || I || C
+/// - `blinders` must point to `FCMP_BLINDERS_SIZE` bytes: r_o || r_i || r_r || r_c
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if `input` re-randomizes `output_tuple` under `blinders`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it does not
+/// - `FCMP_ERROR_INVALID_POINT` if any output point is invalid
+/// - `FCMP_ERROR_INVALID_SCALAR` if any blinder is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_input_matches_output(
+    input: *const FcmpInput,
+    output_tuple: *const u8,
+    blinders: *const u8,
+) -> i32 {
+    if input.is_null() || output_tuple.is_null() || blinders.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let output_bytes = slice::from_raw_parts(output_tuple, OUTPUT_TUPLE_SIZE);
+    let blinder_bytes = slice::from_raw_parts(blinders, FCMP_BLINDERS_SIZE);
+
+    let mut output = [curve25519_dalek::edwards::EdwardsPoint::default(); 3];
+    for (point, bytes) in output.iter_mut().zip(output_bytes.chunks_exact(POINT_SIZE)) {
+        *point = match decode_point(bytes) {
+            Some(p) => p,
+            None => return FCMP_ERROR_INVALID_POINT,
+        };
+    }
+
+    let mut scalars = [curve25519_dalek::scalar::Scalar::ZERO; 4];
+    for (scalar, bytes) in scalars.iter_mut().zip(blinder_bytes.chunks_exact(SCALAR_SIZE)) {
+        *scalar = match decode_scalar(bytes) {
+            Some(s) => s,
+            None => {
+                scalars.zeroize();
+                return FCMP_ERROR_INVALID_SCALAR;
+            }
+        };
+    }
+
+    let expected = rerandomize_output(&output, &scalars);
+    scalars.zeroize();
+    let [o_tilde, i_tilde, r, c_tilde] = match expected {
+        Some(points) => points,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let input = &*input;
+    let matches = input.o_tilde == point_to_affine_bytes(&o_tilde)
+        && input.i_tilde == point_to_affine_bytes(&i_tilde)
+        && input.r == point_to_affine_bytes(&r)
+        && input.c_tilde == point_to_affine_bytes(&c_tilde);
+
+    if matches {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Compare two inputs in constant time.
+///
+/// Compares the four coordinate fields rather than the struct's memory, so
+/// the result cannot depend on padding, and the time taken does not reveal
+/// where two inputs first differ.
+///
+/// # Safety
+/// - `a` and `b` must point to valid `FcmpInput`s
+///
+/// # Returns
+/// - 1 if every field is equal
+/// - 0 if any differs
+/// - `FCMP_ERROR_INVALID_PARAM` if a pointer is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_input_equal(a: *const FcmpInput, b: *const FcmpInput) -> i32 {
+    if a.is_null() || b.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use subtle::ConstantTimeEq;
+
+    let (a, b) = (&*a, &*b);
+    let equal = a.o_tilde.ct_eq(&b.o_tilde)
+        & a.i_tilde.ct_eq(&b.i_tilde)
+        & a.r.ct_eq(&b.r)
+        & a.c_tilde.ct_eq(&b.c_tilde);
+    equal.unwrap_u8() as i32
+}
+
+/// Size of a serialized `FcmpInput`: O~ || I~ || R || C~, 64 bytes each
+pub const FCMP_INPUT_SERIALIZED_SIZE: usize = 4 * 64;
+
+/// Canonical encoding of an input, as used in transaction serialization
+fn input_bytes(input: &FcmpInput) -> [u8; FCMP_INPUT_SERIALIZED_SIZE] {
+    let mut bytes = [0u8; FCMP_INPUT_SERIALIZED_SIZE];
+    for (chunk, coords) in bytes.chunks_exact_mut(64).zip([&input.o_tilde, &input.i_tilde, &input.r, &input.c_tilde]) {
+        chunk.copy_from_slice(coords);
+    }
+    bytes
+}
+
+/// Get the size of the canonical `FcmpInput` serialization.
+///
+/// The encoding has no padding, so unlike `fcmp_sizeof` it does not depend
+/// on the struct layout of the build.
+///
+/// # Returns
+/// `FCMP_INPUT_SERIALIZED_SIZE`
+#[no_mangle]
+pub extern "C" fn fcmp_input_serialized_size() -> usize {
+    FCMP_INPUT_SERIALIZED_SIZE
+}
+
+/// Serialize an input into its canonical wire form: O~ || I~ || R || C~,
+/// each as 64 bytes of affine coordinates x || y. This is the encoding
+/// `fcmp_transaction_serialize` uses for each input.
+///
+/// # Safety
+/// - `input` must point to a valid `FcmpInput`
+/// - `out` must have at least `out_len` bytes available
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with `FCMP_INPUT_SERIALIZED_SIZE` in `written`
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `written` receives the
+///   size needed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_input_serialize(
+    input: *const FcmpInput,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if input.is_null() || out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    *written = FCMP_INPUT_SERIALIZED_SIZE;
+    if out_len < FCMP_INPUT_SERIALIZED_SIZE {
+        return FCMP_ERROR_MEMORY;
+    }
+    ptr::copy_nonoverlapping(input_bytes(&*input).as_ptr(), out, FCMP_INPUT_SERIALIZED_SIZE);
+    FCMP_SUCCESS
+}
+
+// ============================================================================
+// Stealth Outputs
+// ============================================================================
+
+/// Derive a wallet's view and spend key pairs from a seed.
+///
+/// Each secret is `PRF(seed, counter)` under its own domain
+/// ("WATTx_View_Key_v1", "WATTx_Spend_Key_v1"), so the two keys are
+/// independent: neither reveals the other, and a view key can be handed to
+/// a watch-only wallet without exposing spending. The counter starts at 0
+/// and is only bumped if the PRF output reduces to zero. Publics are s*G.
+///
+/// # Safety
+/// - `seed` must point to `seed_len` bytes
+/// - every output must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if a pointer is null or `seed_len` is 0
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_derive_keys(
+    seed: *const u8,
+    seed_len: usize,
+    view_secret_out: *mut u8,
+    view_public_out: *mut u8,
+    spend_secret_out: *mut u8,
+    spend_public_out: *mut u8,
+) -> i32 {
+    if seed.is_null() || seed_len == 0 || view_secret_out.is_null() || view_public_out.is_null() ||
+       spend_secret_out.is_null() || spend_public_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let seed = slice::from_raw_parts(seed, seed_len);
+    let derive = |domain: &[u8]| {
+        (0..=u8::MAX)
+            .map(|counter| prf_scalar(domain, seed, &[counter]))
+            .find(|s| *s != Scalar::ZERO)
+    };
+
+    let pairs = [
+        (b"WATTx_View_Key_v1".as_slice(), view_secret_out, view_public_out),
+        (b"WATTx_Spend_Key_v1".as_slice(), spend_secret_out, spend_public_out),
+    ];
+    for (domain, secret_out, public_out) in pairs {
+        let mut secret = match derive(domain) {
+            Some(secret) => secret,
+            None => return FCMP_ERROR_INTERNAL,
+        };
+        let public = &secret * ED25519_BASEPOINT_TABLE;
+        ptr::copy_nonoverlapping(secret.as_bytes().as_ptr(), secret_out, SCALAR_SIZE);
+        ptr::copy_nonoverlapping(public.compress().as_bytes().as_ptr(), public_out, POINT_SIZE);
+        secret.zeroize();
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Hs(8*shared || index): the scalar offsetting a recipient's spend key.
+///
+/// `shared` is r*A on the sender side and a*R on the receiver side; the
+/// cofactor is cleared so both sides agree even if a key carries torsion.
+fn output_key_offset(
+    shared: &curve25519_dalek::edwards::EdwardsPoint,
+    index: u32,
+) -> curve25519_dalek::scalar::Scalar {
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"WATTx_Output_Key_v1");
+    hasher.update(shared.mul_by_cofactor().compress().as_bytes());
+    hasher.update(index.to_le_bytes());
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    let offset = Scalar::from_bytes_mod_order_wide(&wide);
+    wide.zeroize();
+    offset
+}
+
+/// Derive the one-time key of a new output: P = Hs(8*r*A || index)*G + B
+///
+/// # Arguments
+/// - `tx_secret_r` - Transaction secret key r (its public key R = r*G is published)
+/// - `recipient_view_A` - Recipient's public view key A
+/// - `recipient_spend_B` - Recipient's public spend key B
+/// - `output_index` - Position of the output in the transaction
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `tx_secret_r`, `recipient_view_A` and `recipient_spend_B` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if r is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if A or B is invalid
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "C" fn fcmp_derive_output_key(
+    out: *mut u8,
+    tx_secret_r: *const u8,
+    recipient_view_A: *const u8,
+    recipient_spend_B: *const u8,
+    output_index: u32,
+) -> i32 {
+    if out.is_null() || tx_secret_r.is_null() || recipient_view_A.is_null() || recipient_spend_B.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+    let mut r = match decode_scalar(slice::from_raw_parts(tx_secret_r, SCALAR_SIZE)) {
+        Some(r) => r,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let view = decode_point(slice::from_raw_parts(recipient_view_A, POINT_SIZE));
+    let spend = decode_point(slice::from_raw_parts(recipient_spend_B, POINT_SIZE));
+    let (view, spend) = match (view, spend) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            r.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+
+    let mut offset = output_key_offset(&(r * view), output_index);
+    r.zeroize();
+
+    let key = (&offset * ED25519_BASEPOINT_TABLE + spend).compress();
+    offset.zeroize();
+
+    ptr::copy_nonoverlapping(key.as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Check whether an output belongs to a wallet, as the receiver side of
+/// `fcmp_derive_output_key`.
+///
+/// Recomputes P' = Hs(8*a*R || index)*G + B and compares it with the
+/// output's key. For an owned output `output_secret_out` receives the
+/// offset Hs(8*a*R || index); the one-time secret is that offset plus the
+/// spend secret b. For other outputs it receives zero. The comparison and
+/// the write do not branch on the match result.
+///
+/// # Safety
+/// - `output_pubkey`, `tx_pubkey_R`, `view_secret_a` and `spend_public_B` must each point to 32 bytes
+/// - `owned_out` must be writable
+/// - `output_secret_out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with 1 (owned) or 0 in `owned_out`
+/// - `FCMP_ERROR_INVALID_SCALAR` if a is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if a point is invalid
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "C" fn fcmp_scan_output(
+    output_pubkey: *const u8,
+    tx_pubkey_R: *const u8,
+    view_secret_a: *const u8,
+    spend_public_B: *const u8,
+    output_index: u32,
+    owned_out: *mut i32,
+    output_secret_out: *mut u8,
+) -> i32 {
+    if output_pubkey.is_null() || tx_pubkey_R.is_null() || view_secret_a.is_null() ||
+       spend_public_B.is_null() || owned_out.is_null() || output_secret_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+    use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+    let mut a = match decode_scalar(slice::from_raw_parts(view_secret_a, SCALAR_SIZE)) {
+        Some(a) => a,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let tx_pubkey = decode_point(slice::from_raw_parts(tx_pubkey_R, POINT_SIZE));
+    let spend = decode_point(slice::from_raw_parts(spend_public_B, POINT_SIZE));
+    let (tx_pubkey, spend) = match (tx_pubkey, spend) {
+        (Some(r), Some(b)) => (r, b),
+        _ => {
+            a.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+
+    let mut offset = output_key_offset(&(a * tx_pubkey), output_index);
+    a.zeroize();
+
+    let expected = (&offset * ED25519_BASEPOINT_TABLE + spend).compress();
+    let owned = expected.as_bytes().ct_eq(slice::from_raw_parts(output_pubkey, POINT_SIZE));
+    let mut secret = Scalar::conditional_select(&Scalar::ZERO, &offset, owned);
+    offset.zeroize();
+
+    ptr::copy_nonoverlapping(secret.as_bytes().as_ptr(), output_secret_out, SCALAR_SIZE);
+    *owned_out = owned.unwrap_u8() as i32;
+    secret.zeroize();
+
+    FCMP_SUCCESS
+}
+
+/// Derive the public keys of a wallet's subaddress (major, minor).
+///
+/// With view secret a and spend public B, the subaddress is
+///
+/// D = B + m*G, C = a*D, m = PRF("WATTx_Subaddress_v1", a, major || minor)
+///
+/// with both indices little-endian. Index (0, 0) is the base address itself,
+/// (B, a*G). Every other index gives an address that cannot be linked to
+/// the base one without the view secret.
+///
+/// # Safety
+/// - `view_secret` and `spend_public` must each point to 32 bytes
+/// - `sub_spend_public_out` and `sub_view_public_out` must each point to at
+///   least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if a is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if B is invalid
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_derive_subaddress(
+    index_major: u32,
+    index_minor: u32,
+    view_secret: *const u8,
+    spend_public: *const u8,
+    sub_spend_public_out: *mut u8,
+    sub_view_public_out: *mut u8,
+) -> i32 {
+    if view_secret.is_null() || spend_public.is_null() ||
+       sub_spend_public_out.is_null() || sub_view_public_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+    let mut a = match decode_scalar(slice::from_raw_parts(view_secret, SCALAR_SIZE)) {
+        Some(a) => a,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let spend = match decode_point(slice::from_raw_parts(spend_public, POINT_SIZE)) {
+        Some(b) => b,
+        None => {
+            a.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+
+    let (sub_spend, sub_view) = if index_major == 0 && index_minor == 0 {
+        (spend, &a * ED25519_BASEPOINT_TABLE)
+    } else {
+        let mut index = [0u8; 8];
+        index[..4].copy_from_slice(&index_major.to_le_bytes());
+        index[4..].copy_from_slice(&index_minor.to_le_bytes());
+        let mut m = prf_scalar(b"WATTx_Subaddress_v1", a.as_bytes(), &index);
+        let sub_spend = spend + &m * ED25519_BASEPOINT_TABLE;
+        m.zeroize();
+        (sub_spend, a * sub_spend)
+    };
+    a.zeroize();
+
+    ptr::copy_nonoverlapping(sub_spend.compress().as_bytes().as_ptr(), sub_spend_public_out, POINT_SIZE);
+    ptr::copy_nonoverlapping(sub_view.compress().as_bytes().as_ptr(), sub_view_public_out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Derive the per-output secret a sender and recipient share for encrypting
+/// output data such as amounts.
+///
+/// H("WATTx_Shared_Secret_v1" || 8*secret*public || index), truncated to 32
+/// bytes. The sender passes its transaction secret r and the recipient's
+/// view key A; the recipient passes its view secret a and the transaction
+/// key R. Both compute 8*r*a*G, so they arrive at the same value.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `secret_scalar` and `public_point` must each point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if the secret is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if the public point is invalid or of small
+///   order, which would make the secret public
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_shared_secret(
+    out: *mut u8,
+    secret_scalar: *const u8,
+    public_point: *const u8,
+    output_index: u32,
+) -> i32 {
+    if out.is_null() || secret_scalar.is_null() || public_point.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut secret = match decode_scalar(slice::from_raw_parts(secret_scalar, SCALAR_SIZE)) {
+        Some(s) => s,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let public = match decode_point(slice::from_raw_parts(public_point, POINT_SIZE)) {
+        Some(p) => p,
+        None => {
+            secret.zeroize();
+            return FCMP_ERROR_INVALID_POINT;
+        }
+    };
+
+    let digest = shared_secret(&(secret * public), output_index);
+    secret.zeroize();
+
+    match digest {
+        Some(mut digest) => {
+            ptr::copy_nonoverlapping(digest.as_ptr(), out, 32);
+            digest.zeroize();
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+/// H("WATTx_Shared_Secret_v1" || 8*shared || index), or None if the cleared
+/// point is the identity; see `fcmp_shared_secret`
+fn shared_secret(shared: &curve25519_dalek::edwards::EdwardsPoint, index: u32) -> Option<[u8; 32]> {
+    use curve25519_dalek::traits::IsIdentity;
+
+    let shared = shared.mul_by_cofactor();
+    if shared.is_identity() {
+        return None;
+    }
+
+    let mut shared_bytes = shared.compress().to_bytes();
+    let digest = tagged_digest(b"WATTx_Shared_Secret_v1", &[&shared_bytes, &index.to_le_bytes()]);
+    shared_bytes.zeroize();
+    Some(digest)
+}
+
+/// Amount mask for a shared secret: the first 8 bytes of
+/// H("WATTx_Amount_Mask_v1" || shared_secret). The tag keeps the mask
+/// independent of blinding factors derived from the same secret.
+fn amount_mask(shared_secret: &[u8]) -> [u8; 8] {
+    let mut digest = tagged_digest(b"WATTx_Amount_Mask_v1", &[shared_secret]);
+    let mut mask = [0u8; 8];
+    mask.copy_from_slice(&digest[..8]);
+    digest.zeroize();
+    mask
+}
+
+/// Encrypt an output amount for its recipient.
+///
+/// XORs the little-endian amount with a mask derived from the secret from
+/// `fcmp_shared_secret`. The encryption is not authenticated; the amount
+/// commitment is what lets the recipient check the result.
+///
+/// # Safety
+/// - `out8` must point to at least 8 bytes of writable memory
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_encrypt_amount(out8: *mut u8, amount: u64, shared_secret: *const u8) -> i32 {
+    if out8.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut mask = amount_mask(slice::from_raw_parts(shared_secret, 32));
+    let mut encrypted = amount.to_le_bytes();
+    for (byte, m) in encrypted.iter_mut().zip(mask) {
+        *byte ^= m;
+    }
+    mask.zeroize();
+
+    ptr::copy_nonoverlapping(encrypted.as_ptr(), out8, 8);
+    FCMP_SUCCESS
+}
+
+/// Decrypt an amount from `fcmp_encrypt_amount`.
+///
+/// A wrong shared secret is not detected: it yields an unrelated amount.
+/// Check the result against the output's commitment.
+///
+/// # Safety
+/// - `amount_out` must be writable
+/// - `enc8` must point to 8 bytes
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_decrypt_amount(amount_out: *mut u64, enc8: *const u8, shared_secret: *const u8) -> i32 {
+    if amount_out.is_null() || enc8.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut mask = amount_mask(slice::from_raw_parts(shared_secret, 32));
+    let mut decrypted = [0u8; 8];
+    ptr::copy_nonoverlapping(enc8, decrypted.as_mut_ptr(), 8);
+    for (byte, m) in decrypted.iter_mut().zip(mask) {
+        *byte ^= m;
+    }
+    mask.zeroize();
+
+    *amount_out = u64::from_le_bytes(decrypted);
+    FCMP_SUCCESS
+}
+
+/// Blinding of an output's amount commitment:
+/// PRF("WATTx_Amount_Blinding_v1", shared_secret, counter), with the counter
+/// only bumped if the output reduces to zero
+fn amount_blinding(shared_secret: &[u8]) -> Option<curve25519_dalek::scalar::Scalar> {
+    use curve25519_dalek::scalar::Scalar;
+
+    (0..=u8::MAX)
+        .map(|counter| prf_scalar(b"WATTx_Amount_Blinding_v1", shared_secret, &[counter]))
+        .find(|b| *b != Scalar::ZERO)
+}
+
+/// Derive the blinding of an output's amount commitment from the secret
+/// from `fcmp_shared_secret`.
+///
+/// The sender commits to the amount with this blinding and the recipient
+/// re-derives it, so the blinding never has to be transmitted. Its domain
+/// differs from the amount mask's, so the encrypted amount reveals nothing
+/// about it.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_derive_amount_blinding(out: *mut u8, shared_secret: *const u8) -> i32 {
+    if out.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match amount_blinding(slice::from_raw_parts(shared_secret, 32)) {
+        Some(mut b) => {
+            ptr::copy_nonoverlapping(b.as_bytes().as_ptr(), out, SCALAR_SIZE);
+            b.zeroize();
+            FCMP_SUCCESS
+        }
+        None => FCMP_ERROR_INTERNAL,
+    }
+}
+
+/// Amount commitment amount*G + b*H an output's sender built, with b from
+/// `amount_blinding`
+unsafe fn output_commitment(amount: u64, shared_secret: &[u8]) -> Result<curve25519_dalek::edwards::CompressedEdwardsY, i32> {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let h = pedersen_h().ok_or(FCMP_ERROR_INTERNAL)?;
+    let mut b = amount_blinding(shared_secret).ok_or(FCMP_ERROR_INTERNAL)?;
+    let commitment = (&Scalar::from(amount) * ED25519_BASEPOINT_TABLE + b * h).compress();
+    b.zeroize();
+    Ok(commitment)
+}
+
+/// Rebuild an output's amount commitment from its amount and the secret
+/// from `fcmp_shared_secret`.
+///
+/// Derives the blinding as `fcmp_derive_amount_blinding` does and commits
+/// amount*G + b*H in one call, so the blinding never leaves the library.
+/// The result is the commitment the sender put on chain.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_reconstruct_commitment(out: *mut u8, amount: u64, shared_secret: *const u8) -> i32 {
+    if out.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match output_commitment(amount, slice::from_raw_parts(shared_secret, 32)) {
+        Ok(commitment) => {
+            ptr::copy_nonoverlapping(commitment.as_bytes().as_ptr(), out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Check that an output's commitment opens to a decrypted amount under the
+/// blinding derived from the shared secret.
+///
+/// Lets a recipient reject outputs whose encrypted amount and commitment
+/// disagree before counting them as received.
+///
+/// # Safety
+/// - `commitment` must point to 32 bytes
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if C == amount*G + b*H
+/// - `FCMP_ERROR_PROOF_VERIFICATION` otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_output_amount(commitment: *const u8, amount: u64, shared_secret: *const u8) -> i32 {
+    if commitment.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use subtle::ConstantTimeEq;
+
+    let expected = match output_commitment(amount, slice::from_raw_parts(shared_secret, 32)) {
+        Ok(expected) => expected,
+        Err(e) => return e,
+    };
+
+    if bool::from(expected.as_bytes().ct_eq(slice::from_raw_parts(commitment, POINT_SIZE))) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// View tag for a shared secret: the first byte of
+/// H("WATTx_View_Tag_v1" || shared_secret)
+fn view_tag(shared_secret: &[u8]) -> u8 {
+    let mut digest = tagged_digest(b"WATTx_View_Tag_v1", &[shared_secret]);
+    let tag = digest[0];
+    digest.zeroize();
+    tag
+}
+
+/// Compute the one-byte view tag for an output.
+///
+/// Senders publish the tag with the output. A scanning wallet recomputes it
+/// from the secret from `fcmp_shared_secret` and skips the output on a
+/// mismatch, avoiding the full derivation for all but about 1 in 256
+/// outputs not addressed to it. The tag leaks 8 bits about the shared
+/// secret, so it must only be used for filtering.
+///
+/// # Safety
+/// - `out` must point to at least 1 byte of writable memory
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_view_tag(out: *mut u8, shared_secret: *const u8) -> i32 {
+    if out.is_null() || shared_secret.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    *out = view_tag(slice::from_raw_parts(shared_secret, 32));
+    FCMP_SUCCESS
+}
+
+/// Check an output's view tag against a shared secret.
+///
+/// A match means the output may be addressed to the wallet and needs the
+/// full scan; a mismatch means it certainly is not.
+///
+/// # Safety
+/// - `shared_secret` must point to 32 bytes
+///
+/// # Returns
+/// - 1 if `tag` matches
+/// - 0 otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_view_tag_matches(shared_secret: *const u8, tag: u8) -> i32 {
+    if shared_secret.is_null() {
+        return 0;
+    }
+
+    (view_tag(slice::from_raw_parts(shared_secret, 32)) == tag) as i32
+}
+
+/// Size of a recipient entry for `fcmp_build_outputs`: A || B
+pub const FCMP_RECIPIENT_SIZE: usize = 2 * POINT_SIZE;
+/// Size of an output written by `fcmp_build_outputs`:
+/// P || C || encrypted amount (8) || view tag (1)
+pub const FCMP_BUILT_OUTPUT_SIZE: usize = 2 * POINT_SIZE + 8 + 1;
+
+/// Build every output of a transaction in one call.
+///
+/// Output i goes to recipient i, given as public view key A and spend key
+/// B, and carries `amounts[i]`. With ss_i = `fcmp_shared_secret(r, A, i)`
+/// it holds:
+/// - the one-time key P = `fcmp_derive_output_key(r, A, B, i)`
+/// - the commitment C = amount*G + b*H, b = `fcmp_derive_amount_blinding(ss_i)`
+/// - the amount encrypted with `fcmp_encrypt_amount` under ss_i
+/// - the view tag `fcmp_view_tag(ss_i)`
+///
+/// so each recipient recovers its output with the single-output functions.
+/// Nothing is written on error.
+///
+/// # Safety
+/// - `tx_secret_r` must point to 32 bytes
+/// - `recipients` must point to `count * FCMP_RECIPIENT_SIZE` bytes
+/// - `amounts` must point to `count` values
+/// - `outputs_out` must point to `count * FCMP_BUILT_OUTPUT_SIZE` bytes of
+///   writable memory
+/// - `recipients`, `amounts` and `outputs_out` may be null if `count` is 0
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if r is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if a recipient key is invalid or its shared
+///   secret is degenerate
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_build_outputs(
+    tx_secret_r: *const u8,
+    recipients: *const u8,
+    amounts: *const u64,
+    count: u32,
+    outputs_out: *mut u8,
+) -> i32 {
+    if tx_secret_r.is_null() ||
+       (count > 0 && (recipients.is_null() || amounts.is_null() || outputs_out.is_null())) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+    let mut r = match decode_scalar(slice::from_raw_parts(tx_secret_r, SCALAR_SIZE)) {
+        Some(r) => r,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+
+    let count = count as usize;
+    let recipients = slice::from_raw_parts(recipients, count * FCMP_RECIPIENT_SIZE);
+    let amounts = slice::from_raw_parts(amounts, count);
+    let mut outputs = vec![0u8; count * FCMP_BUILT_OUTPUT_SIZE];
+
+    let mut result = FCMP_SUCCESS;
+    for (i, ((recipient, &amount), output)) in recipients
+        .chunks_exact(FCMP_RECIPIENT_SIZE)
+        .zip(amounts)
+        .zip(outputs.chunks_exact_mut(FCMP_BUILT_OUTPUT_SIZE))
+        .enumerate()
+    {
+        let (view, spend) = match (decode_point(&recipient[..POINT_SIZE]), decode_point(&recipient[POINT_SIZE..])) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                result = FCMP_ERROR_INVALID_POINT;
+                break;
+            }
+        };
+
+        let shared = r * view;
+        let mut ss = match shared_secret(&shared, i as u32) {
+            Some(ss) => ss,
+            None => {
+                result = FCMP_ERROR_INVALID_POINT;
+                break;
+            }
+        };
+        let mut offset = output_key_offset(&shared, i as u32);
+        let mut b = match amount_blinding(&ss) {
+            Some(b) => b,
+            None => {
+                ss.zeroize();
+                offset.zeroize();
+                result = FCMP_ERROR_INTERNAL;
+                break;
+            }
+        };
+        let mut mask = amount_mask(&ss);
+
+        let key = (&offset * ED25519_BASEPOINT_TABLE + spend).compress();
+        let commitment = (&Scalar::from(amount) * ED25519_BASEPOINT_TABLE + b * h).compress();
+        output[..POINT_SIZE].copy_from_slice(key.as_bytes());
+        output[POINT_SIZE..2 * POINT_SIZE].copy_from_slice(commitment.as_bytes());
+        for ((byte, a), m) in output[2 * POINT_SIZE..2 * POINT_SIZE + 8].iter_mut().zip(amount.to_le_bytes()).zip(mask) {
+            *byte = a ^ m;
+        }
+        output[2 * POINT_SIZE + 8] = view_tag(&ss);
+
+        ss.zeroize();
+        offset.zeroize();
+        b.zeroize();
+        mask.zeroize();
+    }
+    r.zeroize();
+
+    if result == FCMP_SUCCESS {
+        ptr::copy_nonoverlapping(outputs.as_ptr(), outputs_out, outputs.len());
+    }
+    result
+}
+
+// ============================================================================
+// Membership Tree
+// ============================================================================
+
+/// Number of children hashed into each tree node
+pub const FCMP_TREE_WIDTH: usize = 8;
+
+/// Hash a group of up to `FCMP_TREE_WIDTH` nodes at `level` into their parent:
+/// BLAKE2b-512("WATTx_FCMP_Tree_Node_v1" || level || count || children) mod l
+fn tree_node_hash(level: usize, children: &[[u8; SCALAR_SIZE]]) -> [u8; SCALAR_SIZE] {
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"WATTx_FCMP_Tree_Node_v1");
+    hasher.update((level as u32).to_le_bytes());
+    hasher.update((children.len() as u32).to_le_bytes());
+    for child in children {
+        hasher.update(child);
+    }
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    Scalar::from_bytes_mod_order_wide(&wide).to_bytes()
+}
+
+/// Opaque append-only membership tree over leaf scalars.
+///
+/// Every level is cached, so appending only rehashes the groups the new
+/// leaves touch. A tree with one leaf has that leaf as its root; an empty
+/// tree has the all-zero root.
+pub struct FcmpTree {
+    /// `levels[0]` holds the leaves, `levels[k + 1]` the hashes of the
+    /// width-sized groups of `levels[k]`
+    levels: Vec<Vec<[u8; SCALAR_SIZE]>>,
+}
+
+impl FcmpTree {
+    fn root(&self) -> [u8; SCALAR_SIZE] {
+        match self.levels.last() {
+            Some(top) if top.len() == 1 => top[0],
+            _ => EMPTY_TREE_ROOT,
+        }
+    }
+
+    /// Append leaves, recomputing only the parents of changed groups
+    fn append(&mut self, leaves: &[[u8; SCALAR_SIZE]]) {
+        let mut start = self.levels[0].len();
+        self.levels[0].extend_from_slice(leaves);
+
+        let mut level = 0;
+        while self.levels[level].len() > 1 {
+            if self.levels.len() == level + 1 {
+                self.levels.push(Vec::new());
+            }
+
+            let nodes = &self.levels[level];
+            let first_parent = start / FCMP_TREE_WIDTH;
+            let parents: Vec<_> = nodes[first_parent * FCMP_TREE_WIDTH..]
+                .chunks(FCMP_TREE_WIDTH)
+                .map(|group| tree_node_hash(level, group))
+                .collect();
+
+            let next = &mut self.levels[level + 1];
+            next.truncate(first_parent);
+            next.extend(parents);
+            start = first_parent;
+            level += 1;
+        }
+    }
+
+    /// Width groups along the path of `leaf_index`, one per level below the root
+    #[cfg(any(test, feature = "testing"))]
+    fn branch_groups(&self, leaf_index: usize) -> Vec<Vec<u8>> {
+        (0..self.levels.len() - 1)
+            .map(|level| {
+                let group = (leaf_index / FCMP_TREE_WIDTH.pow(level as u32)) / FCMP_TREE_WIDTH;
+                self.levels[level].chunks(FCMP_TREE_WIDTH).nth(group).unwrap_or_default().concat()
+            })
+            .collect()
+    }
+}
+
+/// Create an empty membership tree.
+///
+/// # Returns
+/// - Tree handle to release with `fcmp_tree_free`
+#[no_mangle]
+pub extern "C" fn fcmp_tree_new() -> *mut FcmpTree {
+    Box::into_raw(Box::new(FcmpTree { levels: vec![Vec::new()] }))
+}
+
+/// Append leaves to a tree and report its new root.
+///
+/// Costs O(count * depth) hashes: only the groups containing new leaves,
+/// and their ancestors, are rehashed. With `count` 0 the current root is
+/// reported unchanged.
+///
+/// # Safety
+/// - `tree` must be a live handle from `fcmp_tree_new`
+/// - `new_leaves` must point to `count * 32` bytes of leaf scalars, as from
+///   `fcmp_output_leaf_scalar`
+/// - `new_root_out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_SCALAR` if a leaf is not canonical, in which case
+///   nothing is appended
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_tree_root_after_append(
+    tree: *mut FcmpTree,
+    new_leaves: *const u8,
+    count: usize,
+    new_root_out: *mut u8,
+) -> i32 {
+    if tree.is_null() || (new_leaves.is_null() && count > 0) || new_root_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree = &mut *tree;
+    if count > 0 {
+        let leaves = slice::from_raw_parts(new_leaves, count * SCALAR_SIZE);
+        let mut parsed = Vec::with_capacity(count);
+        for leaf in leaves.chunks_exact(SCALAR_SIZE) {
+            match decode_scalar(leaf) {
+                Some(scalar) => parsed.push(scalar.to_bytes()),
+                None => return FCMP_ERROR_INVALID_SCALAR,
+            }
+        }
+        tree.append(&parsed);
+    }
+
+    ptr::copy_nonoverlapping(tree.root().as_ptr(), new_root_out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Report a tree's leaf count and depth.
+///
+/// The depth is the number of sibling layers in a branch of this tree: 0
+/// for an empty or single-leaf tree, otherwise ceil(log_W(leaf_count)) for
+/// width W = `FCMP_TREE_WIDTH`.
+///
+/// # Safety
+/// - `tree` must be a live handle from `fcmp_tree_new`
+/// - `leaf_count_out` and `depth_out` must be writable
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_tree_stats(
+    tree: *const FcmpTree,
+    leaf_count_out: *mut u64,
+    depth_out: *mut u32,
+) -> i32 {
+    if tree.is_null() || leaf_count_out.is_null() || depth_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree = &*tree;
+    *leaf_count_out = tree.levels[0].len() as u64;
+    *depth_out = (tree.levels.len() - 1) as u32;
+    FCMP_SUCCESS
+}
+
+/// Depth of a tree holding `leaf_count` leaves; see `fcmp_tree_stats`
+fn tree_depth_for_leaves(leaf_count: u64) -> u32 {
+    let mut depth = 0;
+    let mut capacity = 1u64;
+    while capacity < leaf_count {
+        capacity = capacity.saturating_mul(FCMP_TREE_WIDTH as u64);
+        depth += 1;
+    }
+    depth
+}
+
+/// Copy out the leaves appended since the tree held `old_leaf_count`.
+///
+/// Lets a light client that knows an older state of the tree catch up by
+/// appending just the new leaves to its own copy.
+///
+/// # Safety
+/// - `tree` must be a live handle from `fcmp_tree_new`
+/// - `new_leaves_out` must point to `max_leaves * 32` bytes of writable memory
+/// - `count_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the number of leaves written in `count_out`
+/// - `FCMP_ERROR_MEMORY` if more than `max_leaves` leaves were appended;
+///   `count_out` receives the number needed and nothing is written
+/// - `FCMP_ERROR_INVALID_PARAM` if `old_leaf_count` exceeds the leaf count
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_tree_delta(
+    tree: *const FcmpTree,
+    old_leaf_count: u64,
+    new_leaves_out: *mut u8,
+    count_out: *mut usize,
+    max_leaves: usize,
+) -> i32 {
+    if tree.is_null() || count_out.is_null() || (new_leaves_out.is_null() && max_leaves > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree = &*tree;
+    let leaves = &tree.levels[0];
+    let delta = match usize::try_from(old_leaf_count).ok().and_then(|old| leaves.get(old..)) {
+        Some(delta) => delta,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    *count_out = delta.len();
+    if delta.len() > max_leaves {
+        return FCMP_ERROR_MEMORY;
+    }
+    for (i, leaf) in delta.iter().enumerate() {
+        ptr::copy_nonoverlapping(leaf.as_ptr(), new_leaves_out.add(i * SCALAR_SIZE), SCALAR_SIZE);
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Free a tree created by `fcmp_tree_new`.
+///
+/// # Safety
+/// - `tree` must be null or a handle from `fcmp_tree_new` not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_tree_free(tree: *mut FcmpTree) {
+    if !tree.is_null() {
+        drop(Box::from_raw(tree));
+    }
+}
+
+// ============================================================================
+// Branches
+// ============================================================================
+
+/// Borrow a branch's layers, checking that none is empty
+unsafe fn branch_layers<'a>(layers: *const FcmpBranchLayer, num_layers: u32) -> Result<&'a [FcmpBranchLayer], i32> {
+    if num_layers == 0 {
+        return Ok(&[]);
+    }
+    if layers.is_null() {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let layers = slice::from_raw_parts(layers, num_layers as usize);
+    for layer in layers {
+        if layer.elements.is_null() || layer.num_elements == 0 {
+            return Err(FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+    Ok(layers)
+}
+
+/// Recompute a tree root from a leaf and its branch and compare.
+///
+/// Checks only the hash path, without any zero-knowledge layer, to tell tree
+/// bugs apart from proof bugs. Layer j must hold the whole group of up to
+/// `FCMP_TREE_WIDTH` nodes containing the path node, which must sit at
+/// position (leaf_index / W^j) mod W. The group's hash is the path node of
+/// the next layer.
+///
+/// # Safety
+/// - `root` must point to 32 bytes
+/// - `leaf` must point to `leaf_len` bytes: either a 32-byte leaf scalar or a
+///   96-byte output tuple, which is hashed with `fcmp_output_leaf_scalar`
+/// - `branch` must be a valid branch
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the branch leads from the leaf to `root`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it does not
+/// - `FCMP_ERROR_INVALID_PARAM` if `leaf_len` is neither size or a layer is
+///   empty or wider than `FCMP_TREE_WIDTH`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_branch_verify(
+    root: *const u8,
+    leaf: *const u8,
+    leaf_len: usize,
+    branch: *const FcmpBranch,
+) -> i32 {
+    if root.is_null() || leaf.is_null() || branch.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match branch_path_root(leaf, leaf_len, &*branch, (*branch).leaf_index) {
+        Ok(node) if node[..] == *slice::from_raw_parts(root, SCALAR_SIZE) => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// Hash a leaf up `branch` as if it sat at `index`, returning the node the
+/// path ends in, or `FCMP_ERROR_PROOF_VERIFICATION` if some layer does not
+/// hold the path node at the position `index` implies
+unsafe fn branch_path_root(
+    leaf: *const u8,
+    leaf_len: usize,
+    branch: &FcmpBranch,
+    index: u64,
+) -> Result<[u8; SCALAR_SIZE], i32> {
+    let mut node = [0u8; SCALAR_SIZE];
+    match leaf_len {
+        SCALAR_SIZE => node.copy_from_slice(slice::from_raw_parts(leaf, SCALAR_SIZE)),
+        OUTPUT_TUPLE_SIZE => {
+            let result = fcmp_output_leaf_scalar(node.as_mut_ptr(), leaf);
+            if result != FCMP_SUCCESS {
+                return Err(result);
+            }
+        }
+        _ => return Err(FCMP_ERROR_INVALID_PARAM),
+    }
+
+    let layers = branch_layers(branch.layers, branch.num_layers)?;
+    let mut index = index;
+    for (level, layer) in layers.iter().enumerate() {
+        if layer.num_elements as usize > FCMP_TREE_WIDTH {
+            return Err(FCMP_ERROR_INVALID_PARAM);
+        }
+        let group: Vec<[u8; SCALAR_SIZE]> = slice::from_raw_parts(layer.elements, layer.num_elements as usize * SCALAR_SIZE)
+            .chunks_exact(SCALAR_SIZE)
+            .map(|e| e.try_into().unwrap_or([0u8; SCALAR_SIZE]))
+            .collect();
+
+        let position = (index % FCMP_TREE_WIDTH as u64) as usize;
+        if group.get(position) != Some(&node) {
+            return Err(FCMP_ERROR_PROOF_VERIFICATION);
+        }
+        node = tree_node_hash(level, &group);
+        index /= FCMP_TREE_WIDTH as u64;
+    }
+
+    if index == 0 {
+        Ok(node)
+    } else {
+        Err(FCMP_ERROR_PROOF_VERIFICATION)
+    }
+}
+
+/// Check that a branch's path places a leaf at `expected_index`.
+///
+/// The index is read off the path itself: at layer j the path node must sit
+/// at position (expected_index / W^j) mod W of its group, and the index must
+/// fit in the branch's depth. The branch's own `leaf_index` is not
+/// consulted, so a prover claiming one index while supplying the path of
+/// another is caught. No root is needed; pair with `fcmp_branch_verify` to
+/// check the path also reaches the tree root.
+///
+/// # Safety
+/// - `branch` must be a valid branch
+/// - `leaf` must point to `leaf_len` bytes, as for `fcmp_branch_verify`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the path implies `expected_index`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it implies another index
+/// - `FCMP_ERROR_INVALID_PARAM` as `fcmp_branch_verify`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_branch_matches_index(
+    branch: *const FcmpBranch,
+    leaf: *const u8,
+    leaf_len: usize,
+    expected_index: u64,
+) -> i32 {
+    if branch.is_null() || leaf.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match branch_path_root(leaf, leaf_len, &*branch, expected_index) {
+        Ok(_) => FCMP_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+/// Extend a branch with sibling layers added since it was fetched.
+///
+/// When the tree grows deeper between proving and verifying, the old branch
+/// plus the new upper layers form a branch against the deeper root. Every
+/// layer is copied, so `branch_out` owns its data and must be released with
+/// `fcmp_branch_free`.
+///
+/// Only the shape of the result is checked here: each layer must hold 1 to
+/// `FCMP_TREE_WIDTH` elements and the depth may not exceed
+/// `FCMP_MAX_TREE_DEPTH`. Whether the new layers actually connect the old
+/// branch to the deeper root is checked only by `fcmp_branch_verify`
+/// against the new root.
+///
+/// # Safety
+/// - `branch_out` must be writable
+/// - `old_branch` must be a valid branch
+/// - `new_layers` must point to `num_new_layers` valid layers
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if a layer is empty or wider than
+///   `FCMP_TREE_WIDTH`, or the result would be deeper than
+///   `FCMP_MAX_TREE_DEPTH`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_branch_extend(
+    branch_out: *mut FcmpBranch,
+    old_branch: *const FcmpBranch,
+    new_layers: *const FcmpBranchLayer,
+    num_new_layers: u32,
+) -> i32 {
+    if branch_out.is_null() || old_branch.is_null() || new_layers.is_null() || num_new_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let old_branch = &*old_branch;
+    let num_layers = old_branch.num_layers as usize + num_new_layers as usize;
+    if num_layers > FCMP_MAX_TREE_DEPTH as usize {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let old_layers = match branch_layers(old_branch.layers, old_branch.num_layers) {
+        Ok(layers) => layers,
+        Err(e) => return e,
+    };
+    let new_layers = match branch_layers(new_layers, num_new_layers) {
+        Ok(layers) => layers,
+        Err(e) => return e,
+    };
+    if old_layers.iter().chain(new_layers).any(|layer| layer.num_elements as usize > FCMP_TREE_WIDTH) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let layers: Box<[FcmpBranchLayer]> = old_layers
+        .iter()
+        .chain(new_layers)
+        .map(|layer| {
+            let elements: Box<[u8]> = slice::from_raw_parts(
+                layer.elements,
+                layer.num_elements as usize * SCALAR_SIZE,
+            ).into();
+            FcmpBranchLayer {
+                num_elements: layer.num_elements,
+                elements: Box::into_raw(elements) as *const u8,
+            }
+        })
+        .collect();
+
+    *branch_out = FcmpBranch {
+        leaf_index: old_branch.leaf_index,
+        num_layers: num_layers as u32,
+        layers: Box::into_raw(layers) as *const FcmpBranchLayer,
+    };
+    FCMP_SUCCESS
+}
+
+/// Free the layers of a branch produced by `fcmp_branch_extend`.
+///
+/// The branch is reset to zero layers, so freeing it twice is harmless.
+///
+/// # Safety
+/// - `branch` must be null or a branch written by `fcmp_branch_extend`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_branch_free(branch: *mut FcmpBranch) {
+    if branch.is_null() || (*branch).layers.is_null() {
+        return;
+    }
+
+    let branch = &mut *branch;
+    let layers = Box::from_raw(ptr::slice_from_raw_parts_mut(
+        branch.layers as *mut FcmpBranchLayer,
+        branch.num_layers as usize,
+    ));
+    for layer in layers.iter() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            layer.elements as *mut u8,
+            layer.num_elements as usize * SCALAR_SIZE,
+        )));
+    }
+
+    branch.num_layers = 0;
+    branch.layers = ptr::null();
+}
+
+// ============================================================================
+// FCMP Proof Operations (Placeholder)
+// ============================================================================
+
+/// Estimate the proof size for given parameters
+///
+/// # Arguments
+/// - `num_inputs` - Number of inputs being proven
+/// - `num_layers` - Number of tree layers
+///
+/// # Returns
+/// - Estimated proof size in bytes, or 0 on error (including arithmetic
+///   overflow for extreme arguments)
+#[no_mangle]
+pub extern "C" fn fcmp_proof_size(num_inputs: u32, num_layers: u32) -> usize {
+    if num_inputs == 0 || num_layers == 0 {
+        return 0;
+    }
+
+    // Simplified estimate based on FCMP++ paper:
+    // Base: 16 elements for bulletproof components
+    // + IPA rounds: 2 * log2(rows) per curve
+    // + commitments
+    // Roughly: 32 * (16 + 2*log2(n) + inputs*layers) + 64
+
+    let base = 32 * 16;
+    let ipa = 32 * 2 * (u32::BITS - num_layers.max(1).leading_zeros()) as usize;
+    let commits = (num_inputs as usize)
+        .checked_mul(num_layers as usize)
+        .and_then(|n| n.checked_mul(32));
+
+    commits
+        .and_then(|c| c.checked_add(base + ipa + 64))
+        .unwrap_or(0)
+}
+
+/// Check whether `len` is a plausible size for a proof over `num_inputs`
+/// inputs and `num_layers` tree layers.
+///
+/// A cheap prefilter for untrusted sizes, run before allocating or parsing.
+/// `len` is plausible when it lies between the serialized size of
+/// `num_inputs` concatenated proofs and the `fcmp_proof_size` estimate,
+/// whichever order those fall in. Layer counts above `FCMP_MAX_TREE_DEPTH`
+/// are never plausible, since such proofs are rejected anyway.
+///
+/// # Returns
+/// - 1 if `len` is plausible for the parameters
+/// - 0 if it is not, or the parameters are zero or out of range
+#[no_mangle]
+pub extern "C" fn fcmp_proof_size_plausible(len: usize, num_inputs: u32, num_layers: u32) -> i32 {
+    if num_layers > FCMP_MAX_TREE_DEPTH {
+        return 0;
+    }
+
+    let estimate = fcmp_proof_size(num_inputs, num_layers);
+    let exact = (num_inputs as usize).checked_mul(proof_len_for_layers(num_layers as usize));
+    match exact {
+        Some(exact) if estimate > 0 => (estimate.min(exact)..=estimate.max(exact)).contains(&len) as i32,
+        _ => 0,
+    }
+}
+
+/// Abstract verification weight of a transaction's proofs, for fee
+/// calculation.
+///
+/// Counts the same units as `fcmp_verify_limited`: one per point
+/// decompression and one per multiscalar multiplication term. Each
+/// membership proof weighs 5*num_layers + 1. The range proof weighs 6 per
+/// proven bit plus 2 for its fixed G and H terms, with `range_bits` the total
+/// across all its commitments; the per-commitment terms are left out, as they
+/// are bounded by the bit count. Pure integer arithmetic, so every platform
+/// computes the same weight.
+///
+/// # Returns
+/// - The weight, saturating at `u64::MAX`
+#[no_mangle]
+pub extern "C" fn fcmp_proof_weight(num_inputs: u32, num_layers: u32, range_bits: u32) -> u64 {
+    let membership = (num_inputs as u64).saturating_mul(5 * num_layers as u64 + 1);
+    let range = if range_bits == 0 { 0 } else { 6 * range_bits as u64 + 2 };
+    membership.saturating_add(range)
+}
+
+/// Get the range of branch depths `fcmp_prove` and `fcmp_verify` accept.
+///
+/// Proofs declaring more layers are rejected before any curve arithmetic,
+/// so untrusted input cannot force arbitrarily long verifications.
+///
+/// # Safety
+/// - `min_out` and `max_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the inclusive bounds written
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_tree_depth_limits(min_out: *mut u32, max_out: *mut u32) -> i32 {
+    if min_out.is_null() || max_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    *min_out = FCMP_MIN_TREE_DEPTH;
+    *max_out = FCMP_MAX_TREE_DEPTH;
+    FCMP_SUCCESS
+}
+
+/// Serialized proof format version produced by this build. Version 2 added
+/// the generator fingerprint to the header.
+pub const FCMP_PROOF_VERSION: u8 = 2;
+/// Size of the proof header: version (1) || num_layers (1) || tree root (32)
+/// || generators fingerprint (32)
+pub const FCMP_PROOF_HEADER_SIZE: usize = 2 + POINT_SIZE + 32;
+/// Position of the tree root in the proof header
+const PROOF_ROOT: std::ops::Range<usize> = 2..2 + POINT_SIZE;
+/// Position of the generators fingerprint in the proof header
+const PROOF_FINGERPRINT: std::ops::Range<usize> = 2 + POINT_SIZE..FCMP_PROOF_HEADER_SIZE;
+/// Size of each per-layer proof section: A || T || s_a || s_r
+pub const FCMP_PROOF_LAYER_SIZE: usize = 2 * POINT_SIZE + 2 * SCALAR_SIZE;
+
+/// Largest layer count the proof header can encode
+const MAX_PROOF_LAYERS: usize = u8::MAX as usize;
+
+/// Shallowest branch proofs may use: a single-leaf tree needs no layers
+pub const FCMP_MIN_TREE_DEPTH: u32 = 0;
+/// Deepest branch proofs may use. At width 8, 22 layers already address
+/// every `u64` leaf index, so deeper branches only cost verifiers time.
+pub const FCMP_MAX_TREE_DEPTH: u32 = 22;
+
+/// Root of a tree with no leaves; nothing can be proven against it
+const EMPTY_TREE_ROOT: [u8; POINT_SIZE] = [0u8; POINT_SIZE];
+
+/// Exact serialized length of a proof over `num_layers` layers
+fn proof_len_for_layers(num_layers: usize) -> usize {
+    FCMP_PROOF_HEADER_SIZE + num_layers * FCMP_PROOF_LAYER_SIZE
+}
+
+/// Derive the commitment generator for a tree layer: hash_to_point("WATTx_FCMP_Layer_G_v1" || index)
+unsafe fn layer_generator(index: u32) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let mut seed = [0u8; 21 + 4];
+    seed[..21].copy_from_slice(b"WATTx_FCMP_Layer_G_v1");
+    seed[21..].copy_from_slice(&index.to_le_bytes());
+
+    let mut g_out = [0u8; POINT_SIZE];
+    if fcmp_hash_to_point(g_out.as_mut_ptr(), seed.as_ptr(), seed.len()) != FCMP_SUCCESS {
+        return None;
+    }
+
+    CompressedEdwardsY(g_out).decompress()
+}
+
+/// Fiat-Shamir transcript shared by the prover and verifier.
+///
+/// Elements are absorbed as length-prefixed groups: the number of points, the
+/// points in order, the number of scalars, then the scalars in order. The
+/// challenge is the 512-bit BLAKE2b digest reduced mod l, unless the
+/// transcript was created with an externally supplied challenge.
+#[derive(Clone)]
+struct Transcript {
+    hasher: blake2::Blake2b512,
+    external: Option<curve25519_dalek::scalar::Scalar>,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        Transcript::with_domain(b"WATTx_FCMP_Transcript_v1")
+    }
+
+    /// Start a transcript under a protocol-specific domain tag
+    fn with_domain(domain: &[u8]) -> Self {
+        use blake2::Digest;
+
+        let mut hasher = blake2::Blake2b512::new();
+        hasher.update(domain);
+        Transcript { hasher, external: None }
+    }
+
+    /// A transcript whose challenge is `challenge`, whatever is absorbed
+    fn with_external_challenge(challenge: curve25519_dalek::scalar::Scalar) -> Self {
+        Transcript { external: Some(challenge), ..Transcript::with_domain(b"WATTx_FCMP_External_Challenge_v1") }
+    }
+
+    fn absorb_count(&mut self, count: usize) {
+        use blake2::Digest;
+        self.hasher.update((count as u64).to_le_bytes());
+    }
+
+    fn absorb(&mut self, element: &[u8]) {
+        use blake2::Digest;
+        self.hasher.update(element);
+    }
+
+    fn challenge(self) -> curve25519_dalek::scalar::Scalar {
+        use blake2::Digest;
+
+        if let Some(challenge) = self.external {
+            return challenge;
+        }
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&self.hasher.finalize());
+        curve25519_dalek::scalar::Scalar::from_bytes_mod_order_wide(&wide)
+    }
+}
+
+/// Compute the proof challenge over points (A_0, T_0, ..., A_n, T_n), the root
+/// and the generators fingerprint
+fn proof_challenge(proof: &[u8], num_layers: usize) -> curve25519_dalek::scalar::Scalar {
+    proof_challenge_with(Transcript::new(), proof, num_layers)
+}
+
+/// Compute the proof challenge continuing from an already-initialized transcript
+fn proof_challenge_with(
+    mut transcript: Transcript,
+    proof: &[u8],
+    num_layers: usize,
+) -> curve25519_dalek::scalar::Scalar {
+    transcript.absorb_count(2 * num_layers);
+    for j in 0..num_layers {
+        let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE;
+        transcript.absorb(&proof[offset..offset + 2 * POINT_SIZE]);
+    }
+    transcript.absorb_count(2);
+    transcript.absorb(&proof[PROOF_ROOT]);
+    transcript.absorb(&proof[PROOF_FINGERPRINT]);
+    transcript.challenge()
+}
+
+/// Compute a Fiat-Shamir challenge over caller-supplied elements.
+///
+/// Uses the transcript behind `fcmp_prove`, so external provers derive
+/// identical challenges. Absorption order, after the domain tag
+/// "WATTx_FCMP_Transcript_v1":
+///
+/// 1. `num_points` as u64 little-endian, then each 32-byte point in order
+/// 2. `num_scalars` as u64 little-endian, then each 32-byte scalar in order
+///
+/// The challenge is the 64-byte BLAKE2b-512 digest reduced mod l. A proof's
+/// challenge is this function over points (A_0, T_0, A_1, T_1, ...) and two
+/// scalar slots holding the tree root and the generators fingerprint.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `points` must point to `num_points * 32` bytes
+/// - `scalars` must point to `num_scalars * 32` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_challenge(
+    out: *mut u8,
+    points: *const u8,
+    num_points: usize,
+    scalars: *const u8,
+    num_scalars: usize,
+) -> i32 {
+    if out.is_null() || (points.is_null() && num_points > 0) || (scalars.is_null() && num_scalars > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut transcript = Transcript::new();
+    transcript.absorb_count(num_points);
+    if num_points > 0 {
+        transcript.absorb(slice::from_raw_parts(points, num_points * POINT_SIZE));
+    }
+    transcript.absorb_count(num_scalars);
+    if num_scalars > 0 {
+        transcript.absorb(slice::from_raw_parts(scalars, num_scalars * SCALAR_SIZE));
+    }
+
+    ptr::copy_nonoverlapping(transcript.challenge().as_bytes().as_ptr(), out, SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Decompress a point, rejecting non-canonical encodings
+fn decode_point(bytes: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let compressed = CompressedEdwardsY::from_slice(bytes).ok()?;
+    let point = compressed.decompress()?;
+    if point.compress() != compressed {
+        return None;
+    }
+    Some(point)
+}
+
+/// Decode a canonical (fully reduced) scalar
+fn decode_scalar(bytes: &[u8]) -> Option<curve25519_dalek::scalar::Scalar> {
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut arr = [0u8; SCALAR_SIZE];
+    arr.copy_from_slice(bytes);
+    Option::from(Scalar::from_canonical_bytes(arr))
+}
+
+/// A challenge of zero or one lets a prover cancel terms in the verification
+/// equations, so both are rejected on the proving and verifying side alike.
+fn is_degenerate_challenge(c: &curve25519_dalek::scalar::Scalar) -> bool {
+    use curve25519_dalek::scalar::Scalar;
+    use subtle::ConstantTimeEq;
+
+    bool::from(c.ct_eq(&Scalar::ZERO) | c.ct_eq(&Scalar::ONE))
+}
+
+/// Length of the proof at the start of `bytes`, which may run on past it
+fn proof_prefix_len(bytes: &[u8]) -> Result<usize, i32> {
+    if bytes.len() < FCMP_PROOF_HEADER_SIZE || bytes[0] != FCMP_PROOF_VERSION {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let len = proof_len_for_layers(bytes[1] as usize);
+    if bytes[1] as u32 > FCMP_MAX_TREE_DEPTH || bytes.len() < len {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    Ok(len)
+}
+
+/// Check the proof header and return the number of layers it declares
+fn proof_num_layers(proof: &[u8]) -> Result<usize, i32> {
+    if proof.len() < FCMP_PROOF_HEADER_SIZE || proof[0] != FCMP_PROOF_VERSION {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let num_layers = proof[1] as usize;
+    if num_layers > FCMP_MAX_TREE_DEPTH as usize || proof.len() != proof_len_for_layers(num_layers) {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    Ok(num_layers)
+}
+
+/// Decoded per-layer proof section
+struct ProofLayer {
+    a: curve25519_dalek::edwards::EdwardsPoint,
+    t: curve25519_dalek::edwards::EdwardsPoint,
+    s_a: curve25519_dalek::scalar::Scalar,
+    s_r: curve25519_dalek::scalar::Scalar,
+}
+
+/// Decode layer `j` of a proof whose header has already been checked
+fn proof_layer(proof: &[u8], j: usize) -> Result<ProofLayer, i32> {
+    let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE;
+    let section = &proof[offset..offset + FCMP_PROOF_LAYER_SIZE];
+
+    let a = decode_point(&section[..POINT_SIZE]).ok_or(FCMP_ERROR_INVALID_POINT)?;
+    let t = decode_point(&section[POINT_SIZE..2 * POINT_SIZE]).ok_or(FCMP_ERROR_INVALID_POINT)?;
+    let s_a = decode_scalar(&section[2 * POINT_SIZE..2 * POINT_SIZE + SCALAR_SIZE])
+        .ok_or(FCMP_ERROR_INVALID_SCALAR)?;
+    let s_r = decode_scalar(&section[2 * POINT_SIZE + SCALAR_SIZE..])
+        .ok_or(FCMP_ERROR_INVALID_SCALAR)?;
+
+    Ok(ProofLayer { a, t, s_a, s_r })
+}
+
+/// Generate an FCMP proof (placeholder implementation)
+///
+/// For every branch layer the prover commits to a digest of the layer,
+/// `A_j = a_j * G_j + r_j * H`, and proves knowledge of the opening with a
+/// Fiat-Shamir Okamoto proof bound to the tree root. The membership relation
+/// itself is enforced once the full FCMP++ circuit is integrated.
+///
+/// Wire format: version || num_layers || root || generators_fingerprint ||
+/// (A_j || T_j || s_a_j || s_r_j)*
+///
+/// A single-leaf tree is proven with a branch of zero layers, in which case
+/// the root must be the output's leaf scalar (`fcmp_output_leaf_scalar`) and
+/// the proof is the bare header. Only `fcmp_verify_single_leaf`, which is
+/// given the output, accepts such a proof. An empty tree has the all-zero root.
+///
+/// # Safety
+/// - All pointers must be valid
+/// - `proof_out` must have at least `proof_max_len` bytes available
+/// - `proof_len_out` must be writable
+/// - `branch.layers` may be null only if `branch.num_layers` is 0
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_EMPTY_TREE` if `tree_root` is the empty-tree root
+/// - `FCMP_ERROR_PROOF_GENERATION` if a zero-layer branch is given for a
+///   root other than the output's leaf
+/// - Error code on failure
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,  // 96 bytes: O || I || C
+    branch: *const FcmpBranch,
+) -> i32 {
+    prove_with_transcript(proof_out, proof_len_out, proof_max_len, tree_root, output, branch, Transcript::new(), None)
+}
+
+/// Shortest nonce key `fcmp_prove_deterministic` accepts
+pub const FCMP_NONCE_KEY_MIN_SIZE: usize = 32;
+
+/// Generate an FCMP proof with randomness derived from a nonce key.
+///
+/// Same as `fcmp_prove`, but every blinding and nonce is derived with a PRF
+/// keyed by `nonce_key` over the witness, the tree root and the challenge
+/// transcript instead of drawn from the OS RNG. Identical inputs and key
+/// give byte-identical proofs, which verify with `fcmp_verify` as usual.
+///
+/// The nonce key must be secret and uniformly random: anyone who knows it
+/// can recompute the blindings, and with them the committed witness.
+///
+/// # Safety
+/// - `nonce_key` must point to `nonce_key_len` bytes
+/// - Remaining pointers must be valid as for `fcmp_prove`
+///
+/// # Returns
+/// - As `fcmp_prove`
+/// - `FCMP_ERROR_INVALID_PARAM` if `nonce_key_len` is below
+///   `FCMP_NONCE_KEY_MIN_SIZE`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_deterministic(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    nonce_key: *const u8,
+    nonce_key_len: usize,
+) -> i32 {
+    if nonce_key.is_null() || nonce_key_len < FCMP_NONCE_KEY_MIN_SIZE {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let nonce_key = slice::from_raw_parts(nonce_key, nonce_key_len);
+    prove_with_transcript(
+        proof_out,
+        proof_len_out,
+        proof_max_len,
+        tree_root,
+        output,
+        branch,
+        Transcript::new(),
+        Some(nonce_key),
+    )
+}
+
+/// Argument checks `fcmp_prove` makes before any hashing, returning the
+/// branch's layer count
+unsafe fn check_prove_args(
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+) -> Result<usize, i32> {
+    if tree_root.is_null() || output.is_null() || branch.is_null() {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let branch = &*branch;
+    if (branch.layers.is_null() && branch.num_layers > 0) || branch.num_layers > FCMP_MAX_TREE_DEPTH {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    if *slice::from_raw_parts(tree_root, POINT_SIZE) == EMPTY_TREE_ROOT {
+        return Err(FCMP_ERROR_EMPTY_TREE);
+    }
+
+    Ok(branch.num_layers as usize)
+}
+
+/// Check `fcmp_prove` arguments without generating a proof.
+///
+/// Runs the null, size and branch-shape checks `fcmp_prove` makes and
+/// returns the code it would fail with, doing no cryptographic work. Bindings
+/// can surface precise errors before committing to a proof. For a
+/// zero-layer branch, `fcmp_prove` also requires the root to be the output's
+/// leaf; that check hashes the output and is not made here.
+///
+/// # Safety
+/// - `tree_root` must be null or point to 32 bytes
+/// - `branch` must be null or point to a valid `FcmpBranch`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if `fcmp_prove` would accept the arguments
+/// - `FCMP_ERROR_INVALID_PARAM` for a null pointer or a malformed branch
+/// - `FCMP_ERROR_EMPTY_TREE` if `tree_root` is the empty-tree root
+/// - `FCMP_ERROR_MEMORY` if `proof_max_len` cannot hold the proof
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_validate_prove_args(
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+) -> i32 {
+    let num_layers = match check_prove_args(tree_root, output, branch) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    if proof_max_len < proof_len_for_layers(num_layers) {
+        return FCMP_ERROR_MEMORY;
+    }
+    if !params_initialized() {
+        return FCMP_ERROR_NOT_INITIALIZED;
+    }
+
+    FCMP_SUCCESS
+}
+
+/// How many more bytes a proof buffer needs for `fcmp_prove` to succeed.
+///
+/// After `fcmp_prove` fails with `FCMP_ERROR_MEMORY`, callers can grow the
+/// buffer by exactly the reported amount instead of retrying blindly. Zero
+/// means `proof_max_len` already suffices.
+///
+/// # Safety
+/// - `tree_root`, `output` and `branch` must be valid as for `fcmp_prove`
+/// - `extra_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the missing byte count in `extra_out`
+/// - `FCMP_ERROR_INVALID_PARAM` or `FCMP_ERROR_EMPTY_TREE` as `fcmp_prove`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_required_extra(
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    extra_out: *mut usize,
+) -> i32 {
+    if extra_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match check_prove_args(tree_root, output, branch) {
+        Ok(num_layers) => {
+            *extra_out = proof_len_for_layers(num_layers).saturating_sub(proof_max_len);
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// `fcmp_prove` with the challenge derived from `transcript`, and blindings
+/// and nonces derived from `nonce_key` when one is given
+#[allow(clippy::too_many_arguments)]
+unsafe fn prove_with_transcript(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    transcript: Transcript,
+    nonce_key: Option<&[u8]>,
+) -> i32 {
+    if proof_out.is_null() || proof_len_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let num_layers = match check_prove_args(tree_root, output, branch) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    let branch_ref = &*branch;
+    let root_bytes = slice::from_raw_parts(tree_root, POINT_SIZE);
+
+    // With no sibling layers the output is the whole tree: root == leaf
+    if num_layers == 0 {
+        let mut leaf = [0u8; SCALAR_SIZE];
+        let ret = fcmp_output_leaf_scalar(leaf.as_mut_ptr(), output);
+        if ret != FCMP_SUCCESS {
+            return ret;
+        }
+        if leaf[..] != *root_bytes {
+            return FCMP_ERROR_PROOF_GENERATION;
+        }
+    }
+
+    let proof_len = proof_len_for_layers(num_layers);
+    if proof_max_len < proof_len {
+        return FCMP_ERROR_MEMORY;
+    }
+
+    let params = match shared_params() {
+        Ok(params) => params,
+        Err(e) => return e,
+    };
+
+    use blake2::{Blake2b512, Digest};
+    use curve25519_dalek::scalar::Scalar;
+
+    let h = params.h;
+    let out = slice::from_raw_parts_mut(proof_out, proof_len);
+    out[0] = FCMP_PROOF_VERSION;
+    out[1] = num_layers as u8;
+    out[PROOF_ROOT].copy_from_slice(root_bytes);
+    out[PROOF_FINGERPRINT].copy_from_slice(&params.fingerprint());
+
+    let output_bytes = slice::from_raw_parts(output, OUTPUT_TUPLE_SIZE);
+    let layers = if num_layers == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(branch_ref.layers, num_layers)
+    };
+
+    // Layer witnesses a_j
+    let mut witnesses: Vec<Scalar> = Vec::with_capacity(num_layers);
+    for (j, layer) in layers.iter().enumerate() {
+        let mut hasher = Blake2b512::new();
+        hasher.update(b"WATTx_FCMP_Layer_v1");
+        hasher.update(output_bytes);
+        hasher.update((j as u32).to_le_bytes());
+        if !layer.elements.is_null() && layer.num_elements > 0 {
+            hasher.update(slice::from_raw_parts(
+                layer.elements,
+                layer.num_elements as usize * SCALAR_SIZE,
+            ));
+        }
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&hasher.finalize());
+        witnesses.push(Scalar::from_bytes_mod_order_wide(&wide));
+        wide.zeroize();
+    }
+
+    // Deterministic randomness is seeded from everything the challenge
+    // depends on, so a nonce is never reused under a different challenge
+    let mut nonce_seed = nonce_key.map(|_| {
+        let mut seed = transcript.clone();
+        seed.absorb(b"WATTx_FCMP_Nonce_Seed_v1");
+        seed.absorb(root_bytes);
+        seed.absorb(output_bytes);
+        for a_j in &witnesses {
+            seed.absorb(a_j.as_bytes());
+        }
+        seed.challenge().to_bytes()
+    });
+    let randomness = |j: usize, label: u8| match (nonce_key, &nonce_seed) {
+        (Some(key), Some(seed)) => {
+            let mut input = [0u8; 37];
+            input[..32].copy_from_slice(seed);
+            input[32..36].copy_from_slice(&(j as u32).to_le_bytes());
+            input[36] = label;
+            let scalar = prf_scalar(b"WATTx_FCMP_Prove_Nonce_v1", key, &input);
+            input.zeroize();
+            scalar
+        }
+        _ => Scalar::random(&mut OsRng),
+    };
+
+    // Witness (a_j, r_j) and nonces (k_a, k_r) per layer
+    let mut secrets: Vec<[Scalar; 4]> = Vec::with_capacity(num_layers);
+    for (j, &a_j) in witnesses.iter().enumerate() {
+        let g_j = params.layer_generators[j];
+
+        let r_j = randomness(j, 0);
+        let k_a = randomness(j, 1);
+        let k_r = randomness(j, 2);
+
+        let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE;
+        let commitment = a_j * g_j + r_j * h;
+        let nonce_commitment = k_a * g_j + k_r * h;
+        out[offset..offset + POINT_SIZE].copy_from_slice(commitment.compress().as_bytes());
+        out[offset + POINT_SIZE..offset + 2 * POINT_SIZE]
+            .copy_from_slice(nonce_commitment.compress().as_bytes());
+
+        secrets.push([a_j, r_j, k_a, k_r]);
+    }
+    witnesses.zeroize();
+    nonce_seed.zeroize();
+
+    let c = proof_challenge_with(transcript, out, num_layers);
+    if is_degenerate_challenge(&c) {
+        secrets.zeroize();
+        return FCMP_ERROR_PROOF_GENERATION;
+    }
+
+    for (j, secret) in secrets.iter().enumerate() {
+        let [a_j, r_j, k_a, k_r] = secret;
+        let s_a = k_a + c * a_j;
+        let s_r = k_r + c * r_j;
+
+        let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE + 2 * POINT_SIZE;
+        out[offset..offset + SCALAR_SIZE].copy_from_slice(s_a.as_bytes());
+        out[offset + SCALAR_SIZE..offset + 2 * SCALAR_SIZE].copy_from_slice(s_r.as_bytes());
+    }
+    secrets.zeroize();
+
+    *proof_len_out = proof_len;
+    FCMP_SUCCESS
+}
+
+/// Verify an FCMP proof (placeholder implementation)
+///
+/// Checks all layers with one batched multiscalar multiplication. A proof
+/// with zero layers is rejected: it is a bare header over a root the prover
+/// chose, so it only means something next to the output, which
+/// `fcmp_verify_single_leaf` takes. Use `fcmp_verify_inplace` where heap
+/// allocation is not acceptable, or an `FcmpVerifier` to amortize setup
+/// across many proofs.
+///
+/// `input` is only checked for null: proofs from `fcmp_prove` are bound to
+/// the tree root, not to the input they are spent as, so the same proof
+/// verifies next to any input. Spends pair proofs with inputs through
+/// `fcmp_prove_for_input` and `fcmp_verify_for_input`, as
+/// `fcmp_verify_transaction` does.
+///
+/// # Safety
+/// - All pointers must be valid
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if proof is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if proof is invalid
+/// - `FCMP_ERROR_GENERATOR_MISMATCH` if it was made under other generators
+/// - Other error codes on failure
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    with_params(|params| {
+        verify_proof_batched(tree_root, proof_bytes, Transcript::new(), params, None)
+    })
+    .unwrap_or_else(|e| e)
+}
+
+/// Verify the zero-layer proof of a single-leaf tree.
+///
+/// Such a proof carries no equations, only the header, so it is accepted
+/// only when `tree_root` is the leaf scalar of `output`
+/// (`fcmp_output_leaf_scalar`). The verifier must therefore know which
+/// output is spent; a one-leaf tree hides nothing anyway.
+///
+/// # Safety
+/// - `tree_root` must point to 32 bytes
+/// - `output` must point to 96 bytes: O || I || C
+/// - `proof` must point to `proof_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the proof is valid for this output
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the root is not the output's leaf
+/// - `FCMP_ERROR_GENERATOR_MISMATCH` if it was made under other generators
+/// - `FCMP_ERROR_INVALID_PARAM` if the proof is malformed or has layers
+/// - `FCMP_ERROR_EMPTY_TREE` if `tree_root` is the empty-tree root
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_single_leaf(
+    tree_root: *const u8,
+    output: *const u8,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if tree_root.is_null() || output.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    match proof_num_layers(proof_bytes) {
+        Ok(0) => {}
+        Ok(_) => return FCMP_ERROR_INVALID_PARAM,
+        Err(e) => return e,
+    }
+    if *tree_root == EMPTY_TREE_ROOT {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+
+    let fingerprint = match with_params(FcmpParams::fingerprint) {
+        Ok(fingerprint) => fingerprint,
+        Err(e) => return e,
+    };
+    if proof_bytes[PROOF_FINGERPRINT] != fingerprint {
+        return FCMP_ERROR_GENERATOR_MISMATCH;
+    }
+
+    let mut leaf = [0u8; SCALAR_SIZE];
+    let ret = fcmp_output_leaf_scalar(leaf.as_mut_ptr(), output);
+    if ret != FCMP_SUCCESS {
+        return ret;
+    }
+    if proof_bytes[PROOF_ROOT] != *tree_root || leaf[..] != *tree_root {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+    FCMP_SUCCESS
+}
+
+/// Verify an FCMP proof and return the verdict as an all-or-nothing mask.
+///
+/// Complements `fcmp_verify` for constant-time consumers, which can feed the
+/// mask straight into a branch-free selection. The verdict itself comes from
+/// `fcmp_verify`; only its conversion to a mask is free of data-dependent
+/// branches. Callers needing the reason for a failure use `fcmp_verify`.
+///
+/// # Safety
+/// - Pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - `0xFF` if the proof is valid
+/// - `0x00` on any failure
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_ct(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> u8 {
+    use subtle::ConstantTimeEq;
+
+    let result = fcmp_verify(tree_root, input, proof, proof_len);
+    0u8.wrapping_sub(result.ct_eq(&FCMP_SUCCESS).unwrap_u8())
+}
+
+/// Transcript for proofs bound to `epoch`
+fn epoch_transcript(epoch: u64) -> Transcript {
+    let mut transcript = Transcript::with_domain(b"WATTx_FCMP_Epoch_Transcript_v1");
+    transcript.absorb(&epoch.to_le_bytes());
+    transcript
+}
+
+/// Generate an FCMP proof bound to an epoch, such as a block height.
+///
+/// Same as `fcmp_prove`, except the epoch is absorbed into the challenge
+/// under its own transcript domain. The proof then only verifies with
+/// `fcmp_verify_epoch` for the same epoch, and never with `fcmp_verify`.
+///
+/// A zero-layer branch is rejected: its proof has no challenge, so nothing
+/// could bind it to the epoch.
+///
+/// # Safety
+/// - Pointers must be valid as for `fcmp_prove`
+///
+/// # Returns
+/// - As `fcmp_prove`
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer branch
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_epoch(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    epoch: u64,
+) -> i32 {
+    if branch.is_null() || (*branch).num_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    prove_with_transcript(proof_out, proof_len_out, proof_max_len, tree_root, output, branch, epoch_transcript(epoch), None)
+}
+
+/// Verify a proof from `fcmp_prove_epoch` against the epoch it must be
+/// bound to.
+///
+/// # Safety
+/// - Pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - As `fcmp_verify`; a proof made for another epoch gives
+///   `FCMP_ERROR_PROOF_VERIFICATION`
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer proof
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_epoch(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    epoch: u64,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    if proof_num_layers(proof_bytes) == Ok(0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    with_params(|params| {
+        verify_proof_batched(tree_root, proof_bytes, epoch_transcript(epoch), params, None)
+    })
+    .unwrap_or_else(|e| e)
+}
+
+/// Transcript for proofs bound to the re-randomized input they are spent as
+fn input_transcript(input: &FcmpInput) -> Transcript {
+    let mut transcript = Transcript::with_domain(b"WATTx_FCMP_Input_Transcript_v1");
+    transcript.absorb_count(4);
+    for point in [&input.o_tilde, &input.i_tilde, &input.r, &input.c_tilde] {
+        transcript.absorb(point);
+    }
+    transcript
+}
+
+/// Generate an FCMP proof bound to the input it is spent as.
+///
+/// Same as `fcmp_prove`, except O~, I~, R and C~ of `input` are absorbed
+/// into the challenge under their own transcript domain. The proof then
+/// only verifies next to that input, so it cannot be lifted into another
+/// transaction. `fcmp_verify_transaction` requires proofs made this way.
+///
+/// A zero-layer branch is rejected: its proof has no challenge, so nothing
+/// could bind it to the input.
+///
+/// # Safety
+/// - Pointers must be valid as for `fcmp_prove`
+/// - `input` must point to a valid `FcmpInput`
+///
+/// # Returns
+/// - As `fcmp_prove`
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer branch
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_for_input(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    input: *const FcmpInput,
+) -> i32 {
+    if input.is_null() || branch.is_null() || (*branch).num_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let transcript = input_transcript(&*input);
+    prove_with_transcript(proof_out, proof_len_out, proof_max_len, tree_root, output, branch, transcript, None)
+}
+
+/// Verify a proof from `fcmp_prove_for_input` next to the input it must be
+/// bound to.
+///
+/// # Safety
+/// - Pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - As `fcmp_verify`; a proof made for another input gives
+///   `FCMP_ERROR_PROOF_VERIFICATION`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_for_input(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    with_params(|params| {
+        verify_proof_batched(tree_root, proof_bytes, input_transcript(&*input), params, None)
+    })
+    .unwrap_or_else(|e| e)
+}
+
+/// Generate an FCMP proof answering a challenge from an external source,
+/// such as a randomness beacon, instead of a Fiat-Shamir hash.
+///
+/// Soundness rests on the source: the challenge must be unpredictable until
+/// the layer commitments are fixed. The proof only verifies with
+/// `fcmp_verify_external_challenge` and the same challenge. A zero-layer
+/// branch is rejected, since its proof answers no challenge.
+///
+/// # Safety
+/// - `challenge` must point to a 32-byte scalar
+/// - Remaining pointers must be valid as for `fcmp_prove`
+///
+/// # Returns
+/// - As `fcmp_prove`
+/// - `FCMP_ERROR_INVALID_SCALAR` if `challenge` is not canonical
+/// - `FCMP_ERROR_PROOF_GENERATION` if `challenge` is zero or one
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer branch
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_external_challenge(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    challenge: *const u8,
+) -> i32 {
+    if challenge.is_null() || branch.is_null() || (*branch).num_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let challenge = match decode_scalar(slice::from_raw_parts(challenge, SCALAR_SIZE)) {
+        Some(c) => c,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+
+    let transcript = Transcript::with_external_challenge(challenge);
+    prove_with_transcript(proof_out, proof_len_out, proof_max_len, tree_root, output, branch, transcript, None)
+}
+
+/// Verify a proof from `fcmp_prove_external_challenge` against the
+/// challenge it must answer.
+///
+/// # Safety
+/// - `challenge` must point to a 32-byte scalar
+/// - Remaining pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - As `fcmp_verify`; a proof answering another challenge, or a zero or
+///   one challenge, gives `FCMP_ERROR_PROOF_VERIFICATION`
+/// - `FCMP_ERROR_INVALID_SCALAR` if `challenge` is not canonical
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer proof
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_external_challenge(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    challenge: *const u8,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() || challenge.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let challenge = match decode_scalar(slice::from_raw_parts(challenge, SCALAR_SIZE)) {
+        Some(c) => c,
+        None => return FCMP_ERROR_INVALID_SCALAR,
+    };
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    if proof_num_layers(proof_bytes) == Ok(0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    with_params(|params| {
+        verify_proof_batched(tree_root, proof_bytes, Transcript::with_external_challenge(challenge), params, None)
+    })
+    .unwrap_or_else(|e| e)
+}
+
+/// Reader callback for `fcmp_verify_stream`: fills up to `len` bytes of
+/// `buf` and returns how many it wrote, 0 at end of stream, or a negative
+/// value on a read error
+pub type FcmpReadCallback = Option<unsafe extern "C" fn(buf: *mut u8, len: usize, ctx: *mut c_void) -> isize>;
+
+/// Read from `read_cb` until `buf` is full or the stream ends, returning
+/// how many bytes were read
+unsafe fn stream_read(
+    read_cb: unsafe extern "C" fn(*mut u8, usize, *mut c_void) -> isize,
+    ctx: *mut c_void,
+    buf: &mut [u8],
+) -> Result<usize, i32> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let remaining = buf.len() - filled;
+        match read_cb(buf[filled..].as_mut_ptr(), remaining, ctx) {
+            0 => break,
+            n if n < 0 || n as usize > remaining => return Err(FCMP_ERROR_INTERNAL),
+            n => filled += n as usize,
+        }
+    }
+    Ok(filled)
+}
+
+/// Verify a stream of proofs against one tree root with bounded memory.
+///
+/// Each proof is read from `read_cb` as a u32 little-endian length followed
+/// by that many proof bytes, and verified as by `fcmp_verify` before the
+/// next is read. Only one proof of at most the `FCMP_MAX_TREE_DEPTH` size is
+/// ever buffered. Verification stops at the end of the stream, which must
+/// fall on a length prefix, or at the first proof that fails.
+///
+/// # Safety
+/// - `read_cb` must behave as described for `FcmpReadCallback`; `ctx` is
+///   passed to it unchanged. A null callback is rejected
+/// - `tree_root` must point to 32 bytes
+/// - `failed_index_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every proof in the stream verifies
+/// - The failing proof's error code, with its zero-based index in
+///   `failed_index_out`; a truncated or oversized proof gives
+///   `FCMP_ERROR_INVALID_PARAM`
+/// - `FCMP_ERROR_INTERNAL` with the index if the callback reports a read error
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_stream(
+    read_cb: FcmpReadCallback,
+    ctx: *mut c_void,
+    tree_root: *const u8,
+    failed_index_out: *mut u32,
+) -> i32 {
+    let read_cb = match read_cb {
+        Some(read_cb) if !tree_root.is_null() && !failed_index_out.is_null() => read_cb,
+        _ => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let mut buf = vec![0u8; proof_len_for_layers(FCMP_MAX_TREE_DEPTH as usize)];
+    let verify_next = |params: &FcmpParams, buf: &mut [u8]| -> Option<i32> {
+        let mut len_bytes = [0u8; 4];
+        let len = match stream_read(read_cb, ctx, &mut len_bytes) {
+            Ok(0) => return None,
+            Ok(4) => u32::from_le_bytes(len_bytes) as usize,
+            Ok(_) => return Some(FCMP_ERROR_INVALID_PARAM),
+            Err(e) => return Some(e),
+        };
+        if len > buf.len() {
+            return Some(FCMP_ERROR_INVALID_PARAM);
+        }
+        Some(match stream_read(read_cb, ctx, &mut buf[..len]) {
+            Ok(n) if n == len => verify_proof_batched(tree_root, &buf[..len], Transcript::new(), params, None),
+            Ok(_) => FCMP_ERROR_INVALID_PARAM,
+            Err(e) => e,
+        })
+    };
+
+    with_params(|params| {
+        let mut index = 0u32;
+        while let Some(result) = verify_next(params, &mut buf) {
+            if result != FCMP_SUCCESS {
+                *failed_index_out = index;
+                return result;
+            }
+            index += 1;
+        }
+        FCMP_SUCCESS
+    })
+    .unwrap_or_else(|e| e)
+}
+
+/// Root a proof is made against once `meta` is bound to the tree root:
+/// Hs(root || meta) = PRF("WATTx_FCMP_Root_Meta_v1", root, meta)
+fn root_with_meta(root: &[u8], meta: &[u8]) -> [u8; SCALAR_SIZE] {
+    prf_scalar(b"WATTx_FCMP_Root_Meta_v1", root, meta).to_bytes()
+}
+
+/// Borrow a caller's metadata, which may be empty
+unsafe fn meta_slice<'a>(meta: *const u8, meta_len: usize) -> &'a [u8] {
+    if meta_len > 0 {
+        slice::from_raw_parts(meta, meta_len)
+    } else {
+        &[]
+    }
+}
+
+/// Generate an FCMP proof against a tree root bound to metadata, such as a
+/// chain id or network name.
+///
+/// Proves against the effective root Hs(root || meta) instead of `root`, so
+/// the proof only verifies with `fcmp_verify_with_root_meta` and the same
+/// metadata. Networks sharing an identical tree cannot replay each other's
+/// proofs. The branch must have at least one layer, since a zero-layer proof
+/// needs the root to be the output's own leaf.
+///
+/// # Safety
+/// - `meta` must point to `meta_len` bytes
+/// - Remaining pointers must be valid as for `fcmp_prove`
+///
+/// # Returns
+/// - As `fcmp_prove`
+/// - `FCMP_ERROR_EMPTY_TREE` if `root` itself is the empty-tree root
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer branch
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_with_root_meta(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    root: *const u8,
+    meta: *const u8,
+    meta_len: usize,
+    output: *const u8,
+    branch: *const FcmpBranch,
+) -> i32 {
+    if root.is_null() || (meta.is_null() && meta_len > 0) || branch.is_null() || (*branch).num_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let root = slice::from_raw_parts(root, POINT_SIZE);
+    if *root == EMPTY_TREE_ROOT {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+
+    let effective = root_with_meta(root, meta_slice(meta, meta_len));
+    fcmp_prove(proof_out, proof_len_out, proof_max_len, effective.as_ptr(), output, branch)
+}
+
+/// Verify a proof from `fcmp_prove_with_root_meta`.
+///
+/// A zero-layer proof is rejected: it has no equations, so nothing would
+/// tie it to the metadata.
+///
+/// # Safety
+/// - `meta` must point to `meta_len` bytes
+/// - Remaining pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - As `fcmp_verify`; a proof bound to other metadata gives
+///   `FCMP_ERROR_PROOF_VERIFICATION`
+/// - `FCMP_ERROR_EMPTY_TREE` if `root` itself is the empty-tree root
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer proof
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_with_root_meta(
+    root: *const u8,
+    meta: *const u8,
+    meta_len: usize,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if root.is_null() || (meta.is_null() && meta_len > 0) || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if proof_num_layers(slice::from_raw_parts(proof, proof_len)) == Ok(0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let root = slice::from_raw_parts(root, POINT_SIZE);
+    if *root == EMPTY_TREE_ROOT {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+
+    let effective = root_with_meta(root, meta_slice(meta, meta_len));
+    fcmp_verify(effective.as_ptr(), input, proof, proof_len)
+}
+
+/// Verify an FCMP proof against a compact tree descriptor.
+///
+/// The descriptor's depth must be the one its leaf count implies, and the
+/// proof must declare exactly that many layers, so a light client holding
+/// only the descriptor still rejects branches of the wrong shape. The proof
+/// is then verified against the descriptor's root as by `fcmp_verify`.
+///
+/// A single-leaf descriptor is rejected: its zero-layer proof is only a
+/// header, and checking it takes the output, as `fcmp_verify_single_leaf`
+/// does.
+///
+/// # Safety
+/// - `descriptor` must point to a valid `FcmpTreeDescriptor`
+/// - Remaining pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - As `fcmp_verify`
+/// - `FCMP_ERROR_EMPTY_TREE` if the descriptor has no leaves
+/// - `FCMP_ERROR_INVALID_PARAM` if its depth does not match its leaf count,
+///   or it describes a single-leaf tree
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof's depth differs from it
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_with_descriptor(
+    descriptor: *const FcmpTreeDescriptor,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if descriptor.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let descriptor = &*descriptor;
+    if descriptor.leaf_count == 0 {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+    if descriptor.depth != tree_depth_for_leaves(descriptor.leaf_count) || descriptor.depth == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match proof_num_layers(slice::from_raw_parts(proof, proof_len)) {
+        Ok(layers) if layers == descriptor.depth as usize => {
+            fcmp_verify(descriptor.root.as_ptr(), input, proof, proof_len)
+        }
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// Verify an FCMP proof and report the peak heap memory it used.
+///
+/// Behaves exactly like `fcmp_verify`. `peak_bytes_out` receives the largest
+/// number of bytes the call had allocated at once on the calling thread,
+/// for sizing batches on memory-constrained verifiers. The `profiling`
+/// feature that provides this installs a tracking global allocator, so it
+/// is meant for profiling builds only.
+///
+/// # Safety
+/// - `tree_root`, `input` and `proof` must be valid as for `fcmp_verify`
+/// - `peak_bytes_out` must be writable
+///
+/// # Returns
+/// - The `fcmp_verify` result; the peak is written whatever it is
+#[cfg(feature = "profiling")]
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_profiled(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    peak_bytes_out: *mut usize,
+) -> i32 {
+    if peak_bytes_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let (result, peak) = profiling::measure_peak(|| fcmp_verify(tree_root, input, proof, proof_len));
+    *peak_bytes_out = peak;
+    result
+}
+
+/// Verify an FCMP proof against caller-supplied generators.
+///
+/// For setups where generators differ per chain: `generators` replaces the
+/// initialized parameters, which are not consulted, so this works before
+/// `fcmp_init`. They are given in `fcmp_get_generator` order, H, G_0, G_1,
+/// ..., and a proof over n layers needs at least the first 1 + n; any
+/// beyond that are ignored. Since only part of the set is known, the
+/// proof's generators fingerprint is not checked.
+///
+/// # Safety
+/// - `generators` must point to `num_generators * 32` bytes
+/// - `tree_root`, `input` and `proof` must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the proof is valid under these generators
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it is not
+/// - `FCMP_ERROR_INVALID_PARAM` if there are fewer generators than the proof needs
+/// - `FCMP_ERROR_INVALID_POINT` if a generator is not a canonical point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_with_generators(
+    generators: *const u8,
+    num_generators: u32,
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if generators.is_null() || tree_root.is_null() || input.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    let num_layers = match proof_num_layers(proof_bytes) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    if (num_generators as usize) < 1 + num_layers {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut points = match slice::from_raw_parts(generators, (1 + num_layers) * POINT_SIZE)
+        .chunks_exact(POINT_SIZE)
+        .map(decode_point)
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(points) => points,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+    let h = points.remove(0);
+    let params = FcmpParams::new(h, points);
+
+    // A partial set has its own fingerprint, so the header is not compared;
+    // the layer equations alone decide whether these generators fit
+    verify_proof_checked(tree_root, proof_bytes, Transcript::new(), &params, None, None, None)
+}
+
+/// Verify an FCMP proof, aborting once it costs more than `max_steps`.
+///
+/// For verifiers that cap the work any one proof may cause. A step is one
+/// expensive operation: each point decompression, and each term of the
+/// final multiscalar multiplication. A proof over n layers costs
+/// 2n decompressions plus 1 + 3n terms, 5n + 1 steps in all. A zero-layer
+/// proof is rejected, as by `fcmp_verify`, before any step is spent. The
+/// limit is checked before each operation, so an aborted call never does
+/// more than `max_steps` of work.
+///
+/// # Safety
+/// - `tree_root`, `input` and `proof` must be valid as for `fcmp_verify`
+/// - `steps_used_out` must be writable
+///
+/// # Returns
+/// - As `fcmp_verify`, with `steps_used_out` set to the steps performed
+/// - `FCMP_ERROR_LIMIT_EXCEEDED` if the next operation would exceed
+///   `max_steps`; `steps_used_out` holds the steps done before aborting
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_limited(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    max_steps: u64,
+    steps_used_out: *mut u64,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() || steps_used_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    let mut meter = StepMeter { used: 0, max: max_steps };
+    let result = with_params(|params| {
+        verify_proof_metered(tree_root, proof_bytes, Transcript::new(), params, None, Some(&mut meter))
+    })
+    .unwrap_or_else(|e| e);
+
+    *steps_used_out = meter.used;
+    result
+}
+
+/// Work done by a verification against its cap
+struct StepMeter {
+    used: u64,
+    max: u64,
+}
+
+impl StepMeter {
+    /// Account for `steps` more operations, failing if they would pass the cap
+    fn charge(&mut self, steps: u64) -> Result<(), i32> {
+        if steps > self.max - self.used {
+            return Err(FCMP_ERROR_LIMIT_EXCEEDED);
+        }
+        self.used += steps;
+        Ok(())
+    }
+}
+
+/// Batched proof check shared by `fcmp_verify` and `FcmpVerifier`.
+///
+/// All layer equations `s_a*G_j + s_r*H == T_j + c*A_j` are combined with
+/// random weights into a single multiscalar multiplication. With cached
+/// `BaseTables` only A_j and T_j are multiplied without precomputation.
+fn verify_proof_batched(
+    tree_root: &[u8],
+    proof_bytes: &[u8],
+    transcript: Transcript,
+    params: &FcmpParams,
+    tables: Option<&BaseTables>,
+) -> i32 {
+    verify_proof_metered(tree_root, proof_bytes, transcript, params, tables, None)
+}
+
+/// `verify_proof_batched`, charging each expensive operation to `meter`
+fn verify_proof_metered(
+    tree_root: &[u8],
+    proof_bytes: &[u8],
+    transcript: Transcript,
+    params: &FcmpParams,
+    tables: Option<&BaseTables>,
+    meter: Option<&mut StepMeter>,
+) -> i32 {
+    verify_proof_checked(tree_root, proof_bytes, transcript, params, tables, meter, Some(&params.fingerprint()))
+}
+
+/// `verify_proof_metered`, rejecting proofs whose header does not carry
+/// `fingerprint`; `None` skips that check for partial generator sets
+fn verify_proof_checked(
+    tree_root: &[u8],
+    proof_bytes: &[u8],
+    transcript: Transcript,
+    params: &FcmpParams,
+    tables: Option<&BaseTables>,
+    mut meter: Option<&mut StepMeter>,
+    fingerprint: Option<&[u8; 32]>,
+) -> i32 {
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul, VartimePrecomputedMultiscalarMul};
+
+    let num_layers = match proof_num_layers(proof_bytes) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+
+    if *tree_root == EMPTY_TREE_ROOT {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+    if proof_bytes[PROOF_ROOT] != *tree_root {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+    if fingerprint.is_some_and(|f| proof_bytes[PROOF_FINGERPRINT] != *f) {
+        return FCMP_ERROR_GENERATOR_MISMATCH;
+    }
+    // A bare header proves nothing about a root the verifier cannot tie to
+    // an output; see `fcmp_verify_single_leaf`
+    if num_layers == 0 {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    let c = proof_challenge_with(transcript, proof_bytes, num_layers);
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    // sum_j w_j * (s_a_j*G_j + s_r_j*H - c*A_j - T_j) == 0
+    // Fixed bases H, G_0, G_1, ... take static scalars; A_j and T_j are dynamic
+    let mut static_scalars = vec![Scalar::ZERO; 1 + num_layers];
+    let mut scalars = Vec::with_capacity(2 * num_layers);
+    let mut points = Vec::with_capacity(2 * num_layers);
+    for j in 0..num_layers {
+        if let Some(Err(e)) = meter.as_deref_mut().map(|m| m.charge(2)) {
+            return e;
+        }
+        let layer = match proof_layer(proof_bytes, j) {
+            Ok(layer) => layer,
+            Err(e) => return e,
+        };
+        let w = Scalar::random(&mut OsRng);
+        static_scalars[0] += w * layer.s_r;
+        static_scalars[1 + j] = w * layer.s_a;
+        scalars.push(-(w * c));
+        points.push(layer.a);
+        scalars.push(-w);
+        points.push(layer.t);
+    }
+
+    if let Some(Err(e)) = meter.map(|m| m.charge(1 + 3 * num_layers as u64)) {
+        return e;
+    }
+
+    let check = match tables {
+        Some(tables) => tables
+            .get(params, num_layers)
+            .vartime_mixed_multiscalar_mul(static_scalars, scalars, points),
+        None => EdwardsPoint::vartime_multiscalar_mul(
+            static_scalars.iter().chain(&scalars),
+            std::iter::once(&params.h)
+                .chain(&params.layer_generators[..num_layers])
+                .chain(&points),
+        ),
+    };
+
+    if check.is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Multiplication tables for the fixed bases H, G_0, ..., G_{n-1}, built
+/// on first use and cached per proof depth n
+struct BaseTables(Vec<std::sync::OnceLock<curve25519_dalek::edwards::VartimeEdwardsPrecomputation>>);
+
+impl BaseTables {
+    fn new() -> Self {
+        BaseTables((0..MAX_PROOF_LAYERS).map(|_| std::sync::OnceLock::new()).collect())
+    }
+
+    /// Tables for a proof over `num_layers` layers (1..=MAX_PROOF_LAYERS)
+    fn get(&self, params: &FcmpParams, num_layers: usize) -> &curve25519_dalek::edwards::VartimeEdwardsPrecomputation {
+        use curve25519_dalek::traits::VartimePrecomputedMultiscalarMul;
+
+        self.0[num_layers - 1].get_or_init(|| {
+            curve25519_dalek::edwards::VartimeEdwardsPrecomputation::new(
+                std::iter::once(&params.h).chain(&params.layer_generators[..num_layers]),
+            )
+        })
+    }
+}
+
+/// Verify an FCMP proof against any of several candidate tree roots.
+///
+/// Intended for reorg windows where a proof made against any recent root is
+/// acceptable. Roots are tried in order and the search stops at the first
+/// one that verifies.
+///
+/// # Safety
+/// - `roots` must point to `num_roots * 32` bytes
+/// - `input` and `proof` must be valid as for `fcmp_verify`
+/// - `matched_root_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the index of the matching root in `matched_root_out`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if no root verifies
+/// - Other error codes if the proof or parameters are invalid
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_any_root(
+    roots: *const u8,
+    num_roots: u32,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    matched_root_out: *mut u32,
+) -> i32 {
+    if roots.is_null() || num_roots == 0 || matched_root_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    for i in 0..num_roots {
+        let root = roots.add(i as usize * POINT_SIZE);
+        match fcmp_verify(root, input, proof, proof_len) {
+            FCMP_SUCCESS => {
+                *matched_root_out = i;
+                return FCMP_SUCCESS;
+            }
+            FCMP_ERROR_PROOF_VERIFICATION => continue,
+            err => return err,
+        }
+    }
+
+    FCMP_ERROR_PROOF_VERIFICATION
+}
+
+/// Extract the tree root a proof was bound to.
+///
+/// A cheap pre-check for mempool admission: compare the result against the
+/// live root before spending CPU on `fcmp_verify`. This only parses the
+/// header and does not verify the proof.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+/// - `root_out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if the proof is malformed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_root(
+    proof: *const u8,
+    proof_len: usize,
+    root_out: *mut u8,
+) -> i32 {
+    if proof.is_null() || root_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    if let Err(e) = proof_num_layers(proof_bytes) {
+        return e;
+    }
+
+    ptr::copy_nonoverlapping(proof_bytes[PROOF_ROOT].as_ptr(), root_out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Read the generators fingerprint a proof was made under.
+///
+/// The prover writes `fcmp_generators_fingerprint` of its parameters into
+/// the header, and the challenge covers it. Verifiers reject a fingerprint
+/// other than their own with `FCMP_ERROR_GENERATOR_MISMATCH`; comparing it
+/// up front tells a node which generator set a peer is on. This only
+/// parses the header and does not verify the proof.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+/// - `out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if the proof is malformed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_generators_fingerprint(
+    proof: *const u8,
+    proof_len: usize,
+    out: *mut u8,
+) -> i32 {
+    if proof.is_null() || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    if let Err(e) = proof_num_layers(proof_bytes) {
+        return e;
+    }
+
+    ptr::copy_nonoverlapping(proof_bytes[PROOF_FINGERPRINT].as_ptr(), out, 32);
+    FCMP_SUCCESS
+}
+
+/// Check whether this build can verify a proof's format, without verifying it.
+///
+/// Looks only at the header's format version and layer count, which must
+/// not exceed `FCMP_MAX_TREE_DEPTH`. Lets a node route or reject proofs
+/// from peers on other versions before spending time on them.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+///
+/// # Returns
+/// - 1 if the format is supported
+/// - 0 if it is not, or the header is missing
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_is_supported(proof: *const u8, proof_len: usize) -> i32 {
+    if proof.is_null() || proof_len < 2 {
+        return 0;
+    }
+
+    (*proof == FCMP_PROOF_VERSION && *proof.add(1) as u32 <= FCMP_MAX_TREE_DEPTH) as i32
+}
+
+/// Verify a proof in any of several accepted format versions, for use during
+/// a format migration window.
+///
+/// The proof's version byte must appear in `accepted_versions`; the proof is
+/// then handed to that version's verifier. Nodes widen the list when a new
+/// format activates and drop the old version once the window closes. A
+/// version this build has no verifier for is rejected even if accepted.
+///
+/// # Safety
+/// - `accepted_versions` must point to `num_versions` bytes
+/// - Remaining pointers must be valid as for `fcmp_verify`
+///
+/// # Returns
+/// - The verifier's result for an accepted, supported version
+/// - `FCMP_ERROR_INVALID_PARAM` if the version is not accepted, has no
+///   verifier in this build, or the list is empty
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_multiversion(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    accepted_versions: *const u8,
+    num_versions: usize,
+) -> i32 {
+    if proof.is_null() || proof_len == 0 || accepted_versions.is_null() || num_versions == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let version = *proof;
+    if !slice::from_raw_parts(accepted_versions, num_versions).contains(&version) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match version {
+        FCMP_PROOF_VERSION => fcmp_verify(tree_root, input, proof, proof_len),
+        _ => FCMP_ERROR_INVALID_PARAM,
+    }
+}
+
+/// Check that a proof decodes, without verifying it.
+///
+/// A cheap gate before queuing a proof for verification: checks the header
+/// and length, that every embedded point is a canonical curve point and
+/// that every scalar is canonical. No algebraic checks are run, so passing
+/// says nothing about validity.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the proof is well-formed
+/// - `FCMP_ERROR_INVALID_PARAM` if the header or length is wrong
+/// - `FCMP_ERROR_INVALID_POINT` if a point fails to decode
+/// - `FCMP_ERROR_INVALID_SCALAR` if a scalar is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_check_structure(proof: *const u8, proof_len: usize) -> i32 {
+    if proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    let num_layers = match proof_num_layers(proof_bytes) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+
+    for j in 0..num_layers {
+        if let Err(e) = proof_layer(proof_bytes, j) {
+            return e;
+        }
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Compute a 32-byte identifier for a serialized proof.
+///
+/// BLAKE2b-512 over a domain tag and the proof bytes, truncated to 32
+/// bytes. Byte-identical proofs share an id and any change alters it. This
+/// is a cache key for verification results, not a cryptographic commitment
+/// to the statement: it says nothing about validity, and a proof that does
+/// not pass the header and length checks is rejected.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+/// - `id_out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if the header or length is wrong
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_id(proof: *const u8, proof_len: usize, id_out: *mut u8) -> i32 {
+    if proof.is_null() || id_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use blake2::{Blake2b512, Digest};
+
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    if let Err(e) = proof_num_layers(proof_bytes) {
+        return e;
+    }
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"WATTx_FCMP_Proof_Id_v1");
+    hasher.update(proof_bytes);
+    ptr::copy_nonoverlapping(hasher.finalize().as_ptr(), id_out, 32);
+
+    FCMP_SUCCESS
+}
+
+/// Parse a proof from the start of a buffer into an owned handle.
+///
+/// The proof's length follows from its header, so it can be read out of a
+/// larger buffer: `consumed_out` receives the number of bytes it occupies
+/// and the caller advances past them. With `exact` set, any trailing bytes
+/// are an error instead. The proof must pass `fcmp_proof_check_structure`.
+///
+/// # Safety
+/// - `proof_out` must be writable
+/// - `bytes` must point to `bytes_len` bytes
+/// - `consumed_out` must be null or writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with a handle to release with `fcmp_proof_free`
+/// - `FCMP_ERROR_INVALID_PARAM` if the buffer is too short, or has trailing
+///   data when `exact` is nonzero
+/// - `FCMP_ERROR_INVALID_POINT` / `FCMP_ERROR_INVALID_SCALAR` for bad encodings
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_deserialize(
+    proof_out: *mut *mut FcmpProof,
+    bytes: *const u8,
+    bytes_len: usize,
+    consumed_out: *mut usize,
+    exact: i32,
+) -> i32 {
+    if proof_out.is_null() || bytes.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let bytes = slice::from_raw_parts(bytes, bytes_len);
+    let len = match proof_prefix_len(bytes) {
+        Ok(len) => len,
+        Err(e) => return e,
+    };
+    if exact != 0 && len != bytes.len() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let result = fcmp_proof_check_structure(bytes.as_ptr(), len);
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    *proof_out = Box::into_raw(Box::new(FcmpProof { data: bytes[..len].to_vec() }));
+    if !consumed_out.is_null() {
+        *consumed_out = len;
+    }
+    FCMP_SUCCESS
+}
+
+/// Get the serialized bytes of a proof handle.
+///
+/// The returned pointer stays valid until the handle is freed.
+///
+/// # Safety
+/// - `proof` must be a live handle from `fcmp_proof_deserialize`
+/// - `len_out` must be writable
+///
+/// # Returns
+/// - Pointer to the proof bytes, or null if an argument is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_bytes(proof: *const FcmpProof, len_out: *mut usize) -> *const u8 {
+    if proof.is_null() || len_out.is_null() {
+        return ptr::null();
+    }
+
+    let proof = &*proof;
+    *len_out = proof.data.len();
+    proof.data.as_ptr()
+}
+
+/// Free a proof created by `fcmp_proof_deserialize`.
+///
+/// # Safety
+/// - `proof` must be null or a handle from `fcmp_proof_deserialize` not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_free(proof: *mut FcmpProof) {
+    if !proof.is_null() {
+        drop(Box::from_raw(proof));
+    }
+}
+
+/// Magic prefix of the verbose proof encoding
+const VERBOSE_PROOF_MAGIC: &[u8; 8] = b"WFCMPVRB";
+
+/// Field tags of the verbose proof encoding, in the order fields appear
+const VERBOSE_TAG_VERSION: u8 = 0x01;
+const VERBOSE_TAG_NUM_LAYERS: u8 = 0x02;
+const VERBOSE_TAG_ROOT: u8 = 0x03;
+const VERBOSE_TAG_FINGERPRINT: u8 = 0x04;
+const VERBOSE_TAG_LAYER: [u8; 4] = [0x10, 0x11, 0x12, 0x13];
+
+/// Expand a compact proof into tagged fields; see `fcmp_proof_to_verbose`
+fn proof_to_verbose(proof: &[u8]) -> Result<Vec<u8>, i32> {
+    let num_layers = proof_num_layers(proof)?;
+
+    let mut fields: Vec<(u8, &[u8])> = vec![
+        (VERBOSE_TAG_VERSION, &proof[..1]),
+        (VERBOSE_TAG_NUM_LAYERS, &proof[1..2]),
+        (VERBOSE_TAG_ROOT, &proof[PROOF_ROOT]),
+        (VERBOSE_TAG_FINGERPRINT, &proof[PROOF_FINGERPRINT]),
+    ];
+    for j in 0..num_layers {
+        let layer = &proof[FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE..][..FCMP_PROOF_LAYER_SIZE];
+        for (tag, field) in VERBOSE_TAG_LAYER.iter().zip(layer.chunks_exact(32)) {
+            fields.push((*tag, field));
+        }
+    }
+
+    let mut verbose = VERBOSE_PROOF_MAGIC.to_vec();
+    for (tag, value) in fields {
+        verbose.push(tag);
+        verbose.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        verbose.extend_from_slice(value);
+    }
+    Ok(verbose)
+}
+
+/// Collapse a verbose proof back into the compact wire format
+fn proof_from_verbose(verbose: &[u8]) -> Result<Vec<u8>, i32> {
+    let mut rest = verbose.strip_prefix(&VERBOSE_PROOF_MAGIC[..]).ok_or(FCMP_ERROR_INVALID_PARAM)?;
+    let mut field = |tag: u8, len: usize| -> Result<&[u8], i32> {
+        if rest.len() < 3 || rest[0] != tag || u16::from_le_bytes([rest[1], rest[2]]) as usize != len {
+            return Err(FCMP_ERROR_INVALID_PARAM);
+        }
+        let value = rest.get(3..3 + len).ok_or(FCMP_ERROR_INVALID_PARAM)?;
+        rest = &rest[3 + len..];
+        Ok(value)
+    };
+
+    let mut proof = field(VERBOSE_TAG_VERSION, 1)?.to_vec();
+    let num_layers = field(VERBOSE_TAG_NUM_LAYERS, 1)?;
+    let num_layers_value = num_layers[0] as usize;
+    proof.extend_from_slice(num_layers);
+    proof.extend_from_slice(field(VERBOSE_TAG_ROOT, POINT_SIZE)?);
+    proof.extend_from_slice(field(VERBOSE_TAG_FINGERPRINT, 32)?);
+    for _ in 0..num_layers_value {
+        for tag in VERBOSE_TAG_LAYER {
+            proof.extend_from_slice(field(tag, 32)?);
+        }
+    }
+
+    if !rest.is_empty() {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+    proof_num_layers(&proof)?;
+    Ok(proof)
+}
+
+/// Copy a converted proof out, reporting its size either way
+unsafe fn write_converted(converted: Result<Vec<u8>, i32>, out: *mut u8, out_len: usize, written: *mut usize) -> i32 {
+    let bytes = match converted {
+        Ok(bytes) => bytes,
+        Err(e) => return e,
+    };
+
+    *written = bytes.len();
+    if out_len < bytes.len() {
+        return FCMP_ERROR_MEMORY;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    FCMP_SUCCESS
+}
+
+/// Convert a compact proof into the verbose, self-describing encoding.
+///
+/// The verbose form is for debugging: "WFCMPVRB" followed by one
+/// tag (u8) || length (u16 LE) || value field per proof field, in wire
+/// order. Tags: 0x01 version, 0x02 layer count, 0x03 root, 0x04 generators
+/// fingerprint, then per layer 0x10 A, 0x11 T, 0x12 s_a, 0x13 s_r. Field
+/// values are copied verbatim, so the conversion is lossless; nothing is
+/// decoded or checked beyond the header and length.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+/// - `out` must have at least `out_len` bytes available
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the verbose size in `written`
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `written` receives the
+///   size needed
+/// - `FCMP_ERROR_INVALID_PARAM` if the proof header or length is wrong
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_to_verbose(
+    proof: *const u8,
+    proof_len: usize,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if proof.is_null() || out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof = slice::from_raw_parts(proof, proof_len);
+    write_converted(proof_to_verbose(proof), out, out_len, written)
+}
+
+/// Convert a verbose proof back into the compact wire format.
+///
+/// Inverse of `fcmp_proof_to_verbose`: fields must appear in order with
+/// their exact tags and lengths, and nothing may follow the last one.
+///
+/// # Safety
+/// - `verbose` must point to `verbose_len` bytes
+/// - `out` must have at least `out_len` bytes available
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the compact size in `written`
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `written` receives the
+///   size needed
+/// - `FCMP_ERROR_INVALID_PARAM` if the verbose encoding is malformed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_from_verbose(
+    verbose: *const u8,
+    verbose_len: usize,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if verbose.is_null() || out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let verbose = slice::from_raw_parts(verbose, verbose_len);
+    write_converted(proof_from_verbose(verbose), out, out_len, written)
+}
+
+/// Copy out the commitment points embedded in a proof for external auditing.
+///
+/// Writes A_0 || T_0 || A_1 || T_1 || ..., the per-layer commitment and
+/// nonce commitment in the order the challenge absorbs them, as 32-byte
+/// compressed points. `count_out` receives the number of points, twice the
+/// layer count. Every layer is decoded before anything is written.
+///
+/// # Safety
+/// - `bytes` must point to `len` bytes
+/// - `out` must have at least `out_len` bytes available
+/// - `count_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `count_out` receives the
+///   number of points needed
+/// - `FCMP_ERROR_INVALID_PARAM` if the proof header or length is wrong
+/// - `FCMP_ERROR_INVALID_POINT` or `FCMP_ERROR_INVALID_SCALAR` if a layer
+///   does not decode
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_extract_commitments(
+    bytes: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_len: usize,
+    count_out: *mut u32,
+) -> i32 {
+    if bytes.is_null() || out.is_null() || count_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof = slice::from_raw_parts(bytes, len);
+    let num_layers = match proof_num_layers(proof) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    for j in 0..num_layers {
+        if let Err(e) = proof_layer(proof, j) {
+            return e;
+        }
+    }
+
+    let count = 2 * num_layers;
+    *count_out = count as u32;
+    if out_len < count * POINT_SIZE {
+        return FCMP_ERROR_MEMORY;
+    }
+
+    for j in 0..num_layers {
+        let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE;
+        ptr::copy_nonoverlapping(proof[offset..].as_ptr(), out.add(j * 2 * POINT_SIZE), 2 * POINT_SIZE);
+    }
+    FCMP_SUCCESS
+}
+
+/// Get the scratch buffer size `fcmp_verify_inplace` needs for a proof.
+///
+/// The scratch holds the challenge followed by, for each layer, the layer
+/// generator and the recomputed nonce commitment.
+///
+/// # Arguments
+/// - `num_layers` - Number of tree layers in the proof
+///
+/// # Returns
+/// - Required scratch size in bytes
+/// - 0 if `num_layers` exceeds `FCMP_MAX_TREE_DEPTH`, since no proof that
+///   deep can exist
+#[no_mangle]
+pub extern "C" fn fcmp_verify_scratch_size(num_layers: u32) -> usize {
+    if num_layers > FCMP_MAX_TREE_DEPTH {
+        return 0;
+    }
+
+    SCALAR_SIZE + num_layers as usize * 2 * POINT_SIZE
+}
+
+/// Verify an FCMP proof without heap allocation.
+///
+/// Checks each layer equation individually, writing every intermediate into
+/// the caller's scratch buffer. Produces the same verdict as `fcmp_verify`,
+/// and like it does not bind the proof to `input`.
+///
+/// # Safety
+/// - All pointers must be valid
+/// - `scratch` must point to `scratch_len` writable bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if proof is valid
+/// - `FCMP_ERROR_MEMORY` if `scratch_len` is below `fcmp_verify_scratch_size`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if proof is invalid
+/// - Other error codes on failure
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_inplace(
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+    scratch: *mut u8,
+    scratch_len: usize,
+) -> i32 {
+    if tree_root.is_null() || input.is_null() || proof.is_null() || scratch.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+    let num_layers = match proof_num_layers(proof_bytes) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+
+    if scratch_len < fcmp_verify_scratch_size(num_layers as u32) {
+        return FCMP_ERROR_MEMORY;
+    }
+    let scratch = slice::from_raw_parts_mut(scratch, scratch_len);
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+
+    with_params(|params| verify_proof_inplace(tree_root, proof_bytes, num_layers, scratch, params))
+        .unwrap_or_else(|e| e)
+}
+
+/// Layer-by-layer proof check writing every intermediate into `scratch`
+fn verify_proof_inplace(
+    tree_root: &[u8],
+    proof_bytes: &[u8],
+    num_layers: usize,
+    scratch: &mut [u8],
+    params: &FcmpParams,
+) -> i32 {
+    use subtle::ConstantTimeEq;
+
+    if *tree_root == EMPTY_TREE_ROOT {
+        return FCMP_ERROR_EMPTY_TREE;
+    }
+    if proof_bytes[PROOF_ROOT] != *tree_root {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+    if proof_bytes[PROOF_FINGERPRINT] != params.fingerprint() {
+        return FCMP_ERROR_GENERATOR_MISMATCH;
+    }
+    if num_layers == 0 {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    let h = params.h;
+    let c = proof_challenge(proof_bytes, num_layers);
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+    scratch[..SCALAR_SIZE].copy_from_slice(c.as_bytes());
+
+    let mut valid = subtle::Choice::from(1u8);
+    for j in 0..num_layers {
+        let layer = match proof_layer(proof_bytes, j) {
+            Ok(layer) => layer,
+            Err(e) => return e,
+        };
+        let g_j = params.layer_generators[j];
+
+        let offset = SCALAR_SIZE + j * 2 * POINT_SIZE;
+        scratch[offset..offset + POINT_SIZE].copy_from_slice(g_j.compress().as_bytes());
+
+        // T_j' = s_a*G_j + s_r*H - c*A_j must reproduce T_j
+        let t_expected = layer.s_a * g_j + layer.s_r * h - c * layer.a;
+        let t_slot = &mut scratch[offset + POINT_SIZE..offset + 2 * POINT_SIZE];
+        t_slot.copy_from_slice(t_expected.compress().as_bytes());
+        valid &= t_slot.ct_eq(layer.t.compress().as_bytes());
+    }
+
+    if bool::from(valid) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Measure average `fcmp_verify` time on this machine.
+///
+/// Proves membership of a fixed output over a representative 8-layer branch,
+/// then times `num_iterations` verifications of that proof. Lets operators
+/// estimate proofs per second before deploying a node.
+///
+/// # Safety
+/// - `out_ns_per_verify` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the mean nanoseconds per verification
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+/// - `FCMP_ERROR_INTERNAL` if the generated proof fails to verify
+#[cfg(feature = "bench")]
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_benchmark(num_iterations: u32, out_ns_per_verify: *mut u64) -> i32 {
+    const LAYERS: usize = 8;
+    const ELEMENTS_PER_LAYER: usize = 4;
+
+    if num_iterations == 0 || out_ns_per_verify.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let elements = [0x5au8; ELEMENTS_PER_LAYER * SCALAR_SIZE];
+    let layers: [FcmpBranchLayer; LAYERS] = std::array::from_fn(|_| FcmpBranchLayer {
+        num_elements: ELEMENTS_PER_LAYER as u32,
+        elements: elements.as_ptr(),
+    });
+    let branch = FcmpBranch { leaf_index: 0, num_layers: LAYERS as u32, layers: layers.as_ptr() };
+    let root = [0x42u8; POINT_SIZE];
+    let output = [0x24u8; OUTPUT_TUPLE_SIZE];
+    let input = FcmpInput { o_tilde: [0u8; 64], i_tilde: [0u8; 64], r: [0u8; 64], c_tilde: [0u8; 64] };
+
+    let mut proof = [0u8; FCMP_PROOF_HEADER_SIZE + LAYERS * FCMP_PROOF_LAYER_SIZE];
+    let mut proof_len = 0usize;
+    let ret = fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch);
+    if ret != FCMP_SUCCESS {
+        return ret;
+    }
+
+    let start = std::time::Instant::now();
+    for _ in 0..num_iterations {
+        if fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len) != FCMP_SUCCESS {
+            return FCMP_ERROR_INTERNAL;
+        }
+    }
+    let elapsed = start.elapsed().as_nanos() / num_iterations as u128;
+
+    *out_ns_per_verify = elapsed.clamp(1, u64::MAX as u128) as u64;
+    FCMP_SUCCESS
+}
+
+/// Build a small valid proof for exercising verifiers from C++.
+///
+/// Appends a fixed output's leaf among 20 filler leaves of a fresh tree,
+/// rerandomizes that output and proves its membership. The root and input
+/// are deterministic; the proof is not, since proving draws fresh nonces.
+/// Passing the three results to `fcmp_verify` succeeds.
+///
+/// # Safety
+/// - `proof_out` must have at least `max_len` bytes available
+/// - `proof_len_out` must be writable
+/// - `root_out` must point to 32 writable bytes
+/// - `input_out` must point to a writable `FcmpInput`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+/// - `FCMP_ERROR_MEMORY` if `max_len` is too small; `proof_len_out`
+///   receives the required size
+#[cfg(feature = "testing")]
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_make_test_proof(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    max_len: usize,
+    root_out: *mut u8,
+    input_out: *mut FcmpInput,
+) -> i32 {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    const NUM_LEAVES: u64 = 20;
+    const LEAF_INDEX: u64 = 13;
+
+    if proof_out.is_null() || proof_len_out.is_null() || root_out.is_null() || input_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut output = [0u8; OUTPUT_TUPLE_SIZE];
+    for (i, slot) in output.chunks_exact_mut(POINT_SIZE).enumerate() {
+        let point = Scalar::from(i as u64 + 1) * ED25519_BASEPOINT_POINT;
+        slot.copy_from_slice(point.compress().as_bytes());
+    }
+    let mut output_leaf = [0u8; SCALAR_SIZE];
+    let ret = fcmp_output_leaf_scalar(output_leaf.as_mut_ptr(), output.as_ptr());
+    if ret != FCMP_SUCCESS {
+        return ret;
+    }
+
+    let leaves: Vec<[u8; SCALAR_SIZE]> = (0..NUM_LEAVES)
+        .map(|i| if i == LEAF_INDEX { output_leaf } else { Scalar::from(i + 1).to_bytes() })
+        .collect();
+    let mut tree = FcmpTree { levels: vec![Vec::new()] };
+    tree.append(&leaves);
+    let root = tree.root();
+
+    let groups = tree.branch_groups(LEAF_INDEX as usize);
+    let layers: Vec<FcmpBranchLayer> = groups
+        .iter()
+        .map(|g| FcmpBranchLayer { num_elements: (g.len() / SCALAR_SIZE) as u32, elements: g.as_ptr() })
+        .collect();
+    let branch = FcmpBranch { leaf_index: LEAF_INDEX, num_layers: layers.len() as u32, layers: layers.as_ptr() };
+
+    let required = proof_len_for_layers(layers.len());
+    if max_len < required {
+        *proof_len_out = required;
+        return FCMP_ERROR_MEMORY;
+    }
+
+    let mut input = FcmpInput { o_tilde: [0u8; 64], i_tilde: [0u8; 64], r: [0u8; 64], c_tilde: [0u8; 64] };
+    let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+    let seed = [0x7eu8; SCALAR_SIZE];
+    let ret = fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), output.as_ptr(), seed.as_ptr());
+    if ret != FCMP_SUCCESS {
+        return ret;
+    }
+
+    let ret = fcmp_prove(proof_out, proof_len_out, max_len, root.as_ptr(), output.as_ptr(), &branch);
+    if ret != FCMP_SUCCESS {
+        return ret;
+    }
+
+    ptr::copy_nonoverlapping(root.as_ptr(), root_out, SCALAR_SIZE);
+    ptr::write(input_out, input);
+    FCMP_SUCCESS
+}
+
+// ============================================================================
+// Key Images
+// ============================================================================
+
+/// Scan a batch of key images for duplicates (double spends).
+///
+/// Reports the index of the first image, in input order, that repeats an
+/// earlier one.
+///
+/// # Safety
+/// - `images` must point to `count * 32` bytes
+/// - `dup_index_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if all images are unique
+/// - `FCMP_ERROR_PROOF_VERIFICATION` with the duplicate's index in `dup_index_out`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_key_images_find_duplicates(
+    images: *const u8,
+    count: u32,
+    dup_index_out: *mut u32,
+) -> i32 {
+    if (images.is_null() && count > 0) || dup_index_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+
+    let images = slice::from_raw_parts(images, count as usize * POINT_SIZE);
+    let mut seen = std::collections::HashSet::with_capacity(count as usize);
+    for (i, image) in images.chunks_exact(POINT_SIZE).enumerate() {
+        if !seen.insert(image) {
+            *dup_index_out = i as u32;
+            return FCMP_ERROR_PROOF_VERIFICATION;
+        }
+    }
+
+    FCMP_SUCCESS
+}
+
+/// Order-independent digest of a batch of key images.
+///
+/// Computes BLAKE2b-512("WATTx_KeyImages_Digest_v1" || count || sorted images)
+/// truncated to 32 bytes, so a light wallet can compare one hash against a
+/// block before fetching its images.
+///
+/// # Safety
+/// - `images` must point to `count * 32` bytes
+/// - `out` must point to a 32-byte buffer
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_key_images_digest(
+    images: *const u8,
+    count: u32,
+    out: *mut u8,
+) -> i32 {
+    use blake2::{Blake2b512, Digest};
+
+    if (images.is_null() && count > 0) || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut sorted: Vec<&[u8]> = if count == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(images, count as usize * POINT_SIZE)
+            .chunks_exact(POINT_SIZE)
+            .collect()
+    };
+    sorted.sort_unstable();
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"WATTx_KeyImages_Digest_v1");
+    hasher.update((count as u64).to_le_bytes());
+    for image in sorted {
+        hasher.update(image);
+    }
+
+    ptr::copy_nonoverlapping(hasher.finalize().as_ptr(), out, 32);
+    FCMP_SUCCESS
+}
+
+/// Check whether a key image is in a set, in constant time.
+///
+/// Every entry is compared with constant-time equality and the scan never
+/// exits early, so timing reveals neither whether nor where the image
+/// matched. The cost is linear in `count`. For large spent sets, narrow
+/// the candidates first (for example by comparing `fcmp_key_images_digest`
+/// of per-block batches) and run this over the remainder.
+///
+/// # Safety
+/// - `image` must point to 32 bytes
+/// - `set` must point to `count * 32` bytes
+///
+/// # Returns
+/// - 1 if the image is in the set
+/// - 0 if it is not
+/// - `FCMP_ERROR_INVALID_PARAM` if a pointer is null
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_key_image_in_set(image: *const u8, set: *const u8, count: u32) -> i32 {
+    if image.is_null() || (set.is_null() && count > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if count == 0 {
+        return 0;
+    }
+
+    use subtle::{Choice, ConstantTimeEq};
+
+    let image = slice::from_raw_parts(image, POINT_SIZE);
+    let found = slice::from_raw_parts(set, count as usize * POINT_SIZE)
+        .chunks_exact(POINT_SIZE)
+        .fold(Choice::from(0), |found, entry| found | entry.ct_eq(image));
+    found.unwrap_u8() as i32
+}
+
+/// Derive the key images of a wallet's owned outputs in one call.
+///
+/// L_i = x_i * Hp(O_i), with Hp the map behind `fcmp_hash_to_point`. For an
+/// output whose I point is Hp(O), as wallets construct them, this is the key
+/// image `fcmp_sal_sign` reports when spending it. Hp is computed once per
+/// distinct O, so repeated public keys share the hashing. Secrets are wiped
+/// as they are used, and nothing is written on error.
+///
+/// # Safety
+/// - `images_out` must point to `count * 32` bytes of writable memory
+/// - `secrets` must point to `count * 32` bytes of spend keys
+/// - `output_pubkeys` must point to `count * 32` bytes of O points
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the images in input order
+/// - `FCMP_ERROR_INVALID_SCALAR` if a secret is not canonical
+/// - `FCMP_ERROR_INVALID_POINT` if an output key is not a canonical point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_key_image_batch(
+    images_out: *mut u8,
+    secrets: *const u8,
+    output_pubkeys: *const u8,
+    count: u32,
+) -> i32 {
+    if count > 0 && (images_out.is_null() || secrets.is_null() || output_pubkeys.is_null()) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+
+    let count = count as usize;
+    let secrets = slice::from_raw_parts(secrets, count * SCALAR_SIZE);
+    let pubkeys = slice::from_raw_parts(output_pubkeys, count * POINT_SIZE);
+
+    let mut hashed = std::collections::HashMap::with_capacity(count);
+    let mut images = Vec::with_capacity(count);
+    for (secret, pubkey) in secrets.chunks_exact(SCALAR_SIZE).zip(pubkeys.chunks_exact(POINT_SIZE)) {
+        if decode_point(pubkey).is_none() {
+            return FCMP_ERROR_INVALID_POINT;
+        }
+        let hp = match hashed.get(pubkey) {
+            Some(&hp) => hp,
+            None => match hash_to_point(pubkey) {
+                Some(hp) => *hashed.entry(pubkey).or_insert(hp),
+                None => return FCMP_ERROR_INTERNAL,
+            },
+        };
+        let mut x = match decode_scalar(secret) {
+            Some(x) => x,
+            None => return FCMP_ERROR_INVALID_SCALAR,
+        };
+        images.push((x * hp).compress());
+        x.zeroize();
+    }
+
+    for (i, image) in images.iter().enumerate() {
+        ptr::copy_nonoverlapping(image.as_bytes().as_ptr(), images_out.add(i * POINT_SIZE), POINT_SIZE);
+    }
+    FCMP_SUCCESS
+}
+
+// ============================================================================
+// Spend Authorization and Linkability (SA+L)
+// ============================================================================
+
+/// Size of an SA+L signature: A_1 || A_2 || A_3 || s_x || s_o || s_w || s_z
+pub const FCMP_SAL_SIGNATURE_SIZE: usize = 3 * POINT_SIZE + 4 * SCALAR_SIZE;
+
+/// Decode the four blinders written by `fcmp_output_rerandomize`
+fn decode_blinders(bytes: &[u8]) -> Option<[curve25519_dalek::scalar::Scalar; 4]> {
+    let mut blinders = [curve25519_dalek::scalar::Scalar::ZERO; 4];
+    for (blinder, chunk) in blinders.iter_mut().zip(bytes.chunks_exact(SCALAR_SIZE)) {
+        *blinder = decode_scalar(chunk)?;
+    }
+    Some(blinders)
+}
+
+/// SA+L challenge over the input, key image and nonce commitments, followed
+/// by the length-prefixed message. A prehashed message is absorbed under its
+/// own domain, so a digest signed as raw bytes never verifies as prehashed.
+fn sal_challenge(
+    input: &FcmpInput,
+    key_image: &[u8],
+    nonces: &[u8],
+    message: &[u8],
+    prehashed: bool,
+) -> curve25519_dalek::scalar::Scalar {
+    let domain: &[u8] = if prehashed { b"WATTx_FCMP_SAL_Prehashed_v1" } else { b"WATTx_FCMP_SAL_v1" };
+    let mut transcript = Transcript::with_domain(domain);
+    transcript.absorb_count(8);
+    for field in [&input.o_tilde, &input.i_tilde, &input.r, &input.c_tilde] {
+        transcript.absorb(field);
+    }
+    transcript.absorb(key_image);
+    transcript.absorb(nonces);
+    transcript.absorb_count(0);
+    transcript.absorb_count(message.len());
+    transcript.absorb(message);
+    transcript.challenge()
+}
+
+/// Produce an SA+L signature and the key image for a re-randomized input.
+///
+/// For an output with O = x*G the signer proves knowledge of
+/// (x, r_o, w = x*r_i, z = x*r_r) satisfying
+///
+/// O~ = x*G + r_o*T, L = x*I~ - w*U, 0 = x*R - w*V - z*T
+///
+/// The last relation forces w = x*r_i, so L = x*I is the output's key image.
+unsafe fn sal_sign(
+    x: &curve25519_dalek::scalar::Scalar,
+    blinders: &[curve25519_dalek::scalar::Scalar; 4],
+    input: &FcmpInput,
+    message: &[u8],
+    prehashed: bool,
+) -> Result<([u8; FCMP_SAL_SIGNATURE_SIZE], [u8; POINT_SIZE]), i32> {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    let g = ED25519_BASEPOINT_POINT;
+    let [o_tilde, i_tilde, r, _] = validate_input(input)?;
+    let [t, u, v] = rerandomization_generators().ok_or(FCMP_ERROR_INTERNAL)?;
+    let [r_o, r_i, r_r, _] = blinders;
+
+    // The secret and blinders must open the input being signed for
+    if x * g + r_o * t != o_tilde {
+        return Err(FCMP_ERROR_PROOF_GENERATION);
+    }
+    let key_image = (x * (i_tilde - r_i * u)).compress().to_bytes();
+
+    let mut witness = [*x, *r_o, x * r_i, x * r_r];
+    let mut k = [(); 4].map(|_| Scalar::random(&mut OsRng));
+
+    let mut sig = [0u8; FCMP_SAL_SIGNATURE_SIZE];
+    let nonces = [
+        k[0] * g + k[1] * t,
+        k[0] * i_tilde - k[2] * u,
+        k[0] * r - k[2] * v - k[3] * t,
+    ];
+    for (j, nonce) in nonces.iter().enumerate() {
+        sig[j * POINT_SIZE..(j + 1) * POINT_SIZE].copy_from_slice(nonce.compress().as_bytes());
+    }
+
+    let c = sal_challenge(input, &key_image, &sig[..3 * POINT_SIZE], message, prehashed);
+    if is_degenerate_challenge(&c) {
+        witness.zeroize();
+        k.zeroize();
+        return Err(FCMP_ERROR_PROOF_GENERATION);
+    }
+
+    for j in 0..4 {
+        let s = k[j] + c * witness[j];
+        let offset = 3 * POINT_SIZE + j * SCALAR_SIZE;
+        sig[offset..offset + SCALAR_SIZE].copy_from_slice(s.as_bytes());
+    }
+    witness.zeroize();
+    k.zeroize();
+
+    Ok((sig, key_image))
+}
+
+/// Check an SA+L signature, combining its three relations with random
+/// weights into a single multiscalar multiplication.
+unsafe fn sal_verify(sig: &[u8], input: &FcmpInput, key_image: &[u8], message: &[u8], prehashed: bool) -> i32 {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    let [o_tilde, i_tilde, r, _] = match validate_input(input) {
+        Ok(points) => points,
+        Err(e) => return e,
+    };
+
+    // A torsion component would let one output yield several key images
+    let l = match decode_point(key_image) {
+        Some(l) if l.is_torsion_free() && !l.is_identity() => l,
+        _ => return FCMP_ERROR_INVALID_POINT,
+    };
+
+    let mut a = [EdwardsPoint::default(); 3];
+    for (point, chunk) in a.iter_mut().zip(sig[..3 * POINT_SIZE].chunks_exact(POINT_SIZE)) {
+        *point = match decode_point(chunk) {
+            Some(p) => p,
+            None => return FCMP_ERROR_INVALID_POINT,
+        };
+    }
+    let mut s = [Scalar::ZERO; 4];
+    for (scalar, chunk) in s.iter_mut().zip(sig[3 * POINT_SIZE..].chunks_exact(SCALAR_SIZE)) {
+        *scalar = match decode_scalar(chunk) {
+            Some(x) => x,
+            None => return FCMP_ERROR_INVALID_SCALAR,
+        };
+    }
+
+    let [t, u, v] = match rerandomization_generators() {
+        Some(gens) => gens,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let c = sal_challenge(input, key_image, &sig[..3 * POINT_SIZE], message, prehashed);
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    // w_1*(s_x*G + s_o*T - A_1 - c*O~)
+    //   + w_2*(s_x*I~ - s_w*U - A_2 - c*L)
+    //   + w_3*(s_x*R - s_w*V - s_z*T - A_3) == 0
+    let [s_x, s_o, s_w, s_z] = s;
+    let w = [(); 3].map(|_| Scalar::random(&mut OsRng));
+    let scalars = [
+        w[0] * s_x,
+        w[0] * s_o - w[2] * s_z,
+        -w[0],
+        -(w[0] * c),
+        w[1] * s_x,
+        -(w[1] * s_w),
+        -w[1],
+        -(w[1] * c),
+        w[2] * s_x,
+        -(w[2] * s_w),
+        -w[2],
+    ];
+    let points = [ED25519_BASEPOINT_POINT, t, a[0], o_tilde, i_tilde, u, a[1], l, r, v, a[2]];
+
+    if EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Sign the spend of a re-randomized input (SA+L) and derive its key image.
+///
+/// # Safety
+/// - `sig_out` must point to at least `FCMP_SAL_SIGNATURE_SIZE` bytes of writable memory
+/// - `key_image_out` must point to at least 32 bytes of writable memory
+/// - `secret` must point to the 32-byte spend key x, with O = x*G
+/// - `blinders` must point to the `FCMP_BLINDERS_SIZE` bytes from `fcmp_output_rerandomize`
+/// - `input` must point to a valid `FcmpInput`
+/// - `message` must point to `message_len` bytes
+///
+/// With `prehashed = 1`, `message` is a digest from `fcmp_message_prehash`
+/// and the signature is domain-separated from one over raw bytes; verify it
+/// with `prehashed = 1` too. Aggregation and transaction checks only accept
+/// raw-message signatures.
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `prehashed` is not 0 or 1, or is 1 with a
+///   `message_len` other than `FCMP_PREHASH_SIZE`
+/// - `FCMP_ERROR_INVALID_SCALAR` if the secret or a blinder is not canonical
+/// - `FCMP_ERROR_PROOF_GENERATION` if the secret and blinders do not open `input`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_sal_sign(
+    sig_out: *mut u8,
+    key_image_out: *mut u8,
+    secret: *const u8,
+    blinders: *const u8,
+    input: *const FcmpInput,
+    message: *const u8,
+    message_len: usize,
+    prehashed: i32,
+) -> i32 {
+    if sig_out.is_null() || key_image_out.is_null() || secret.is_null() ||
+       blinders.is_null() || input.is_null() || (message.is_null() && message_len > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let prehashed = match prehashed_flag(prehashed, message_len) {
+        Ok(prehashed) => prehashed,
+        Err(e) => return e,
+    };
+
+    let message = if message_len > 0 {
+        slice::from_raw_parts(message, message_len)
+    } else {
+        &[]
+    };
+
+    let x = decode_scalar(slice::from_raw_parts(secret, SCALAR_SIZE));
+    let r = decode_blinders(slice::from_raw_parts(blinders, FCMP_BLINDERS_SIZE));
+    let (mut x, mut r) = match (x, r) {
+        (Some(x), Some(r)) => (x, r),
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+
+    let result = sal_sign(&x, &r, &*input, message, prehashed);
+    x.zeroize();
+    r.zeroize();
+
+    match result {
+        Ok((sig, key_image)) => {
+            ptr::copy_nonoverlapping(sig.as_ptr(), sig_out, FCMP_SAL_SIGNATURE_SIZE);
+            ptr::copy_nonoverlapping(key_image.as_ptr(), key_image_out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Verify an SA+L signature for an input and its key image.
+///
+/// # Safety
+/// - `sig` must point to `FCMP_SAL_SIGNATURE_SIZE` bytes
+/// - `input` must point to a valid `FcmpInput`
+/// - `key_image` must point to 32 bytes
+/// - `message` must point to `message_len` bytes
+///
+/// `prehashed` must match the flag the signature was made with.
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the signature is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it is not
+/// - `FCMP_ERROR_INVALID_POINT` if the key image is not a prime-order point
+/// - `FCMP_ERROR_INVALID_PARAM` for a bad `prehashed` flag, as in `fcmp_sal_sign`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_sal_verify(
+    sig: *const u8,
+    input: *const FcmpInput,
+    key_image: *const u8,
+    message: *const u8,
+    message_len: usize,
+    prehashed: i32,
+) -> i32 {
+    if sig.is_null() || input.is_null() || key_image.is_null() ||
+       (message.is_null() && message_len > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let prehashed = match prehashed_flag(prehashed, message_len) {
+        Ok(prehashed) => prehashed,
+        Err(e) => return e,
+    };
+
+    let message = if message_len > 0 {
+        slice::from_raw_parts(message, message_len)
+    } else {
+        &[]
+    };
+
+    sal_verify(
+        slice::from_raw_parts(sig, FCMP_SAL_SIGNATURE_SIZE),
+        &*input,
+        slice::from_raw_parts(key_image, POINT_SIZE),
+        message,
+        prehashed,
+    )
+}
+
+/// Size of one signer's entry in an aggregate SA+L signature: A_1 || A_2 || A_3 || s_x
+pub const FCMP_SAL_AGGREGATE_ENTRY_SIZE: usize = 3 * POINT_SIZE + SCALAR_SIZE;
+
+/// Size of an aggregate SA+L signature over `count` signers: one entry per
+/// signer followed by the aggregated S_o || S_w || S_z
+///
+/// # Returns
+/// - Size in bytes, or 0 if `count` is 0
+#[no_mangle]
+pub extern "C" fn fcmp_sal_aggregate_size(count: u32) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    count as usize * FCMP_SAL_AGGREGATE_ENTRY_SIZE + 3 * SCALAR_SIZE
+}
+
+/// Borrow `count` caller messages given as pointer and length arrays
+unsafe fn message_slices<'a>(
+    messages: *const *const u8,
+    msg_lens: *const usize,
+    count: usize,
+) -> Option<Vec<&'a [u8]>> {
+    let messages = slice::from_raw_parts(messages, count);
+    let msg_lens = slice::from_raw_parts(msg_lens, count);
+    messages
+        .iter()
+        .zip(msg_lens)
+        .map(|(&message, &len)| match (message.is_null(), len) {
+            (_, 0) => Some(&[][..]),
+            (true, _) => None,
+            (false, len) => Some(slice::from_raw_parts(message, len)),
+        })
+        .collect()
+}
+
+/// Aggregation weights z_i, each hashing every signer's input, key image,
+/// nonce commitments and message together with the signer's index
+fn sal_aggregate_weights(
+    inputs: &[FcmpInput],
+    key_images: &[u8],
+    nonces: &[&[u8]],
+    messages: &[&[u8]],
+) -> Option<Vec<curve25519_dalek::scalar::Scalar>> {
+    use curve25519_dalek::scalar::Scalar;
+
+    let mut transcript = Transcript::with_domain(b"WATTx_FCMP_SAL_Agg_v1");
+    transcript.absorb_count(inputs.len());
+    for (i, input) in inputs.iter().enumerate() {
+        for field in [&input.o_tilde, &input.i_tilde, &input.r, &input.c_tilde] {
+            transcript.absorb(field);
+        }
+        transcript.absorb(&key_images[i * POINT_SIZE..(i + 1) * POINT_SIZE]);
+        transcript.absorb(nonces[i]);
+        transcript.absorb_count(messages[i].len());
+        transcript.absorb(messages[i]);
+    }
+
+    (0..inputs.len())
+        .map(|i| {
+            let mut indexed = transcript.clone();
+            indexed.absorb_count(i);
+            Some(indexed.challenge()).filter(|z| *z != Scalar::ZERO)
+        })
+        .collect()
+}
+
+/// Combine SA+L signatures from several signers into one aggregate.
+///
+/// Uses Schnorr half-aggregation. Each signer keeps its nonce commitments
+/// and s_x, because s_x multiplies per-signer bases (I~, R). The responses on
+/// the shared bases T, U and V are folded into single scalars,
+/// S = sum(z_i * s_i). For n signers this is 128n + 96 bytes instead of
+/// 224n.
+///
+/// Rogue-key handling: no public keys are ever combined. Every signer's
+/// relations are still checked against its own input and key image, under a
+/// challenge that binds them. The weights z_i hash every component of every
+/// signature, so a signer who has seen the others' signatures cannot pick
+/// its own so that terms cancel. No proof of possession is needed.
+///
+/// Every signature is verified before it is aggregated.
+///
+/// # Safety
+/// - `agg_sig_out` must have `agg_sig_max_len` writable bytes
+/// - `agg_sig_len_out` must be writable
+/// - `sigs` must point to `count * FCMP_SAL_SIGNATURE_SIZE` bytes
+/// - `inputs` must point to `count` valid `FcmpInput`s
+/// - `key_images` must point to `count * 32` bytes
+/// - `messages` and `msg_lens` must each point to `count` entries, each
+///   message pointing to its length in bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_MEMORY` if `agg_sig_max_len` is below `fcmp_sal_aggregate_size(count)`
+/// - The first failing signature's verification error otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_sal_aggregate(
+    agg_sig_out: *mut u8,
+    agg_sig_len_out: *mut usize,
+    agg_sig_max_len: usize,
+    sigs: *const u8,
+    inputs: *const FcmpInput,
+    key_images: *const u8,
+    messages: *const *const u8,
+    msg_lens: *const usize,
+    count: u32,
+) -> i32 {
+    if agg_sig_out.is_null() || agg_sig_len_out.is_null() || sigs.is_null() || inputs.is_null() ||
+       key_images.is_null() || messages.is_null() || msg_lens.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let n = count as usize;
+    let agg_len = fcmp_sal_aggregate_size(count);
+    if agg_sig_max_len < agg_len {
+        return FCMP_ERROR_MEMORY;
+    }
+
+    let sigs = slice::from_raw_parts(sigs, n * FCMP_SAL_SIGNATURE_SIZE);
+    let inputs = slice::from_raw_parts(inputs, n);
+    let key_images = slice::from_raw_parts(key_images, n * POINT_SIZE);
+    let messages = match message_slices(messages, msg_lens, n) {
+        Some(messages) => messages,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    let sigs: Vec<&[u8]> = sigs.chunks_exact(FCMP_SAL_SIGNATURE_SIZE).collect();
+    for (i, sig) in sigs.iter().enumerate() {
+        let result = sal_verify(sig, &inputs[i], &key_images[i * POINT_SIZE..(i + 1) * POINT_SIZE], messages[i], false);
+        if result != FCMP_SUCCESS {
+            return result;
+        }
+    }
+
+    let nonces: Vec<&[u8]> = sigs.iter().map(|sig| &sig[..3 * POINT_SIZE]).collect();
+    let z = match sal_aggregate_weights(inputs, key_images, &nonces, &messages) {
+        Some(z) => z,
+        None => return FCMP_ERROR_PROOF_GENERATION,
+    };
+
+    let out = slice::from_raw_parts_mut(agg_sig_out, agg_len);
+    let mut folded = [Scalar::ZERO; 3];
+    for (i, sig) in sigs.iter().enumerate() {
+        let entry = &mut out[i * FCMP_SAL_AGGREGATE_ENTRY_SIZE..(i + 1) * FCMP_SAL_AGGREGATE_ENTRY_SIZE];
+        entry.copy_from_slice(&sig[..FCMP_SAL_AGGREGATE_ENTRY_SIZE]);
+        for (j, sum) in folded.iter_mut().enumerate() {
+            let offset = FCMP_SAL_AGGREGATE_ENTRY_SIZE + j * SCALAR_SIZE;
+            // Verified above, so the response is canonical
+            let s = decode_scalar(&sig[offset..offset + SCALAR_SIZE]).unwrap_or(Scalar::ZERO);
+            *sum += z[i] * s;
+        }
+    }
+    for (j, sum) in folded.iter().enumerate() {
+        let offset = n * FCMP_SAL_AGGREGATE_ENTRY_SIZE + j * SCALAR_SIZE;
+        out[offset..offset + SCALAR_SIZE].copy_from_slice(sum.as_bytes());
+    }
+
+    *agg_sig_len_out = agg_len;
+    FCMP_SUCCESS
+}
+
+/// Verify an aggregate SA+L signature from `fcmp_sal_aggregate`.
+///
+/// Checks the z-weighted sum of every signer's three relations in a single
+/// multiscalar multiplication.
+///
+/// # Safety
+/// - `agg_sig` must point to `agg_sig_len` bytes
+/// - `inputs`, `key_images`, `messages` and `msg_lens` must be valid as for
+///   `fcmp_sal_aggregate`
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the aggregate is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it is not
+/// - `FCMP_ERROR_INVALID_POINT` if a key image is not a prime-order point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_sal_aggregate_verify(
+    agg_sig: *const u8,
+    agg_sig_len: usize,
+    inputs: *const FcmpInput,
+    key_images: *const u8,
+    messages: *const *const u8,
+    msg_lens: *const usize,
+    count: u32,
+) -> i32 {
+    if agg_sig.is_null() || inputs.is_null() || key_images.is_null() ||
+       messages.is_null() || msg_lens.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    let n = count as usize;
+    if agg_sig_len != fcmp_sal_aggregate_size(count) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let agg_sig = slice::from_raw_parts(agg_sig, agg_sig_len);
+    let inputs = slice::from_raw_parts(inputs, n);
+    let key_images = slice::from_raw_parts(key_images, n * POINT_SIZE);
+    let messages = match message_slices(messages, msg_lens, n) {
+        Some(messages) => messages,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    let entries: Vec<&[u8]> = agg_sig[..n * FCMP_SAL_AGGREGATE_ENTRY_SIZE]
+        .chunks_exact(FCMP_SAL_AGGREGATE_ENTRY_SIZE)
+        .collect();
+    let nonces: Vec<&[u8]> = entries.iter().map(|entry| &entry[..3 * POINT_SIZE]).collect();
+    let mut folded = [Scalar::ZERO; 3];
+    for (j, sum) in folded.iter_mut().enumerate() {
+        let offset = n * FCMP_SAL_AGGREGATE_ENTRY_SIZE + j * SCALAR_SIZE;
+        *sum = match decode_scalar(&agg_sig[offset..offset + SCALAR_SIZE]) {
+            Some(s) => s,
+            None => return FCMP_ERROR_INVALID_SCALAR,
+        };
+    }
+    let [s_o, s_w, s_z] = folded;
+
+    let z = match sal_aggregate_weights(inputs, key_images, &nonces, &messages) {
+        Some(z) => z,
+        None => return FCMP_ERROR_PROOF_VERIFICATION,
+    };
+    let [t, u, v] = match rerandomization_generators() {
+        Some(gens) => gens,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    // sum_i z_i * [w_1*(s_x_i*G + s_o_i*T - A_1i - c_i*O~_i)
+    //            + w_2*(s_x_i*I~_i - s_w_i*U - A_2i - c_i*L_i)
+    //            + w_3*(s_x_i*R_i - s_w_i*V - s_z_i*T - A_3i)] == 0
+    let w = [(); 3].map(|_| Scalar::random(&mut OsRng));
+    let mut g_scalar = Scalar::ZERO;
+    let mut scalars = vec![w[0] * s_o - w[2] * s_z, -(w[1] * s_w), -(w[2] * s_w)];
+    let mut points = vec![t, u, v];
+    for (i, entry) in entries.iter().enumerate() {
+        let [o_tilde, i_tilde, r, _] = match validate_input(&inputs[i]) {
+            Ok(points) => points,
+            Err(e) => return e,
+        };
+        let key_image = &key_images[i * POINT_SIZE..(i + 1) * POINT_SIZE];
+        let l = match decode_point(key_image) {
+            Some(l) if l.is_torsion_free() && !l.is_identity() => l,
+            _ => return FCMP_ERROR_INVALID_POINT,
+        };
+
+        let mut a = [EdwardsPoint::default(); 3];
+        for (point, chunk) in a.iter_mut().zip(nonces[i].chunks_exact(POINT_SIZE)) {
+            *point = match decode_point(chunk) {
+                Some(p) => p,
+                None => return FCMP_ERROR_INVALID_POINT,
+            };
+        }
+        let s_x = match decode_scalar(&entry[3 * POINT_SIZE..]) {
+            Some(s) => s,
+            None => return FCMP_ERROR_INVALID_SCALAR,
+        };
+
+        let c = sal_challenge(&inputs[i], key_image, nonces[i], messages[i], false);
+        if is_degenerate_challenge(&c) {
+            return FCMP_ERROR_PROOF_VERIFICATION;
+        }
+
+        let zs = z[i] * s_x;
+        let zc = z[i] * c;
+        g_scalar += w[0] * zs;
+        scalars.extend([
+            -(w[0] * z[i]),
+            -(w[0] * zc),
+            w[1] * zs,
+            -(w[1] * z[i]),
+            -(w[1] * zc),
+            w[2] * zs,
+            -(w[2] * z[i]),
+        ]);
+        points.extend([a[0], o_tilde, i_tilde, a[1], l, r, a[2]]);
+    }
+    scalars.push(g_scalar);
+    points.push(ED25519_BASEPOINT_POINT);
+
+    if EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Opaque spend of one output: the spend key, the re-randomization blinders
+/// and the input they produce, kept together so the input and its SA+L
+/// signature always use the same blinders
+pub struct FcmpSpendContext {
+    secret: curve25519_dalek::scalar::Scalar,
+    blinders: [curve25519_dalek::scalar::Scalar; 4],
+    input: FcmpInput,
+}
+
+impl Drop for FcmpSpendContext {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.blinders.zeroize();
+    }
+}
+
+/// Start spending an output.
+///
+/// Re-randomizes the output as `fcmp_output_rerandomize` does, with a fresh
+/// random seed, and keeps the blinders inside the context. They never cross
+/// the FFI boundary, so `fcmp_spend_context_input` and
+/// `fcmp_spend_context_sign` cannot be given mismatched blinders.
+///
+/// # Safety
+/// - `output` must point to 96 bytes: O || I || C
+/// - `secret` must point to the 32-byte spend key x, with O = x*G
+///
+/// # Returns
+/// - Context handle to release with `fcmp_spend_context_free`
+/// - Null if a pointer is null, an output point or the secret is invalid,
+///   or the secret does not open O
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_spend_context_new(output: *const u8, secret: *const u8) -> *mut FcmpSpendContext {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use rand_core::RngCore;
+
+    if output.is_null() || secret.is_null() {
+        return ptr::null_mut();
+    }
+
+    let mut x = match decode_scalar(slice::from_raw_parts(secret, SCALAR_SIZE)) {
+        Some(x) => x,
+        None => return ptr::null_mut(),
+    };
+    if decode_point(slice::from_raw_parts(output, POINT_SIZE)) != Some(x * ED25519_BASEPOINT_POINT) {
+        x.zeroize();
+        return ptr::null_mut();
+    }
+
+    let mut seed = [0u8; SCALAR_SIZE];
+    OsRng.fill_bytes(&mut seed);
+    let mut input = FcmpInput { o_tilde: [0u8; 64], i_tilde: [0u8; 64], r: [0u8; 64], c_tilde: [0u8; 64] };
+    let mut blinder_bytes = [0u8; FCMP_BLINDERS_SIZE];
+    let result = fcmp_output_rerandomize(&mut input, blinder_bytes.as_mut_ptr(), output, seed.as_ptr());
+    let blinders = decode_blinders(&blinder_bytes);
+    seed.zeroize();
+    blinder_bytes.zeroize();
+
+    match blinders {
+        Some(blinders) if result == FCMP_SUCCESS => {
+            Box::into_raw(Box::new(FcmpSpendContext { secret: x, blinders, input }))
+        }
+        _ => {
+            x.zeroize();
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Copy out the re-randomized input of a spend context.
+///
+/// # Safety
+/// - `ctx` must be a live handle from `fcmp_spend_context_new`
+/// - `input_out` must point to a writable `FcmpInput`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_spend_context_input(ctx: *const FcmpSpendContext, input_out: *mut FcmpInput) -> i32 {
+    if ctx.is_null() || input_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let input = &(*ctx).input;
+    ptr::write(
+        input_out,
+        FcmpInput { o_tilde: input.o_tilde, i_tilde: input.i_tilde, r: input.r, c_tilde: input.c_tilde },
+    );
+    FCMP_SUCCESS
+}
+
+/// Sign a spend context's input (SA+L) over a raw message and derive its
+/// key image.
+///
+/// The signature verifies with `fcmp_sal_verify` against the input from
+/// `fcmp_spend_context_input` on the same context, with `prehashed = 0`.
+///
+/// # Safety
+/// - `ctx` must be a live handle from `fcmp_spend_context_new`
+/// - `message` must point to `message_len` bytes
+/// - `sig_out` must point to at least `FCMP_SAL_SIGNATURE_SIZE` bytes of writable memory
+/// - `key_image_out` must point to at least 32 bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - Other error codes as `fcmp_sal_sign`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_spend_context_sign(
+    ctx: *const FcmpSpendContext,
+    message: *const u8,
+    message_len: usize,
+    sig_out: *mut u8,
+    key_image_out: *mut u8,
+) -> i32 {
+    if ctx.is_null() || sig_out.is_null() || key_image_out.is_null() ||
+       (message.is_null() && message_len > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let message = if message_len > 0 {
+        slice::from_raw_parts(message, message_len)
+    } else {
+        &[]
+    };
+
+    let ctx = &*ctx;
+    match sal_sign(&ctx.secret, &ctx.blinders, &ctx.input, message, false) {
+        Ok((sig, key_image)) => {
+            ptr::copy_nonoverlapping(sig.as_ptr(), sig_out, FCMP_SAL_SIGNATURE_SIZE);
+            ptr::copy_nonoverlapping(key_image.as_ptr(), key_image_out, POINT_SIZE);
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Free a spend context, wiping its secret and blinders.
+///
+/// # Safety
+/// - `ctx` must be null or a handle from `fcmp_spend_context_new` not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_spend_context_free(ctx: *mut FcmpSpendContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+// ============================================================================
+// Schnorr Signatures
+// ============================================================================
+
+/// Size of a Schnorr signature: R || s
+pub const FCMP_SCHNORR_SIGNATURE_SIZE: usize = POINT_SIZE + SCALAR_SIZE;
+
+/// Schnorr challenge over the nonce commitment, public key and the
+/// length-prefixed message, under a separate domain for prehashed messages
+fn schnorr_challenge(
+    nonce: &[u8],
+    public: &[u8],
+    message: &[u8],
+    prehashed: bool,
+) -> curve25519_dalek::scalar::Scalar {
+    let domain: &[u8] = if prehashed { b"WATTx_Schnorr_Prehashed_v1" } else { b"WATTx_Schnorr_v1" };
+    let mut transcript = Transcript::with_domain(domain);
+    transcript.absorb(nonce);
+    transcript.absorb(public);
+    transcript.absorb_count(message.len());
+    transcript.absorb(message);
+    transcript.challenge()
+}
+
+/// Decode a Schnorr public key; it must be a prime-order point
+fn decode_schnorr_public(bytes: &[u8]) -> Option<curve25519_dalek::edwards::EdwardsPoint> {
+    use curve25519_dalek::traits::IsIdentity;
+
+    decode_point(bytes).filter(|p| p.is_torsion_free() && !p.is_identity())
+}
+
+/// Decode a signature into R and s, and the challenge binding it to `public`
+/// and `message`
+fn schnorr_parts(
+    sig: &[u8],
+    public: &[u8],
+    message: &[u8],
+    prehashed: bool,
+) -> Result<(curve25519_dalek::edwards::EdwardsPoint, curve25519_dalek::scalar::Scalar, curve25519_dalek::scalar::Scalar), i32> {
+    let r = decode_point(&sig[..POINT_SIZE]).ok_or(FCMP_ERROR_INVALID_POINT)?;
+    let s = decode_scalar(&sig[POINT_SIZE..]).ok_or(FCMP_ERROR_INVALID_SCALAR)?;
+    let c = schnorr_challenge(&sig[..POINT_SIZE], public, message, prehashed);
+    Ok((r, s, c))
+}
+
+/// Check one Schnorr signature against the cofactored equation
+/// 8*(s*G - R - c*P) == 0
+fn schnorr_verify(sig: &[u8], public: &[u8], message: &[u8], prehashed: bool) -> i32 {
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::traits::IsIdentity;
+
+    let p = match decode_schnorr_public(public) {
+        Some(p) => p,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+    let (r, s, c) = match schnorr_parts(sig, public, message, prehashed) {
+        Ok(parts) => parts,
+        Err(e) => return e,
+    };
+
+    let check = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c, &p, &s) - r;
+    if check.mul_by_cofactor().is_identity() {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Sign a message with a plain Schnorr signature.
+///
+/// With P = x*G, the signature is (R = k*G, s = k + c*x) for the challenge
+/// c = H("WATTx_Schnorr_v1" || R || P || message). As in RFC 8032 the nonce
+/// is derived rather than drawn, k = PRF("WATTx_Schnorr_Nonce_v1", x, P ||
+/// message), so signing the same message twice yields the same signature and
+/// a weak RNG can never leak the key through a repeated nonce.
+///
+/// With `prehashed = 1`, `message` is a digest from `fcmp_message_prehash`.
+/// Both the challenge and the nonce then use their own domains
+/// ("WATTx_Schnorr_Prehashed_v1", "WATTx_Schnorr_Prehashed_Nonce_v1"), so
+/// the same 32 bytes signed raw and prehashed never share a nonce.
+///
+/// # Safety
+/// - `sig_out` must point to at least `FCMP_SCHNORR_SIGNATURE_SIZE` bytes of writable memory
+/// - `secret` must point to the 32-byte secret key x
+/// - `message` must point to `msg_len` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `prehashed` is not 0 or 1, or is 1 with a
+///   `msg_len` other than `FCMP_PREHASH_SIZE`
+/// - `FCMP_ERROR_INVALID_SCALAR` if the secret is zero or not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_schnorr_sign(
+    sig_out: *mut u8,
+    secret: *const u8,
+    message: *const u8,
+    msg_len: usize,
+    prehashed: i32,
+) -> i32 {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    if sig_out.is_null() || secret.is_null() || (message.is_null() && msg_len > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let prehashed = match prehashed_flag(prehashed, msg_len) {
+        Ok(prehashed) => prehashed,
+        Err(e) => return e,
+    };
+
+    let message = if msg_len > 0 {
+        slice::from_raw_parts(message, msg_len)
+    } else {
+        &[]
+    };
+
+    let mut x = match decode_scalar(slice::from_raw_parts(secret, SCALAR_SIZE)) {
+        Some(x) if x != Scalar::ZERO => x,
+        _ => return FCMP_ERROR_INVALID_SCALAR,
+    };
+    let public = (&x * ED25519_BASEPOINT_TABLE).compress().to_bytes();
+
+    let mut nonce_input = Vec::with_capacity(POINT_SIZE + message.len());
+    nonce_input.extend_from_slice(&public);
+    nonce_input.extend_from_slice(message);
+    let nonce_domain: &[u8] = if prehashed { b"WATTx_Schnorr_Prehashed_Nonce_v1" } else { b"WATTx_Schnorr_Nonce_v1" };
+    let mut k = prf_scalar(nonce_domain, x.as_bytes(), &nonce_input);
+
+    let r = (&k * ED25519_BASEPOINT_TABLE).compress().to_bytes();
+    let c = schnorr_challenge(&r, &public, message, prehashed);
+    let s = k + c * x;
+    k.zeroize();
+    x.zeroize();
+
+    ptr::copy_nonoverlapping(r.as_ptr(), sig_out, POINT_SIZE);
+    ptr::copy_nonoverlapping(s.as_bytes().as_ptr(), sig_out.add(POINT_SIZE), SCALAR_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Verify a Schnorr signature from `fcmp_schnorr_sign`.
+///
+/// Checks the cofactored equation 8*(s*G - R - c*P) == 0.
+///
+/// # Safety
+/// - `sig` must point to `FCMP_SCHNORR_SIGNATURE_SIZE` bytes
+/// - `public` must point to 32 bytes
+/// - `message` must point to `msg_len` bytes
+///
+/// `prehashed` must match the flag the signature was made with.
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the signature is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it is not
+/// - `FCMP_ERROR_INVALID_POINT` if the public key is not a prime-order point
+///   or R does not decode
+/// - `FCMP_ERROR_INVALID_SCALAR` if s is not canonical
+/// - `FCMP_ERROR_INVALID_PARAM` for a bad `prehashed` flag, as in `fcmp_schnorr_sign`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_schnorr_verify(
+    sig: *const u8,
+    public: *const u8,
+    message: *const u8,
+    msg_len: usize,
+    prehashed: i32,
+) -> i32 {
+    if sig.is_null() || public.is_null() || (message.is_null() && msg_len > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    let prehashed = match prehashed_flag(prehashed, msg_len) {
+        Ok(prehashed) => prehashed,
+        Err(e) => return e,
+    };
+
+    let message = if msg_len > 0 {
+        slice::from_raw_parts(message, msg_len)
+    } else {
+        &[]
+    };
+
+    schnorr_verify(
+        slice::from_raw_parts(sig, FCMP_SCHNORR_SIGNATURE_SIZE),
+        slice::from_raw_parts(public, POINT_SIZE),
+        message,
+        prehashed,
+    )
+}
+
+/// Verify many Schnorr signatures with one multiscalar multiplication.
+///
+/// Each signature's equation is weighted by a fresh random scalar and the
+/// sum is checked at once; only if that fails is each signature checked on
+/// its own to find the bad ones. `results_out[i]` receives the code
+/// `fcmp_schnorr_verify` would return for signature i over a raw message.
+///
+/// Batch verification is variable-time in the signatures, keys and
+/// messages. Only use it on public data, such as signatures in a block.
+///
+/// # Safety
+/// - `sigs` must point to `count * FCMP_SCHNORR_SIGNATURE_SIZE` bytes
+/// - `publics` must point to `count * 32` bytes
+/// - `messages` and `msg_lens` must each point to `count` entries, each
+///   message pointing to its length in bytes
+/// - `results_out` must point to `count` writable i32 values
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every signature is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if any is not; see `results_out`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_schnorr_verify_batch(
+    sigs: *const u8,
+    publics: *const u8,
+    messages: *const *const u8,
+    msg_lens: *const usize,
+    count: u32,
+    results_out: *mut i32,
+) -> i32 {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+    if sigs.is_null() || publics.is_null() || messages.is_null() ||
+       msg_lens.is_null() || results_out.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let count = count as usize;
+    let sigs = slice::from_raw_parts(sigs, count * FCMP_SCHNORR_SIGNATURE_SIZE);
+    let publics = slice::from_raw_parts(publics, count * POINT_SIZE);
+    let messages = match message_slices(messages, msg_lens, count) {
+        Some(messages) => messages,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+    let results = slice::from_raw_parts_mut(results_out, count);
+
+    // sum_i w_i*(s_i*G - R_i - c_i*P_i), cofactored
+    let mut g_scalar = Scalar::ZERO;
+    let mut scalars = Vec::with_capacity(2 * count + 1);
+    let mut points = Vec::with_capacity(2 * count + 1);
+    for i in 0..count {
+        let sig = &sigs[i * FCMP_SCHNORR_SIGNATURE_SIZE..(i + 1) * FCMP_SCHNORR_SIGNATURE_SIZE];
+        let public = &publics[i * POINT_SIZE..(i + 1) * POINT_SIZE];
+
+        let p = match decode_schnorr_public(public) {
+            Some(p) => p,
+            None => {
+                results[i] = FCMP_ERROR_INVALID_POINT;
+                continue;
+            }
+        };
+        results[i] = match schnorr_parts(sig, public, messages[i], false) {
+            Ok((r, s, c)) => {
+                let w = Scalar::random(&mut OsRng);
+                g_scalar += w * s;
+                scalars.extend([-w, -(w * c)]);
+                points.extend([r, p]);
+                FCMP_SUCCESS
+            }
+            Err(e) => e,
+        };
+    }
+    scalars.push(g_scalar);
+    points.push(ED25519_BASEPOINT_POINT);
+
+    let combined = EdwardsPoint::vartime_multiscalar_mul(scalars, points);
+    if !combined.mul_by_cofactor().is_identity() {
+        for i in 0..count {
+            if results[i] == FCMP_SUCCESS {
+                let sig = &sigs[i * FCMP_SCHNORR_SIGNATURE_SIZE..(i + 1) * FCMP_SCHNORR_SIGNATURE_SIZE];
+                results[i] = schnorr_verify(sig, &publics[i * POINT_SIZE..(i + 1) * POINT_SIZE], messages[i], false);
+            }
+        }
+    }
+
+    if results.iter().all(|&r| r == FCMP_SUCCESS) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+// ============================================================================
+// Range Proofs
+// ============================================================================
+
+/// Widest range a proof can assert, in bits
+pub const FCMP_RANGE_MAX_BITS: u32 = 64;
+/// Size of each per-bit section: C_i || R0_i || R1_i || c0_i || s0_i || s1_i
+pub const FCMP_RANGE_BIT_SIZE: usize = 3 * POINT_SIZE + 3 * SCALAR_SIZE;
+
+/// Size of a range proof over `count` commitments of `bits` bits each.
+///
+/// Wire format: bits || (C_i || R0_i || R1_i || c0_i || s0_i || s1_i)*
+///
+/// # Returns
+/// Proof size in bytes, or 0 if `count` is 0, `bits` is outside
+/// `1..=FCMP_RANGE_MAX_BITS`, or the size overflows
+#[no_mangle]
+pub extern "C" fn fcmp_range_proof_size(count: u32, bits: u32) -> usize {
+    if count == 0 || bits == 0 || bits > FCMP_RANGE_MAX_BITS {
+        return 0;
+    }
+
+    (count as usize)
+        .checked_mul(bits as usize)
+        .and_then(|n| n.checked_mul(FCMP_RANGE_BIT_SIZE))
+        .and_then(|n| n.checked_add(1))
+        .unwrap_or(0)
+}
+
+/// Transcript for standalone range proofs
+fn range_transcript() -> Transcript {
+    Transcript::with_domain(b"WATTx_FCMP_Range_v1")
+}
+
+/// Range proof challenge over the commitments and every bit's points, then
+/// the bit width, continuing `transcript`
+fn range_challenge(
+    mut transcript: Transcript,
+    proof: &[u8],
+    commitments: &[u8],
+    bits: usize,
+) -> curve25519_dalek::scalar::Scalar {
+    use curve25519_dalek::scalar::Scalar;
+
+    let sections = proof[1..].chunks_exact(FCMP_RANGE_BIT_SIZE);
+    transcript.absorb_count(commitments.len() / POINT_SIZE + 3 * sections.len());
+    transcript.absorb(commitments);
+    for section in sections {
+        transcript.absorb(&section[..3 * POINT_SIZE]);
+    }
+    transcript.absorb_count(1);
+    transcript.absorb(Scalar::from(bits as u64).as_bytes());
+    transcript.challenge()
+}
+
+/// Prove every `values[m]*G + blindings[m]*H` commits to a value below 2^bits.
+///
+/// Each value is split into bit commitments C_i = b_i*G + r_i*H with
+/// sum(2^i * C_i) = C, and every C_i carries a Fiat-Shamir OR proof that it
+/// commits to 0 or 1. The proof is linear in the bit count; it stands in for
+/// an aggregated Bulletproof until one is integrated. The challenge continues
+/// `transcript`.
+unsafe fn range_prove(
+    values: &[u64],
+    blindings: &[curve25519_dalek::scalar::Scalar],
+    bits: usize,
+    transcript: Transcript,
+) -> Result<Vec<u8>, i32> {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    let g = ED25519_BASEPOINT_POINT;
+    let h = pedersen_h().ok_or(FCMP_ERROR_INTERNAL)?;
+
+    let mut proof = vec![0u8; 1 + values.len() * bits * FCMP_RANGE_BIT_SIZE];
+    proof[0] = bits as u8;
+    let mut commitments = Vec::with_capacity(values.len() * POINT_SIZE);
+
+    // Per bit: the bit itself and [r_i, k, c_sim, s_sim]
+    let mut bit_values = Vec::with_capacity(values.len() * bits);
+    let mut secrets = Vec::with_capacity(values.len() * bits);
+    for (value, blinding) in values.iter().zip(blindings) {
+        commitments.extend_from_slice((Scalar::from(*value) * g + blinding * h).compress().as_bytes());
+
+        // The top bit's blinding makes sum(2^i * r_i) equal the commitment's blinding
+        let mut weighted = Scalar::ZERO;
+        let mut power = Scalar::ONE;
+        for i in 0..bits {
+            let bit = ((value >> i) & 1) as u8;
+            let r_i = if i + 1 < bits {
+                Scalar::random(&mut OsRng)
+            } else {
+                (blinding - weighted) * power.invert()
+            };
+            weighted += power * r_i;
+            power += power;
+
+            let c_i = Scalar::from(bit) * g + r_i * h;
+            let k = Scalar::random(&mut OsRng);
+            let c_sim = Scalar::random(&mut OsRng);
+            let s_sim = Scalar::random(&mut OsRng);
+
+            // The branch for the real bit commits honestly; the other is simulated
+            let real = k * h;
+            let simulated = s_sim * h - c_sim * (c_i - Scalar::from(1 - bit) * g);
+            let (r0, r1) = if bit == 0 { (real, simulated) } else { (simulated, real) };
+
+            let offset = 1 + bit_values.len() * FCMP_RANGE_BIT_SIZE;
+            for (j, point) in [c_i, r0, r1].iter().enumerate() {
+                proof[offset + j * POINT_SIZE..offset + (j + 1) * POINT_SIZE]
+                    .copy_from_slice(point.compress().as_bytes());
+            }
+            bit_values.push(bit);
+            secrets.push([r_i, k, c_sim, s_sim]);
+        }
+    }
+
+    let c = range_challenge(transcript, &proof, &commitments, bits);
+    if is_degenerate_challenge(&c) {
+        bit_values.zeroize();
+        secrets.zeroize();
+        return Err(FCMP_ERROR_PROOF_GENERATION);
+    }
+
+    for (n, (bit, [r_i, k, c_sim, s_sim])) in bit_values.iter().zip(&secrets).enumerate() {
+        let c_real = c - c_sim;
+        let s_real = k + c_real * r_i;
+        let (c0, s0, s1) = if *bit == 0 { (c_real, s_real, *s_sim) } else { (*c_sim, *s_sim, s_real) };
+
+        let offset = 1 + n * FCMP_RANGE_BIT_SIZE + 3 * POINT_SIZE;
+        for (j, scalar) in [c0, s0, s1].iter().enumerate() {
+            proof[offset + j * SCALAR_SIZE..offset + (j + 1) * SCALAR_SIZE].copy_from_slice(scalar.as_bytes());
+        }
+    }
+    bit_values.zeroize();
+    secrets.zeroize();
+
+    Ok(proof)
+}
+
+/// Randomly weighted terms of a range proof's verification equations.
+///
+/// The proof holds iff sum(scalars[i] * points[i]) + g_scalar*G + h_scalar*H
+/// is the identity. Terms of several proofs can be summed into one check.
+struct RangeTerms {
+    bits: u32,
+    scalars: Vec<curve25519_dalek::scalar::Scalar>,
+    points: Vec<curve25519_dalek::edwards::EdwardsPoint>,
+    g_scalar: curve25519_dalek::scalar::Scalar,
+    h_scalar: curve25519_dalek::scalar::Scalar,
+}
+
+impl RangeTerms {
+    /// Evaluate the (possibly combined) equations with one multiscalar multiplication
+    unsafe fn holds(self) -> Result<bool, i32> {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::edwards::EdwardsPoint;
+        use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+        let h = pedersen_h().ok_or(FCMP_ERROR_INTERNAL)?;
+        let scalars = self.scalars.into_iter().chain([self.g_scalar, self.h_scalar]);
+        let points = self.points.into_iter().chain([ED25519_BASEPOINT_POINT, h]);
+        Ok(EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity())
+    }
+}
+
+/// Verify a range proof over concatenated 32-byte commitments with one
+/// batched multiscalar multiplication, returning the bit width it asserts.
+unsafe fn range_verify(proof: &[u8], commitments: &[u8]) -> Result<u32, i32> {
+    range_verify_with(proof, commitments, range_transcript())
+}
+
+/// `range_verify` with the challenge derived from `transcript`
+unsafe fn range_verify_with(proof: &[u8], commitments: &[u8], transcript: Transcript) -> Result<u32, i32> {
+    let terms = range_terms_with(proof, commitments, transcript)?;
+    let bits = terms.bits;
+    if terms.holds()? {
+        Ok(bits)
+    } else {
+        Err(FCMP_ERROR_PROOF_VERIFICATION)
+    }
+}
+
+/// Parse a range proof into its weighted verification terms
+fn range_terms(proof: &[u8], commitments: &[u8]) -> Result<RangeTerms, i32> {
+    range_terms_with(proof, commitments, range_transcript())
+}
+
+/// `range_terms` with the challenge derived from `transcript`
+fn range_terms_with(proof: &[u8], commitments: &[u8], transcript: Transcript) -> Result<RangeTerms, i32> {
+    use curve25519_dalek::scalar::Scalar;
+
+    let count = commitments.len() / POINT_SIZE;
+    let bits = match proof.first() {
+        Some(&bits) => bits as usize,
+        None => return Err(FCMP_ERROR_INVALID_PARAM),
+    };
+    if fcmp_range_proof_size(count as u32, bits as u32) != proof.len() {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let c = range_challenge(transcript, proof, commitments, bits);
+    if is_degenerate_challenge(&c) {
+        return Err(FCMP_ERROR_PROOF_VERIFICATION);
+    }
+
+    // Per bit: w0*(s0*H - R0 - c0*C_i) + w1*(s1*H - R1 - c1*(C_i - G)) == 0
+    // Per commitment: w_m*(sum(2^i * C_i) - C) == 0
+    let mut scalars = Vec::with_capacity(count * (3 * bits + 1));
+    let mut points = Vec::with_capacity(count * (3 * bits + 1));
+    let mut g_scalar = Scalar::ZERO;
+    let mut h_scalar = Scalar::ZERO;
+    let mut sections = proof[1..].chunks_exact(FCMP_RANGE_BIT_SIZE);
+    for commitment in commitments.chunks_exact(POINT_SIZE) {
+        let w_m = Scalar::random(&mut OsRng);
+        scalars.push(-w_m);
+        points.push(decode_point(commitment).ok_or(FCMP_ERROR_INVALID_POINT)?);
+
+        let mut power = Scalar::ONE;
+        for section in sections.by_ref().take(bits) {
+            let point = |j: usize| decode_point(&section[j * POINT_SIZE..(j + 1) * POINT_SIZE]);
+            let scalar = |j: usize| {
+                let offset = 3 * POINT_SIZE + j * SCALAR_SIZE;
+                decode_scalar(&section[offset..offset + SCALAR_SIZE])
+            };
+            let (c_i, r0, r1) = match (point(0), point(1), point(2)) {
+                (Some(c_i), Some(r0), Some(r1)) => (c_i, r0, r1),
+                _ => return Err(FCMP_ERROR_INVALID_POINT),
+            };
+            let (c0, s0, s1) = match (scalar(0), scalar(1), scalar(2)) {
+                (Some(c0), Some(s0), Some(s1)) => (c0, s0, s1),
+                _ => return Err(FCMP_ERROR_INVALID_SCALAR),
+            };
+            let c1 = c - c0;
+
+            let w0 = Scalar::random(&mut OsRng);
+            let w1 = Scalar::random(&mut OsRng);
+            h_scalar += w0 * s0 + w1 * s1;
+            g_scalar += w1 * c1;
+            scalars.push(-w0);
+            points.push(r0);
+            scalars.push(-w1);
+            points.push(r1);
+            scalars.push(w_m * power - w0 * c0 - w1 * c1);
+            points.push(c_i);
+            power += power;
+        }
+    }
+
+    Ok(RangeTerms { bits: bits as u32, scalars, points, g_scalar, h_scalar })
+}
+
+/// Prove that each of `count` commitments value*G + blinding*H opens to a
+/// value below 2^bits.
+///
+/// # Safety
+/// - `proof_out` must have at least `proof_max_len` bytes available
+/// - `proof_len_out` must be writable
+/// - `values` must point to `count` u64 values
+/// - `blindings` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the proof length in `proof_len_out`
+/// - `FCMP_ERROR_INVALID_PARAM` if `bits` is unsupported or a value does not fit
+/// - `FCMP_ERROR_MEMORY` if `proof_max_len` is below `fcmp_range_proof_size(count, bits)`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_range_prove(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    values: *const u64,
+    blindings: *const u8,
+    count: u32,
+    bits: u32,
+) -> i32 {
+    range_prove_ffi(proof_out, proof_len_out, proof_max_len, values, blindings, count, bits, range_transcript())
+}
+
+/// `fcmp_range_prove` with the challenge derived from `transcript`
+#[allow(clippy::too_many_arguments)]
+unsafe fn range_prove_ffi(
+    proof_out: *mut u8,
+    proof_len_out: *mut usize,
+    proof_max_len: usize,
+    values: *const u64,
+    blindings: *const u8,
+    count: u32,
+    bits: u32,
+    transcript: Transcript,
+) -> i32 {
+    if proof_out.is_null() || proof_len_out.is_null() || values.is_null() || blindings.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof_len = fcmp_range_proof_size(count, bits);
+    if proof_len == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if proof_max_len < proof_len {
+        return FCMP_ERROR_MEMORY;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let values = slice::from_raw_parts(values, count as usize);
+    if bits < u64::BITS && values.iter().any(|v| v >> bits != 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut blindings: Vec<Scalar> = slice::from_raw_parts(blindings, count as usize * SCALAR_SIZE)
+        .chunks_exact(SCALAR_SIZE)
+        .map(|chunk| {
+            let mut arr = [0u8; SCALAR_SIZE];
+            arr.copy_from_slice(chunk);
+            let b = Scalar::from_bytes_mod_order(arr);
+            arr.zeroize();
+            b
+        })
+        .collect();
+
+    let result = range_prove(values, &blindings, bits as usize, transcript);
+    blindings.zeroize();
+
+    match result {
+        Ok(proof) => {
+            ptr::copy_nonoverlapping(proof.as_ptr(), proof_out, proof.len());
+            *proof_len_out = proof.len();
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Verify a range proof that `count` commitments open to values below 2^bits.
+///
+/// # Safety
+/// - `proof` must point to `proof_len` bytes
+/// - `commitments` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the proof is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if it is invalid or asserts a different width
+/// - Other error codes if the proof is malformed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_range_verify(
+    proof: *const u8,
+    proof_len: usize,
+    commitments: *const u8,
+    count: u32,
+    bits: u32,
+) -> i32 {
+    if proof.is_null() || commitments.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof = slice::from_raw_parts(proof, proof_len);
+    let commitments = slice::from_raw_parts(commitments, count as usize * POINT_SIZE);
+    match range_verify(proof, commitments) {
+        Ok(proven) if proven == bits => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// Verify a range proof and report the bit width it asserts.
+///
+/// For audit logs: the width bounds every committed amount below 2^bits
+/// without revealing the amounts. `bits_out` is only written on success.
+///
+/// # Safety
+/// - `commitments` must point to `count * 32` bytes
+/// - `range_proof` must point to `range_len` bytes
+/// - `bits_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the proven width in `bits_out`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof is invalid
+/// - Other error codes if the proof is malformed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_and_report_range(
+    commitments: *const u8,
+    count: u32,
+    range_proof: *const u8,
+    range_len: usize,
+    bits_out: *mut u32,
+) -> i32 {
+    if commitments.is_null() || count == 0 || range_proof.is_null() || bits_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let proof = slice::from_raw_parts(range_proof, range_len);
+    let commitments = slice::from_raw_parts(commitments, count as usize * POINT_SIZE);
+    match range_verify(proof, commitments) {
+        Ok(bits) => {
+            *bits_out = bits;
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Verify many single-commitment range proofs with one multiscalar multiplication.
+///
+/// Proof m asserts `commitments[m]` opens to a value below 2^bits[m]. The
+/// randomly weighted equations of every well-formed proof are summed and
+/// checked at once; only if that fails is each proof checked on its own to
+/// find the bad ones. `results_out[m]` receives the code `fcmp_range_verify`
+/// would return for proof m.
+///
+/// # Safety
+/// - `proofs` and `proof_lens` must each point to `count` entries, with
+///   `proofs[m]` pointing to `proof_lens[m]` bytes
+/// - `commitments` must point to `count * 32` bytes
+/// - `bits` must point to `count` values
+/// - `results_out` must point to `count` writable i32 values
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every proof is valid
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if any is not; see `results_out`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_range_verify_batch(
+    proofs: *const *const u8,
+    proof_lens: *const usize,
+    commitments: *const u8,
+    bits: *const u32,
+    count: u32,
+    results_out: *mut i32,
+) -> i32 {
+    if proofs.is_null() || proof_lens.is_null() || commitments.is_null() ||
+       bits.is_null() || results_out.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::scalar::Scalar;
+
+    let count = count as usize;
+    let proof_ptrs = slice::from_raw_parts(proofs, count);
+    let proof_lens = slice::from_raw_parts(proof_lens, count);
+    let commitments = slice::from_raw_parts(commitments, count * POINT_SIZE);
+    let bits = slice::from_raw_parts(bits, count);
+    let results = slice::from_raw_parts_mut(results_out, count);
+
+    let mut proofs = Vec::with_capacity(count);
+    let mut combined = RangeTerms {
+        bits: 0,
+        scalars: Vec::new(),
+        points: Vec::new(),
+        g_scalar: Scalar::ZERO,
+        h_scalar: Scalar::ZERO,
+    };
+    for m in 0..count {
+        let proof: &[u8] = if proof_ptrs[m].is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(proof_ptrs[m], proof_lens[m])
+        };
+        let commitment = &commitments[m * POINT_SIZE..(m + 1) * POINT_SIZE];
+        proofs.push(proof);
+
+        results[m] = match range_terms(proof, commitment) {
+            Ok(terms) if terms.bits == bits[m] => {
+                combined.scalars.extend(terms.scalars);
+                combined.points.extend(terms.points);
+                combined.g_scalar += terms.g_scalar;
+                combined.h_scalar += terms.h_scalar;
+                FCMP_SUCCESS
+            }
+            Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+            Err(e) => e,
+        };
+    }
+
+    match combined.holds() {
+        Ok(true) => {}
+        Ok(false) => {
+            for m in 0..count {
+                if results[m] == FCMP_SUCCESS {
+                    let commitment = &commitments[m * POINT_SIZE..(m + 1) * POINT_SIZE];
+                    if let Err(e) = range_verify(proofs[m], commitment) {
+                        results[m] = e;
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            results.iter_mut().for_each(|r| *r = e);
+            return e;
+        }
+    }
+
+    if results.iter().all(|&r| r == FCMP_SUCCESS) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Check one block output: a well-formed commitment and a valid range proof
+fn validate_block_output(commitment: &[u8], proof: &[u8], bits: u32) -> i32 {
+    match decode_point(commitment) {
+        Some(c) if c.is_torsion_free() => {}
+        _ => return FCMP_ERROR_INVALID_POINT,
+    }
+
+    match unsafe { range_verify(proof, commitment) } {
+        Ok(proven) if proven == bits => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// First failing output among `indices`, as (index, error code)
+fn first_block_output_failure(
+    indices: std::ops::Range<usize>,
+    commitments: &[u8],
+    proofs: &[&[u8]],
+    bits: u32,
+) -> Option<(usize, i32)> {
+    indices.into_iter().find_map(|i| {
+        let commitment = &commitments[i * POINT_SIZE..(i + 1) * POINT_SIZE];
+        match validate_block_output(commitment, proofs[i], bits) {
+            FCMP_SUCCESS => None,
+            e => Some((i, e)),
+        }
+    })
+}
+
+/// Validate every output of a block.
+///
+/// Output i passes if `commitments[i]` is a canonical, torsion-free point
+/// and `range_proofs[i]` is a valid single-commitment range proof for it
+/// of exactly `bits` bits. With the `parallel` feature the outputs are
+/// split across the available cores; otherwise they are checked in order.
+/// Either way the reported failure is the one with the lowest index.
+///
+/// # Safety
+/// - `commitments` must point to `count * 32` bytes
+/// - `range_proofs` and `proof_lens` must each point to `count` entries,
+///   with `range_proofs[i]` pointing to `proof_lens[i]` bytes
+/// - `failed_index_out` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every output is valid
+/// - The first failing output's error code otherwise, with its index in
+///   `failed_index_out`
+/// - `FCMP_ERROR_INTERNAL` if a worker thread panics; `failed_index_out`
+///   is left unwritten
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_validate_block_outputs(
+    commitments: *const u8,
+    range_proofs: *const *const u8,
+    proof_lens: *const usize,
+    bits: u32,
+    count: u32,
+    failed_index_out: *mut u32,
+) -> i32 {
+    if failed_index_out.is_null() ||
+       (count > 0 && (commitments.is_null() || range_proofs.is_null() || proof_lens.is_null())) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+    if count == 0 {
+        return FCMP_SUCCESS;
+    }
+
+    let count = count as usize;
+    let commitments = slice::from_raw_parts(commitments, count * POINT_SIZE);
+    let proofs: Vec<&[u8]> = slice::from_raw_parts(range_proofs, count)
+        .iter()
+        .zip(slice::from_raw_parts(proof_lens, count))
+        .map(|(&proof, &len)| if proof.is_null() { &[][..] } else { slice::from_raw_parts(proof, len) })
+        .collect();
+
+    #[cfg(feature = "parallel")]
+    let failure = {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(count);
+        let chunk = count.div_ceil(threads);
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..count)
+                .step_by(chunk)
+                .map(|start| {
+                    let (commitments, proofs) = (commitments, &proofs);
+                    scope.spawn(move || {
+                        first_block_output_failure(start..(start + chunk).min(count), commitments, proofs, bits)
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|worker| worker.join()).collect()
+        });
+        match results.into_iter().collect::<Result<Vec<_>, _>>() {
+            Ok(failures) => failures.into_iter().flatten().min_by_key(|&(i, _)| i),
+            // A panicked worker has no output to blame, so report no index
+            Err(_) => return FCMP_ERROR_INTERNAL,
+        }
+    };
+    #[cfg(not(feature = "parallel"))]
+    let failure = first_block_output_failure(0..count, commitments, &proofs, bits);
+
+    match failure {
+        Some((i, e)) => {
+            *failed_index_out = i as u32;
+            e
+        }
+        None => FCMP_SUCCESS,
+    }
+}
+
+/// Transcript shared by both halves of a proof bundle. The membership proof
+/// is made under it directly; the range proof continues it after absorbing
+/// the membership proof.
+fn bundle_transcript(bits: u32) -> Transcript {
+    let mut transcript = Transcript::with_domain(b"WATTx_FCMP_Bundle_v1");
+    transcript.absorb(&bits.to_le_bytes());
+    transcript
+}
+
+/// Generate a membership proof and a range proof bound together by one
+/// shared transcript.
+///
+/// The bundle is the membership proof followed by the range proof over
+/// `values[m]*G + blindings[m]*H`. The range proof's challenge covers the
+/// membership proof, so neither half verifies next to a half taken from
+/// another bundle, and neither verifies on its own with `fcmp_verify` or
+/// `fcmp_range_verify`. The branch must have at least one layer.
+///
+/// # Safety
+/// - `bundle_out` must point to `bundle_max_len` writable bytes
+/// - `bundle_len_out` must be writable
+/// - `tree_root`, `output` and `branch` must be valid as for `fcmp_prove`
+/// - `values` must point to `count` u64 values
+/// - `blindings` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the bundle length in `bundle_len_out`
+/// - `FCMP_ERROR_INVALID_PARAM` for a zero-layer branch, or as `fcmp_range_prove`
+/// - `FCMP_ERROR_MEMORY` if `bundle_max_len` cannot hold both proofs
+/// - Other error codes as `fcmp_prove`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_prove_bundle(
+    bundle_out: *mut u8,
+    bundle_len_out: *mut usize,
+    bundle_max_len: usize,
+    tree_root: *const u8,
+    output: *const u8,
+    branch: *const FcmpBranch,
+    values: *const u64,
+    blindings: *const u8,
+    count: u32,
+    bits: u32,
+) -> i32 {
+    if bundle_out.is_null() || bundle_len_out.is_null() || branch.is_null() || (*branch).num_layers == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let range_len = fcmp_range_proof_size(count, bits);
+    if range_len == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut membership_len = 0usize;
+    let result = prove_with_transcript(
+        bundle_out,
+        &mut membership_len,
+        bundle_max_len,
+        tree_root,
+        output,
+        branch,
+        bundle_transcript(bits),
+        None,
+    );
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    let mut transcript = bundle_transcript(bits);
+    transcript.absorb(slice::from_raw_parts(bundle_out, membership_len));
+
+    let mut range_written = 0usize;
+    let result = range_prove_ffi(
+        bundle_out.add(membership_len),
+        &mut range_written,
+        bundle_max_len - membership_len,
+        values,
+        blindings,
+        count,
+        bits,
+        transcript,
+    );
+    if result == FCMP_SUCCESS {
+        *bundle_len_out = membership_len + range_written;
+    }
+    result
+}
+
+/// Verify a bundle from `fcmp_prove_bundle` against the tree root and the
+/// `count` commitments its range proof covers.
+///
+/// # Safety
+/// - `bundle` must point to `bundle_len` bytes
+/// - `tree_root` and `input` must be valid as for `fcmp_verify`
+/// - `commitments` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if both proofs verify under the shared transcript
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if either fails, including a half
+///   swapped in from another bundle, or the range proof asserts another width
+/// - `FCMP_ERROR_INVALID_PARAM` if the bundle is malformed or its membership
+///   proof has no layers
+/// - Other error codes as `fcmp_verify` and `fcmp_range_verify`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_bundle(
+    bundle: *const u8,
+    bundle_len: usize,
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    commitments: *const u8,
+    count: u32,
+    bits: u32,
+) -> i32 {
+    if bundle.is_null() || tree_root.is_null() || input.is_null() || commitments.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let bundle = slice::from_raw_parts(bundle, bundle_len);
+    let (membership, range) = match proof_prefix_len(bundle) {
+        Ok(len) => bundle.split_at(len),
+        Err(e) => return e,
+    };
+    if proof_num_layers(membership) == Ok(0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tree_root = slice::from_raw_parts(tree_root, POINT_SIZE);
+    let result = with_params(|params| {
+        verify_proof_batched(tree_root, membership, bundle_transcript(bits), params, None)
+    })
+    .unwrap_or_else(|e| e);
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    let mut transcript = bundle_transcript(bits);
+    transcript.absorb(membership);
+    let commitments = slice::from_raw_parts(commitments, count as usize * POINT_SIZE);
+    match range_verify_with(range, commitments, transcript) {
+        Ok(proven) if proven == bits => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+/// Size of each commitment's opening proof in a reserves proof: R || s_v || s_b
+pub const FCMP_RESERVES_OPENING_SIZE: usize = POINT_SIZE + 2 * SCALAR_SIZE;
+
+/// Size of a reserves proof over `count` commitments.
+///
+/// Wire format: (R_i || s_v,i || s_b,i)* || range proof
+///
+/// # Returns
+/// Proof size in bytes, or 0 if `count` is 0 or the size overflows
+#[no_mangle]
+pub extern "C" fn fcmp_reserves_proof_size(count: u32) -> usize {
+    if count == 0 {
+        return 0;
+    }
+
+    (count as usize)
+        .checked_mul(FCMP_RESERVES_OPENING_SIZE)
+        .and_then(|n| n.checked_add(fcmp_range_proof_size(1, FCMP_RANGE_MAX_BITS)))
+        .unwrap_or(0)
+}
+
+/// Transcript of a reserves proof over the commitments and claimed minimum
+fn reserves_transcript(commitments: &[u8], min_total: u64) -> Transcript {
+    let mut transcript = Transcript::with_domain(b"WATTx_Reserves_v1");
+    transcript.absorb_count(commitments.len() / POINT_SIZE);
+    for commitment in commitments.chunks_exact(POINT_SIZE) {
+        transcript.absorb(commitment);
+    }
+    transcript.absorb(&min_total.to_le_bytes());
+    transcript
+}
+
+/// Decode a reserves commitment set, rejecting repeats and torsion.
+///
+/// Counting one output twice would double its value, so every encoding
+/// must be distinct. A torsion component would let C and C + T pass as two
+/// commitments to the same value, so only torsion-free points are taken,
+/// which also makes distinct encodings distinct commitments.
+fn reserves_commitments(commitments: &[u8]) -> Result<Vec<curve25519_dalek::edwards::EdwardsPoint>, i32> {
+    let points = commitments
+        .chunks_exact(POINT_SIZE)
+        .map(|bytes| decode_point(bytes).filter(|p| p.is_torsion_free()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(FCMP_ERROR_INVALID_POINT)?;
+
+    let mut keys: Vec<&[u8]> = commitments.chunks_exact(POINT_SIZE).collect();
+    keys.sort_unstable();
+    if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+    Ok(points)
+}
+
+/// Prove control of commitments whose values total at least `min_total`.
+///
+/// For each C_i = v_i*G + b_i*H the proof shows knowledge of (v_i, b_i)
+/// with a Schnorr proof on G and H: R_i = k_v*G + k_b*H, s_v = k_v + c*v_i,
+/// s_b = k_b + c*b_i, all under one challenge. A range proof then shows
+/// sum(C_i) - min_total*G commits to a value below 2^64, that is
+/// sum(v_i) >= min_total, without revealing the total. Its challenge covers
+/// the opening proofs. The commitments themselves are public; only their
+/// values and the total stay hidden. Each commitment may appear only once.
+///
+/// # Safety
+/// - `proof_out` must point to `fcmp_reserves_proof_size(count)` bytes of writable memory
+/// - `secrets` must point to `count * 64` bytes: value || blinding for each
+///   commitment, both 32-byte scalars
+/// - `commitments` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if a commitment is repeated
+/// - `FCMP_ERROR_INVALID_SCALAR` if a secret is not canonical or a value
+///   does not fit in 64 bits
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment is not a canonical,
+///   torsion-free point
+/// - `FCMP_ERROR_PROOF_GENERATION` if a secret does not open its commitment
+///   or the total is below `min_total` (or 2^64 or more above it)
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_reserves_prove(
+    proof_out: *mut u8,
+    secrets: *const u8,
+    commitments: *const u8,
+    count: u32,
+    min_total: u64,
+) -> i32 {
+    if proof_out.is_null() || secrets.is_null() || commitments.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let count = count as usize;
+    let secrets = slice::from_raw_parts(secrets, count * 2 * SCALAR_SIZE);
+    let commitments = slice::from_raw_parts(commitments, count * POINT_SIZE);
+    if let Err(e) = reserves_commitments(commitments) {
+        return e;
+    }
+
+    match reserves_proof(secrets, commitments, min_total) {
+        Ok(proof) => {
+            ptr::copy_nonoverlapping(proof.as_ptr(), proof_out, proof.len());
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Build the proof for `fcmp_reserves_prove` once the set has been checked
+unsafe fn reserves_proof(secrets: &[u8], commitments: &[u8], min_total: u64) -> Result<Vec<u8>, i32> {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    let g = ED25519_BASEPOINT_POINT;
+    let h = pedersen_h().ok_or(FCMP_ERROR_INTERNAL)?;
+    let count = commitments.len() / POINT_SIZE;
+
+    let mut openings = Vec::with_capacity(count);
+    let mut total = 0u128;
+    let mut blinding = Scalar::ZERO;
+    let mut result = FCMP_SUCCESS;
+    for (secret, commitment) in secrets.chunks_exact(2 * SCALAR_SIZE).zip(commitments.chunks_exact(POINT_SIZE)) {
+        let (v, b) = match (decode_scalar(&secret[..SCALAR_SIZE]), decode_scalar(&secret[SCALAR_SIZE..])) {
+            (Some(v), Some(b)) if secret[8..SCALAR_SIZE].iter().all(|&byte| byte == 0) => (v, b),
+            _ => {
+                result = FCMP_ERROR_INVALID_SCALAR;
+                break;
+            }
+        };
+        match decode_point(commitment) {
+            Some(c) if c == v * g + b * h => {}
+            Some(_) => {
+                result = FCMP_ERROR_PROOF_GENERATION;
+                break;
+            }
+            None => {
+                result = FCMP_ERROR_INVALID_POINT;
+                break;
+            }
+        }
+        total += u64::from_le_bytes(secret[..8].try_into().unwrap()) as u128;
+        blinding += b;
+        openings.push((v, b));
+    }
+
+    let excess = total.checked_sub(min_total as u128).and_then(|d| u64::try_from(d).ok());
+    let excess = match (result, excess) {
+        (FCMP_SUCCESS, Some(excess)) => excess,
+        (FCMP_SUCCESS, None) => {
+            blinding.zeroize();
+            openings.zeroize();
+            return Err(FCMP_ERROR_PROOF_GENERATION);
+        }
+        (e, _) => {
+            blinding.zeroize();
+            openings.zeroize();
+            return Err(e);
+        }
+    };
+
+    let mut transcript = reserves_transcript(commitments, min_total);
+    let mut nonces: Vec<(Scalar, Scalar)> = (0..count).map(|_| (Scalar::random(&mut OsRng), Scalar::random(&mut OsRng))).collect();
+    let mut proof = vec![0u8; count * FCMP_RESERVES_OPENING_SIZE];
+    for (section, (k_v, k_b)) in proof.chunks_exact_mut(FCMP_RESERVES_OPENING_SIZE).zip(&nonces) {
+        let nonce = (k_v * g + k_b * h).compress();
+        section[..POINT_SIZE].copy_from_slice(nonce.as_bytes());
+        transcript.absorb(nonce.as_bytes());
+    }
+    let c = transcript.clone().challenge();
+    if is_degenerate_challenge(&c) {
+        blinding.zeroize();
+        openings.zeroize();
+        nonces.zeroize();
+        return Err(FCMP_ERROR_PROOF_GENERATION);
+    }
+    for (section, ((v, b), (k_v, k_b))) in proof.chunks_exact_mut(FCMP_RESERVES_OPENING_SIZE).zip(openings.iter().zip(&nonces)) {
+        section[POINT_SIZE..POINT_SIZE + SCALAR_SIZE].copy_from_slice((k_v + c * v).as_bytes());
+        section[POINT_SIZE + SCALAR_SIZE..].copy_from_slice((k_b + c * b).as_bytes());
+    }
+    openings.zeroize();
+    nonces.zeroize();
+
+    transcript.absorb(&proof);
+    let range = range_prove(&[excess], &[blinding], FCMP_RANGE_MAX_BITS as usize, transcript);
+    blinding.zeroize();
+    proof.extend_from_slice(&range?);
+    Ok(proof)
+}
+
+/// Verify a proof from `fcmp_reserves_prove`.
+///
+/// Checks every opening proof, s_v*G + s_b*H == R_i + c*C_i, then the range
+/// proof over sum(C_i) - min_total*G. The range proof bounds the excess
+/// below 2^64 but not each value, so the total is only meaningful if every
+/// C_i is itself range-proven, as on-chain outputs are. A set that repeats
+/// a commitment is refused outright, as is any commitment with torsion.
+///
+/// # Safety
+/// - `proof` must point to `fcmp_reserves_proof_size(count)` bytes
+/// - `commitments` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the commitments are shown to total at least `min_total`
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if the proof is invalid
+/// - `FCMP_ERROR_INVALID_PARAM` if a commitment is repeated
+/// - `FCMP_ERROR_INVALID_POINT` / `FCMP_ERROR_INVALID_SCALAR` for bad
+///   encodings, or a commitment with torsion
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_reserves_verify(
+    proof: *const u8,
+    commitments: *const u8,
+    count: u32,
+    min_total: u64,
+) -> i32 {
+    if proof.is_null() || commitments.is_null() || count == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+
+    let g = ED25519_BASEPOINT_POINT;
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+    let count = count as usize;
+    let proof = slice::from_raw_parts(proof, fcmp_reserves_proof_size(count as u32));
+    let commitments = slice::from_raw_parts(commitments, count * POINT_SIZE);
+    let (openings, range) = proof.split_at(count * FCMP_RESERVES_OPENING_SIZE);
+
+    let points = match reserves_commitments(commitments) {
+        Ok(points) => points,
+        Err(e) => return e,
+    };
+
+    let mut transcript = reserves_transcript(commitments, min_total);
+    for section in openings.chunks_exact(FCMP_RESERVES_OPENING_SIZE) {
+        transcript.absorb(&section[..POINT_SIZE]);
+    }
+    let c = transcript.clone().challenge();
+    if is_degenerate_challenge(&c) {
+        return FCMP_ERROR_PROOF_VERIFICATION;
+    }
+
+    for (section, commitment) in openings.chunks_exact(FCMP_RESERVES_OPENING_SIZE).zip(&points) {
+        let nonce = match decode_point(&section[..POINT_SIZE]) {
+            Some(nonce) => nonce,
+            None => return FCMP_ERROR_INVALID_POINT,
+        };
+        let (s_v, s_b) = match (
+            decode_scalar(&section[POINT_SIZE..POINT_SIZE + SCALAR_SIZE]),
+            decode_scalar(&section[POINT_SIZE + SCALAR_SIZE..]),
+        ) {
+            (Some(s_v), Some(s_b)) => (s_v, s_b),
+            _ => return FCMP_ERROR_INVALID_SCALAR,
+        };
+        if s_v * g + s_b * h != nonce + c * commitment {
+            return FCMP_ERROR_PROOF_VERIFICATION;
+        }
+    }
+
+    transcript.absorb(openings);
+    let excess = (points.iter().sum::<EdwardsPoint>() - Scalar::from(min_total) * g).compress();
+    match range_verify_with(range, excess.as_bytes(), transcript) {
+        Ok(FCMP_RANGE_MAX_BITS) => FCMP_SUCCESS,
+        Ok(_) => FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => e,
+    }
+}
+
+// ============================================================================
+// Transactions
+// ============================================================================
+
+/// Check sum(inputs) == sum(outputs) + fee*G
+fn balances(
+    inputs: &[curve25519_dalek::edwards::EdwardsPoint],
+    outputs: &[curve25519_dalek::edwards::EdwardsPoint],
+    fee: u64,
+) -> bool {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::IsIdentity;
+
+    let fee_commitment = Scalar::from(fee) * ED25519_BASEPOINT_POINT;
+    (inputs.iter().sum::<EdwardsPoint>() - outputs.iter().sum::<EdwardsPoint>() - fee_commitment)
+        .is_identity()
+}
+
+/// Run every validation stage of a transaction, stopping at the first failure
+unsafe fn verify_transaction(tx: &FcmpTransaction, params: &FcmpParams) -> i32 {
+    let num_inputs = tx.num_inputs as usize;
+    let tree_root = slice::from_raw_parts(tx.tree_root, POINT_SIZE);
+    let prefix_hash = slice::from_raw_parts(tx.prefix_hash, 32);
+    let inputs = slice::from_raw_parts(tx.inputs, num_inputs);
+    let key_images = slice::from_raw_parts(tx.key_images, num_inputs * POINT_SIZE);
+    let signatures = slice::from_raw_parts(tx.signatures, num_inputs * FCMP_SAL_SIGNATURE_SIZE);
+    let outputs = slice::from_raw_parts(tx.output_commitments, tx.num_outputs as usize * POINT_SIZE);
+
+    // 1. Membership: proofs are self-delimiting through their headers, and
+    //    each is bound to its input
+    let mut proofs = slice::from_raw_parts(tx.proofs, tx.proofs_len);
+    for input in inputs {
+        let len = match proof_prefix_len(proofs) {
+            Ok(len) => len,
+            Err(e) => return e,
+        };
+        let (proof, rest) = proofs.split_at(len);
+        let result = verify_proof_batched(tree_root, proof, input_transcript(input), params, None);
+        if result != FCMP_SUCCESS {
+            return result;
+        }
+        proofs = rest;
+    }
+    if !proofs.is_empty() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    // 2. No key image may be spent twice within the transaction
+    let mut dup_index = 0u32;
+    let result = fcmp_key_images_find_duplicates(tx.key_images, tx.num_inputs, &mut dup_index);
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    // 3. Spend authorization over the prefix hash
+    let mut pseudo_outputs = Vec::with_capacity(num_inputs);
+    for (j, input) in inputs.iter().enumerate() {
+        let result = sal_verify(
+            &signatures[j * FCMP_SAL_SIGNATURE_SIZE..(j + 1) * FCMP_SAL_SIGNATURE_SIZE],
+            input,
+            &key_images[j * POINT_SIZE..(j + 1) * POINT_SIZE],
+            prefix_hash,
+            false,
+        );
+        if result != FCMP_SUCCESS {
+            return result;
+        }
+        match validate_input(input) {
+            Ok([_, _, _, c_tilde]) => pseudo_outputs.push(c_tilde),
+            Err(e) => return e,
+        }
+    }
+
+    // 4. Output amounts are in range, over the full width
+    let range_proof = slice::from_raw_parts(tx.range_proof, tx.range_proof_len);
+    match range_verify(range_proof, outputs) {
+        Ok(FCMP_RANGE_MAX_BITS) => {}
+        Ok(_) => return FCMP_ERROR_PROOF_VERIFICATION,
+        Err(e) => return e,
+    }
+
+    // 5. Inputs balance outputs plus fee
+    let outputs = match outputs.chunks_exact(POINT_SIZE).map(decode_point).collect::<Option<Vec<_>>>() {
+        Some(points) => points,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+    if balances(&pseudo_outputs, &outputs, tx.fee) {
+        FCMP_SUCCESS
+    } else {
+        FCMP_ERROR_PROOF_VERIFICATION
+    }
+}
+
+/// Validate a transaction's full proof bundle in one call.
+///
+/// Stages run in a fixed order and the first failure is returned:
+/// 1. membership proof of every input against `tree_root`, made with
+///    `fcmp_prove_for_input` for that input
+/// 2. key images unique within the transaction
+/// 3. SA+L signature of every input over `prefix_hash`
+/// 4. `FCMP_RANGE_MAX_BITS`-bit range proof over the output commitments
+/// 5. sum(C~) == sum(outputs) + fee*G
+///
+/// # Safety
+/// - `tx` must point to a valid `FcmpTransaction` whose buffers match its counts
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if every stage passes
+/// - The failing stage's error code otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_transaction(tx: *const FcmpTransaction) -> i32 {
+    if tx.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tx = &*tx;
+    if tx.num_inputs == 0 || tx.num_outputs == 0 ||
+       tx.tree_root.is_null() || tx.prefix_hash.is_null() || tx.inputs.is_null() ||
+       tx.key_images.is_null() || tx.proofs.is_null() || tx.signatures.is_null() ||
+       tx.output_commitments.is_null() || tx.range_proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    with_params(|params| verify_transaction(tx, params)).unwrap_or_else(|e| e)
+}
+
+/// Validate a transaction and hand out the key images it authorizes.
+///
+/// Fuses `fcmp_verify_transaction` with reading the key images, so a node
+/// can never consume images from a bundle that did not verify. The images
+/// are copied before verification and it is that copy which is checked
+/// and written out, so a caller mutating `tx.key_images` concurrently
+/// cannot slip in unverified ones. `images_out` is untouched on failure.
+///
+/// Membership proofs do not carry key images themselves; they are bound to
+/// their inputs by the SA+L signatures, so the whole bundle is needed.
+///
+/// # Safety
+/// - `tx` must be valid as for `fcmp_verify_transaction`
+/// - `images_out` must point to `tx.num_inputs * 32` bytes of writable memory
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the key images in `images_out`
+/// - The failing stage's error code otherwise
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_and_extract_images(tx: *const FcmpTransaction, images_out: *mut u8) -> i32 {
+    if tx.is_null() || images_out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let tx = &*tx;
+    if tx.key_images.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let images = slice::from_raw_parts(tx.key_images, tx.num_inputs as usize * POINT_SIZE).to_vec();
+    let snapshot = FcmpTransaction {
+        tree_root: tx.tree_root,
+        prefix_hash: tx.prefix_hash,
+        num_inputs: tx.num_inputs,
+        inputs: tx.inputs,
+        key_images: images.as_ptr(),
+        proofs: tx.proofs,
+        proofs_len: tx.proofs_len,
+        signatures: tx.signatures,
+        num_outputs: tx.num_outputs,
+        output_commitments: tx.output_commitments,
+        range_proof: tx.range_proof,
+        range_proof_len: tx.range_proof_len,
+        fee: tx.fee,
+    };
+
+    let result = fcmp_verify_transaction(&snapshot);
+    if result == FCMP_SUCCESS {
+        ptr::copy_nonoverlapping(images.as_ptr(), images_out, images.len());
+    }
+    result
+}
+
+/// Number of bits a key image sets in a spent-set bloom filter
+pub const FCMP_SPENT_FILTER_HASHES: usize = 4;
+
+/// Bit positions of a key image in a filter of `filter_bits` bits.
+///
+/// Position k is the k-th little-endian u64 of
+/// BLAKE2b-512("WATTx_Spent_Filter_v1" || image)[..32], reduced mod
+/// `filter_bits`. Bit i of the filter is bit i % 8 of byte i / 8.
+fn spent_filter_bits(image: &[u8], filter_bits: usize) -> [usize; FCMP_SPENT_FILTER_HASHES] {
+    let digest = tagged_digest(b"WATTx_Spent_Filter_v1", &[image]);
+    let mut bits = [0usize; FCMP_SPENT_FILTER_HASHES];
+    for (bit, word) in bits.iter_mut().zip(digest.chunks_exact(8)) {
+        *bit = (u64::from_le_bytes(word.try_into().unwrap()) % filter_bits as u64) as usize;
+    }
+    bits
+}
+
+/// Add a key image to a spent-set bloom filter.
+///
+/// Nodes build the filter passed to `fcmp_verify_with_spent_filter` from
+/// their spent images with this; it sets `FCMP_SPENT_FILTER_HASHES` bits.
+///
+/// # Safety
+/// - `filter` must point to `ceil(filter_bits / 8)` bytes of writable memory
+/// - `image` must point to 32 bytes
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_spent_filter_insert(filter: *mut u8, filter_bits: usize, image: *const u8) -> i32 {
+    if filter.is_null() || filter_bits == 0 || image.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let filter = slice::from_raw_parts_mut(filter, filter_bits.div_ceil(8));
+    for bit in spent_filter_bits(slice::from_raw_parts(image, POINT_SIZE), filter_bits) {
+        filter[bit / 8] |= 1 << (bit % 8);
+    }
+    FCMP_SUCCESS
+}
+
+/// Validate a transaction and check its key images against spent outputs.
+///
+/// Runs `fcmp_verify_and_extract_images`, then looks up every verified key
+/// image in a bloom filter of spent images built with
+/// `fcmp_spent_filter_insert`. A hit may be a false positive, so
+/// `FCMP_ERROR_DOUBLE_SPEND` means "probably spent": the node should
+/// confirm against its exact spent set before rejecting. A miss is
+/// definite.
+///
+/// # Safety
+/// - `tx` must be valid as for `fcmp_verify_transaction`
+/// - `filter` must point to `ceil(filter_bits / 8)` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the bundle verifies and no key image is in the filter
+/// - `FCMP_ERROR_DOUBLE_SPEND` if a key image probably is
+/// - The failing stage's error code if verification fails
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verify_with_spent_filter(
+    tx: *const FcmpTransaction,
+    filter: *const u8,
+    filter_bits: usize,
+) -> i32 {
+    if tx.is_null() || filter.is_null() || filter_bits == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let mut images = vec![0u8; (*tx).num_inputs as usize * POINT_SIZE];
+    let result = fcmp_verify_and_extract_images(tx, images.as_mut_ptr());
+    if result != FCMP_SUCCESS {
+        return result;
+    }
+
+    let filter = slice::from_raw_parts(filter, filter_bits.div_ceil(8));
+    let spent = images.chunks_exact(POINT_SIZE).any(|image| {
+        spent_filter_bits(image, filter_bits)
+            .iter()
+            .all(|&bit| filter[bit / 8] & (1 << (bit % 8)) != 0)
+    });
+    if spent {
+        FCMP_ERROR_DOUBLE_SPEND
+    } else {
+        FCMP_SUCCESS
+    }
+}
+
+/// Check that pseudo-output commitments balance the outputs and fee.
+///
+/// Verifies sum(pseudo_in) - sum(out) - fee*G is the identity, the final
+/// stage of `fcmp_verify_transaction` on its own. The fee is committed with
+/// zero blinding, so the input and output blindings must cancel exactly.
+///
+/// # Safety
+/// - `pseudo_in_commits` must point to `n_in * 32` bytes
+/// - `out_commits` must point to `n_out * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` if the commitments balance
+/// - `FCMP_ERROR_PROOF_VERIFICATION` if they do not
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment is not a canonical point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_check_balance(
+    pseudo_in_commits: *const u8,
+    n_in: u32,
+    out_commits: *const u8,
+    n_out: u32,
+    fee: u64,
+) -> i32 {
+    if (pseudo_in_commits.is_null() && n_in > 0) || (out_commits.is_null() && n_out > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let decode_all = |commits: *const u8, count: u32| {
+        if count == 0 {
+            return Some(Vec::new());
+        }
+        slice::from_raw_parts(commits, count as usize * POINT_SIZE)
+            .chunks_exact(POINT_SIZE)
+            .map(decode_point)
+            .collect::<Option<Vec<_>>>()
+    };
+
+    match (decode_all(pseudo_in_commits, n_in), decode_all(out_commits, n_out)) {
+        (Some(inputs), Some(outputs)) if balances(&inputs, &outputs, fee) => FCMP_SUCCESS,
+        (Some(_), Some(_)) => FCMP_ERROR_PROOF_VERIFICATION,
+        _ => FCMP_ERROR_INVALID_POINT,
+    }
+}
+
+/// Build pseudo-output commitments that balance a transaction.
+///
+/// Pseudo-output i commits to `input_amounts[i]` under blinding b_i. Every
+/// b_i but the last is derived with a PRF keyed by that input's blinding,
+/// so it is reproducible by the wallet but reveals nothing about the key;
+/// the last is sum(output_blinders) - sum(other b_i). The pseudo-outputs
+/// then pass `fcmp_check_balance` against the real outputs whenever the
+/// amounts balance: sum(input_amounts) == sum(output amounts) + fee.
+///
+/// # Safety
+/// - `pseudo_commits_out` and `pseudo_blinders_out` must each point to
+///   `n_in * 32` bytes of writable memory
+/// - `input_amounts` must point to `n_in` values
+/// - `input_blinders` must point to `n_in * 32` bytes
+/// - `output_blinders` must point to `n_out * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `n_in` is 0 or the input amounts
+///   overflow or fall short of the fee
+/// - `FCMP_ERROR_INVALID_SCALAR` if a blinding is not canonical
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_make_pseudo_outputs(
+    pseudo_commits_out: *mut u8,
+    pseudo_blinders_out: *mut u8,
+    input_amounts: *const u64,
+    input_blinders: *const u8,
+    n_in: u32,
+    output_blinders: *const u8,
+    n_out: u32,
+    fee: u64,
+) -> i32 {
+    if pseudo_commits_out.is_null() || pseudo_blinders_out.is_null() || input_amounts.is_null() ||
+       input_blinders.is_null() || (output_blinders.is_null() && n_out > 0) || n_in == 0 {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+    use curve25519_dalek::scalar::Scalar;
+
+    let n_in = n_in as usize;
+    let amounts = slice::from_raw_parts(input_amounts, n_in);
+
+    // The fee is committed with zero blinding, so it only bounds the amounts
+    match amounts.iter().try_fold(0u64, |sum, &a| sum.checked_add(a)) {
+        Some(total) if total >= fee => {}
+        _ => return FCMP_ERROR_INVALID_PARAM,
+    }
+    let input_blinders = slice::from_raw_parts(input_blinders, n_in * SCALAR_SIZE);
+    let output_blinders = if n_out == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(output_blinders, n_out as usize * SCALAR_SIZE)
+    };
+
+    let h = match pedersen_h() {
+        Some(h) => h,
+        None => return FCMP_ERROR_INTERNAL,
+    };
+
+    let mut remaining = Scalar::ZERO;
+    for chunk in output_blinders.chunks_exact(SCALAR_SIZE) {
+        match decode_scalar(chunk) {
+            Some(b) => remaining += b,
+            None => {
+                remaining.zeroize();
+                return FCMP_ERROR_INVALID_SCALAR;
+            }
+        }
+    }
+    if input_blinders.chunks_exact(SCALAR_SIZE).any(|b| decode_scalar(b).is_none()) {
+        remaining.zeroize();
+        return FCMP_ERROR_INVALID_SCALAR;
+    }
+
+    for (i, (amount, key)) in amounts.iter().zip(input_blinders.chunks_exact(SCALAR_SIZE)).enumerate() {
+        let mut blinding = if i + 1 < n_in {
+            prf_scalar(b"WATTx_Pseudo_Blinding_v1", key, &(i as u32).to_le_bytes())
+        } else {
+            remaining
+        };
+        remaining -= blinding;
+
+        let commitment = &Scalar::from(*amount) * ED25519_BASEPOINT_TABLE + blinding * h;
+        ptr::copy_nonoverlapping(commitment.compress().as_bytes().as_ptr(), pseudo_commits_out.add(i * POINT_SIZE), POINT_SIZE);
+        ptr::copy_nonoverlapping(blinding.as_bytes().as_ptr(), pseudo_blinders_out.add(i * SCALAR_SIZE), SCALAR_SIZE);
+        blinding.zeroize();
+    }
+    remaining.zeroize();
+
+    FCMP_SUCCESS
+}
+
+/// Sum a transaction's output commitments into one point.
+///
+/// The "total committed" a wallet displays: by the homomorphism it commits
+/// to the total value under the total blinding. The sum is one
+/// variable-time multiscalar multiplication with unit weights. Every
+/// commitment must decompress canonically; zero commitments sum to the
+/// identity.
+///
+/// # Safety
+/// - `out` must point to at least 32 bytes of writable memory
+/// - `output_commitments` must point to `count * 32` bytes
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_POINT` if a commitment is not a canonical point
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_outputs_aggregate_commitment(
+    out: *mut u8,
+    output_commitments: *const u8,
+    count: u32,
+) -> i32 {
+    if out.is_null() || (output_commitments.is_null() && count > 0) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+    let commitments = if count == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(output_commitments, count as usize * POINT_SIZE)
+    };
+    let points = match commitments
+        .chunks_exact(POINT_SIZE)
+        .map(decode_point)
+        .collect::<Option<Vec<EdwardsPoint>>>()
+    {
+        Some(points) => points,
+        None => return FCMP_ERROR_INVALID_POINT,
+    };
+    // Commitments are public, so the variable-time MSM is safe here
+    let total = EdwardsPoint::vartime_multiscalar_mul(std::iter::repeat_n(Scalar::ONE, points.len()), &points);
+
+    ptr::copy_nonoverlapping(total.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+    FCMP_SUCCESS
+}
+
+/// Wire form of a transaction bundle, or `FCMP_ERROR_INVALID_PARAM` if a
+/// buffer is null
+unsafe fn transaction_bytes(tx: &FcmpTransaction) -> Result<Vec<u8>, i32> {
+    if tx.tree_root.is_null() || tx.prefix_hash.is_null() || tx.inputs.is_null() ||
+       tx.key_images.is_null() || tx.proofs.is_null() || tx.signatures.is_null() ||
+       tx.output_commitments.is_null() || tx.range_proof.is_null() {
+        return Err(FCMP_ERROR_INVALID_PARAM);
+    }
+
+    let num_inputs = tx.num_inputs as usize;
+    let num_outputs = tx.num_outputs as usize;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(slice::from_raw_parts(tx.tree_root, POINT_SIZE));
+    bytes.extend_from_slice(slice::from_raw_parts(tx.prefix_hash, 32));
+    bytes.extend_from_slice(&tx.num_inputs.to_le_bytes());
+    for input in slice::from_raw_parts(tx.inputs, num_inputs) {
+        bytes.extend_from_slice(&input_bytes(input));
+    }
+    bytes.extend_from_slice(slice::from_raw_parts(tx.key_images, num_inputs * POINT_SIZE));
+    bytes.extend_from_slice(slice::from_raw_parts(tx.proofs, tx.proofs_len));
+    bytes.extend_from_slice(slice::from_raw_parts(tx.signatures, num_inputs * FCMP_SAL_SIGNATURE_SIZE));
+    bytes.extend_from_slice(&tx.num_outputs.to_le_bytes());
+    bytes.extend_from_slice(slice::from_raw_parts(tx.output_commitments, num_outputs * POINT_SIZE));
+    bytes.extend_from_slice(slice::from_raw_parts(tx.range_proof, tx.range_proof_len));
+    bytes.extend_from_slice(&tx.fee.to_le_bytes());
+    Ok(bytes)
+}
+
+/// Serialize a transaction bundle into its wire form.
+///
+/// Wire format: tree_root || prefix_hash || num_inputs (u32 LE) ||
+/// inputs (O~ || I~ || R || C~ each) || key images || proofs || signatures ||
+/// num_outputs (u32 LE) || output commitments || range proof || fee (u64 LE)
+///
+/// Proofs and the range proof are self-delimiting through their headers, so
+/// no lengths are stored for them.
+///
+/// # Safety
+/// - `tx` must point to a valid `FcmpTransaction` whose buffers match its counts
+/// - `out` must have at least `out_len` bytes available
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the serialized size in `written`
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `written` receives the
+///   size needed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_transaction_serialize(
+    tx: *const FcmpTransaction,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if tx.is_null() || out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let bytes = match transaction_bytes(&*tx) {
+        Ok(bytes) => bytes,
+        Err(e) => return e,
+    };
+
+    *written = bytes.len();
+    if out_len < bytes.len() {
+        return FCMP_ERROR_MEMORY;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    FCMP_SUCCESS
+}
+
+/// Compute a transaction's id.
+///
+/// BLAKE2b-512("WATTx_Transaction_Id_v1" || wire form)[..32], over the
+/// serialization of `fcmp_transaction_serialize`. Every component, the fee
+/// included, is in the wire form, so changing any of them changes the id.
+/// The bundle is not verified.
+///
+/// # Safety
+/// - `tx` must point to a valid `FcmpTransaction` whose buffers match its counts
+/// - `out` must point to at least 32 bytes of writable memory
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_transaction_id(tx: *const FcmpTransaction, out: *mut u8) -> i32 {
+    if tx.is_null() || out.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    match transaction_bytes(&*tx) {
+        Ok(bytes) => {
+            let id = tagged_digest(b"WATTx_Transaction_Id_v1", &[&bytes]);
+            ptr::copy_nonoverlapping(id.as_ptr(), out, id.len());
+            FCMP_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+/// Estimate the serialized size of a transaction bundle for fee sizing.
+///
+/// Sums the fields of `fcmp_transaction_serialize` for `num_inputs` inputs
+/// whose membership proofs each have `num_layers` layers and `num_outputs`
+/// outputs under one `FCMP_RANGE_MAX_BITS`-bit range proof. Bundles using a
+/// narrower range proof are smaller, so the estimate is then an upper bound.
+///
+/// # Returns
+/// Size in bytes, or 0 if a count is 0, `num_layers` exceeds
+/// `MAX_PROOF_LAYERS`, or the size overflows
+#[no_mangle]
+pub extern "C" fn fcmp_transaction_size_estimate(num_inputs: u32, num_outputs: u32, num_layers: u32) -> usize {
+    if num_inputs == 0 || num_layers as usize > MAX_PROOF_LAYERS {
+        return 0;
+    }
+
+    let range_proof_len = fcmp_range_proof_size(num_outputs, FCMP_RANGE_MAX_BITS);
+    if range_proof_len == 0 {
+        return 0;
+    }
+
+    let per_input = FCMP_INPUT_SERIALIZED_SIZE + POINT_SIZE + proof_len_for_layers(num_layers as usize) + FCMP_SAL_SIGNATURE_SIZE;
+    (num_inputs as usize)
+        .checked_mul(per_input)
+        .and_then(|n| n.checked_add((num_outputs as usize).checked_mul(POINT_SIZE)?))
+        .and_then(|n| n.checked_add(range_proof_len))
+        .and_then(|n| n.checked_add(POINT_SIZE + 32 + 4 + 4 + 8))
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// Reusable Verifier
+// ============================================================================
+
+/// Opaque verifier with decompressed generators, precomputed base tables
+/// and a primed transcript, reused across proofs during block sync
+pub struct FcmpVerifier {
+    params: FcmpParams,
+    bases: BaseTables,
+    transcript: Transcript,
+}
+
+/// Create a reusable verifier.
+///
+/// Snapshots the initialized generators and primes the transcript. The first
+/// proof of each depth builds multiplication tables for the fixed bases,
+/// which later `fcmp_verifier_verify` calls reuse. `benches/verify.rs` shows
+/// no measurable gain over `fcmp_verify` at current proof sizes, where point
+/// decompression dominates.
+///
+/// # Returns
+/// - Verifier handle to release with `fcmp_verifier_free`
+/// - Null if the library is not initialized
+#[no_mangle]
+pub extern "C" fn fcmp_verifier_new() -> *mut FcmpVerifier {
+    match with_params(FcmpParams::clone) {
+        Ok(params) => Box::into_raw(Box::new(FcmpVerifier {
+            bases: BaseTables::new(),
+            params,
+            transcript: Transcript::new(),
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Verify an FCMP proof with a reusable verifier.
+///
+/// Produces the same verdict as `fcmp_verify`.
+///
+/// # Safety
+/// - `verifier` must be a live handle from `fcmp_verifier_new`
+/// - Remaining pointers must be valid as for `fcmp_verify`
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verifier_verify(
+    verifier: *const FcmpVerifier,
+    tree_root: *const u8,
+    input: *const FcmpInput,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if verifier.is_null() || tree_root.is_null() || input.is_null() || proof.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let verifier = &*verifier;
+    verify_proof_batched(
+        slice::from_raw_parts(tree_root, POINT_SIZE),
+        slice::from_raw_parts(proof, proof_len),
+        verifier.transcript.clone(),
+        &verifier.params,
+        Some(&verifier.bases),
+    )
+}
+
+/// Free a verifier created by `fcmp_verifier_new`.
+///
+/// # Safety
+/// - `verifier` must be null or a handle from `fcmp_verifier_new` not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_verifier_free(verifier: *mut FcmpVerifier) {
+    if !verifier.is_null() {
+        drop(Box::from_raw(verifier));
+    }
+}
+
+/// Export the initialized generators as a frozen verification key.
+///
+/// The key is in the `fcmp_params_save` file format, so its version and
+/// checksum are checked on import. It holds every generator a verifier
+/// multiplies by; the fixed-base tables are rebuilt from them on first use
+/// rather than shipped, as they are several times larger.
+///
+/// # Safety
+/// - `out` must have at least `out_len` bytes available
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with the key size in `written`
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small; `written` receives the
+///   size needed
+/// - `FCMP_ERROR_NOT_INITIALIZED` if the library is not initialized
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_export_verification_key(out: *mut u8, out_len: usize, written: *mut usize) -> i32 {
+    if out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let bytes = match with_params(FcmpParams::to_file_bytes) {
+        Ok(bytes) => bytes,
+        Err(e) => return e,
+    };
+
+    *written = bytes.len();
+    if out_len < bytes.len() {
+        return FCMP_ERROR_MEMORY;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    FCMP_SUCCESS
+}
+
+/// Create a reusable verifier from a key written by
+/// `fcmp_export_verification_key`.
+///
+/// Nothing is derived and the library need not be initialized, so the
+/// verifier does not depend on the generator derivation code. It gives the
+/// same verdicts as one from `fcmp_verifier_new` with the exporting
+/// library's parameters.
+///
+/// # Safety
+/// - `bytes` must point to `len` bytes
+///
+/// # Returns
+/// - Verifier handle to release with `fcmp_verifier_free`
+/// - Null if the key is from another version, corrupt, or holds a
+///   generator that does not decode
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_import_verification_key(bytes: *const u8, len: usize) -> *mut FcmpVerifier {
+    if bytes.is_null() {
+        return ptr::null_mut();
+    }
+
+    match FcmpParams::from_file_bytes(slice::from_raw_parts(bytes, len)) {
+        Ok(params) => Box::into_raw(Box::new(FcmpVerifier {
+            bases: BaseTables::new(),
+            params,
+            transcript: Transcript::new(),
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+// ============================================================================
+// Utility Functions
+// ============================================================================
+
+/// Get the library version string
+///
+/// # Returns
+/// Pointer to a null-terminated version string
+#[no_mangle]
+// cbindgen's parser predates C string literals, so this stays a byte string
+#[allow(clippy::manual_c_str_literals)]
+pub extern "C" fn fcmp_version() -> *const c_char {
+    b"0.1.0\0".as_ptr() as *const c_char
+}
+
+/// Report whether curve25519-dalek's SIMD backend is likely compiled in.
+///
+/// This is a best guess, not dalek's actual selection, which it does not
+/// expose. The build script repeats dalek's default rule: the SIMD backend
+/// is built on x86_64 with 64-bit limbs unless the serial or fiat backend
+/// is forced through `--cfg curve25519_dalek_backend`. When it is present,
+/// dalek dispatches to AVX2 (or AVX512-IFMA on nightly) at runtime if the
+/// CPU supports it.
+///
+/// # Returns
+/// - 1 if the SIMD backend is expected to be compiled in
+/// - 0 otherwise
+#[no_mangle]
+pub extern "C" fn fcmp_has_simd() -> i32 {
+    cfg!(wattx_fcmp_simd) as i32
+}
+
+/// Get the size in bytes of an FFI type as laid out by this build.
+///
+/// Bindings should compare these against their own struct definitions at load
+/// time so layout mismatches fail loudly instead of corrupting memory. All
+/// multi-byte integers in FFI structs are native-endian; scalars and points
+/// are 32-byte little-endian encodings.
+///
+/// # Arguments
+/// - `type_id` - One of the `FcmpTypeId` values
+///
+/// # Returns
+/// - Size in bytes, or 0 for an unknown type id
+#[no_mangle]
+pub extern "C" fn fcmp_sizeof(type_id: i32) -> usize {
+    use std::mem::size_of;
+
+    match type_id {
+        x if x == FcmpTypeId::Scalar as i32 => SCALAR_SIZE,
+        x if x == FcmpTypeId::Point as i32 => POINT_SIZE,
+        x if x == FcmpTypeId::Input as i32 => size_of::<FcmpInput>(),
+        x if x == FcmpTypeId::Branch as i32 => size_of::<FcmpBranch>(),
+        x if x == FcmpTypeId::BranchLayer as i32 => size_of::<FcmpBranchLayer>(),
+        x if x == FcmpTypeId::Transaction as i32 => size_of::<FcmpTransaction>(),
+        x if x == FcmpTypeId::Config as i32 => size_of::<FcmpConfig>(),
+        x if x == FcmpTypeId::TreeDescriptor as i32 => size_of::<FcmpTreeDescriptor>(),
+        _ => 0,
+    }
+}
+
+/// Standard base64 alphabet (RFC 4648, section 4)
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Padded base64 encoding of `bytes`
+fn base64_encode(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3f]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode padded base64, rejecting anything but its canonical encoding:
+/// characters outside the alphabet, missing or misplaced padding, and
+/// non-zero bits left over in the final group
+fn base64_decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    let groups = encoded.len() / 4;
+    for (g, group) in encoded.chunks_exact(4).enumerate() {
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && g + 1 != groups) {
+            return None;
+        }
+
+        let mut bits = 0u32;
+        for &c in &group[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            bits = (bits << 6) | value;
+        }
+        bits <<= 6 * padding;
+
+        let bytes = bits.to_be_bytes();
+        let len = 3 - padding;
+        if bytes[1 + len..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        decoded.extend_from_slice(&bytes[1..1 + len]);
+    }
+    Some(decoded)
+}
+
+/// Encode a proof, or any byte string, as padded base64 for JSON APIs.
+///
+/// Writes the encoding followed by a NUL terminator. `written` receives the
+/// encoded length, excluding the terminator, on success; if `out_len` is too
+/// small it receives the buffer size needed, including the terminator.
+///
+/// # Safety
+/// - `bytes` must point to `len` bytes (may be null if `len` is 0)
+/// - `out` must point to `out_len` writable bytes
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_to_base64(
+    bytes: *const u8,
+    len: usize,
+    out: *mut c_char,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if (bytes.is_null() && len > 0) || out.is_null() || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let bytes = if len > 0 { slice::from_raw_parts(bytes, len) } else { &[] };
+    let encoded = base64_encode(bytes);
+    if out_len < encoded.len() + 1 {
+        *written = encoded.len() + 1;
+        return FCMP_ERROR_MEMORY;
+    }
+
+    ptr::copy_nonoverlapping(encoded.as_ptr(), out as *mut u8, encoded.len());
+    *out.add(encoded.len()) = 0;
+    *written = encoded.len();
+    FCMP_SUCCESS
+}
+
+/// Decode a proof from padded base64.
+///
+/// Decoding is strict: the input must be the exact output of
+/// `fcmp_proof_to_base64`, without whitespace, line breaks or a NUL
+/// terminator counted in `s_len`. `written` receives the decoded length, or
+/// the buffer size needed if `out_len` is too small.
+///
+/// # Safety
+/// - `s` must point to `s_len` bytes (may be null if `s_len` is 0)
+/// - `out` must point to `out_len` writable bytes (may be null if `out_len` is 0)
+/// - `written` must be writable
+///
+/// # Returns
+/// - `FCMP_SUCCESS` on success
+/// - `FCMP_ERROR_INVALID_PARAM` if `s` is not canonical padded base64
+/// - `FCMP_ERROR_MEMORY` if `out_len` is too small
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_proof_from_base64(
+    s: *const c_char,
+    s_len: usize,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> i32 {
+    if (s.is_null() && s_len > 0) || (out.is_null() && out_len > 0) || written.is_null() {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    let encoded = if s_len > 0 { slice::from_raw_parts(s as *const u8, s_len) } else { &[] };
+    let decoded = match base64_decode(encoded) {
+        Some(decoded) => decoded,
+        None => return FCMP_ERROR_INVALID_PARAM,
+    };
+
+    *written = decoded.len();
+    if out_len < decoded.len() {
+        return FCMP_ERROR_MEMORY;
+    }
+    if !decoded.is_empty() {
+        ptr::copy_nonoverlapping(decoded.as_ptr(), out, decoded.len());
+    }
+    FCMP_SUCCESS
+}
+
+/// Get error message for an error code
+///
+/// # Returns
+/// Pointer to a null-terminated error string
+#[no_mangle]
+// cbindgen's parser predates C string literals, so messages stay byte strings
+#[allow(clippy::manual_c_str_literals)]
+pub extern "C" fn fcmp_error_string(code: i32) -> *const c_char {
+    match code {
+        FCMP_SUCCESS => b"Success\0".as_ptr() as *const c_char,
+        FCMP_ERROR_INVALID_PARAM => b"Invalid parameter\0".as_ptr() as *const c_char,
+        FCMP_ERROR_PROOF_GENERATION => b"Proof generation failed\0".as_ptr() as *const c_char,
+        FCMP_ERROR_PROOF_VERIFICATION => b"Proof verification failed\0".as_ptr() as *const c_char,
+        FCMP_ERROR_MEMORY => b"Memory allocation failed\0".as_ptr() as *const c_char,
+        FCMP_ERROR_INVALID_POINT => b"Invalid curve point\0".as_ptr() as *const c_char,
+        FCMP_ERROR_INVALID_SCALAR => b"Invalid scalar\0".as_ptr() as *const c_char,
+        FCMP_ERROR_NOT_INITIALIZED => b"Library not initialized\0".as_ptr() as *const c_char,
+        FCMP_ERROR_EMPTY_TREE => b"Membership tree is empty\0".as_ptr() as *const c_char,
+        FCMP_ERROR_LIMIT_EXCEEDED => b"Verification step limit exceeded\0".as_ptr() as *const c_char,
+        FCMP_ERROR_DOUBLE_SPEND => b"Key image already spent\0".as_ptr() as *const c_char,
+        FCMP_ERROR_GENERATOR_MISMATCH => b"Proof made under different generators\0".as_ptr() as *const c_char,
+        FCMP_ERROR_INTERNAL => b"Internal error\0".as_ptr() as *const c_char,
+        _ => b"Unknown error\0".as_ptr() as *const c_char,
+    }
+}
+
+/// Copy the error message for an error code into a caller buffer.
+///
+/// Follows `snprintf` semantics: at most `buf_len - 1` bytes of the message
+/// are written followed by a NUL terminator, and the return value is always
+/// the full message length so callers can detect truncation and retry. Pass
+/// a null `buf` with `buf_len == 0` to query the length.
+///
+/// # Safety
+/// - `buf` must point to `buf_len` writable bytes (may be null if `buf_len` is 0)
+///
+/// # Returns
+/// The untruncated message length in bytes, excluding the NUL terminator
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_error_string_buf(code: i32, buf: *mut c_char, buf_len: usize) -> usize {
+    let message = std::ffi::CStr::from_ptr(fcmp_error_string(code)).to_bytes();
+
+    if !buf.is_null() && buf_len > 0 {
+        let copy_len = message.len().min(buf_len - 1);
+        ptr::copy_nonoverlapping(message.as_ptr(), buf as *mut u8, copy_len);
+        *buf.add(copy_len) = 0;
+    }
+
+    message.len()
+}
+
+/// List every defined error code with its message.
+///
+/// Lets language bindings build their error enum at load time instead of
+/// hardcoding codes. `FCMP_SUCCESS` is not included. Messages are the
+/// static strings returned by `fcmp_error_string` and must not be freed.
+/// Pass null arrays with `max == 0` to query the count.
+///
+/// # Safety
+/// - `codes_out` and `messages_out` must each have room for `max` entries
+///   (may be null if `max` is 0)
+/// - `count_out` must be a valid pointer
+///
+/// # Returns
+/// - `FCMP_SUCCESS` with `count_out` set to the number of codes written
+/// - `FCMP_ERROR_MEMORY` if `max` is too small, with `count_out` set to
+///   the number required
+#[no_mangle]
+pub unsafe extern "C" fn fcmp_error_codes(
+    codes_out: *mut i32,
+    messages_out: *mut *const c_char,
+    max: usize,
+    count_out: *mut usize,
+) -> i32 {
+    if count_out.is_null() || (max > 0 && (codes_out.is_null() || messages_out.is_null())) {
+        return FCMP_ERROR_INVALID_PARAM;
+    }
+
+    *count_out = FCMP_ERROR_CODES.len();
+    if max < FCMP_ERROR_CODES.len() {
+        return FCMP_ERROR_MEMORY;
+    }
+
+    for (i, &code) in FCMP_ERROR_CODES.iter().enumerate() {
+        *codes_out.add(i) = code;
+        *messages_out.add(i) = fcmp_error_string(code);
+    }
+
+    FCMP_SUCCESS
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Serializes tests that depend on the global init state
+    static GLOBAL_LOCK: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn init_lock() -> MutexGuard<'static, ()> {
+        let guard = GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(fcmp_init(), FCMP_SUCCESS);
+        guard
+    }
+
+    /// Owned branch data backing an `FcmpBranch`
+    struct TestBranch {
+        leaf_index: u64,
+        _elements: Vec<Vec<u8>>,
+        layers: Vec<FcmpBranchLayer>,
+    }
+
+    impl TestBranch {
+        fn new(num_layers: usize, elements_per_layer: usize) -> Self {
+            let elements: Vec<Vec<u8>> = (0..num_layers)
+                .map(|j| (0..elements_per_layer * SCALAR_SIZE).map(|i| (i + j) as u8).collect())
+                .collect();
+            let layers = elements
+                .iter()
+                .map(|e| FcmpBranchLayer {
+                    num_elements: elements_per_layer as u32,
+                    elements: e.as_ptr(),
+                })
+                .collect();
+            TestBranch { leaf_index: 0, _elements: elements, layers }
+        }
+
+        /// Branch of leaf `leaf_index` read from a tree's cached levels
+        fn from_tree(tree: &FcmpTree, leaf_index: u64) -> Self {
+            let elements = tree.branch_groups(leaf_index as usize);
+            let layers = elements
+                .iter()
+                .map(|e| FcmpBranchLayer { num_elements: (e.len() / SCALAR_SIZE) as u32, elements: e.as_ptr() })
+                .collect();
+            TestBranch { leaf_index, _elements: elements, layers }
+        }
+
+        fn as_ffi(&self) -> FcmpBranch {
+            FcmpBranch {
+                leaf_index: self.leaf_index,
+                num_layers: self.layers.len() as u32,
+                layers: self.layers.as_ptr(),
+            }
+        }
+    }
+
+    fn test_input() -> FcmpInput {
+        FcmpInput { o_tilde: [1u8; 64], i_tilde: [2u8; 64], r: [3u8; 64], c_tilde: [4u8; 64] }
+    }
+
+    /// Prove membership of a fixed output under `root` with the given branch
+    unsafe fn prove_with(root: &[u8; POINT_SIZE], branch: &TestBranch) -> Vec<u8> {
+        let output = [9u8; OUTPUT_TUPLE_SIZE];
+        let mut proof = vec![0u8; proof_len_for_layers(branch.layers.len())];
+        let mut proof_len = 0usize;
+        let ffi_branch = branch.as_ffi();
+        assert_eq!(
+            fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &ffi_branch),
+            FCMP_SUCCESS
+        );
+        assert_eq!(proof_len, proof.len());
+        proof
+    }
+
+    #[test]
+    fn test_init_cleanup() {
+        let _guard = GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(fcmp_init(), FCMP_SUCCESS);
+        assert_eq!(fcmp_is_initialized(), 1);
+        fcmp_cleanup();
+        assert_eq!(fcmp_is_initialized(), 0);
+    }
+
+    #[test]
+    fn test_point_operations() {
+        unsafe {
+            let mut basepoint = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_is_valid(basepoint.as_ptr()), 1);
+
+            // Scalar mul: 2 * G
+            let two = [2u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                       0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let mut two_g = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_mul(two_g.as_mut_ptr(), two.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_is_valid(two_g.as_ptr()), 1);
+
+            // G + G should equal 2*G
+            let mut g_plus_g = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_add(g_plus_g.as_mut_ptr(), basepoint.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(two_g, g_plus_g);
+        }
+    }
+
+    #[test]
+    fn test_hash_to_point() {
+        unsafe {
+            let data = b"test data";
+            let mut point = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(point.as_mut_ptr(), data.as_ptr(), data.len()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_is_valid(point.as_ptr()), 1);
+
+            // Same input should give same output
+            let mut point2 = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(point2.as_mut_ptr(), data.as_ptr(), data.len()), FCMP_SUCCESS);
+            assert_eq!(point, point2);
+
+            // Different input should give different output
+            let data2 = b"other data";
+            let mut point3 = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(point3.as_mut_ptr(), data2.as_ptr(), data2.len()), FCMP_SUCCESS);
+            assert_ne!(point, point3);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_commit() {
+        unsafe {
+            let value = [42u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let blinding = [1u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_is_valid(commitment.as_ptr()), 1);
+
+            // Same inputs should give same commitment
+            let mut commitment2 = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment2.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(commitment, commitment2);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_recover_value() {
+        unsafe {
+            let value = [42u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let blinding = [7u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            let mut recovered = 0u64;
+            assert_eq!(fcmp_pedersen_recover_value(commitment.as_ptr(), blinding.as_ptr(), 1000, &mut recovered), FCMP_SUCCESS);
+            assert_eq!(recovered, 42);
+
+            // Out of the searched range
+            assert_eq!(fcmp_pedersen_recover_value(commitment.as_ptr(), blinding.as_ptr(), 41, &mut recovered), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_sizeof() {
+        use std::mem::size_of;
+
+        assert_eq!(fcmp_sizeof(FcmpTypeId::Scalar as i32), SCALAR_SIZE);
+        assert_eq!(fcmp_sizeof(FcmpTypeId::Point as i32), POINT_SIZE);
+        assert_eq!(fcmp_sizeof(FcmpTypeId::Input as i32), size_of::<FcmpInput>());
+        assert_eq!(fcmp_sizeof(FcmpTypeId::Branch as i32), size_of::<FcmpBranch>());
+        assert_eq!(fcmp_sizeof(FcmpTypeId::BranchLayer as i32), size_of::<FcmpBranchLayer>());
+        assert_eq!(fcmp_sizeof(FcmpTypeId::TreeDescriptor as i32), size_of::<FcmpTreeDescriptor>());
+        assert_eq!(fcmp_sizeof(-1), 0);
+    }
+
+    #[test]
+    fn test_verify_inplace_scratch() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [5u8; POINT_SIZE];
+            let branch = TestBranch::new(3, 4);
+            let proof = prove_with(&root, &branch);
+            let input = test_input();
+
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            let needed = fcmp_verify_scratch_size(3);
+            assert_ne!(fcmp_verify_scratch_size(FCMP_MAX_TREE_DEPTH), 0);
+            assert_eq!(fcmp_verify_scratch_size(FCMP_MAX_TREE_DEPTH + 1), 0);
+            let mut scratch = vec![0u8; needed];
+            assert_eq!(
+                fcmp_verify_inplace(root.as_ptr(), &input, proof.as_ptr(), proof.len(), scratch.as_mut_ptr(), needed),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_verify_inplace(root.as_ptr(), &input, proof.as_ptr(), proof.len(), scratch.as_mut_ptr(), needed - 1),
+                FCMP_ERROR_MEMORY
+            );
+
+            // A tampered response fails both paths
+            let mut bad = proof.clone();
+            bad[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, bad.as_ptr(), bad.len()), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(
+                fcmp_verify_inplace(root.as_ptr(), &input, bad.as_ptr(), bad.len(), scratch.as_mut_ptr(), needed),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+        }
+    }
+
+    #[test]
+    fn test_proof_root() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [0xabu8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(2, 4));
+
+            let mut extracted = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_proof_root(proof.as_ptr(), proof.len(), extracted.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(extracted, root);
+
+            // Truncated proofs are rejected
+            assert_eq!(fcmp_proof_root(proof.as_ptr(), proof.len() - 1, extracted.as_mut_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_scalar_identities() {
+        unsafe {
+            let mut zero = [0xffu8; SCALAR_SIZE];
+            let mut one = [0xffu8; SCALAR_SIZE];
+            assert_eq!(fcmp_scalar_zero(zero.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_scalar_one(one.as_mut_ptr()), FCMP_SUCCESS);
+
+            let mut expected_one = [0u8; SCALAR_SIZE];
+            expected_one[0] = 1;
+            assert_eq!(zero, [0u8; SCALAR_SIZE]);
+            assert_eq!(one, expected_one);
+
+            let a = curve25519_dalek::scalar::Scalar::random(&mut OsRng).to_bytes();
+            let mut out = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_scalar_add(out.as_mut_ptr(), a.as_ptr(), zero.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(out, a);
+            assert_eq!(fcmp_scalar_mul(out.as_mut_ptr(), a.as_ptr(), one.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(out, a);
+            assert_eq!(fcmp_scalar_mul(out.as_mut_ptr(), a.as_ptr(), zero.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(out, zero);
+        }
+    }
+
+    #[test]
+    fn test_point_identity() {
+        unsafe {
+            let mut identity = [0u8; POINT_SIZE];
+            let mut basepoint = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_identity(identity.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+
+            let mut sum = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_add(sum.as_mut_ptr(), basepoint.as_ptr(), identity.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(sum, basepoint);
+        }
+    }
+
+    #[test]
+    fn test_error_string_buf() {
+        unsafe {
+            let full = b"Invalid parameter";
+
+            let mut buf = [0x55 as c_char; 32];
+            let len = fcmp_error_string_buf(FCMP_ERROR_INVALID_PARAM, buf.as_mut_ptr(), buf.len());
+            assert_eq!(len, full.len());
+            assert_eq!(std::ffi::CStr::from_ptr(buf.as_ptr()).to_bytes(), full);
+
+            // Truncated copy is NUL-terminated and still reports the full length
+            let mut small = [0x55 as c_char; 8];
+            let len = fcmp_error_string_buf(FCMP_ERROR_INVALID_PARAM, small.as_mut_ptr(), small.len());
+            assert_eq!(len, full.len());
+            assert_eq!(std::ffi::CStr::from_ptr(small.as_ptr()).to_bytes(), &full[..7]);
+
+            // Length query without a buffer
+            assert_eq!(fcmp_error_string_buf(FCMP_ERROR_INVALID_PARAM, ptr::null_mut(), 0), full.len());
+        }
+    }
+
+    #[test]
+    fn test_verify_any_root() {
+        let _guard = init_lock();
+        unsafe {
+            let roots = [[1u8; POINT_SIZE], [2u8; POINT_SIZE], [3u8; POINT_SIZE]];
+            let proof = prove_with(&roots[1], &TestBranch::new(2, 4));
+            let input = test_input();
+
+            let mut matched = u32::MAX;
+            assert_eq!(
+                fcmp_verify_any_root(roots.as_ptr() as *const u8, 3, &input, proof.as_ptr(), proof.len(), &mut matched),
+                FCMP_SUCCESS
+            );
+            assert_eq!(matched, 1);
+
+            // Only non-matching roots
+            let others = [roots[0], roots[2]];
+            assert_eq!(
+                fcmp_verify_any_root(others.as_ptr() as *const u8, 2, &input, proof.as_ptr(), proof.len(), &mut matched),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+        }
+    }
+
+    #[test]
+    fn test_verifier_matches_stateless() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [7u8; POINT_SIZE];
+            let other_root = [8u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(4, 3));
+            let mut tampered = proof.clone();
+            tampered[FCMP_PROOF_HEADER_SIZE + 3 * POINT_SIZE] ^= 1;
+            let input = test_input();
+
+            let verifier = fcmp_verifier_new();
+            assert!(!verifier.is_null());
+
+            for (r, p) in [(&root, &proof), (&other_root, &proof), (&root, &tampered)] {
+                assert_eq!(
+                    fcmp_verifier_verify(verifier, r.as_ptr(), &input, p.as_ptr(), p.len()),
+                    fcmp_verify(r.as_ptr(), &input, p.as_ptr(), p.len())
+                );
+            }
+            assert_eq!(fcmp_verifier_verify(verifier, root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            fcmp_verifier_free(verifier);
+        }
+    }
+
+    #[test]
+    fn test_verification_key_round_trip() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [7u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(4, 3));
+            let mut tampered = proof.clone();
+            tampered[FCMP_PROOF_HEADER_SIZE + 3 * POINT_SIZE] ^= 1;
+            let input = test_input();
+
+            let mut written = 0usize;
+            let mut key = vec![0u8; 1];
+            assert_eq!(fcmp_export_verification_key(key.as_mut_ptr(), key.len(), &mut written), FCMP_ERROR_MEMORY);
+            key.resize(written, 0);
+            assert_eq!(fcmp_export_verification_key(key.as_mut_ptr(), key.len(), &mut written), FCMP_SUCCESS);
+            assert_eq!(written, key.len());
+
+            // The imported verifier works without the library's own parameters
+            let derived = fcmp_verifier_new();
+            fcmp_cleanup();
+            let imported = fcmp_import_verification_key(key.as_ptr(), key.len());
+            assert!(!imported.is_null());
+            for p in [&proof, &tampered] {
+                assert_eq!(
+                    fcmp_verifier_verify(imported, root.as_ptr(), &input, p.as_ptr(), p.len()),
+                    fcmp_verifier_verify(derived, root.as_ptr(), &input, p.as_ptr(), p.len())
+                );
+            }
+            assert_eq!(fcmp_verifier_verify(imported, root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+            assert_eq!(fcmp_export_verification_key(key.as_mut_ptr(), key.len(), &mut written), FCMP_ERROR_NOT_INITIALIZED);
+            assert_eq!(fcmp_init(), FCMP_SUCCESS);
+
+            key[20] ^= 1;
+            assert!(fcmp_import_verification_key(key.as_ptr(), key.len()).is_null());
+            assert!(fcmp_import_verification_key(key.as_ptr(), key.len() - 1).is_null());
+
+            fcmp_verifier_free(imported);
+            fcmp_verifier_free(derived);
+        }
+    }
+
+    #[test]
+    fn test_commitment_scale() {
+        unsafe {
+            let commit = |v: &[u8; SCALAR_SIZE], b: &[u8; SCALAR_SIZE]| {
+                let mut c = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_pedersen_commit(c.as_mut_ptr(), v.as_ptr(), b.as_ptr()), FCMP_SUCCESS);
+                c
+            };
+            let mul = |a: &[u8; SCALAR_SIZE], b: &[u8; SCALAR_SIZE]| {
+                let mut out = [0u8; SCALAR_SIZE];
+                assert_eq!(fcmp_scalar_mul(out.as_mut_ptr(), a.as_ptr(), b.as_ptr()), FCMP_SUCCESS);
+                out
+            };
+
+            // scale(commit(v, b), k) == commit(k*v, k*b)
+            let mut value = [0u8; SCALAR_SIZE];
+            value[..8].copy_from_slice(&1_250u64.to_le_bytes());
+            let blinding = [9u8; SCALAR_SIZE];
+            let mut factor = [0u8; SCALAR_SIZE];
+            factor[0] = 100;
+
+            let mut scaled = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_commitment_scale(scaled.as_mut_ptr(), commit(&value, &blinding).as_ptr(), factor.as_ptr()), FCMP_SUCCESS);
+            let mut scaled_blinding = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_scale_blinding(scaled_blinding.as_mut_ptr(), blinding.as_ptr(), factor.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(scaled_blinding, mul(&factor, &blinding));
+
+            let mut expected_value = [0u8; SCALAR_SIZE];
+            expected_value[..8].copy_from_slice(&125_000u64.to_le_bytes());
+            assert_eq!(mul(&factor, &value), expected_value);
+            assert_eq!(scaled, commit(&expected_value, &scaled_blinding));
+
+            // Zero and non-canonical factors are rejected
+            let zero = [0u8; SCALAR_SIZE];
+            let unreduced = [0xffu8; SCALAR_SIZE];
+            for bad in [zero, unreduced] {
+                assert_eq!(fcmp_commitment_scale(scaled.as_mut_ptr(), commit(&value, &blinding).as_ptr(), bad.as_ptr()), FCMP_ERROR_INVALID_SCALAR);
+                assert_eq!(fcmp_scale_blinding(scaled_blinding.as_mut_ptr(), blinding.as_ptr(), bad.as_ptr()), FCMP_ERROR_INVALID_SCALAR);
+            }
+            assert_eq!(fcmp_commitment_scale(scaled.as_mut_ptr(), [0xffu8; POINT_SIZE].as_ptr(), factor.as_ptr()), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(fcmp_commitment_scale(scaled.as_mut_ptr(), ptr::null(), factor.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(fcmp_scale_blinding(ptr::null_mut(), blinding.as_ptr(), factor.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_commitment_order_key() {
+        unsafe {
+            let commitments: Vec<[u8; POINT_SIZE]> = (0..16u8)
+                .map(|i| {
+                    let mut c = [0u8; POINT_SIZE];
+                    assert_eq!(fcmp_pedersen_commit(c.as_mut_ptr(), [i; SCALAR_SIZE].as_ptr(), [i ^ 5; SCALAR_SIZE].as_ptr()), FCMP_SUCCESS);
+                    c
+                })
+                .collect();
+            let sorted = |order: &[usize]| {
+                let mut keyed: Vec<([u8; POINT_SIZE], usize)> = order
+                    .iter()
+                    .map(|&i| {
+                        let mut key = [0u8; POINT_SIZE];
+                        assert_eq!(fcmp_commitment_order_key(commitments[i].as_ptr(), key.as_mut_ptr()), FCMP_SUCCESS);
+                        (key, i)
+                    })
+                    .collect();
+                keyed.sort();
+                keyed.into_iter().map(|(_, i)| i).collect::<Vec<_>>()
+            };
+
+            // Any input order sorts to the same sequence
+            let forward: Vec<usize> = (0..16).collect();
+            let shuffled: Vec<usize> = (0..16).map(|i| (i * 7 + 3) % 16).collect();
+            assert_eq!(sorted(&forward), sorted(&shuffled));
+            assert_eq!(sorted(&forward), sorted(&forward));
+
+            // y = p + 1 and the identity with its sign bit set both decode to
+            // the identity, but neither is its canonical encoding
+            let mut key = [0u8; POINT_SIZE];
+            let mut y_above_p = [0xffu8; POINT_SIZE];
+            y_above_p[0] = 0xee;
+            y_above_p[31] = 0x7f;
+            let mut negative_zero = [0u8; POINT_SIZE];
+            negative_zero[0] = 1;
+            negative_zero[31] = 0x80;
+            for encoding in [y_above_p, negative_zero] {
+                assert_eq!(fcmp_commitment_order_key(encoding.as_ptr(), key.as_mut_ptr()), FCMP_ERROR_INVALID_POINT);
+            }
+        }
+    }
+
+    #[test]
+    fn test_commitment_window() {
+        use curve25519_dalek::traits::Identity;
+
+        unsafe {
+            let commitments: Vec<[u8; POINT_SIZE]> = (1..=6u8)
+                .map(|i| {
+                    let mut c = [0u8; POINT_SIZE];
+                    assert_eq!(fcmp_pedersen_commit(c.as_mut_ptr(), [i; SCALAR_SIZE].as_ptr(), [i + 10; SCALAR_SIZE].as_ptr()), FCMP_SUCCESS);
+                    c
+                })
+                .collect();
+            let identity = curve25519_dalek::edwards::EdwardsPoint::identity().compress().to_bytes();
+
+            let mut window = ptr::null_mut();
+            assert_eq!(fcmp_commitment_window_new(&mut window), FCMP_SUCCESS);
+            let sum = |window: *const FcmpCommitmentWindow| {
+                let mut out = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_commitment_window_sum(window, out.as_mut_ptr()), FCMP_SUCCESS);
+                out
+            };
+            assert_eq!(sum(window), identity);
+
+            // Add then remove returns to the identity
+            assert_eq!(fcmp_commitment_window_add(window, commitments[0].as_ptr()), FCMP_SUCCESS);
+            assert_ne!(sum(window), identity);
+            assert_eq!(fcmp_commitment_window_remove(window, commitments[0].as_ptr()), FCMP_SUCCESS);
+            assert_eq!(sum(window), identity);
+
+            // Slide a three-wide window across the commitments
+            for (i, c) in commitments.iter().enumerate() {
+                assert_eq!(fcmp_commitment_window_add(window, c.as_ptr()), FCMP_SUCCESS);
+                if i >= 3 {
+                    assert_eq!(fcmp_commitment_window_remove(window, commitments[i - 3].as_ptr()), FCMP_SUCCESS);
+                }
+                let start = i.saturating_sub(2);
+                let in_window = commitments[start..=i].concat();
+                let mut expected = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_outputs_aggregate_commitment(expected.as_mut_ptr(), in_window.as_ptr(), (i + 1 - start) as u32),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(sum(window), expected);
+            }
+
+            let before = sum(window);
+            assert_eq!(fcmp_commitment_window_add(window, [0xffu8; POINT_SIZE].as_ptr()), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(sum(window), before);
+
+            fcmp_commitment_window_free(window);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_reblind() {
+        unsafe {
+            let value = [42u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let blinding = [3u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            let mut reblinded = [0u8; POINT_SIZE];
+            let mut delta = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_pedersen_reblind(reblinded.as_mut_ptr(), delta.as_mut_ptr(), commitment.as_ptr()), FCMP_SUCCESS);
+            assert_ne!(reblinded, commitment);
+
+            // The value is recoverable under blinding + delta
+            let mut new_blinding = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_scalar_add(new_blinding.as_mut_ptr(), blinding.as_ptr(), delta.as_ptr()), FCMP_SUCCESS);
+            let mut recovered = 0u64;
+            assert_eq!(fcmp_pedersen_recover_value(reblinded.as_ptr(), new_blinding.as_ptr(), 100, &mut recovered), FCMP_SUCCESS);
+            assert_eq!(recovered, 42);
+        }
+    }
+
+    #[test]
+    fn test_proof_size_overflow() {
+        assert!(fcmp_proof_size(1, 1) > 0);
+        assert!(fcmp_proof_size(2, 8) > fcmp_proof_size(1, 8));
+        assert_eq!(fcmp_proof_size(0, 8), 0);
+        assert_eq!(fcmp_proof_size(1, 0), 0);
+
+        // 32 * inputs * layers no longer wraps around to a tiny size
+        assert_eq!(fcmp_proof_size(u32::MAX, u32::MAX), 0);
+        assert_eq!(fcmp_proof_size(u32::MAX, u32::MAX / 2), 0);
+        assert_eq!(fcmp_proof_size(u32::MAX / 2, u32::MAX), 0);
+    }
+
+    #[test]
+    fn test_proof_size_plausible() {
+        for (inputs, layers) in [(1, 1), (2, 8), (16, FCMP_MAX_TREE_DEPTH)] {
+            let estimate = fcmp_proof_size(inputs, layers);
+            assert_eq!(fcmp_proof_size_plausible(estimate, inputs, layers), 1);
+            assert_eq!(
+                fcmp_proof_size_plausible(inputs as usize * proof_len_for_layers(layers as usize), inputs, layers),
+                1
+            );
+            assert_eq!(fcmp_proof_size_plausible(estimate * 1000, inputs, layers), 0);
+            assert_eq!(fcmp_proof_size_plausible(0, inputs, layers), 0);
+        }
+
+        assert_eq!(fcmp_proof_size_plausible(fcmp_proof_size(1, 8), 0, 8), 0);
+        assert_eq!(fcmp_proof_size_plausible(fcmp_proof_size(1, 64), 1, 64), 0);
+        assert_eq!(fcmp_proof_size_plausible(usize::MAX, u32::MAX, FCMP_MAX_TREE_DEPTH), 0);
+    }
+
+    #[test]
+    fn test_proof_weight() {
+        assert_eq!(fcmp_proof_weight(0, 0, 0), 0);
+        for inputs in 1..8 {
+            for layers in 1..FCMP_MAX_TREE_DEPTH {
+                let weight = fcmp_proof_weight(inputs, layers, 64);
+                assert!(fcmp_proof_weight(inputs + 1, layers, 64) > weight);
+                assert!(fcmp_proof_weight(inputs, layers + 1, 64) > weight);
+                assert!(fcmp_proof_weight(inputs, layers, 128) > weight);
+            }
+        }
+
+        // One membership proof weighs what fcmp_verify_limited charges for it
+        assert_eq!(fcmp_proof_weight(1, 4, 0), 21);
+        assert_eq!(fcmp_proof_weight(u32::MAX, u32::MAX, u32::MAX), u64::MAX);
+    }
+
+    /// Build an output tuple O || I || C from three scalar multiples of G
+    fn test_output(seed: u8) -> [u8; OUTPUT_TUPLE_SIZE] {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        let mut output = [0u8; OUTPUT_TUPLE_SIZE];
+        for i in 0..3 {
+            let point = Scalar::from(seed as u64 * 3 + i as u64 + 1) * ED25519_BASEPOINT_POINT;
+            output[i * POINT_SIZE..(i + 1) * POINT_SIZE].copy_from_slice(point.compress().as_bytes());
+        }
+        output
+    }
+
+    #[test]
+    fn test_point_to_affine_basepoint() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+
+        let (x, y) = point_to_affine(&ED25519_BASEPOINT_POINT);
+        let expected_x = [
+            0x1a, 0xd5, 0x25, 0x8f, 0x60, 0x2d, 0x56, 0xc9, 0xb2, 0xa7, 0x25, 0x95, 0x60, 0xc7, 0x2c, 0x69,
+            0x5c, 0xdc, 0xd6, 0xfd, 0x31, 0xe2, 0xa4, 0xc0, 0xfe, 0x53, 0x6e, 0xcd, 0xd3, 0x36, 0x69, 0x21,
+        ];
+        assert_eq!(x, expected_x);
+        assert_eq!(y[0], 0x58);
+        assert!(y[1..].iter().all(|&b| b == 0x66));
+
+        // -G has the same y and the negated x
+        let (neg_x, neg_y) = point_to_affine(&-ED25519_BASEPOINT_POINT);
+        assert_eq!(neg_y, y);
+        assert_ne!(neg_x, x);
+    }
+
+    #[test]
+    fn test_output_leaf_scalar() {
+        unsafe {
+            let output = test_output(1);
+
+            let mut leaf = [0u8; SCALAR_SIZE];
+            let mut leaf2 = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_output_leaf_scalar(leaf.as_mut_ptr(), output.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_output_leaf_scalar(leaf2.as_mut_ptr(), output.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(leaf, leaf2);
+
+            // Matches the two-step composition
+            let mut elements = [0u8; ELEMENTS_PER_OUTPUT * SCALAR_SIZE];
+            assert_eq!(fcmp_output_to_elements(elements.as_mut_ptr(), output.as_ptr()), FCMP_SUCCESS);
+            let mut composed = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_hash_leaf(composed.as_mut_ptr(), elements.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(leaf, composed);
+
+            let other = test_output(2);
+            assert_eq!(fcmp_output_leaf_scalar(leaf2.as_mut_ptr(), other.as_ptr()), FCMP_SUCCESS);
+            assert_ne!(leaf, leaf2);
+        }
+    }
+
+    #[test]
+    fn test_key_image_batch() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            // Two outputs appear twice, as when a wallet holds repeated keys
+            let keys = [11u64, 12, 11, 13, 12];
+            let secrets: Vec<u8> = keys.iter().flat_map(|&k| Scalar::from(k).to_bytes()).collect();
+            let pubkeys: Vec<u8> = keys
+                .iter()
+                .flat_map(|&k| (Scalar::from(k) * ED25519_BASEPOINT_POINT).compress().to_bytes())
+                .collect();
+
+            let mut images = vec![0u8; keys.len() * POINT_SIZE];
+            assert_eq!(fcmp_key_image_batch(images.as_mut_ptr(), secrets.as_ptr(), pubkeys.as_ptr(), 5), FCMP_SUCCESS);
+            for i in 0..keys.len() {
+                let mut single = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_key_image_batch(single.as_mut_ptr(), secrets[i * SCALAR_SIZE..].as_ptr(), pubkeys[i * POINT_SIZE..].as_ptr(), 1),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(images[i * POINT_SIZE..(i + 1) * POINT_SIZE], single);
+            }
+            assert_eq!(images[..POINT_SIZE], images[2 * POINT_SIZE..3 * POINT_SIZE]);
+
+            // Matches the key image an SA+L spend of the output reports
+            let spend = TestSpend::new(11, &[1u8; 32], b"batch");
+            assert_eq!(images[..POINT_SIZE], spend.key_image);
+
+            let mut bad_secrets = secrets.clone();
+            bad_secrets[SCALAR_SIZE + 31] = 0xff;
+            let before = images.clone();
+            assert_eq!(
+                fcmp_key_image_batch(images.as_mut_ptr(), bad_secrets.as_ptr(), pubkeys.as_ptr(), 5),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+            assert_eq!(images, before);
+        }
+    }
+
+    #[test]
+    fn test_key_images_find_duplicates() {
+        unsafe {
+            let mut images = [[0u8; POINT_SIZE]; 5];
+            for (i, image) in images.iter_mut().enumerate() {
+                image[0] = i as u8;
+            }
+
+            let mut dup = u32::MAX;
+            assert_eq!(fcmp_key_images_find_duplicates(images.as_ptr() as *const u8, 5, &mut dup), FCMP_SUCCESS);
+            assert_eq!(dup, u32::MAX);
+
+            images[3] = images[1];
+            assert_eq!(
+                fcmp_key_images_find_duplicates(images.as_ptr() as *const u8, 5, &mut dup),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(dup, 3);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_commit_checked() {
+        unsafe {
+            let value = [42u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let blinding = [1u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+            let mut lenient = [0u8; POINT_SIZE];
+            let mut checked = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(lenient.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_pedersen_commit_checked(checked.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(lenient, checked);
+
+            // A value above l is reduced by the lenient variant but rejected here
+            let above_l = [0xffu8; SCALAR_SIZE];
+            assert_eq!(fcmp_pedersen_commit(lenient.as_mut_ptr(), above_l.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_pedersen_commit_checked(checked.as_mut_ptr(), above_l.as_ptr(), blinding.as_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+            assert_eq!(
+                fcmp_pedersen_commit_checked(checked.as_mut_ptr(), value.as_ptr(), above_l.as_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
+    }
+
+    #[test]
+    fn test_scalar_is_zero_one() {
+        unsafe {
+            let mut zero = [0u8; SCALAR_SIZE];
+            let mut one = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_scalar_zero(zero.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_scalar_one(one.as_mut_ptr()), FCMP_SUCCESS);
+
+            assert_eq!(fcmp_scalar_is_zero(zero.as_ptr()), 1);
+            assert_eq!(fcmp_scalar_is_zero(one.as_ptr()), 0);
+            assert_eq!(fcmp_scalar_is_one(one.as_ptr()), 1);
+            assert_eq!(fcmp_scalar_is_one(zero.as_ptr()), 0);
+
+            let random = curve25519_dalek::scalar::Scalar::random(&mut OsRng).to_bytes();
+            assert_eq!(fcmp_scalar_is_zero(random.as_ptr()), 0);
+            assert_eq!(fcmp_scalar_is_one(random.as_ptr()), 0);
+
+            // l itself reduces to zero
+            let l: [u8; SCALAR_SIZE] = [
+                0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+            ];
+            assert_eq!(fcmp_scalar_is_zero(l.as_ptr()), 1);
+
+            assert_eq!(fcmp_scalar_is_zero(ptr::null()), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(fcmp_scalar_is_one(ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_commit_deterministic() {
+        unsafe {
+            let mut value = [0u8; SCALAR_SIZE];
+            value[0] = 100;
+            let seed = b"fixture seed 1";
+            let other_seed = b"fixture seed 2";
+
+            let mut c1 = [0u8; POINT_SIZE];
+            let mut b1 = [0u8; SCALAR_SIZE];
+            let mut c2 = [0u8; POINT_SIZE];
+            let mut b2 = [0u8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_pedersen_commit_deterministic(c1.as_mut_ptr(), b1.as_mut_ptr(), value.as_ptr(), seed.as_ptr(), seed.len()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_pedersen_commit_deterministic(c2.as_mut_ptr(), b2.as_mut_ptr(), value.as_ptr(), seed.as_ptr(), seed.len()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(c1, c2);
+            assert_eq!(b1, b2);
+
+            // The blinding opens the commitment
+            let mut expected = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(expected.as_mut_ptr(), value.as_ptr(), b1.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(c1, expected);
+
+            assert_eq!(
+                fcmp_pedersen_commit_deterministic(c2.as_mut_ptr(), b2.as_mut_ptr(), value.as_ptr(), other_seed.as_ptr(), other_seed.len()),
+                FCMP_SUCCESS
+            );
+            assert_ne!(c1, c2);
+            assert_ne!(b1, b2);
+
+            assert_eq!(
+                fcmp_pedersen_commit_deterministic(c2.as_mut_ptr(), b2.as_mut_ptr(), value.as_ptr(), seed.as_ptr(), 0),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_rerandomize() {
+        use curve25519_dalek::edwards::EdwardsPoint;
+
+        unsafe {
+            let output = test_output(2);
+            let seed = [7u8; 32];
+
+            let mut input = test_input();
+            let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), output.as_ptr(), seed.as_ptr()),
+                FCMP_SUCCESS
+            );
+
+            let point = |b: &[u8]| decode_point(b).unwrap();
+            let (o, i, c) = (point(&output[..32]), point(&output[32..64]), point(&output[64..]));
+            let scalar = |k: usize| decode_scalar(&blinders[k * SCALAR_SIZE..(k + 1) * SCALAR_SIZE]).unwrap();
+            let (r_o, r_i, r_r, r_c) = (scalar(0), scalar(1), scalar(2), scalar(3));
+            let [t, u, v] = rerandomization_generators().unwrap();
+            let h = pedersen_h().unwrap();
+
+            // The blinders relate every re-randomized point to the original
+            assert_eq!(input.o_tilde, point_to_affine_bytes(&(o + r_o * t)));
+            assert_eq!(input.i_tilde, point_to_affine_bytes(&(i + r_i * u)));
+            assert_eq!(input.r, point_to_affine_bytes(&(r_i * v + r_r * t)));
+            assert_eq!(input.c_tilde, point_to_affine_bytes(&(c + r_c * h)));
+            assert_ne!(input.o_tilde, point_to_affine_bytes(&o));
+            assert_ne!(input.r, point_to_affine_bytes(&EdwardsPoint::default()));
+
+            // Deterministic in the seed
+            let mut again = test_input();
+            let mut blinders2 = [0u8; FCMP_BLINDERS_SIZE];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut again, blinders2.as_mut_ptr(), output.as_ptr(), seed.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(again.o_tilde, input.o_tilde);
+            assert_eq!(blinders2, blinders);
+        }
+    }
+
+    #[test]
+    fn test_input_matches_output() {
+        unsafe {
+            let output = test_output(2);
+            let mut input = test_input();
+            let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), output.as_ptr(), [7u8; 32].as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_input_matches_output(&input, output.as_ptr(), blinders.as_ptr()), FCMP_SUCCESS);
+
+            // Tampering with any re-randomized point breaks the match
+            for field in 0..4 {
+                let mut tampered = test_input();
+                assert_eq!(
+                    fcmp_output_rerandomize(&mut tampered, blinders.as_mut_ptr(), output.as_ptr(), [7u8; 32].as_ptr()),
+                    FCMP_SUCCESS
+                );
+                [&mut tampered.o_tilde, &mut tampered.i_tilde, &mut tampered.r, &mut tampered.c_tilde][field][0] ^= 1;
+                assert_eq!(
+                    fcmp_input_matches_output(&tampered, output.as_ptr(), blinders.as_ptr()),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+
+            // A different output does not match under the same blinders
+            let other = test_output(3);
+            assert_eq!(
+                fcmp_input_matches_output(&input, other.as_ptr(), blinders.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // Nor do the right output and the wrong blinders
+            let mut wrong = blinders;
+            wrong[3 * SCALAR_SIZE] ^= 1;
+            assert_eq!(
+                fcmp_input_matches_output(&input, output.as_ptr(), wrong.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            wrong[3 * SCALAR_SIZE..].copy_from_slice(&[0xff; SCALAR_SIZE]);
+            assert_eq!(
+                fcmp_input_matches_output(&input, output.as_ptr(), wrong.as_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
+    }
+
+    /// Amount and blinding of every output spent by `TestSpend`
+    const SPEND_VALUE: u64 = 1000;
+    const SPEND_BLINDING: u64 = 777;
+
+    /// A re-randomized input with its SA+L signature
+    struct TestSpend {
+        input: FcmpInput,
+        blinders: [u8; FCMP_BLINDERS_SIZE],
+        sig: [u8; FCMP_SAL_SIGNATURE_SIZE],
+        key_image: [u8; POINT_SIZE],
+    }
+
+    impl TestSpend {
+        /// Re-randomize an output with O = secret*G and sign `message` for it
+        unsafe fn new(secret: u64, seed: &[u8; 32], message: &[u8]) -> Self {
+            use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+            use curve25519_dalek::scalar::Scalar;
+
+            let x = Scalar::from(secret);
+            let mut value_bytes = [0u8; SCALAR_SIZE];
+            value_bytes[..8].copy_from_slice(&SPEND_VALUE.to_le_bytes());
+            let o = (x * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let mut output = [0u8; OUTPUT_TUPLE_SIZE];
+            output[..POINT_SIZE].copy_from_slice(&o);
+            assert_eq!(fcmp_hash_to_point(output[POINT_SIZE..].as_mut_ptr(), o.as_ptr(), o.len()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_pedersen_commit(
+                    output[2 * POINT_SIZE..].as_mut_ptr(),
+                    value_bytes.as_ptr(),
+                    Scalar::from(SPEND_BLINDING).as_bytes().as_ptr(),
+                ),
+                FCMP_SUCCESS
+            );
+
+            let mut spend = TestSpend {
+                input: test_input(),
+                blinders: [0u8; FCMP_BLINDERS_SIZE],
+                sig: [0u8; FCMP_SAL_SIGNATURE_SIZE],
+                key_image: [0u8; POINT_SIZE],
+            };
+            assert_eq!(
+                fcmp_output_rerandomize(&mut spend.input, spend.blinders.as_mut_ptr(), output.as_ptr(), seed.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_sal_sign(
+                    spend.sig.as_mut_ptr(),
+                    spend.key_image.as_mut_ptr(),
+                    x.as_bytes().as_ptr(),
+                    spend.blinders.as_ptr(),
+                    &spend.input,
+                    message.as_ptr(),
+                    message.len(),
+                    0,
+                ),
+                FCMP_SUCCESS
+            );
+            spend
+        }
+    }
+
+    /// Owned buffers backing an `FcmpTransaction`
+    struct TestTransaction {
+        root: [u8; POINT_SIZE],
+        prefix_hash: [u8; 32],
+        inputs: Vec<FcmpInput>,
+        key_images: Vec<u8>,
+        proofs: Vec<u8>,
+        signatures: Vec<u8>,
+        outputs: Vec<u8>,
+        range_proof: Vec<u8>,
+        fee: u64,
+    }
+
+    impl TestTransaction {
+        /// Spend one 1000-unit output per (secret, seed) pair into a single
+        /// output paying `fee`
+        unsafe fn new(spends: &[(u64, [u8; 32])], fee: u64) -> Self {
+            Self::with_range_bits(spends, fee, FCMP_RANGE_MAX_BITS)
+        }
+
+        /// As `new`, with a `range_bits`-bit range proof over the output
+        unsafe fn with_range_bits(spends: &[(u64, [u8; 32])], fee: u64, range_bits: u32) -> Self {
+            use curve25519_dalek::scalar::Scalar;
+
+            let value = SPEND_VALUE;
+            let mut tx = TestTransaction {
+                root: [0x42; POINT_SIZE],
+                prefix_hash: [0x55; 32],
+                inputs: Vec::new(),
+                key_images: Vec::new(),
+                proofs: Vec::new(),
+                signatures: Vec::new(),
+                outputs: vec![0u8; POINT_SIZE],
+                range_proof: vec![0u8; fcmp_range_proof_size(1, range_bits)],
+                fee,
+            };
+
+            let branch = TestBranch::new(2, 4);
+            let mut out_blinding = Scalar::ZERO;
+            for (secret, seed) in spends {
+                let spend = TestSpend::new(*secret, seed, &tx.prefix_hash);
+                out_blinding += Scalar::from(SPEND_BLINDING) + decode_scalar(&spend.blinders[3 * SCALAR_SIZE..]).unwrap();
+                let mut proof = vec![0u8; proof_len_for_layers(branch.layers.len())];
+                let mut proof_len = 0usize;
+                assert_eq!(
+                    fcmp_prove_for_input(
+                        proof.as_mut_ptr(),
+                        &mut proof_len,
+                        proof.len(),
+                        tx.root.as_ptr(),
+                        [9u8; OUTPUT_TUPLE_SIZE].as_ptr(),
+                        &branch.as_ffi(),
+                        &spend.input,
+                    ),
+                    FCMP_SUCCESS
+                );
+                tx.proofs.extend(proof);
+                tx.signatures.extend_from_slice(&spend.sig);
+                tx.key_images.extend_from_slice(&spend.key_image);
+                tx.inputs.push(spend.input);
+            }
+
+            let out_value = value * spends.len() as u64 - fee;
+            let mut out_value_bytes = [0u8; SCALAR_SIZE];
+            out_value_bytes[..8].copy_from_slice(&out_value.to_le_bytes());
+            assert_eq!(
+                fcmp_pedersen_commit(tx.outputs.as_mut_ptr(), out_value_bytes.as_ptr(), out_blinding.as_bytes().as_ptr()),
+                FCMP_SUCCESS
+            );
+
+            let mut range_len = 0usize;
+            assert_eq!(
+                fcmp_range_prove(
+                    tx.range_proof.as_mut_ptr(),
+                    &mut range_len,
+                    tx.range_proof.len(),
+                    &out_value,
+                    out_blinding.as_bytes().as_ptr(),
+                    1,
+                    range_bits,
+                ),
+                FCMP_SUCCESS
+            );
+            assert_eq!(range_len, tx.range_proof.len());
+            tx
+        }
+
+        fn as_ffi(&self) -> FcmpTransaction {
+            FcmpTransaction {
+                tree_root: self.root.as_ptr(),
+                prefix_hash: self.prefix_hash.as_ptr(),
+                num_inputs: self.inputs.len() as u32,
+                inputs: self.inputs.as_ptr(),
+                key_images: self.key_images.as_ptr(),
+                proofs: self.proofs.as_ptr(),
+                proofs_len: self.proofs.len(),
+                signatures: self.signatures.as_ptr(),
+                num_outputs: 1,
+                output_commitments: self.outputs.as_ptr(),
+                range_proof: self.range_proof.as_ptr(),
+                range_proof_len: self.range_proof.len(),
+                fee: self.fee,
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_transaction() {
+        let _guard = init_lock();
+
+        unsafe {
+            let verify = |tx: &TestTransaction| fcmp_verify_transaction(&tx.as_ffi());
+            let mut tx = TestTransaction::new(&[(11, [1u8; 32]), (12, [2u8; 32])], 10);
+            assert_eq!(verify(&tx), FCMP_SUCCESS);
+
+            // Break one component at a time, restoring it afterwards
+
+            // Membership proof
+            tx.proofs[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.proofs[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            let last = tx.proofs.pop().unwrap();
+            assert_eq!(verify(&tx), FCMP_ERROR_INVALID_PARAM);
+            tx.proofs.push(last);
+
+            // Proofs are bound to their inputs: swapped between inputs, or
+            // next to an input from another transaction, they fail
+            let half = tx.proofs.len() / 2;
+            tx.proofs.rotate_left(half);
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.proofs.rotate_left(half);
+            let foreign = TestSpend::new(13, &[3u8; 32], &tx.prefix_hash).input;
+            let own = std::mem::replace(&mut tx.inputs[0], foreign);
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.inputs[0] = own;
+            let unbound = prove_with(&tx.root, &TestBranch::new(2, 4));
+            assert_eq!(unbound.len(), half);
+            let bound = std::mem::replace(&mut tx.proofs, [&unbound[..], &unbound[..]].concat());
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.proofs = bound;
+
+            // SA+L signature, and a signature over a different prefix
+            tx.signatures[3 * POINT_SIZE] ^= 1;
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.signatures[3 * POINT_SIZE] ^= 1;
+            tx.prefix_hash[0] ^= 1;
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.prefix_hash[0] ^= 1;
+
+            // Key images that do not belong to their inputs
+            tx.key_images.rotate_left(POINT_SIZE);
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.key_images.rotate_left(POINT_SIZE);
+
+            // Range proof
+            tx.range_proof[1 + 4 * SCALAR_SIZE] ^= 1;
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.range_proof[1 + 4 * SCALAR_SIZE] ^= 1;
+            let narrow = TestTransaction::with_range_bits(&[(11, [1u8; 32]), (12, [2u8; 32])], 10, 32);
+            assert_eq!(verify(&narrow), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Balance
+            tx.fee += 1;
+            assert_eq!(verify(&tx), FCMP_ERROR_PROOF_VERIFICATION);
+            tx.fee -= 1;
+
+            assert_eq!(verify(&tx), FCMP_SUCCESS);
+            assert_eq!(fcmp_verify_transaction(ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_verify_transaction_double_spend() {
+        let _guard = init_lock();
+
+        unsafe {
+            // The same output re-randomized twice still yields one key image
+            let tx = TestTransaction::new(&[(11, [1u8; 32]), (11, [2u8; 32])], 10);
+            assert_ne!(tx.inputs[0].o_tilde, tx.inputs[1].o_tilde);
+            assert_eq!(tx.key_images[..POINT_SIZE], tx.key_images[POINT_SIZE..]);
+            assert_eq!(fcmp_verify_transaction(&tx.as_ffi()), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Each input on its own is a valid spend
+            for j in 0..2 {
+                let sig = &tx.signatures[j * FCMP_SAL_SIGNATURE_SIZE..];
+                assert_eq!(
+                    fcmp_sal_verify(sig.as_ptr(), &tx.inputs[j], tx.key_images.as_ptr(), tx.prefix_hash.as_ptr(), 32, 0),
+                    FCMP_SUCCESS
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_proof() {
+        unsafe {
+            let values = [5u64, 255];
+            let blindings = [[3u8; SCALAR_SIZE], [4u8; SCALAR_SIZE]];
+            let mut commitments = [0u8; 2 * POINT_SIZE];
+            for (j, (value, blinding)) in values.iter().zip(&blindings).enumerate() {
+                let mut value_bytes = [0u8; SCALAR_SIZE];
+                value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+                assert_eq!(
+                    fcmp_pedersen_commit(commitments[j * POINT_SIZE..].as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()),
+                    FCMP_SUCCESS
+                );
+            }
+
+            let mut proof = vec![0u8; fcmp_range_proof_size(2, 8)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), values.as_ptr(), blindings.as_ptr() as *const u8, 2, 8),
+                FCMP_SUCCESS
+            );
+            assert_eq!(proof_len, 1 + 2 * 8 * FCMP_RANGE_BIT_SIZE);
+            assert_eq!(fcmp_range_verify(proof.as_ptr(), proof_len, commitments.as_ptr(), 2, 8), FCMP_SUCCESS);
+
+            // The proof states its width and is bound to the commitments
+            assert_eq!(fcmp_range_verify(proof.as_ptr(), proof_len, commitments.as_ptr(), 2, 16), FCMP_ERROR_PROOF_VERIFICATION);
+            commitments.swap(0, POINT_SIZE);
+            assert_ne!(fcmp_range_verify(proof.as_ptr(), proof_len, commitments.as_ptr(), 2, 8), FCMP_SUCCESS);
+
+            // 256 does not fit in 8 bits
+            let too_big = [256u64, 0];
+            assert_eq!(
+                fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), too_big.as_ptr(), blindings.as_ptr() as *const u8, 2, 8),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            assert_eq!(
+                fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len() - 1, values.as_ptr(), blindings.as_ptr() as *const u8, 2, 8),
+                FCMP_ERROR_MEMORY
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_and_report_range() {
+        unsafe {
+            let value = 70_000u64;
+            let blinding = [6u8; SCALAR_SIZE];
+            let mut value_bytes = [0u8; SCALAR_SIZE];
+            value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            for bits in [64, 32] {
+                let mut proof = vec![0u8; fcmp_range_proof_size(1, bits)];
+                let mut proof_len = 0usize;
+                assert_eq!(
+                    fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), &value, blinding.as_ptr(), 1, bits),
+                    FCMP_SUCCESS
+                );
+
+                let mut reported = 0u32;
+                assert_eq!(
+                    fcmp_verify_and_report_range(commitment.as_ptr(), 1, proof.as_ptr(), proof_len, &mut reported),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(reported, bits);
+
+                // A failed verification reports nothing
+                proof[proof_len - 1] ^= 1;
+                let mut reported = 0u32;
+                assert_ne!(
+                    fcmp_verify_and_report_range(commitment.as_ptr(), 1, proof.as_ptr(), proof_len, &mut reported),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(reported, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserves_proof() {
+        use curve25519_dalek::constants::EIGHT_TORSION;
+
+        unsafe {
+            // Three outputs worth 1000 in total
+            let values = [500u64, 300, 200];
+            let mut secrets = Vec::new();
+            let mut commitments = Vec::new();
+            for (j, value) in values.iter().enumerate() {
+                let mut secret = [0u8; 2 * SCALAR_SIZE];
+                secret[..8].copy_from_slice(&value.to_le_bytes());
+                secret[SCALAR_SIZE] = j as u8 + 1;
+                let mut commitment = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_pedersen_commit(commitment.as_mut_ptr(), secret.as_ptr(), secret[SCALAR_SIZE..].as_ptr()),
+                    FCMP_SUCCESS
+                );
+                secrets.extend_from_slice(&secret);
+                commitments.extend_from_slice(&commitment);
+            }
+
+            let mut proof = vec![0u8; fcmp_reserves_proof_size(3)];
+            for min_total in [0, 999, 1000] {
+                assert_eq!(
+                    fcmp_reserves_prove(proof.as_mut_ptr(), secrets.as_ptr(), commitments.as_ptr(), 3, min_total),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(fcmp_reserves_verify(proof.as_ptr(), commitments.as_ptr(), 3, min_total), FCMP_SUCCESS);
+                // The proof is bound to the minimum it was made for
+                assert_eq!(
+                    fcmp_reserves_verify(proof.as_ptr(), commitments.as_ptr(), 3, min_total + 1),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+
+            // A total below the claimed minimum cannot be proven
+            assert_eq!(
+                fcmp_reserves_prove(proof.as_mut_ptr(), secrets.as_ptr(), commitments.as_ptr(), 3, 1001),
+                FCMP_ERROR_PROOF_GENERATION
+            );
+            // Dropping an output from the claimed set breaks the proof
+            assert_eq!(
+                fcmp_reserves_prove(proof.as_mut_ptr(), secrets.as_ptr(), commitments.as_ptr(), 3, 1000),
+                FCMP_SUCCESS
+            );
+            assert_ne!(fcmp_reserves_verify(proof.as_ptr(), commitments.as_ptr(), 2, 1000), FCMP_SUCCESS);
+            let mut other = commitments.clone();
+            other.swap(0, POINT_SIZE);
+            assert_ne!(fcmp_reserves_verify(proof.as_ptr(), other.as_ptr(), 3, 1000), FCMP_SUCCESS);
+
+            // Secrets must open their commitments
+            let mut wrong = secrets.clone();
+            wrong[0] ^= 1;
+            assert_eq!(
+                fcmp_reserves_prove(proof.as_mut_ptr(), wrong.as_ptr(), commitments.as_ptr(), 3, 0),
+                FCMP_ERROR_PROOF_GENERATION
+            );
+
+            // A proof reused for a set with another output in it fails
+            let mut reused = commitments.clone();
+            assert_eq!(
+                fcmp_pedersen_commit(reused[POINT_SIZE..].as_mut_ptr(), [9u8; SCALAR_SIZE].as_ptr(), [9u8; SCALAR_SIZE].as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_reserves_verify(proof.as_ptr(), reused.as_ptr(), 3, 1000), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Counting the 500 output twice would claim 1000. The prover
+            // refuses, and a proof forged past that check is refused too
+            let doubled_secrets = [&secrets[..2 * SCALAR_SIZE], &secrets[..2 * SCALAR_SIZE]].concat();
+            let doubled = [&commitments[..POINT_SIZE], &commitments[..POINT_SIZE]].concat();
+            let mut doubled_proof = vec![0u8; fcmp_reserves_proof_size(2)];
+            assert_eq!(
+                fcmp_reserves_prove(doubled_proof.as_mut_ptr(), doubled_secrets.as_ptr(), doubled.as_ptr(), 2, 1000),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            let forged = reserves_proof(&doubled_secrets, &doubled, 1000).unwrap();
+            assert_eq!(fcmp_reserves_verify(forged.as_ptr(), doubled.as_ptr(), 2, 1000), FCMP_ERROR_INVALID_PARAM);
+
+            // Nor may an output reappear with a torsion component
+            let torsioned = (decode_point(&commitments[..POINT_SIZE]).unwrap() + EIGHT_TORSION[1]).compress().to_bytes();
+            let aliased = [&commitments[..POINT_SIZE], &torsioned[..]].concat();
+            assert_eq!(
+                fcmp_reserves_prove(doubled_proof.as_mut_ptr(), doubled_secrets.as_ptr(), aliased.as_ptr(), 2, 1000),
+                FCMP_ERROR_INVALID_POINT
+            );
+            assert_eq!(fcmp_reserves_verify(forged.as_ptr(), aliased.as_ptr(), 2, 1000), FCMP_ERROR_INVALID_POINT);
+        }
+    }
+
+    #[test]
+    fn test_range_verify_batch() {
+        unsafe {
+            // Commitment and single-commitment proof of `value` in `bits` bits
+            let prove = |value: u64, bits: u32| {
+                let blinding = [(value % 101) as u8 + 1; SCALAR_SIZE];
+                let mut value_bytes = [0u8; SCALAR_SIZE];
+                value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+                let mut commitment = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_pedersen_commit(commitment.as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()),
+                    FCMP_SUCCESS
+                );
+                let mut proof = vec![0u8; fcmp_range_proof_size(1, bits)];
+                let mut proof_len = 0usize;
+                assert_eq!(
+                    fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), &value, blinding.as_ptr(), 1, bits),
+                    FCMP_SUCCESS
+                );
+                (commitment, proof)
+            };
+
+            let (c0, p0) = prove(5, 8);
+            let (c1, p1) = prove(255, 8);
+            let (c2, p2) = prove(300, 16);
+            let (c3, p3) = prove(40000, 16);
+            let (too_big, _) = prove(256, 16);
+
+            let verify = |proofs: &[&Vec<u8>], commitments: &[[u8; POINT_SIZE]], bits: &[u32]| {
+                let ptrs: Vec<*const u8> = proofs.iter().map(|p| p.as_ptr()).collect();
+                let lens: Vec<usize> = proofs.iter().map(|p| p.len()).collect();
+                let commitments = commitments.concat();
+                let mut results = vec![i32::MIN; proofs.len()];
+                let ret = fcmp_range_verify_batch(
+                    ptrs.as_ptr(),
+                    lens.as_ptr(),
+                    commitments.as_ptr(),
+                    bits.as_ptr(),
+                    proofs.len() as u32,
+                    results.as_mut_ptr(),
+                );
+                (ret, results)
+            };
+
+            let (ret, results) = verify(&[&p0, &p1, &p2, &p3], &[c0, c1, c2, c3], &[8, 8, 16, 16]);
+            assert_eq!(ret, FCMP_SUCCESS);
+            assert_eq!(results, [FCMP_SUCCESS; 4]);
+
+            // 300 is not below 2^8, and 256 has no 8-bit proof to reuse
+            let (ret, results) = verify(&[&p0, &p2, &p1, &p3], &[c0, c2, too_big, c3], &[8, 8, 8, 16]);
+            assert_eq!(ret, FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(results, [FCMP_SUCCESS, FCMP_ERROR_PROOF_VERIFICATION, FCMP_ERROR_PROOF_VERIFICATION, FCMP_SUCCESS]);
+
+            // Each entry matches the single-proof verifier, malformed ones included
+            let truncated = p3[..p3.len() - 1].to_vec();
+            let (ret, results) = verify(&[&truncated, &p1], &[c3, c1], &[16, 8]);
+            assert_eq!(ret, FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(results, [fcmp_range_verify(truncated.as_ptr(), truncated.len(), c3.as_ptr(), 1, 16), FCMP_SUCCESS]);
+            assert_eq!(results[0], FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_precomputed_bases_match_plain_msm() {
+        let _guard = init_lock();
+        unsafe {
+            let params = with_params(FcmpParams::clone).unwrap();
+            let bases = BaseTables::new();
+            let root = [3u8; POINT_SIZE];
+
+            for num_layers in [1, 8, FCMP_MAX_TREE_DEPTH as usize] {
+                let proof = prove_with(&root, &TestBranch::new(num_layers, 2));
+                let mut tampered = proof.clone();
+                let last = FCMP_PROOF_HEADER_SIZE + (num_layers - 1) * FCMP_PROOF_LAYER_SIZE;
+                tampered[last + 2 * POINT_SIZE] ^= 1;
+
+                for p in [&proof, &tampered] {
+                    let plain = verify_proof_batched(&root, p, Transcript::new(), &params, None);
+                    let precomputed = verify_proof_batched(&root, p, Transcript::new(), &params, Some(&bases));
+                    assert_eq!(plain, precomputed);
+                }
+                assert_eq!(verify_proof_batched(&root, &proof, Transcript::new(), &params, Some(&bases)), FCMP_SUCCESS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_edwards_montgomery_roundtrip() {
+        unsafe {
+            let mut basepoint = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+
+            // The Ed25519 basepoint maps to the X25519 basepoint u = 9
+            let mut u = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_edwards_to_montgomery(u.as_mut_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
+            let mut nine = [0u8; POINT_SIZE];
+            nine[0] = 9;
+            assert_eq!(u, nine);
+
+            // Its x is non-negative, so sign 0 recovers it and sign 1 its negation
+            let mut back = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_montgomery_to_edwards(back.as_mut_ptr(), u.as_ptr(), 0), FCMP_SUCCESS);
+            assert_eq!(back, basepoint);
+            assert_eq!(fcmp_montgomery_to_edwards(back.as_mut_ptr(), u.as_ptr(), 1), FCMP_SUCCESS);
+            let mut negated = basepoint;
+            negated[31] ^= 0x80;
+            assert_eq!(back, negated);
+
+            // u = -1 has no Edwards point
+            let mut minus_one = [0xffu8; POINT_SIZE];
+            minus_one[0] = 0xec;
+            minus_one[31] = 0x7f;
+            assert_eq!(fcmp_montgomery_to_edwards(back.as_mut_ptr(), minus_one.as_ptr(), 0), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(fcmp_montgomery_to_edwards(back.as_mut_ptr(), u.as_ptr(), 2), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_derive_output_key() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let view_secret = Scalar::random(&mut OsRng);
+            let spend_secret = Scalar::random(&mut OsRng);
+            let tx_secret = Scalar::random(&mut OsRng);
+            let view_public = (view_secret * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let spend_public = (spend_secret * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let tx_public = tx_secret * ED25519_BASEPOINT_POINT;
+
+            let mut key0 = [0u8; POINT_SIZE];
+            let mut key1 = [0u8; POINT_SIZE];
+            for (key, index) in [(&mut key0, 0), (&mut key1, 1)] {
+                assert_eq!(
+                    fcmp_derive_output_key(key.as_mut_ptr(), tx_secret.as_bytes().as_ptr(), view_public.as_ptr(), spend_public.as_ptr(), index),
+                    FCMP_SUCCESS
+                );
+            }
+            assert_ne!(key0, key1);
+
+            // The receiver recomputes the key from a*R, and knows its secret
+            let offset = output_key_offset(&(view_secret * tx_public), 0);
+            let expected = offset * ED25519_BASEPOINT_POINT + spend_secret * ED25519_BASEPOINT_POINT;
+            assert_eq!(key0, expected.compress().to_bytes());
+            assert_eq!(key0, ((offset + spend_secret) * ED25519_BASEPOINT_POINT).compress().to_bytes());
+
+            let bad_scalar = [0xffu8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_derive_output_key(key0.as_mut_ptr(), bad_scalar.as_ptr(), view_public.as_ptr(), spend_public.as_ptr(), 0),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_output() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let view_secret = Scalar::random(&mut OsRng);
+            let spend_secret = Scalar::random(&mut OsRng);
+            let tx_secret = Scalar::random(&mut OsRng);
+            let view_public = (view_secret * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let spend_public = (spend_secret * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let tx_public = (tx_secret * ED25519_BASEPOINT_POINT).compress().to_bytes();
+
+            let mut key = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_derive_output_key(key.as_mut_ptr(), tx_secret.as_bytes().as_ptr(), view_public.as_ptr(), spend_public.as_ptr(), 3),
+                FCMP_SUCCESS
+            );
+
+            // Owned: the offset plus the spend secret opens the one-time key
+            let mut owned = -1;
+            let mut offset = [0xffu8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_scan_output(key.as_ptr(), tx_public.as_ptr(), view_secret.as_bytes().as_ptr(), spend_public.as_ptr(), 3, &mut owned, offset.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(owned, 1);
+            let secret = decode_scalar(&offset).unwrap() + spend_secret;
+            assert_eq!((secret * ED25519_BASEPOINT_POINT).compress().to_bytes(), key);
+
+            // Another wallet's view key, or the wrong index, does not match
+            let other_view = Scalar::random(&mut OsRng);
+            for (view, index) in [(other_view, 3), (view_secret, 4)] {
+                assert_eq!(
+                    fcmp_scan_output(key.as_ptr(), tx_public.as_ptr(), view.as_bytes().as_ptr(), spend_public.as_ptr(), index, &mut owned, offset.as_mut_ptr()),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(owned, 0);
+                assert_eq!(offset, [0u8; SCALAR_SIZE]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_input_rejects_non_canonical_coordinates() {
+        unsafe {
+            let mut input = test_input();
+            let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), test_output(4).as_ptr(), [5u8; 32].as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert!(validate_input(&input).is_ok());
+
+            // p = 2^255 - 19 itself is one past the largest canonical element
+            let mut p = [0xffu8; 32];
+            p[0] = 0xed;
+            p[31] = 0x7f;
+            assert!(!field::Fe::is_canonical(&p));
+            p[0] = 0xec;
+            assert!(field::Fe::is_canonical(&p));
+            p[0] = 0xed;
+
+            // A valid input with C~.y replaced by p
+            let mut bad = FcmpInput { o_tilde: input.o_tilde, i_tilde: input.i_tilde, r: input.r, c_tilde: input.c_tilde };
+            bad.c_tilde[32..].copy_from_slice(&p);
+            assert_eq!(validate_input(&bad).err(), Some(FCMP_ERROR_INVALID_POINT));
+
+            let sig = [0u8; FCMP_SAL_SIGNATURE_SIZE];
+            let key_image = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &bad, key_image.as_ptr(), ptr::null(), 0, 0),
+                FCMP_ERROR_INVALID_POINT
+            );
+        }
+    }
+
+    #[test]
+    fn test_params_save_load() {
+        let _guard = init_lock();
+        unsafe {
+            let path = std::env::temp_dir().join(format!("wattx_fcmp_params_{}.bin", std::process::id()));
+            let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+            let fresh: Vec<[u8; POINT_SIZE]> = (0..fcmp_generator_count())
+                .map(|i| {
+                    let mut g = [0u8; POINT_SIZE];
+                    assert_eq!(fcmp_get_generator(i, g.as_mut_ptr()), FCMP_SUCCESS);
+                    g
+                })
+                .collect();
+            let root = [6u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            assert_eq!(fcmp_params_save(c_path.as_ptr()), FCMP_SUCCESS);
+
+            fcmp_cleanup();
+            assert_eq!(fcmp_params_save(c_path.as_ptr()), FCMP_ERROR_NOT_INITIALIZED);
+            assert_eq!(fcmp_params_load(c_path.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_is_initialized(), 1);
+
+            // The loaded context matches a freshly derived one
+            for (i, expected) in fresh.iter().enumerate() {
+                let mut g = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_get_generator(i as u32, g.as_mut_ptr()), FCMP_SUCCESS);
+                assert_eq!(&g, expected);
+            }
+            let input = test_input();
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            // Corrupt and stale files are rejected
+            let original = std::fs::read(&path).unwrap();
+            let mut corrupt = original.clone();
+            corrupt[100] ^= 1;
+            std::fs::write(&path, &corrupt).unwrap();
+            assert_eq!(fcmp_params_load(c_path.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+            let mut stale = original.clone();
+            stale[PARAMS_FILE_MAGIC.len()] = PARAMS_FILE_VERSION + 1;
+            std::fs::write(&path, &stale).unwrap();
+            assert_eq!(fcmp_params_load(c_path.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+
+            // A well-formed file with a chosen generator swapped in is refused
+            let mut chosen = FcmpParams::from_file_bytes(&original).unwrap();
+            chosen.layer_generators[0] = curve25519_dalek::scalar::Scalar::from(5u64) * chosen.h;
+            let chosen = FcmpParams::new(chosen.h, chosen.layer_generators);
+            std::fs::write(&path, chosen.to_file_bytes()).unwrap();
+            assert_eq!(fcmp_params_load(c_path.as_ptr()), FCMP_ERROR_GENERATOR_MISMATCH);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(fcmp_params_load(c_path.as_ptr()), FCMP_ERROR_INTERNAL);
+        }
+    }
+
+    #[test]
+    fn test_key_images_digest() {
+        unsafe {
+            let images: Vec<u8> = (1..=4u8).flat_map(|i| [i; POINT_SIZE]).collect();
+            let mut digest = [0u8; 32];
+            assert_eq!(fcmp_key_images_digest(images.as_ptr(), 4, digest.as_mut_ptr()), FCMP_SUCCESS);
+
+            // Input order does not matter
+            let permuted: Vec<u8> = [3u8, 1, 4, 2].iter().flat_map(|&i| [i; POINT_SIZE]).collect();
+            let mut permuted_digest = [0u8; 32];
+            assert_eq!(fcmp_key_images_digest(permuted.as_ptr(), 4, permuted_digest.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(digest, permuted_digest);
+
+            // Any changed image does
+            let mut changed = images.clone();
+            changed[2 * POINT_SIZE] ^= 1;
+            let mut changed_digest = [0u8; 32];
+            assert_eq!(fcmp_key_images_digest(changed.as_ptr(), 4, changed_digest.as_mut_ptr()), FCMP_SUCCESS);
+            assert_ne!(digest, changed_digest);
+
+            assert_eq!(fcmp_key_images_digest(ptr::null(), 0, digest.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_key_images_digest(ptr::null(), 1, digest.as_mut_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_single_leaf_tree() {
+        let _guard = init_lock();
+        unsafe {
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let mut root = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_output_leaf_scalar(root.as_mut_ptr(), output.as_ptr()), FCMP_SUCCESS);
+
+            // Zero sibling layers; the layer pointer may be null
+            let branch = FcmpBranch { leaf_index: 0, num_layers: 0, layers: ptr::null() };
+            let mut proof = [0u8; FCMP_PROOF_HEADER_SIZE];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch),
+                FCMP_SUCCESS
+            );
+            assert_eq!(proof_len, FCMP_PROOF_HEADER_SIZE);
+
+            assert_eq!(fcmp_verify_single_leaf(root.as_ptr(), output.as_ptr(), proof.as_ptr(), proof_len), FCMP_SUCCESS);
+
+            // Without the output a bare header proves nothing, so the
+            // input-only verifiers refuse it
+            let input = test_input();
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+            let mut scratch = vec![0u8; fcmp_verify_scratch_size(0)];
+            assert_eq!(
+                fcmp_verify_inplace(root.as_ptr(), &input, proof.as_ptr(), proof_len, scratch.as_mut_ptr(), scratch.len()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // A one-leaf proof only holds for the output that is the leaf
+            let other = [7u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), other.as_ptr(), output.as_ptr(), &branch),
+                FCMP_ERROR_PROOF_GENERATION
+            );
+
+            // A hand-built header over an arbitrary root is not a proof
+            let mut forged = vec![FCMP_PROOF_VERSION, 0];
+            forged.extend_from_slice(&other);
+            forged.extend_from_slice(&proof[PROOF_FINGERPRINT]);
+            assert_eq!(fcmp_verify(other.as_ptr(), &input, forged.as_ptr(), forged.len()), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(
+                fcmp_verify_single_leaf(other.as_ptr(), output.as_ptr(), forged.as_ptr(), forged.len()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let layered = prove_with(&root, &TestBranch::new(1, 2));
+            assert_eq!(
+                fcmp_verify_single_leaf(root.as_ptr(), output.as_ptr(), layered.as_ptr(), layered.len()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let _guard = init_lock();
+        unsafe {
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let branch = FcmpBranch { leaf_index: 0, num_layers: 0, layers: ptr::null() };
+            let mut proof = [0u8; FCMP_PROOF_HEADER_SIZE];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), EMPTY_TREE_ROOT.as_ptr(), output.as_ptr(), &branch),
+                FCMP_ERROR_EMPTY_TREE
+            );
+
+            proof[0] = FCMP_PROOF_VERSION;
+            let input = test_input();
+            assert_eq!(
+                fcmp_verify(EMPTY_TREE_ROOT.as_ptr(), &input, proof.as_ptr(), proof.len()),
+                FCMP_ERROR_EMPTY_TREE
+            );
+            let message = std::ffi::CStr::from_ptr(fcmp_error_string(FCMP_ERROR_EMPTY_TREE));
+            assert_eq!(message.to_str().unwrap(), "Membership tree is empty");
+        }
+    }
+
+    #[cfg(feature = "bench")]
+    #[test]
+    fn test_verify_benchmark() {
+        let _guard = init_lock();
+        unsafe {
+            let mut ns = 0u64;
+            assert_eq!(fcmp_verify_benchmark(4, &mut ns), FCMP_SUCCESS);
+            // Anywhere from a microsecond to a second per proof is plausible
+            assert!((1_000..1_000_000_000).contains(&ns));
+            assert_eq!(fcmp_verify_benchmark(0, &mut ns), FCMP_ERROR_INVALID_PARAM);
+
+            fcmp_cleanup();
+            assert_eq!(fcmp_verify_benchmark(1, &mut ns), FCMP_ERROR_NOT_INITIALIZED);
+        }
+    }
+
+    #[test]
+    fn test_branch_extend() {
+        let _guard = init_lock();
+        unsafe {
+            // A full 64-leaf tree is two layers deep
+            let tree = fcmp_tree_new();
+            let leaves = test_leaves(0..100);
+            let mut old_root = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(tree, leaves.as_ptr(), 64, old_root.as_mut_ptr()), FCMP_SUCCESS);
+            let leaf = &leaves[5 * SCALAR_SIZE..][..SCALAR_SIZE];
+            let old = TestBranch::from_tree(&*tree, 5);
+            assert_eq!(old.layers.len(), 2);
+            assert_eq!(fcmp_branch_verify(old_root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &old.as_ffi()), FCMP_SUCCESS);
+
+            // Growing to 100 leaves adds a third layer above the old root
+            let mut new_root = [0u8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_tree_root_after_append(tree, leaves[64 * SCALAR_SIZE..].as_ptr(), 36, new_root.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            let current = TestBranch::from_tree(&*tree, 5);
+            assert_eq!(current.layers.len(), 3);
+            assert_eq!(fcmp_branch_verify(new_root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &old.as_ffi()), FCMP_ERROR_PROOF_VERIFICATION);
+
+            let mut extended = FcmpBranch { leaf_index: 0, num_layers: 0, layers: ptr::null() };
+            assert_eq!(
+                fcmp_branch_extend(&mut extended, &old.as_ffi(), current.layers[2..].as_ptr(), 1),
+                FCMP_SUCCESS
+            );
+            assert_eq!(extended.num_layers, 3);
+            assert_eq!(extended.leaf_index, 5);
+            let layers = slice::from_raw_parts(extended.layers, 3);
+            for (layer, expected) in layers.iter().zip(&current.layers) {
+                assert_eq!(layer.num_elements, expected.num_elements);
+                assert_eq!(
+                    slice::from_raw_parts(layer.elements, layer.num_elements as usize * SCALAR_SIZE),
+                    slice::from_raw_parts(expected.elements, expected.num_elements as usize * SCALAR_SIZE)
+                );
+            }
+            assert_eq!(fcmp_branch_verify(new_root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &extended), FCMP_SUCCESS);
+
+            // The extended branch proves membership under the deeper root
+            let input = test_input();
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let mut proof = vec![0u8; proof_len_for_layers(3)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), new_root.as_ptr(), output.as_ptr(), &extended),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_verify(new_root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_SUCCESS);
+            fcmp_branch_free(&mut extended);
+
+            // Upper layers that do not sit above the old root lead elsewhere
+            let mut wrong = TestBranch::from_tree(&*tree, 5);
+            wrong._elements[2][0] ^= 1;
+            assert_eq!(
+                fcmp_branch_extend(&mut extended, &old.as_ffi(), wrong.layers[2..].as_ptr(), 1),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_branch_verify(new_root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &extended), FCMP_ERROR_PROOF_VERIFICATION);
+            fcmp_tree_free(tree);
+
+            fcmp_branch_free(&mut extended);
+            assert!(extended.layers.is_null());
+            fcmp_branch_free(&mut extended);
+
+            // Structurally inconsistent extensions are rejected
+            let empty = [FcmpBranchLayer { num_elements: 0, elements: ptr::null() }];
+            assert_eq!(fcmp_branch_extend(&mut extended, &old.as_ffi(), empty.as_ptr(), 1), FCMP_ERROR_INVALID_PARAM);
+            let wide = TestBranch::new(1, FCMP_TREE_WIDTH + 1);
+            assert_eq!(fcmp_branch_extend(&mut extended, &old.as_ffi(), wide.layers.as_ptr(), 1), FCMP_ERROR_INVALID_PARAM);
+
+            // The result may reach the depth cap but not pass it
+            let fill = FCMP_MAX_TREE_DEPTH - old.layers.len() as u32;
+            let deep = TestBranch::new(fill as usize + 1, 1);
+            assert_eq!(fcmp_branch_extend(&mut extended, &old.as_ffi(), deep.layers.as_ptr(), fill), FCMP_SUCCESS);
+            assert_eq!(extended.num_layers, FCMP_MAX_TREE_DEPTH);
+            fcmp_branch_free(&mut extended);
+            assert_eq!(
+                fcmp_branch_extend(&mut extended, &old.as_ffi(), deep.layers.as_ptr(), fill + 1),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_to_point_ex() {
+        unsafe {
+            let data = b"cofactor test";
+            let mut cleared = [0u8; POINT_SIZE];
+            let mut raw = [0u8; POINT_SIZE];
+            let mut default = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point_ex(cleared.as_mut_ptr(), data.as_ptr(), data.len(), 1), FCMP_SUCCESS);
+            assert_eq!(fcmp_hash_to_point_ex(raw.as_mut_ptr(), data.as_ptr(), data.len(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_hash_to_point(default.as_mut_ptr(), data.as_ptr(), data.len()), FCMP_SUCCESS);
+
+            assert_eq!(cleared, default);
+            assert_ne!(cleared, raw);
+
+            // Clearing the raw point by hand reproduces the default
+            let raw_point = decode_point(&raw).unwrap();
+            assert_eq!(raw_point.mul_by_cofactor().compress().to_bytes(), cleared);
+
+            assert_eq!(fcmp_hash_to_point_ex(raw.as_mut_ptr(), data.as_ptr(), data.len(), 2), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_hash_to_point_callback() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        /// The built-in map, reimplemented as an external caller would
+        unsafe extern "C" fn builtin(data: *const u8, len: usize, out: *mut u8) -> i32 {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            match hash_to_point_with_domain(HASH_TO_POINT_DOMAIN, slice::from_raw_parts(data, len), true) {
+                Some(point) => {
+                    ptr::copy_nonoverlapping(point.compress().as_bytes().as_ptr(), out, POINT_SIZE);
+                    FCMP_SUCCESS
+                }
+                None => FCMP_ERROR_INTERNAL,
+            }
+        }
+
+        let _guard = init_lock();
+        unsafe {
+            let root = [5u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let input = test_input();
+            let mut expected = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(expected.as_mut_ptr(), b"callback".as_ptr(), 8), FCMP_SUCCESS);
+
+            // Generators re-derived through the callback verify the same proof
+            assert_eq!(fcmp_set_hash_to_point_callback(Some(builtin)), FCMP_SUCCESS);
+            fcmp_cleanup();
+            assert_eq!(fcmp_init(), FCMP_SUCCESS);
+            assert!(CALLS.load(Ordering::Relaxed) > 0);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            let mut point = [0u8; POINT_SIZE];
+            let before = CALLS.load(Ordering::Relaxed);
+            assert_eq!(fcmp_hash_to_point(point.as_mut_ptr(), b"callback".as_ptr(), 8), FCMP_SUCCESS);
+            assert!(CALLS.load(Ordering::Relaxed) > before);
+            assert_eq!(point, expected);
+
+            assert_eq!(fcmp_set_hash_to_point_callback(None), FCMP_SUCCESS);
+            fcmp_cleanup();
+            assert_eq!(fcmp_init(), FCMP_SUCCESS);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_is_well_formed() {
+        unsafe {
+            let mut value = [0u8; SCALAR_SIZE];
+            value[0] = 100;
+            let mut blinding = [0u8; SCALAR_SIZE];
+            blinding[0] = 11;
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_pedersen_is_well_formed(commitment.as_ptr()), 1);
+
+            // Adding a point of order 8 keeps it on the curve but taints it
+            let tainted = decode_point(&commitment).unwrap()
+                + curve25519_dalek::constants::EIGHT_TORSION[1];
+            let tainted = tainted.compress().to_bytes();
+            assert_eq!(fcmp_point_is_valid(tainted.as_ptr()), 1);
+            assert_eq!(fcmp_pedersen_is_well_formed(tainted.as_ptr()), 0);
+
+            // y = p + 1 decodes to the identity but is not the canonical encoding
+            let mut non_canonical = [0xffu8; POINT_SIZE];
+            non_canonical[0] = 0xee;
+            non_canonical[31] = 0x7f;
+            assert_eq!(fcmp_point_is_valid(non_canonical.as_ptr()), 1);
+            assert_eq!(fcmp_pedersen_is_well_formed(non_canonical.as_ptr()), 0);
+
+            assert_eq!(fcmp_pedersen_is_well_formed(ptr::null()), 0);
+        }
+    }
+
+    #[test]
+    fn test_derive_blinding() {
+        unsafe {
+            let master = [0x33u8; SCALAR_SIZE];
+            let blinders: Vec<[u8; SCALAR_SIZE]> = (0..16u64)
+                .map(|i| {
+                    let mut b = [0u8; SCALAR_SIZE];
+                    assert_eq!(fcmp_derive_blinding(b.as_mut_ptr(), master.as_ptr(), i), FCMP_SUCCESS);
+                    assert!(decode_scalar(&b).is_some());
+                    assert_ne!(b, [0u8; SCALAR_SIZE]);
+                    b
+                })
+                .collect();
+
+            // Distinct indices never collide
+            let unique: std::collections::HashSet<_> = blinders.iter().collect();
+            assert_eq!(unique.len(), blinders.len());
+
+            // The same index reproduces the same blinder
+            let mut again = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_derive_blinding(again.as_mut_ptr(), master.as_ptr(), 5), FCMP_SUCCESS);
+            assert_eq!(again, blinders[5]);
+
+            // A different master gives different blinders
+            let other = [0x34u8; SCALAR_SIZE];
+            assert_eq!(fcmp_derive_blinding(again.as_mut_ptr(), other.as_ptr(), 5), FCMP_SUCCESS);
+            assert_ne!(again, blinders[5]);
+
+            assert_eq!(fcmp_derive_blinding(again.as_mut_ptr(), ptr::null(), 0), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_proof_check_structure() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [6u8; POINT_SIZE];
+            let mut proof = prove_with(&root, &TestBranch::new(3, 2));
+            assert_eq!(fcmp_proof_check_structure(proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            // A different canonical s_a decodes fine but no longer verifies
+            let s_a = FCMP_PROOF_HEADER_SIZE + FCMP_PROOF_LAYER_SIZE + 2 * POINT_SIZE;
+            proof[s_a..s_a + SCALAR_SIZE].copy_from_slice(&[1u8; SCALAR_SIZE]);
+            assert_eq!(fcmp_proof_check_structure(proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+            let input = test_input();
+            assert_eq!(
+                fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // Truncated, non-canonical scalar, undecodable point
+            assert_eq!(fcmp_proof_check_structure(proof.as_ptr(), proof.len() - 1), FCMP_ERROR_INVALID_PARAM);
+            proof[s_a + SCALAR_SIZE - 1] = 0xff;
+            assert_eq!(fcmp_proof_check_structure(proof.as_ptr(), proof.len()), FCMP_ERROR_INVALID_SCALAR);
+            let a = FCMP_PROOF_HEADER_SIZE;
+            proof[a..a + POINT_SIZE].copy_from_slice(&[0xffu8; POINT_SIZE]);
+            assert_eq!(fcmp_proof_check_structure(proof.as_ptr(), proof.len()), FCMP_ERROR_INVALID_POINT);
+        }
+    }
+
+    #[test]
+    fn test_proof_deserialize_consumed() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [6u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let mut buffer = proof.clone();
+            buffer.extend_from_slice(b"trailing data");
+
+            let mut handle: *mut FcmpProof = ptr::null_mut();
+            let mut consumed = 0usize;
+            assert_eq!(
+                fcmp_proof_deserialize(&mut handle, buffer.as_ptr(), buffer.len(), &mut consumed, 0),
+                FCMP_SUCCESS
+            );
+            assert_eq!(consumed, proof.len());
+            assert_eq!(&buffer[consumed..], b"trailing data");
+
+            let mut len = 0usize;
+            let bytes = fcmp_proof_bytes(handle, &mut len);
+            assert_eq!(slice::from_raw_parts(bytes, len), &proof[..]);
+            let input = test_input();
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, bytes, len), FCMP_SUCCESS);
+            fcmp_proof_free(handle);
+
+            // Exact-fit mode rejects the trailing bytes but accepts the bare proof
+            let mut other: *mut FcmpProof = ptr::null_mut();
+            assert_eq!(
+                fcmp_proof_deserialize(&mut other, buffer.as_ptr(), buffer.len(), ptr::null_mut(), 1),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            assert!(other.is_null());
+            assert_eq!(
+                fcmp_proof_deserialize(&mut other, proof.as_ptr(), proof.len(), ptr::null_mut(), 1),
+                FCMP_SUCCESS
+            );
+            fcmp_proof_free(other);
+
+            assert_eq!(
+                fcmp_proof_deserialize(&mut other, proof.as_ptr(), proof.len() - 1, &mut consumed, 0),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_point_mul_batch() {
+        unsafe {
+            let mut basepoint = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+            let mut other = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(other.as_mut_ptr(), b"batch".as_ptr(), 5), FCMP_SUCCESS);
+
+            // Mix of repeated points and the basepoint
+            let count = 6;
+            let scalars: Vec<u8> = (0..count).flat_map(|i| [i as u8 + 3; SCALAR_SIZE]).collect();
+            let points: Vec<u8> = (0..count)
+                .flat_map(|i| if i % 3 == 0 { basepoint } else { other })
+                .collect();
+
+            let mut batch = vec![0u8; count * POINT_SIZE];
+            assert_eq!(
+                fcmp_point_mul_batch(batch.as_mut_ptr(), scalars.as_ptr(), points.as_ptr(), count),
+                FCMP_SUCCESS
+            );
+            for i in 0..count {
+                let mut single = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_point_mul(single.as_mut_ptr(), scalars[i * SCALAR_SIZE..].as_ptr(), points[i * POINT_SIZE..].as_ptr()),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(batch[i * POINT_SIZE..(i + 1) * POINT_SIZE], single);
+            }
+
+            // An invalid point fails the whole batch without writing
+            let invalid = (2u8..)
+                .map(|y| {
+                    let mut bytes = [0u8; POINT_SIZE];
+                    bytes[0] = y;
+                    bytes
+                })
+                .find(|bytes| fcmp_point_is_valid(bytes.as_ptr()) == 0)
+                .unwrap();
+            let mut bad_points = points.clone();
+            bad_points[POINT_SIZE * 4..POINT_SIZE * 5].copy_from_slice(&invalid);
+            let mut untouched = vec![0u8; count * POINT_SIZE];
+            assert_eq!(
+                fcmp_point_mul_batch(untouched.as_mut_ptr(), scalars.as_ptr(), bad_points.as_ptr(), count),
+                FCMP_ERROR_INVALID_POINT
+            );
+            assert!(untouched.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_challenge() {
+        let _guard = init_lock();
+        unsafe {
+            // Pinned for fixed elements so any transcript change is caught
+            let points: Vec<u8> = (1..=3u8).flat_map(|i| [i; POINT_SIZE]).collect();
+            let scalars = [7u8; SCALAR_SIZE];
+            let mut c = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_challenge(c.as_mut_ptr(), points.as_ptr(), 3, scalars.as_ptr(), 1), FCMP_SUCCESS);
+            assert_eq!(c, [
+                0xcc, 0x96, 0x4a, 0x8e, 0x85, 0xfe, 0x98, 0xfa, 0x47, 0xf7, 0xf6, 0x9b, 0xce, 0x42, 0x5f, 0x39,
+                0x04, 0xf9, 0x47, 0x51, 0xfc, 0x79, 0xb6, 0xc8, 0x9d, 0x0d, 0x43, 0x0a, 0x2a, 0xa9, 0x55, 0x06,
+            ]);
+
+            // Matches the challenge of a real proof
+            let root = [6u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let points: Vec<u8> = (0..3)
+                .flat_map(|j| {
+                    let offset = FCMP_PROOF_HEADER_SIZE + j * FCMP_PROOF_LAYER_SIZE;
+                    proof[offset..offset + 2 * POINT_SIZE].to_vec()
+                })
+                .collect();
+            let header = &proof[PROOF_ROOT.start..FCMP_PROOF_HEADER_SIZE];
+            assert_eq!(fcmp_challenge(c.as_mut_ptr(), points.as_ptr(), 6, header.as_ptr(), 2), FCMP_SUCCESS);
+            assert_eq!(c, proof_challenge(&proof, 3).to_bytes());
+
+            assert_eq!(fcmp_challenge(c.as_mut_ptr(), ptr::null(), 0, ptr::null(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_challenge(c.as_mut_ptr(), ptr::null(), 1, ptr::null(), 0), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_commit_ristretto() {
+        unsafe {
+            let scalar = |x: u8| {
+                let mut bytes = [0u8; SCALAR_SIZE];
+                bytes[0] = x;
+                bytes
+            };
+            let commit = |v: u8, b: u8| {
+                let mut c = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_pedersen_commit_ristretto(c.as_mut_ptr(), scalar(v).as_ptr(), scalar(b).as_ptr()), FCMP_SUCCESS);
+                c
+            };
+
+            // commit(v1, b1) + commit(v2, b2) == commit(v1 + v2, b1 + b2)
+            let c1 = commit(30, 5);
+            let c2 = commit(12, 9);
+            let mut sum = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_ristretto_add(sum.as_mut_ptr(), c1.as_ptr(), c2.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(sum, commit(42, 14));
+
+            // Not interchangeable with the Edwards commitment
+            let mut edwards = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(edwards.as_mut_ptr(), scalar(30).as_ptr(), scalar(5).as_ptr()), FCMP_SUCCESS);
+            assert_ne!(edwards, c1);
+
+            let invalid = [0xffu8; POINT_SIZE];
+            assert_eq!(fcmp_ristretto_add(sum.as_mut_ptr(), c1.as_ptr(), invalid.as_ptr()), FCMP_ERROR_INVALID_POINT);
+        }
+    }
+
+    #[test]
+    fn test_check_balance() {
+        unsafe {
+            let commit = |v: u64, b: u8| {
+                let mut value = [0u8; SCALAR_SIZE];
+                value[..8].copy_from_slice(&v.to_le_bytes());
+                let mut blinding = [0u8; SCALAR_SIZE];
+                blinding[0] = b;
+                let mut c = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_pedersen_commit(c.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+                c
+            };
+
+            // 100 + 50 in, 120 + 20 out, fee 10; blindings 7 + 3 == 6 + 4
+            let inputs: Vec<u8> = [commit(100, 7), commit(50, 3)].concat();
+            let outputs: Vec<u8> = [commit(120, 6), commit(20, 4)].concat();
+            assert_eq!(fcmp_check_balance(inputs.as_ptr(), 2, outputs.as_ptr(), 2, 10), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_check_balance(inputs.as_ptr(), 2, outputs.as_ptr(), 2, 11),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(
+                fcmp_check_balance(inputs.as_ptr(), 2, outputs.as_ptr(), 2, 9),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let mut bad = outputs.clone();
+            bad[..POINT_SIZE].copy_from_slice(&[0xffu8; POINT_SIZE]);
+            assert_eq!(fcmp_check_balance(inputs.as_ptr(), 2, bad.as_ptr(), 2, 10), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(fcmp_check_balance(ptr::null(), 1, outputs.as_ptr(), 2, 10), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_scalar_reduce_wide() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let wide: [u8; 64] = std::array::from_fn(|i| (i * 37 + 11) as u8);
+            let mut out = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_scalar_reduce_wide(out.as_mut_ptr(), wide.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(out, Scalar::from_bytes_mod_order_wide(&wide).to_bytes());
+
+            // (2^512 - 1) mod l
+            assert_eq!(fcmp_scalar_reduce_wide(out.as_mut_ptr(), [0xffu8; 64].as_ptr()), FCMP_SUCCESS);
+            assert_eq!(out, [
+                0x00, 0x0f, 0x9c, 0x44, 0xe3, 0x11, 0x06, 0xa4, 0x47, 0x93, 0x85, 0x68, 0xa7, 0x1b, 0x0e, 0xd0,
+                0x65, 0xbe, 0xf5, 0x17, 0xd2, 0x73, 0xec, 0xce, 0x3d, 0x9a, 0x30, 0x7c, 0x1b, 0x41, 0x99, 0x03,
+            ]);
+
+            assert_eq!(fcmp_scalar_reduce_wide(out.as_mut_ptr(), ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_sal_aggregate() {
+        unsafe {
+            let messages: [&[u8]; 3] = [b"first spend", b"second", b"third spend message"];
+            let spends: Vec<TestSpend> = messages
+                .iter()
+                .enumerate()
+                .map(|(i, message)| TestSpend::new(21 + i as u64, &[i as u8 + 1; 32], message))
+                .collect();
+
+            let sigs: Vec<u8> = spends.iter().flat_map(|s| s.sig).collect();
+            let inputs: Vec<FcmpInput> = spends
+                .iter()
+                .map(|s| FcmpInput { o_tilde: s.input.o_tilde, i_tilde: s.input.i_tilde, r: s.input.r, c_tilde: s.input.c_tilde })
+                .collect();
+            let key_images: Vec<u8> = spends.iter().flat_map(|s| s.key_image).collect();
+            let msg_ptrs: Vec<*const u8> = messages.iter().map(|m| m.as_ptr()).collect();
+            let msg_lens: Vec<usize> = messages.iter().map(|m| m.len()).collect();
+
+            let mut agg = vec![0u8; fcmp_sal_aggregate_size(3)];
+            let mut agg_len = 0usize;
+            assert_eq!(
+                fcmp_sal_aggregate(
+                    agg.as_mut_ptr(), &mut agg_len, agg.len(), sigs.as_ptr(), inputs.as_ptr(),
+                    key_images.as_ptr(), msg_ptrs.as_ptr(), msg_lens.as_ptr(), 3,
+                ),
+                FCMP_SUCCESS
+            );
+            assert_eq!(agg_len, 3 * FCMP_SAL_AGGREGATE_ENTRY_SIZE + 3 * SCALAR_SIZE);
+            assert!(agg_len < 3 * FCMP_SAL_SIGNATURE_SIZE);
+
+            let verify = |agg: &[u8], key_images: &[u8], msg_lens: &[usize]| {
+                fcmp_sal_aggregate_verify(
+                    agg.as_ptr(), agg.len(), inputs.as_ptr(), key_images.as_ptr(),
+                    msg_ptrs.as_ptr(), msg_lens.as_ptr(), 3,
+                )
+            };
+            assert_eq!(verify(&agg, &key_images, &msg_lens), FCMP_SUCCESS);
+
+            // A forged s_x for one signer breaks the aggregate
+            let mut forged = agg.clone();
+            let s_x = FCMP_SAL_AGGREGATE_ENTRY_SIZE + 3 * POINT_SIZE;
+            forged[s_x..s_x + SCALAR_SIZE].copy_from_slice(&[1u8; SCALAR_SIZE]);
+            assert_eq!(verify(&forged, &key_images, &msg_lens), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // So does a swapped key image or a truncated message
+            let mut swapped = key_images.clone();
+            swapped.copy_within(0..POINT_SIZE, POINT_SIZE);
+            assert_eq!(verify(&agg, &swapped, &msg_lens), FCMP_ERROR_PROOF_VERIFICATION);
+            let short = [msg_lens[0], msg_lens[1] - 1, msg_lens[2]];
+            assert_eq!(verify(&agg, &key_images, &short), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // An invalid component signature is refused at aggregation time
+            let mut bad_sigs = sigs.clone();
+            bad_sigs[FCMP_SAL_SIGNATURE_SIZE + 3 * POINT_SIZE] ^= 1;
+            assert_eq!(
+                fcmp_sal_aggregate(
+                    agg.as_mut_ptr(), &mut agg_len, agg.len(), bad_sigs.as_ptr(), inputs.as_ptr(),
+                    key_images.as_ptr(), msg_ptrs.as_ptr(), msg_lens.as_ptr(), 3,
+                ),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+        }
+    }
+
+    #[test]
+    fn test_has_simd() {
+        assert_eq!(fcmp_has_simd(), (env!("WATTX_FCMP_DALEK_BACKEND") == "simd") as i32);
+        if !cfg!(target_arch = "x86_64") {
+            assert_eq!(fcmp_has_simd(), 0);
+        }
+    }
+
+    /// Leaf scalars for tests: canonical, distinct per index
+    fn test_leaves(range: std::ops::Range<u32>) -> Vec<u8> {
+        range
+            .flat_map(|i| curve25519_dalek::scalar::Scalar::from(i as u64 * 7919 + 1).to_bytes())
+            .collect()
+    }
+
+    /// Root of a tree over `leaves`, hashing every level from scratch
+    fn rebuild_root(leaves: &[u8]) -> [u8; SCALAR_SIZE] {
+        let mut nodes: Vec<[u8; SCALAR_SIZE]> = leaves
+            .chunks_exact(SCALAR_SIZE)
+            .map(|leaf| leaf.try_into().unwrap())
+            .collect();
+        let mut level = 0;
+        while nodes.len() > 1 {
+            nodes = nodes.chunks(FCMP_TREE_WIDTH).map(|group| tree_node_hash(level, group)).collect();
+            level += 1;
+        }
+        nodes.first().copied().unwrap_or(EMPTY_TREE_ROOT)
+    }
+
+    #[test]
+    fn test_tree_root_after_append() {
+        unsafe {
+            let tree = fcmp_tree_new();
+            let mut root = [0xaau8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(tree, ptr::null(), 0, root.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(root, EMPTY_TREE_ROOT);
+
+            // Batches that cross group and depth boundaries
+            let mut appended = 0u32;
+            for batch in [1u32, 1, 6, 1, 55, 3, 0, 70, 1] {
+                let leaves = test_leaves(appended..appended + batch);
+                assert_eq!(
+                    fcmp_tree_root_after_append(tree, leaves.as_ptr(), batch as usize, root.as_mut_ptr()),
+                    FCMP_SUCCESS
+                );
+                appended += batch;
+                assert_eq!(root, rebuild_root(&test_leaves(0..appended)));
+            }
+
+            // A single leaf is its own root
+            let single = fcmp_tree_new();
+            let leaf = test_leaves(0..1);
+            assert_eq!(fcmp_tree_root_after_append(single, leaf.as_ptr(), 1, root.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(root[..], leaf[..]);
+
+            // A non-canonical leaf is rejected without changing the tree
+            let mut bad = test_leaves(0..2);
+            bad[SCALAR_SIZE + 31] = 0xff;
+            assert_eq!(fcmp_tree_root_after_append(single, bad.as_ptr(), 2, root.as_mut_ptr()), FCMP_ERROR_INVALID_SCALAR);
+            assert_eq!(fcmp_tree_root_after_append(single, ptr::null(), 0, root.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(root[..], leaf[..]);
+
+            fcmp_tree_free(single);
+            fcmp_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn test_tree_stats() {
+        unsafe {
+            let tree = fcmp_tree_new();
+            let mut root = [0u8; SCALAR_SIZE];
+            let mut count = u64::MAX;
+            let mut depth = u32::MAX;
+            assert_eq!(fcmp_tree_stats(tree, &mut count, &mut depth), FCMP_SUCCESS);
+            assert_eq!((count, depth), (0, 0));
+
+            // (total leaves, expected depth) at width 8
+            let mut appended = 0u32;
+            for (total, expected_depth) in [(1u32, 0u32), (2, 1), (8, 1), (9, 2), (64, 2), (65, 3), (100, 3)] {
+                let leaves = test_leaves(appended..total);
+                assert_eq!(
+                    fcmp_tree_root_after_append(tree, leaves.as_ptr(), (total - appended) as usize, root.as_mut_ptr()),
+                    FCMP_SUCCESS
+                );
+                appended = total;
+                assert_eq!(fcmp_tree_stats(tree, &mut count, &mut depth), FCMP_SUCCESS);
+                assert_eq!((count, depth), (total as u64, expected_depth));
+            }
+
+            assert_eq!(fcmp_tree_stats(tree, ptr::null_mut(), &mut depth), FCMP_ERROR_INVALID_PARAM);
+            fcmp_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn test_branch_matches_index() {
+        unsafe {
+            let tree = fcmp_tree_new();
+            let leaves = test_leaves(0..100);
+            let mut root = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(tree, leaves.as_ptr(), 100, root.as_mut_ptr()), FCMP_SUCCESS);
+
+            for index in [0u64, 9, 63, 99] {
+                let leaf = &leaves[index as usize * SCALAR_SIZE..][..SCALAR_SIZE];
+                let branch = TestBranch::from_tree(&*tree, index);
+                let matches = |expected: u64, branch: &FcmpBranch| {
+                    fcmp_branch_matches_index(branch, leaf.as_ptr(), SCALAR_SIZE, expected)
+                };
+                assert_eq!(matches(index, &branch.as_ffi()), FCMP_SUCCESS);
+
+                // Another index in the same group, or in another group, or
+                // beyond the branch's depth
+                assert_eq!(matches(index ^ 1, &branch.as_ffi()), FCMP_ERROR_PROOF_VERIFICATION);
+                assert_eq!(matches(index ^ 8, &branch.as_ffi()), FCMP_ERROR_PROOF_VERIFICATION);
+                assert_eq!(matches(index + 512, &branch.as_ffi()), FCMP_ERROR_PROOF_VERIFICATION);
+
+                // The path decides, not the index the branch claims
+                let mut spoofed = branch.as_ffi();
+                spoofed.leaf_index = index ^ 1;
+                assert_eq!(matches(index, &spoofed), FCMP_SUCCESS);
+                assert_eq!(matches(index ^ 1, &spoofed), FCMP_ERROR_PROOF_VERIFICATION);
+            }
+
+            let branch = TestBranch::from_tree(&*tree, 0);
+            assert_eq!(
+                fcmp_branch_matches_index(&branch.as_ffi(), leaves.as_ptr(), 7, 0),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            fcmp_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn test_branch_verify() {
+        unsafe {
+            let tree = fcmp_tree_new();
+            let leaves = test_leaves(0..100);
+            let mut root = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(tree, leaves.as_ptr(), 100, root.as_mut_ptr()), FCMP_SUCCESS);
+
+            for index in [0u64, 7, 8, 63, 64, 99] {
+                let leaf = &leaves[index as usize * SCALAR_SIZE..][..SCALAR_SIZE];
+                let branch = TestBranch::from_tree(&*tree, index);
+                assert_eq!(branch.layers.len(), 3);
+                assert_eq!(
+                    fcmp_branch_verify(root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &branch.as_ffi()),
+                    FCMP_SUCCESS
+                );
+
+                // Wrong position for the same leaf
+                let mut moved = branch.as_ffi();
+                moved.leaf_index = index ^ 1;
+                assert_eq!(
+                    fcmp_branch_verify(root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &moved),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+
+            // A corrupted sibling breaks the path
+            let leaf = &leaves[9 * SCALAR_SIZE..][..SCALAR_SIZE];
+            let mut branch = TestBranch::from_tree(&*tree, 9);
+            branch._elements[1][0] ^= 1;
+            assert_eq!(
+                fcmp_branch_verify(root.as_ptr(), leaf.as_ptr(), SCALAR_SIZE, &branch.as_ffi()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // An output tuple is hashed to its leaf first
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let mut output_leaf = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_output_leaf_scalar(output_leaf.as_mut_ptr(), output.as_ptr()), FCMP_SUCCESS);
+            let with_output = fcmp_tree_new();
+            let pair = [test_leaves(0..1), output_leaf.to_vec()].concat();
+            assert_eq!(fcmp_tree_root_after_append(with_output, pair.as_ptr(), 2, root.as_mut_ptr()), FCMP_SUCCESS);
+            let branch = TestBranch::from_tree(&*with_output, 1);
+            assert_eq!(
+                fcmp_branch_verify(root.as_ptr(), output.as_ptr(), OUTPUT_TUPLE_SIZE, &branch.as_ffi()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_branch_verify(root.as_ptr(), output.as_ptr(), 64, &branch.as_ffi()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+
+            fcmp_tree_free(with_output);
+            fcmp_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn test_hash_empty_to_point() {
+        unsafe {
+            let mut empty = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_empty_to_point(empty.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_is_valid(empty.as_ptr()), 1);
+
+            // Pinned: changing this breaks every protocol using the sentinel
+            assert_eq!(empty, [
+                0x15, 0x12, 0x0f, 0x53, 0x0a, 0x85, 0x3d, 0xa2, 0x54, 0xcd, 0x81, 0x3b, 0xfc, 0xc8, 0x6c, 0xb3,
+                0xcc, 0x70, 0xf1, 0x03, 0xa3, 0xfd, 0xfa, 0xb1, 0xb4, 0x54, 0x32, 0x0c, 0xed, 0x4f, 0x09, 0xd8,
+            ]);
+
+            let mut no_data = [0u8; POINT_SIZE];
+            let mut zero_byte = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(no_data.as_mut_ptr(), ptr::null(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_hash_to_point(zero_byte.as_mut_ptr(), [0u8].as_ptr(), 1), FCMP_SUCCESS);
+            assert_ne!(empty, no_data);
+            assert_ne!(empty, zero_byte);
+            assert_ne!(no_data, zero_byte);
+
+            assert_eq!(fcmp_hash_empty_to_point(ptr::null_mut()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_h() {
+        unsafe {
+            let mut h = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_h(h.as_mut_ptr()), FCMP_SUCCESS);
+            // Pinned: every commitment in the chain depends on this generator
+            assert_eq!(h, [
+                0xff, 0xce, 0x69, 0xbe, 0xc0, 0xae, 0xcb, 0xa8, 0xef, 0xd2, 0xf0, 0x6d, 0x96, 0x85, 0x73, 0xc2,
+                0x69, 0x8d, 0xc9, 0x4b, 0x3c, 0x2f, 0x75, 0x90, 0x31, 0xf2, 0x62, 0x1b, 0x12, 0xb3, 0x52, 0xec,
+            ]);
+
+            // commit(0, 1) = 0*G + 1*H
+            let zero = [0u8; SCALAR_SIZE];
+            let mut one = [0u8; SCALAR_SIZE];
+            one[0] = 1;
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), zero.as_ptr(), one.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(commitment, h);
+
+            assert_eq!(fcmp_pedersen_h(ptr::null_mut()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_proof_id() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [6u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(2, 3));
+
+            let mut id = [0u8; 32];
+            let mut again = [0u8; 32];
+            assert_eq!(fcmp_proof_id(proof.as_ptr(), proof.len(), id.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_proof_id(proof.as_ptr(), proof.len(), again.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(id, again);
+
+            // A fresh proof of the same statement is different bytes
+            let other = prove_with(&root, &TestBranch::new(2, 3));
+            assert_eq!(fcmp_proof_id(other.as_ptr(), other.len(), again.as_mut_ptr()), FCMP_SUCCESS);
+            assert_ne!(id, again);
+
+            // Any one-bit change alters the id
+            for bit in [16usize, 8 * proof.len() - 1] {
+                let mut flipped = proof.clone();
+                flipped[bit / 8] ^= 1 << (bit % 8);
+                assert_eq!(fcmp_proof_id(flipped.as_ptr(), flipped.len(), again.as_mut_ptr()), FCMP_SUCCESS);
+                assert_ne!(id, again);
+            }
+
+            assert_eq!(
+                fcmp_proof_id(proof.as_ptr(), proof.len() - 1, again.as_mut_ptr()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_blinding_random() {
+        unsafe {
+            let mut seen = std::collections::HashSet::new();
+            for _ in 0..1000 {
+                let mut blinding = [0u8; SCALAR_SIZE];
+                assert_eq!(fcmp_blinding_random(blinding.as_mut_ptr()), FCMP_SUCCESS);
+                assert_ne!(blinding, [0u8; SCALAR_SIZE]);
+                assert!(decode_scalar(&blinding).is_some());
+                assert!(seen.insert(blinding));
+            }
+
+            assert_eq!(fcmp_blinding_random(ptr::null_mut()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_proof_verbose_roundtrip() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [6u8; POINT_SIZE];
+            for num_layers in [0usize, 1, 3] {
+                let proof = if num_layers == 0 {
+                    let mut header = vec![FCMP_PROOF_VERSION, 0];
+                    header.extend_from_slice(&root);
+                    header.extend_from_slice(&[7u8; 32]);
+                    header
+                } else {
+                    prove_with(&root, &TestBranch::new(num_layers, 2))
+                };
+
+                let mut verbose = vec![0u8; 1024];
+                let mut verbose_len = 0usize;
+                assert_eq!(
+                    fcmp_proof_to_verbose(proof.as_ptr(), proof.len(), verbose.as_mut_ptr(), verbose.len(), &mut verbose_len),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(verbose_len, 8 + 4 * 3 + 2 + POINT_SIZE + 32 + num_layers * 4 * (3 + 32));
+                assert_eq!(&verbose[..8], b"WFCMPVRB");
+                assert_eq!(&verbose[8..12], &[VERBOSE_TAG_VERSION, 1, 0, FCMP_PROOF_VERSION]);
+
+                let mut compact = vec![0u8; proof.len()];
+                let mut compact_len = 0usize;
+                assert_eq!(
+                    fcmp_proof_from_verbose(verbose.as_ptr(), verbose_len, compact.as_mut_ptr(), compact.len(), &mut compact_len),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(compact, proof);
+                assert_eq!(compact_len, proof.len());
+
+                // Short buffers report the size needed
+                assert_eq!(
+                    fcmp_proof_to_verbose(proof.as_ptr(), proof.len(), verbose.as_mut_ptr(), verbose_len - 1, &mut compact_len),
+                    FCMP_ERROR_MEMORY
+                );
+                assert_eq!(compact_len, verbose_len);
+
+                // Truncated, padded or re-tagged verbose input is rejected
+                let mut bad = verbose[..verbose_len].to_vec();
+                bad[12] = VERBOSE_TAG_ROOT;
+                for malformed in [&verbose[..verbose_len - 1], &verbose[..verbose_len + 1], &bad[..]] {
+                    assert_eq!(
+                        fcmp_proof_from_verbose(malformed.as_ptr(), malformed.len(), compact.as_mut_ptr(), compact.len(), &mut compact_len),
+                        FCMP_ERROR_INVALID_PARAM
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transaction_size_estimate() {
+        let _guard = init_lock();
+        unsafe {
+            // The estimate assumes a full-width range proof
+            let tx = TestTransaction::with_range_bits(&[(11, [1u8; 32]), (12, [2u8; 32])], 30, FCMP_RANGE_MAX_BITS);
+            let mut bytes = vec![0u8; 32768];
+            let mut written = 0usize;
+            assert_eq!(
+                fcmp_transaction_serialize(&tx.as_ffi(), bytes.as_mut_ptr(), bytes.len(), &mut written),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_transaction_size_estimate(2, 1, 2), written);
+
+            // The serialized form carries every field verbatim
+            assert_eq!(&bytes[..POINT_SIZE], &tx.root);
+            assert_eq!(&bytes[written - 8..written], &30u64.to_le_bytes());
+
+            assert_eq!(
+                fcmp_transaction_serialize(&tx.as_ffi(), bytes.as_mut_ptr(), written - 1, &mut written),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(fcmp_transaction_size_estimate(2, 1, 2), written);
+
+            assert_eq!(fcmp_transaction_size_estimate(0, 1, 2), 0);
+            assert_eq!(fcmp_transaction_size_estimate(1, 0, 2), 0);
+            assert_eq!(fcmp_transaction_size_estimate(1, 1, MAX_PROOF_LAYERS as u32 + 1), 0);
+        }
+    }
+
+    #[test]
+    fn test_transaction_id() {
+        let _guard = init_lock();
+        unsafe {
+            let mut tx = TestTransaction::new(&[(11, [1u8; 32])], 30);
+            let id = |tx: &TestTransaction| {
+                let mut id = [0u8; 32];
+                assert_eq!(fcmp_transaction_id(&tx.as_ffi(), id.as_mut_ptr()), FCMP_SUCCESS);
+                id
+            };
+
+            let original = id(&tx);
+            assert_eq!(id(&tx), original);
+
+            tx.fee += 1;
+            assert_ne!(id(&tx), original);
+            tx.fee -= 1;
+
+            // Every component is bound, not only the fee
+            tx.range_proof[1] ^= 1;
+            assert_ne!(id(&tx), original);
+            tx.range_proof[1] ^= 1;
+            tx.signatures[0] ^= 1;
+            assert_ne!(id(&tx), original);
+            tx.signatures[0] ^= 1;
+            assert_eq!(id(&tx), original);
+
+            let mut ffi = tx.as_ffi();
+            ffi.proofs = ptr::null();
+            let mut out = [0u8; 32];
+            assert_eq!(fcmp_transaction_id(&ffi, out.as_mut_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_verify_with_generators() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [6u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let input = test_input();
+
+            let mut generators = vec![0u8; 4 * POINT_SIZE];
+            for (i, out) in generators.chunks_exact_mut(POINT_SIZE).enumerate() {
+                assert_eq!(fcmp_get_generator(i as u32, out.as_mut_ptr()), FCMP_SUCCESS);
+            }
+            let verify = |generators: &[u8], count: u32| {
+                fcmp_verify_with_generators(generators.as_ptr(), count, root.as_ptr(), &input, proof.as_ptr(), proof.len())
+            };
+            assert_eq!(verify(&generators, 4), FCMP_SUCCESS);
+
+            // Another chain's generators do not verify this proof
+            let mut other = generators.clone();
+            assert_eq!(fcmp_hash_to_point(other[POINT_SIZE..].as_mut_ptr(), b"other chain".as_ptr(), 11), FCMP_SUCCESS);
+            assert_eq!(verify(&other, 4), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // A three-layer proof needs H and three layer generators
+            assert_eq!(verify(&generators, 3), FCMP_ERROR_INVALID_PARAM);
+            generators.extend_from_slice(&other[POINT_SIZE..2 * POINT_SIZE]);
+            assert_eq!(verify(&generators, 5), FCMP_SUCCESS);
+        }
+    }
+
+    #[test]
+    fn test_make_pseudo_outputs() {
+        unsafe {
+            // Inputs of 700 and 500 pay outputs of 900 and 250 plus a fee of 50
+            let input_amounts = [700u64, 500];
+            let input_blinders = [[3u8; SCALAR_SIZE], [4u8; SCALAR_SIZE]].concat();
+            let output_amounts = [900u64, 250];
+            let output_blinders = [[5u8; SCALAR_SIZE], [6u8; SCALAR_SIZE]].concat();
+            let fee = 50;
+
+            let mut outputs = [0u8; 2 * POINT_SIZE];
+            for (j, amount) in output_amounts.iter().enumerate() {
+                let mut value = [0u8; SCALAR_SIZE];
+                value[..8].copy_from_slice(&amount.to_le_bytes());
+                assert_eq!(
+                    fcmp_pedersen_commit(outputs[j * POINT_SIZE..].as_mut_ptr(), value.as_ptr(), output_blinders[j * SCALAR_SIZE..].as_ptr()),
+                    FCMP_SUCCESS
+                );
+            }
+
+            let mut pseudo = [0u8; 2 * POINT_SIZE];
+            let mut pseudo_blinders = [0u8; 2 * SCALAR_SIZE];
+            let make = |pseudo: &mut [u8], pseudo_blinders: &mut [u8], fee: u64| {
+                fcmp_make_pseudo_outputs(
+                    pseudo.as_mut_ptr(),
+                    pseudo_blinders.as_mut_ptr(),
+                    input_amounts.as_ptr(),
+                    input_blinders.as_ptr(),
+                    2,
+                    output_blinders.as_ptr(),
+                    2,
+                    fee,
+                )
+            };
+            assert_eq!(make(&mut pseudo, &mut pseudo_blinders, fee), FCMP_SUCCESS);
+            assert_eq!(fcmp_check_balance(pseudo.as_ptr(), 2, outputs.as_ptr(), 2, fee), FCMP_SUCCESS);
+            assert_eq!(fcmp_check_balance(pseudo.as_ptr(), 2, outputs.as_ptr(), 2, fee + 1), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Each pseudo-output opens to its input amount under the returned blinding
+            let mut value = [0u8; SCALAR_SIZE];
+            value[..8].copy_from_slice(&input_amounts[0].to_le_bytes());
+            let mut opened = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(opened.as_mut_ptr(), value.as_ptr(), pseudo_blinders.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(opened, pseudo[..POINT_SIZE]);
+            assert_ne!(pseudo_blinders[..SCALAR_SIZE], input_blinders[..SCALAR_SIZE]);
+
+            // Deterministic in the input blindings
+            let mut again = [0u8; 2 * POINT_SIZE];
+            let mut again_blinders = [0u8; 2 * SCALAR_SIZE];
+            assert_eq!(make(&mut again, &mut again_blinders, fee), FCMP_SUCCESS);
+            assert_eq!(again, pseudo);
+
+            assert_eq!(make(&mut again, &mut again_blinders, 1201), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_proof_is_supported() {
+        let _guard = init_lock();
+        unsafe {
+            let proof = prove_with(&[6u8; POINT_SIZE], &TestBranch::new(2, 2));
+            assert_eq!(fcmp_proof_is_supported(proof.as_ptr(), proof.len()), 1);
+
+            // A header from a future format version
+            let mut future = proof.clone();
+            future[0] = FCMP_PROOF_VERSION + 1;
+            assert_eq!(fcmp_proof_is_supported(future.as_ptr(), future.len()), 0);
+
+            // A layer count past the depth cap no verifier would take
+            let mut deep = proof.clone();
+            deep[1] = FCMP_MAX_TREE_DEPTH as u8;
+            assert_eq!(fcmp_proof_is_supported(deep.as_ptr(), deep.len()), 1);
+            deep[1] += 1;
+            assert_eq!(fcmp_proof_is_supported(deep.as_ptr(), deep.len()), 0);
+
+            assert_eq!(fcmp_proof_is_supported(proof.as_ptr(), 1), 0);
+            assert_eq!(fcmp_proof_is_supported(ptr::null(), 0), 0);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_vector_commit() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let values: Vec<u8> = [10u64, 20, 30, 40].iter().flat_map(|v| Scalar::from(*v).to_bytes()).collect();
+            let blinding = Scalar::from(99u64).to_bytes();
+
+            let mut commitment = [0u8; POINT_SIZE];
+            let mut opening = vec![0u8; fcmp_pedersen_vector_opening_size(4)];
+            assert_eq!(
+                fcmp_pedersen_commit_vector_open(commitment.as_mut_ptr(), opening.as_mut_ptr(), values.as_ptr(), 4, blinding.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(opening.len(), 4 + 5 * SCALAR_SIZE);
+            assert_eq!(opening[..4], 4u32.to_le_bytes());
+
+            // The opening re-verifies the commitment
+            let (opened_values, opened_blinding) = opening[4..].split_at(4 * SCALAR_SIZE);
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), opened_values.as_ptr(), 4, opened_blinding.as_ptr()),
+                FCMP_SUCCESS
+            );
+
+            // Any changed value, reordering or blinding breaks it
+            let mut tampered = values.clone();
+            tampered[2 * SCALAR_SIZE] ^= 1;
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), tampered.as_ptr(), 4, blinding.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            let mut swapped = values.clone();
+            swapped[..2 * SCALAR_SIZE].rotate_left(SCALAR_SIZE);
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), swapped.as_ptr(), 4, blinding.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            let other_blinding = Scalar::from(100u64).to_bytes();
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), values.as_ptr(), 4, other_blinding.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), values.as_ptr(), 0, blinding.as_ptr()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            assert_eq!(
+                fcmp_pedersen_verify_vector(commitment.as_ptr(), values.as_ptr(), 4, [0xff; SCALAR_SIZE].as_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
+    }
+
+    #[test]
+    fn test_derive_keys() {
+        unsafe {
+            let derive = |seed: &[u8]| {
+                let mut keys = [[0u8; 32]; 4];
+                let [view_secret, view_public, spend_secret, spend_public] = &mut keys;
+                assert_eq!(
+                    fcmp_derive_keys(
+                        seed.as_ptr(),
+                        seed.len(),
+                        view_secret.as_mut_ptr(),
+                        view_public.as_mut_ptr(),
+                        spend_secret.as_mut_ptr(),
+                        spend_public.as_mut_ptr(),
+                    ),
+                    FCMP_SUCCESS
+                );
+                keys
+            };
+
+            let keys = derive(b"correct horse battery staple");
+            assert_eq!(derive(b"correct horse battery staple"), keys);
+            let [view_secret, view_public, spend_secret, spend_public] = keys;
+
+            // Independent keys, each public matching its secret
+            assert_ne!(view_secret, spend_secret);
+            assert_ne!(view_public, spend_public);
+            for (secret, public) in [(view_secret, view_public), (spend_secret, spend_public)] {
+                assert!(decode_scalar(&secret).is_some());
+                let mut expected = [0u8; POINT_SIZE];
+                let mut basepoint = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+                assert_eq!(fcmp_point_mul(expected.as_mut_ptr(), secret.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
+                assert_eq!(public, expected);
+            }
+
+            // A different seed changes both pairs
+            let other = derive(b"correct horse battery stapler");
+            assert_ne!(other[0], view_secret);
+            assert_ne!(other[2], spend_secret);
+
+            let mut out = [0u8; 32];
+            let p = out.as_mut_ptr();
+            assert_eq!(fcmp_derive_keys(b"x".as_ptr(), 0, p, p, p, p), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_input_equal() {
+        unsafe {
+            let mut a = test_input();
+            assert_eq!(fcmp_output_rerandomize(&mut a, [0u8; FCMP_BLINDERS_SIZE].as_mut_ptr(), test_output(2).as_ptr(), [7u8; 32].as_ptr()), FCMP_SUCCESS);
+            let b = FcmpInput { o_tilde: a.o_tilde, i_tilde: a.i_tilde, r: a.r, c_tilde: a.c_tilde };
+            assert_eq!(fcmp_input_equal(&a, &b), 1);
+            assert_eq!(fcmp_input_equal(&a, &a), 1);
+
+            // A single differing byte in any field is enough
+            for field in 0..4 {
+                let mut c = FcmpInput { o_tilde: a.o_tilde, i_tilde: a.i_tilde, r: a.r, c_tilde: a.c_tilde };
+                let coords = [&mut c.o_tilde, &mut c.i_tilde, &mut c.r, &mut c.c_tilde];
+                coords[field][63] ^= 1;
+                assert_eq!(fcmp_input_equal(&a, &c), 0);
+            }
+
+            assert_eq!(fcmp_input_equal(&a, ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_verify_and_extract_images() {
+        let _guard = init_lock();
+        unsafe {
+            let mut tx = TestTransaction::new(&[(11, [1u8; 32]), (12, [2u8; 32])], 10);
+            let mut images = [0xaau8; 2 * POINT_SIZE];
+
+            // A failing bundle leaves the buffer untouched
+            tx.signatures[3 * POINT_SIZE] ^= 1;
+            assert_eq!(fcmp_verify_and_extract_images(&tx.as_ffi(), images.as_mut_ptr()), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(images, [0xaau8; 2 * POINT_SIZE]);
+            tx.signatures[3 * POINT_SIZE] ^= 1;
+
+            assert_eq!(fcmp_verify_and_extract_images(&tx.as_ffi(), images.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(images[..], tx.key_images[..]);
+        }
+    }
+
+    #[test]
+    fn test_verify_with_spent_filter() {
+        let _guard = init_lock();
+        unsafe {
+            let tx = TestTransaction::new(&[(11, [1u8; 32]), (12, [2u8; 32])], 10);
+            let filter_bits = 1021usize;
+            let mut filter = vec![0u8; filter_bits.div_ceil(8)];
+
+            // Fill the filter with unrelated spent images
+            for i in 0..20u8 {
+                let mut image = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_hash_to_point(image.as_mut_ptr(), &i, 1), FCMP_SUCCESS);
+                assert_eq!(fcmp_spent_filter_insert(filter.as_mut_ptr(), filter_bits, image.as_ptr()), FCMP_SUCCESS);
+            }
+            assert_eq!(fcmp_verify_with_spent_filter(&tx.as_ffi(), filter.as_ptr(), filter_bits), FCMP_SUCCESS);
+
+            // Spending the second input's image is caught
+            let second = tx.key_images[POINT_SIZE..].as_ptr();
+            assert_eq!(fcmp_spent_filter_insert(filter.as_mut_ptr(), filter_bits, second), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_verify_with_spent_filter(&tx.as_ffi(), filter.as_ptr(), filter_bits),
+                FCMP_ERROR_DOUBLE_SPEND
+            );
+
+            // Cryptographic failures take precedence
+            let mut bad = TestTransaction::new(&[(11, [1u8; 32])], 10);
+            bad.signatures[3 * POINT_SIZE] ^= 1;
+            assert_eq!(
+                fcmp_verify_with_spent_filter(&bad.as_ffi(), filter.as_ptr(), filter_bits),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(fcmp_verify_with_spent_filter(&tx.as_ffi(), filter.as_ptr(), 0), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_tree_delta() {
+        unsafe {
+            let tree = fcmp_tree_new();
+            let mut root = [0u8; SCALAR_SIZE];
+            let leaves = test_leaves(0..50);
+            assert_eq!(fcmp_tree_root_after_append(tree, leaves.as_ptr(), 30, root.as_mut_ptr()), FCMP_SUCCESS);
+
+            // A client syncs at 30 leaves, then 20 more are appended
+            let client = fcmp_tree_new();
+            assert_eq!(fcmp_tree_root_after_append(client, leaves.as_ptr(), 30, root.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_tree_root_after_append(tree, leaves[30 * SCALAR_SIZE..].as_ptr(), 20, root.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+
+            let mut delta = vec![0u8; 20 * SCALAR_SIZE];
+            let mut count = 0usize;
+            assert_eq!(fcmp_tree_delta(tree, 30, delta.as_mut_ptr(), &mut count, 20), FCMP_SUCCESS);
+            assert_eq!(count, 20);
+            assert_eq!(delta[..], leaves[30 * SCALAR_SIZE..]);
+
+            // Applying the delta brings the client to the same root
+            let mut client_root = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(client, delta.as_ptr(), count, client_root.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(client_root, root);
+
+            // Too small a buffer reports the count needed
+            assert_eq!(fcmp_tree_delta(tree, 10, delta.as_mut_ptr(), &mut count, 20), FCMP_ERROR_MEMORY);
+            assert_eq!(count, 40);
+
+            assert_eq!(fcmp_tree_delta(tree, 50, ptr::null_mut(), &mut count, 0), FCMP_SUCCESS);
+            assert_eq!(count, 0);
+            assert_eq!(fcmp_tree_delta(tree, 51, delta.as_mut_ptr(), &mut count, 20), FCMP_ERROR_INVALID_PARAM);
+
+            fcmp_tree_free(client);
+            fcmp_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn test_commitments_digest() {
+        unsafe {
+            // Commitments need not decode; the digest is over bytes
+            let commitments: Vec<u8> = (0..7u8).flat_map(|i| [i; POINT_SIZE]).collect();
+            let mut digest = [0u8; 32];
+            assert_eq!(fcmp_commitments_digest(commitments.as_ptr(), 7, digest.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_commitments_verify_digest(commitments.as_ptr(), 7, digest.as_ptr()), FCMP_SUCCESS);
+
+            let mut reordered = commitments.clone();
+            reordered[..2 * POINT_SIZE].rotate_left(POINT_SIZE);
+            let mut mutated = commitments.clone();
+            mutated[6 * POINT_SIZE + 5] ^= 1;
+            for changed in [&reordered, &mutated] {
+                assert_eq!(
+                    fcmp_commitments_verify_digest(changed.as_ptr(), 7, digest.as_ptr()),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+
+            // Dropping the carried-up last commitment changes the count and the root
+            assert_eq!(
+                fcmp_commitments_verify_digest(commitments.as_ptr(), 6, digest.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let mut empty = [0u8; 32];
+            assert_eq!(fcmp_commitments_digest(ptr::null(), 0, empty.as_mut_ptr()), FCMP_SUCCESS);
+            assert_ne!(empty, digest);
+        }
+    }
+
+    #[test]
+    fn test_points_validate() {
+        use curve25519_dalek::constants::EIGHT_TORSION;
+
+        unsafe {
+            let mut points = vec![0u8; 6 * POINT_SIZE];
+            for (i, point) in points.chunks_exact_mut(POINT_SIZE).enumerate() {
+                assert_eq!(fcmp_hash_to_point(point.as_mut_ptr(), [i as u8].as_ptr(), 1), FCMP_SUCCESS);
+            }
+            let mut first_invalid = u32::MAX;
+            assert_eq!(fcmp_points_validate(points.as_ptr(), 6, &mut first_invalid), FCMP_SUCCESS);
+            assert_eq!(first_invalid, u32::MAX);
+
+            // Off-curve at index 3, with a later torsion point not reached
+            let invalid = (2u8..)
+                .map(|y| {
+                    let mut bytes = [0u8; POINT_SIZE];
+                    bytes[0] = y;
+                    bytes
+                })
+                .find(|bytes| fcmp_point_is_valid(bytes.as_ptr()) == 0)
+                .unwrap();
+            let mut bad = points.clone();
+            bad[3 * POINT_SIZE..4 * POINT_SIZE].copy_from_slice(&invalid);
+            bad[5 * POINT_SIZE..].copy_from_slice(EIGHT_TORSION[1].compress().as_bytes());
+            assert_eq!(fcmp_points_validate(bad.as_ptr(), 6, &mut first_invalid), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(first_invalid, 3);
+
+            // On-curve points with a torsion component are rejected too
+            assert_eq!(fcmp_points_validate(bad[4 * POINT_SIZE..].as_ptr(), 2, &mut first_invalid), FCMP_ERROR_INVALID_POINT);
+            assert_eq!(first_invalid, 1);
+        }
+    }
+
+    #[test]
+    fn test_scalar_mul_generators() {
+        let _guard = init_lock();
+        unsafe {
+            let scalar = [0x2au8; SCALAR_SIZE];
+            let count = 5u32;
+            let mut generators = vec![0u8; count as usize * POINT_SIZE];
+            let mut expected = vec![0u8; count as usize * POINT_SIZE];
+            for i in 0..count as usize {
+                assert_eq!(fcmp_get_generator(i as u32, generators[i * POINT_SIZE..].as_mut_ptr()), FCMP_SUCCESS);
+                assert_eq!(
+                    fcmp_point_mul(expected[i * POINT_SIZE..].as_mut_ptr(), scalar.as_ptr(), generators[i * POINT_SIZE..].as_ptr()),
+                    FCMP_SUCCESS
+                );
+            }
+
+            // Library generators through their tables, twice to hit the cache
+            let mut out = vec![0u8; count as usize * POINT_SIZE];
+            for _ in 0..2 {
+                assert_eq!(fcmp_scalar_mul_generators(out.as_mut_ptr(), scalar.as_ptr(), ptr::null(), count), FCMP_SUCCESS);
+                assert_eq!(out, expected);
+            }
+
+            // Caller-supplied generators
+            out.fill(0);
+            assert_eq!(fcmp_scalar_mul_generators(out.as_mut_ptr(), scalar.as_ptr(), generators.as_ptr(), count), FCMP_SUCCESS);
+            assert_eq!(out, expected);
+
+            assert_eq!(
+                fcmp_scalar_mul_generators(out.as_mut_ptr(), scalar.as_ptr(), ptr::null(), fcmp_generator_count() + 1),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_outputs_aggregate_commitment() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let values = [100u64, 250, 7];
+            let blindings = [Scalar::from(11u64), Scalar::from(22u64), Scalar::from(33u64)];
+            let mut commitments = [0u8; 3 * POINT_SIZE];
+            for (i, (value, blinding)) in values.iter().zip(&blindings).enumerate() {
+                let v = Scalar::from(*value).to_bytes();
+                assert_eq!(
+                    fcmp_pedersen_commit(commitments[i * POINT_SIZE..].as_mut_ptr(), v.as_ptr(), blinding.as_bytes().as_ptr()),
+                    FCMP_SUCCESS
+                );
+            }
+
+            // Commits to the total value under the total blinding
+            let mut aggregate = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_outputs_aggregate_commitment(aggregate.as_mut_ptr(), commitments.as_ptr(), 3), FCMP_SUCCESS);
+            let total_value = Scalar::from(values.iter().sum::<u64>()).to_bytes();
+            let total_blinding = blindings.iter().sum::<Scalar>().to_bytes();
+            let mut expected = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(expected.as_mut_ptr(), total_value.as_ptr(), total_blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(aggregate, expected);
+
+            let mut identity = [0u8; POINT_SIZE];
+            identity[0] = 1;
+            assert_eq!(fcmp_outputs_aggregate_commitment(aggregate.as_mut_ptr(), ptr::null(), 0), FCMP_SUCCESS);
+            assert_eq!(aggregate, identity);
+
+            commitments[POINT_SIZE..2 * POINT_SIZE].copy_from_slice(&[0xff; POINT_SIZE]);
+            assert_eq!(
+                fcmp_outputs_aggregate_commitment(aggregate.as_mut_ptr(), commitments.as_ptr(), 3),
+                FCMP_ERROR_INVALID_POINT
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_image_in_set() {
+        unsafe {
+            let set: Vec<u8> = (1..=9u8).flat_map(|i| [i; POINT_SIZE]).collect();
+            for i in [1u8, 5, 9] {
+                assert_eq!(fcmp_key_image_in_set([i; POINT_SIZE].as_ptr(), set.as_ptr(), 9), 1);
+            }
+
+            let mut absent = [5u8; POINT_SIZE];
+            absent[31] = 6;
+            assert_eq!(fcmp_key_image_in_set(absent.as_ptr(), set.as_ptr(), 9), 0);
+            assert_eq!(fcmp_key_image_in_set([9u8; POINT_SIZE].as_ptr(), set.as_ptr(), 8), 0);
+            assert_eq!(fcmp_key_image_in_set(absent.as_ptr(), ptr::null(), 0), 0);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_make_test_proof() {
+        let _guard = init_lock();
+        unsafe {
+            let mut proof = [0u8; 1024];
+            let mut proof_len = 0usize;
+            let mut root = [0u8; SCALAR_SIZE];
+            let mut input = test_input();
+            assert_eq!(
+                fcmp_make_test_proof(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_mut_ptr(), &mut input),
+                FCMP_SUCCESS
+            );
+            assert_eq!(proof_num_layers(&proof[..proof_len]), Ok(2));
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_SUCCESS);
+
+            // Root and input are fixed across calls
+            let mut root_again = [0u8; SCALAR_SIZE];
+            let mut input_again = test_input();
+            assert_eq!(
+                fcmp_make_test_proof(proof.as_mut_ptr(), &mut proof_len, proof.len(), root_again.as_mut_ptr(), &mut input_again),
+                FCMP_SUCCESS
+            );
+            assert_eq!(root_again, root);
+            assert!(fcmp_input_equal(&input_again, &input) == 1);
+
+            let mut small_len = 0usize;
+            assert_eq!(
+                fcmp_make_test_proof(proof.as_mut_ptr(), &mut small_len, 8, root.as_mut_ptr(), &mut input),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(small_len, proof_len);
+        }
+    }
+
+    #[test]
+    fn test_tree_depth_limits() {
+        let _guard = init_lock();
+        unsafe {
+            let (mut min, mut max) = (u32::MAX, 0u32);
+            assert_eq!(fcmp_tree_depth_limits(&mut min, &mut max), FCMP_SUCCESS);
+            assert_eq!((min, max), (FCMP_MIN_TREE_DEPTH, FCMP_MAX_TREE_DEPTH));
+            assert_eq!(fcmp_tree_depth_limits(ptr::null_mut(), &mut max), FCMP_ERROR_INVALID_PARAM);
+
+            // A full u64 index fits within the deepest branch
+            assert!((FCMP_TREE_WIDTH as u128).pow(max) > u64::MAX as u128);
+
+            let root = [8u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let mut proof = vec![0u8; proof_len_for_layers(max as usize + 1)];
+            let mut proof_len = 0usize;
+
+            let deepest = TestBranch::new(max as usize, 1);
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &deepest.as_ffi()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_SUCCESS);
+
+            let too_deep = TestBranch::new(max as usize + 1, 1);
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &too_deep.as_ffi()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+
+            // A proof claiming one layer too many is refused on its header
+            proof[1] = max as u8 + 1;
+            assert_eq!(
+                fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len_for_layers(max as usize + 1)),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_schnorr_sign_verify() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let secret = Scalar::from(0x5eed_u64).to_bytes();
+            let public = (Scalar::from(0x5eed_u64) * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let message = b"attestation";
+
+            let mut sig = [0u8; FCMP_SCHNORR_SIGNATURE_SIZE];
+            assert_eq!(fcmp_schnorr_sign(sig.as_mut_ptr(), secret.as_ptr(), message.as_ptr(), message.len(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_schnorr_verify(sig.as_ptr(), public.as_ptr(), message.as_ptr(), message.len(), 0), FCMP_SUCCESS);
+
+            // Nonces are derived, so signing again reproduces the signature
+            let mut again = [0u8; FCMP_SCHNORR_SIGNATURE_SIZE];
+            assert_eq!(fcmp_schnorr_sign(again.as_mut_ptr(), secret.as_ptr(), message.as_ptr(), message.len(), 0), FCMP_SUCCESS);
+            assert_eq!(again, sig);
+
+            let tampered = b"attestatioN";
+            assert_eq!(
+                fcmp_schnorr_verify(sig.as_ptr(), public.as_ptr(), tampered.as_ptr(), tampered.len(), 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            let other = ED25519_BASEPOINT_POINT.compress().to_bytes();
+            assert_eq!(
+                fcmp_schnorr_verify(sig.as_ptr(), other.as_ptr(), message.as_ptr(), message.len(), 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // Empty messages are signable
+            assert_eq!(fcmp_schnorr_sign(sig.as_mut_ptr(), secret.as_ptr(), ptr::null(), 0, 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_schnorr_verify(sig.as_ptr(), public.as_ptr(), ptr::null(), 0, 0), FCMP_SUCCESS);
+
+            let zero = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_schnorr_sign(sig.as_mut_ptr(), zero.as_ptr(), ptr::null(), 0, 0), FCMP_ERROR_INVALID_SCALAR);
+            let identity = curve25519_dalek::edwards::EdwardsPoint::default().compress().to_bytes();
+            assert_eq!(fcmp_schnorr_verify(sig.as_ptr(), identity.as_ptr(), ptr::null(), 0, 0), FCMP_ERROR_INVALID_POINT);
+        }
+    }
+
+    #[test]
+    fn test_schnorr_verify_batch() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            const COUNT: usize = 6;
+            let messages: Vec<Vec<u8>> = (0..COUNT).map(|i| vec![i as u8; i * 5]).collect();
+            let mut sigs = vec![0u8; COUNT * FCMP_SCHNORR_SIGNATURE_SIZE];
+            let mut publics = vec![0u8; COUNT * POINT_SIZE];
+            for i in 0..COUNT {
+                let x = Scalar::from(i as u64 + 11);
+                publics[i * POINT_SIZE..(i + 1) * POINT_SIZE]
+                    .copy_from_slice((x * ED25519_BASEPOINT_POINT).compress().as_bytes());
+                assert_eq!(
+                    fcmp_schnorr_sign(
+                        sigs[i * FCMP_SCHNORR_SIGNATURE_SIZE..].as_mut_ptr(),
+                        x.as_bytes().as_ptr(),
+                        messages[i].as_ptr(),
+                        messages[i].len(),
+                        0
+                    ),
+                    FCMP_SUCCESS
+                );
+            }
+            let message_ptrs: Vec<*const u8> = messages.iter().map(|m| m.as_ptr()).collect();
+            let msg_lens: Vec<usize> = messages.iter().map(Vec::len).collect();
+            let verify = |sigs: &[u8], publics: &[u8], results: &mut [i32; COUNT]| {
+                fcmp_schnorr_verify_batch(
+                    sigs.as_ptr(),
+                    publics.as_ptr(),
+                    message_ptrs.as_ptr(),
+                    msg_lens.as_ptr(),
+                    COUNT as u32,
+                    results.as_mut_ptr(),
+                )
+            };
+
+            let mut results = [-1i32; COUNT];
+            assert_eq!(verify(&sigs, &publics, &mut results), FCMP_SUCCESS);
+            assert_eq!(results, [FCMP_SUCCESS; COUNT]);
+
+            // A wrong key, a corrupted response and an undecodable key
+            let mut bad_sigs = sigs.clone();
+            let mut bad_publics = publics.clone();
+            bad_publics.copy_within(0..POINT_SIZE, POINT_SIZE);
+            bad_sigs[3 * FCMP_SCHNORR_SIGNATURE_SIZE + POINT_SIZE] ^= 1;
+            bad_publics[5 * POINT_SIZE..].copy_from_slice(&[0u8; POINT_SIZE]);
+            assert_eq!(verify(&bad_sigs, &bad_publics, &mut results), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(
+                results,
+                [
+                    FCMP_SUCCESS,
+                    FCMP_ERROR_PROOF_VERIFICATION,
+                    FCMP_SUCCESS,
+                    FCMP_ERROR_PROOF_VERIFICATION,
+                    FCMP_SUCCESS,
+                    FCMP_ERROR_INVALID_POINT,
+                ]
+            );
+            for i in 0..COUNT {
+                assert_eq!(
+                    results[i],
+                    fcmp_schnorr_verify(
+                        bad_sigs[i * FCMP_SCHNORR_SIGNATURE_SIZE..].as_ptr(),
+                        bad_publics[i * POINT_SIZE..].as_ptr(),
+                        messages[i].as_ptr(),
+                        messages[i].len(),
+                        0
+                    )
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_derive_subaddress() {
+        unsafe {
+            let seed = b"subaddress wallet";
+            let (mut a, mut view, mut b, mut spend) = ([0u8; 32], [0u8; 32], [0u8; 32], [0u8; 32]);
+            assert_eq!(
+                fcmp_derive_keys(seed.as_ptr(), seed.len(), a.as_mut_ptr(), view.as_mut_ptr(), b.as_mut_ptr(), spend.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+
+            let derive = |major: u32, minor: u32| {
+                let (mut d, mut c) = ([0u8; POINT_SIZE], [0u8; POINT_SIZE]);
+                assert_eq!(
+                    fcmp_derive_subaddress(major, minor, a.as_ptr(), spend.as_ptr(), d.as_mut_ptr(), c.as_mut_ptr()),
+                    FCMP_SUCCESS
+                );
+                (d, c)
+            };
+
+            assert_eq!(derive(0, 0), (spend, view));
+
+            let indices = [(0, 0), (0, 1), (1, 0), (1, 1), (0, 2), (2, 0)];
+            let addresses: Vec<_> = indices.iter().map(|&(major, minor)| derive(major, minor)).collect();
+            for i in 0..addresses.len() {
+                for j in i + 1..addresses.len() {
+                    assert_ne!(addresses[i].0, addresses[j].0);
+                    assert_ne!(addresses[i].1, addresses[j].1);
+                }
+            }
+
+            // C = a*D, so the view secret still scans subaddress outputs
+            let (d, c) = derive(3, 7);
+            let a = decode_scalar(&a).unwrap();
+            assert_eq!((a * decode_point(&d).unwrap()).compress().to_bytes(), c);
+
+            let (mut d, mut c) = ([0u8; POINT_SIZE], [0u8; POINT_SIZE]);
+            let bad = [0xffu8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_derive_subaddress(1, 1, bad.as_ptr(), spend.as_ptr(), d.as_mut_ptr(), c.as_mut_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
+    }
+
+    #[test]
+    fn test_shared_secret_agrees() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let r = Scalar::from(0x1234_5678_u64);
+            let a = Scalar::from(0x9abc_def0_u64);
+            let tx_pubkey = (r * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let view_public = (a * ED25519_BASEPOINT_POINT).compress().to_bytes();
+
+            let mut sender = [0u8; 32];
+            let mut receiver = [0u8; 32];
+            assert_eq!(fcmp_shared_secret(sender.as_mut_ptr(), r.as_bytes().as_ptr(), view_public.as_ptr(), 3), FCMP_SUCCESS);
+            assert_eq!(fcmp_shared_secret(receiver.as_mut_ptr(), a.as_bytes().as_ptr(), tx_pubkey.as_ptr(), 3), FCMP_SUCCESS);
+            assert_eq!(sender, receiver);
+
+            // Each output index gets its own secret
+            let mut next = [0u8; 32];
+            assert_eq!(fcmp_shared_secret(next.as_mut_ptr(), r.as_bytes().as_ptr(), view_public.as_ptr(), 4), FCMP_SUCCESS);
+            assert_ne!(next, sender);
+
+            // A torsion component on the public key is cleared
+            let torsioned = (a * ED25519_BASEPOINT_POINT + curve25519_dalek::constants::EIGHT_TORSION[1])
+                .compress()
+                .to_bytes();
+            assert_eq!(fcmp_shared_secret(next.as_mut_ptr(), r.as_bytes().as_ptr(), torsioned.as_ptr(), 3), FCMP_SUCCESS);
+            assert_eq!(next, sender);
+
+            let small_order = curve25519_dalek::constants::EIGHT_TORSION[1].compress().to_bytes();
+            assert_eq!(
+                fcmp_shared_secret(next.as_mut_ptr(), r.as_bytes().as_ptr(), small_order.as_ptr(), 3),
+                FCMP_ERROR_INVALID_POINT
+            );
+        }
+    }
+
+    #[test]
+    fn test_amount_encryption() {
+        unsafe {
+            let secret = [0x31u8; 32];
+            let amount = 1_234_567_890_123u64;
+
+            let mut enc = [0u8; 8];
+            assert_eq!(fcmp_encrypt_amount(enc.as_mut_ptr(), amount, secret.as_ptr()), FCMP_SUCCESS);
+            assert_ne!(enc, amount.to_le_bytes());
+
+            let mut decrypted = 0u64;
+            assert_eq!(fcmp_decrypt_amount(&mut decrypted, enc.as_ptr(), secret.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(decrypted, amount);
+
+            let wrong = [0x32u8; 32];
+            assert_eq!(fcmp_decrypt_amount(&mut decrypted, enc.as_ptr(), wrong.as_ptr()), FCMP_SUCCESS);
+            assert_ne!(decrypted, amount);
+
+            assert_eq!(fcmp_decrypt_amount(ptr::null_mut(), enc.as_ptr(), secret.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_amount_blinding_reconstructs_commitment() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let r = Scalar::from(0x7777_u64);
+            let a = Scalar::from(0x8888_u64);
+            let tx_pubkey = (r * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let view_public = (a * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let amount = Scalar::from(42_000u64).to_bytes();
+
+            // Sender commits and encrypts
+            let mut shared = [0u8; 32];
+            let mut blinding = [0u8; SCALAR_SIZE];
+            let mut commitment = [0u8; POINT_SIZE];
+            let mut enc = [0u8; 8];
+            assert_eq!(fcmp_shared_secret(shared.as_mut_ptr(), r.as_bytes().as_ptr(), view_public.as_ptr(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_derive_amount_blinding(blinding.as_mut_ptr(), shared.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), amount.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_encrypt_amount(enc.as_mut_ptr(), 42_000, shared.as_ptr()), FCMP_SUCCESS);
+
+            // Receiver rebuilds the same commitment from R alone
+            let mut shared_rx = [0u8; 32];
+            let mut blinding_rx = [0u8; SCALAR_SIZE];
+            let mut amount_rx = 0u64;
+            let mut commitment_rx = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_shared_secret(shared_rx.as_mut_ptr(), a.as_bytes().as_ptr(), tx_pubkey.as_ptr(), 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_derive_amount_blinding(blinding_rx.as_mut_ptr(), shared_rx.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_decrypt_amount(&mut amount_rx, enc.as_ptr(), shared_rx.as_ptr()), FCMP_SUCCESS);
+            let amount_rx = Scalar::from(amount_rx).to_bytes();
+            assert_eq!(
+                fcmp_pedersen_commit(commitment_rx.as_mut_ptr(), amount_rx.as_ptr(), blinding_rx.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(commitment_rx, commitment);
+        }
+    }
+
+    #[test]
+    fn test_verify_output_amount() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let shared = [0x5cu8; 32];
+            let mut blinding = [0u8; SCALAR_SIZE];
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_derive_amount_blinding(blinding.as_mut_ptr(), shared.as_ptr()), FCMP_SUCCESS);
+            let amount = Scalar::from(9_999u64).to_bytes();
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), amount.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            assert_eq!(fcmp_verify_output_amount(commitment.as_ptr(), 9_999, shared.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_verify_output_amount(commitment.as_ptr(), 10_000, shared.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            let other = [0x5du8; 32];
+            assert_eq!(
+                fcmp_verify_output_amount(commitment.as_ptr(), 9_999, other.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_commitment() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            // Sender: derive the blinding, then commit
+            let shared = [0x6au8; 32];
+            let mut blinding = [0u8; SCALAR_SIZE];
+            let mut sent = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_derive_amount_blinding(blinding.as_mut_ptr(), shared.as_ptr()), FCMP_SUCCESS);
+            let amount = Scalar::from(123_456u64).to_bytes();
+            assert_eq!(fcmp_pedersen_commit(sent.as_mut_ptr(), amount.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            let mut rebuilt = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_reconstruct_commitment(rebuilt.as_mut_ptr(), 123_456, shared.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(rebuilt, sent);
+
+            let other = [0x6bu8; 32];
+            assert_eq!(fcmp_reconstruct_commitment(rebuilt.as_mut_ptr(), 123_456, other.as_ptr()), FCMP_SUCCESS);
+            assert_ne!(rebuilt, sent);
+            assert_eq!(fcmp_reconstruct_commitment(ptr::null_mut(), 1, shared.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn test_verify_profiled() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(4, 2));
+            let input = test_input();
+
+            let mut peaks = [0usize; 3];
+            for peak in peaks.iter_mut() {
+                assert_eq!(
+                    fcmp_verify_profiled(root.as_ptr(), &input, proof.as_ptr(), proof.len(), peak),
+                    FCMP_SUCCESS
+                );
+            }
+            assert!(peaks[0] > 0);
+            assert_eq!(peaks[1], peaks[0]);
+            assert_eq!(peaks[2], peaks[0]);
+
+            // Failures are measured too
+            let mut peak = 0usize;
+            assert_eq!(
+                fcmp_verify_profiled(root.as_ptr(), &input, proof.as_ptr(), 3, &mut peak),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            assert_eq!(
+                fcmp_verify_profiled(root.as_ptr(), &input, proof.as_ptr(), proof.len(), ptr::null_mut()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn test_measure_peak_after_early_free() {
+        // Freeing a buffer from before the measurement must not hide the
+        // allocation that follows it
+        let old = vec![0u8; 1 << 16];
+        let ((), peak) = profiling::measure_peak(move || {
+            drop(old);
+            std::hint::black_box(vec![0u8; 4096]);
+        });
+        assert!(peak >= 4096);
+    }
+
+    #[test]
+    fn test_epoch_bound_proof() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let branch = TestBranch::new(3, 2);
+            let mut proof = vec![0u8; proof_len_for_layers(3)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove_epoch(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch.as_ffi(), 1000),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_verify_epoch(root.as_ptr(), &input, proof.as_ptr(), proof_len, 1000), FCMP_SUCCESS);
+
+            for epoch in [0, 999, 1001, u64::MAX] {
+                assert_eq!(
+                    fcmp_verify_epoch(root.as_ptr(), &input, proof.as_ptr(), proof_len, epoch),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Nor does an unbound proof pass as one for any epoch
+            let plain = prove_with(&root, &branch);
+            assert_eq!(
+                fcmp_verify_epoch(root.as_ptr(), &input, plain.as_ptr(), plain.len(), 1000),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let leafless = TestBranch::new(0, 0);
+            assert_eq!(
+                fcmp_prove_epoch(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &leafless.as_ffi(), 1000),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_bound_proof() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = TestSpend::new(11, &[1u8; 32], b"msg").input;
+            let branch = TestBranch::new(3, 2);
+            let mut proof = vec![0u8; proof_len_for_layers(3)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove_for_input(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch.as_ffi(), &input),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_verify_for_input(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_SUCCESS);
+
+            // A foreign input, or any changed point of this one, does not verify
+            let foreign = TestSpend::new(12, &[2u8; 32], b"msg").input;
+            assert_eq!(fcmp_verify_for_input(root.as_ptr(), &foreign, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+            let mut tampered = TestSpend::new(11, &[1u8; 32], b"msg").input;
+            tampered.c_tilde[0] ^= 1;
+            assert_eq!(fcmp_verify_for_input(root.as_ptr(), &tampered, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+
+            let plain = prove_with(&root, &branch);
+            assert_eq!(fcmp_verify_for_input(root.as_ptr(), &input, plain.as_ptr(), plain.len()), FCMP_ERROR_PROOF_VERIFICATION);
+
+            let leafless = TestBranch::new(0, 0);
+            assert_eq!(
+                fcmp_prove_for_input(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &leafless.as_ffi(), &input),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_points_to_scalar() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let points: Vec<u8> = (1..=3u64)
+                .flat_map(|k| (Scalar::from(k) * ED25519_BASEPOINT_POINT).compress().to_bytes())
+                .collect();
+
+            let mut first = [0u8; SCALAR_SIZE];
+            let mut second = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_hash_points_to_scalar(first.as_mut_ptr(), points.as_ptr(), 3), FCMP_SUCCESS);
+            assert_eq!(fcmp_hash_points_to_scalar(second.as_mut_ptr(), points.as_ptr(), 3), FCMP_SUCCESS);
+            assert_eq!(first, second);
+            assert!(decode_scalar(&first).is_some());
+
+            // Swapping two points changes the scalar
+            let mut swapped = points.clone();
+            swapped[..2 * POINT_SIZE].rotate_left(POINT_SIZE);
+            assert_eq!(fcmp_hash_points_to_scalar(second.as_mut_ptr(), swapped.as_ptr(), 3), FCMP_SUCCESS);
+            assert_ne!(first, second);
+
+            // A prefix hashes differently from the whole sequence
+            assert_eq!(fcmp_hash_points_to_scalar(second.as_mut_ptr(), points.as_ptr(), 2), FCMP_SUCCESS);
+            assert_ne!(first, second);
+            assert_eq!(fcmp_hash_points_to_scalar(second.as_mut_ptr(), ptr::null(), 0), FCMP_SUCCESS);
+
+            let mut invalid = points.clone();
+            invalid[POINT_SIZE..2 * POINT_SIZE].copy_from_slice(&[0xffu8; POINT_SIZE]);
+            assert_eq!(fcmp_hash_points_to_scalar(second.as_mut_ptr(), invalid.as_ptr(), 3), FCMP_ERROR_INVALID_POINT);
+        }
+    }
+
+    #[test]
+    fn test_scalar_is_clamped() {
+        unsafe {
+            let mut key = [0xabu8; SCALAR_SIZE];
+            key[0] &= 248;
+            key[31] &= 127;
+            key[31] |= 64;
+            assert_eq!(fcmp_scalar_is_clamped(key.as_ptr()), 1);
+
+            for (byte, flip) in [(0, 0x01), (0, 0x04), (31, 0x40), (31, 0x80)] {
+                let mut unclamped = key;
+                unclamped[byte] ^= flip;
+                assert_eq!(fcmp_scalar_is_clamped(unclamped.as_ptr()), 0);
+            }
+
+            // Canonical scalars below 2^253 are never clamped
+            let one = curve25519_dalek::scalar::Scalar::ONE.to_bytes();
+            assert_eq!(fcmp_scalar_is_clamped(one.as_ptr()), 0);
+            assert_eq!(fcmp_scalar_is_clamped(ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_scalar_clamp() {
+        unsafe {
+            for seed in [0x00u8, 0x5a, 0xff] {
+                let raw = [seed; SCALAR_SIZE];
+                let mut clamped = [0u8; SCALAR_SIZE];
+                assert_eq!(fcmp_scalar_clamp(clamped.as_mut_ptr(), raw.as_ptr()), FCMP_SUCCESS);
+                assert_eq!(fcmp_scalar_is_clamped(clamped.as_ptr()), 1);
+                assert_eq!(clamped[1..31], raw[1..31]);
+
+                // Clamping in place is idempotent
+                let mut again = clamped;
+                assert_eq!(fcmp_scalar_clamp(again.as_mut_ptr(), again.as_ptr()), FCMP_SUCCESS);
+                assert_eq!(again, clamped);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pedersen_commit_batch() {
+        unsafe {
+            const COUNT: usize = 9;
+            let values: Vec<u8> = (0..COUNT * SCALAR_SIZE).map(|i| (i * 13) as u8).collect();
+            let blindings: Vec<u8> = (0..COUNT * SCALAR_SIZE).map(|i| (i * 29 + 1) as u8).collect();
+
+            let mut batch = vec![0u8; COUNT * POINT_SIZE];
+            assert_eq!(
+                fcmp_pedersen_commit_batch(batch.as_mut_ptr(), values.as_ptr(), blindings.as_ptr(), COUNT),
+                FCMP_SUCCESS
+            );
+            for i in 0..COUNT {
+                let mut single = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_pedersen_commit(
+                        single.as_mut_ptr(),
+                        values[i * SCALAR_SIZE..].as_ptr(),
+                        blindings[i * SCALAR_SIZE..].as_ptr()
+                    ),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(batch[i * POINT_SIZE..(i + 1) * POINT_SIZE], single);
+            }
+
+            // H's table is kept for the next batch
+            assert!(cached_pedersen_h().unwrap().table.get().is_some());
+
+            assert_eq!(fcmp_pedersen_commit_batch(ptr::null_mut(), ptr::null(), ptr::null(), 0), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_pedersen_commit_batch(ptr::null_mut(), values.as_ptr(), blindings.as_ptr(), 1),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_proof_extract_commitments() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+
+            let mut points = [0u8; 6 * POINT_SIZE];
+            let mut count = 0u32;
+            assert_eq!(
+                fcmp_proof_extract_commitments(proof.as_ptr(), proof.len(), points.as_mut_ptr(), points.len(), &mut count),
+                FCMP_SUCCESS
+            );
+            assert_eq!(count, 6);
+            for (i, point) in points.chunks_exact(POINT_SIZE).enumerate() {
+                assert!(decode_point(point).is_some());
+                let layer = proof_layer(&proof, i / 2).unwrap();
+                let expected = if i % 2 == 0 { layer.a } else { layer.t };
+                assert_eq!(point, expected.compress().as_bytes());
+            }
+
+            count = 0;
+            assert_eq!(
+                fcmp_proof_extract_commitments(proof.as_ptr(), proof.len(), points.as_mut_ptr(), 5 * POINT_SIZE, &mut count),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(count, 6);
+
+            let mut bad = proof.clone();
+            bad[FCMP_PROOF_HEADER_SIZE + FCMP_PROOF_LAYER_SIZE..][..POINT_SIZE].fill(0xff);
+            assert_eq!(
+                fcmp_proof_extract_commitments(bad.as_ptr(), bad.len(), points.as_mut_ptr(), points.len(), &mut count),
+                FCMP_ERROR_INVALID_POINT
+            );
+            assert_eq!(
+                fcmp_proof_extract_commitments(proof.as_ptr(), proof.len() - 1, points.as_mut_ptr(), points.len(), &mut count),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_prehashed_signing() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        let _guard = init_lock();
+        unsafe {
+            let body = vec![0x42u8; 64 * 1024];
+            let mut digest = [0u8; FCMP_PREHASH_SIZE];
+            assert_eq!(fcmp_message_prehash(digest.as_mut_ptr(), body.as_ptr(), body.len()), FCMP_SUCCESS);
+            let mut again = [0u8; FCMP_PREHASH_SIZE];
+            assert_eq!(fcmp_message_prehash(again.as_mut_ptr(), body.as_ptr(), body.len()), FCMP_SUCCESS);
+            assert_eq!(again, digest);
+
+            // Schnorr: the digest verifies only in the mode it was signed in
+            let x = Scalar::from(0xfeed_u64);
+            let public = (x * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let mut prehashed = [0u8; FCMP_SCHNORR_SIGNATURE_SIZE];
+            let mut raw = [0u8; FCMP_SCHNORR_SIGNATURE_SIZE];
+            assert_eq!(fcmp_schnorr_sign(prehashed.as_mut_ptr(), x.as_bytes().as_ptr(), digest.as_ptr(), 32, 1), FCMP_SUCCESS);
+            assert_eq!(fcmp_schnorr_sign(raw.as_mut_ptr(), x.as_bytes().as_ptr(), digest.as_ptr(), 32, 0), FCMP_SUCCESS);
+            assert_eq!(fcmp_schnorr_verify(prehashed.as_ptr(), public.as_ptr(), digest.as_ptr(), 32, 1), FCMP_SUCCESS);
+            assert_eq!(fcmp_schnorr_verify(raw.as_ptr(), public.as_ptr(), digest.as_ptr(), 32, 0), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_schnorr_verify(prehashed.as_ptr(), public.as_ptr(), digest.as_ptr(), 32, 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(
+                fcmp_schnorr_verify(raw.as_ptr(), public.as_ptr(), digest.as_ptr(), 32, 1),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            // Distinct nonces, so the two signatures cannot be combined to recover x
+            assert_ne!(prehashed[..POINT_SIZE], raw[..POINT_SIZE]);
+
+            assert_eq!(
+                fcmp_schnorr_sign(raw.as_mut_ptr(), x.as_bytes().as_ptr(), body.as_ptr(), body.len(), 1),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            assert_eq!(
+                fcmp_schnorr_sign(raw.as_mut_ptr(), x.as_bytes().as_ptr(), digest.as_ptr(), 32, 2),
+                FCMP_ERROR_INVALID_PARAM
+            );
+
+            // SA+L: same separation
+            let x = Scalar::from(77u64);
+            let output = [
+                (x * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+                (Scalar::from(78u64) * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+                (Scalar::from(79u64) * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+            ]
+            .concat();
+            let mut input = test_input();
+            let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+            let seed = [5u8; 32];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), output.as_ptr(), seed.as_ptr()),
+                FCMP_SUCCESS
+            );
+            let mut sig = [0u8; FCMP_SAL_SIGNATURE_SIZE];
+            let mut key_image = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_sal_sign(
+                    sig.as_mut_ptr(),
+                    key_image.as_mut_ptr(),
+                    x.as_bytes().as_ptr(),
+                    blinders.as_ptr(),
+                    &input,
+                    digest.as_ptr(),
+                    32,
+                    1
+                ),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_sal_verify(sig.as_ptr(), &input, key_image.as_ptr(), digest.as_ptr(), 32, 1), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &input, key_image.as_ptr(), digest.as_ptr(), 32, 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+        }
+    }
+
+    #[test]
+    fn test_root_meta_binding() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let branch = TestBranch::new(2, 3);
+            let (mainnet, testnet) = (b"wattx-mainnet", b"wattx-testnet");
+
+            let mut proof = vec![0u8; proof_len_for_layers(2)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove_with_root_meta(
+                    proof.as_mut_ptr(),
+                    &mut proof_len,
+                    proof.len(),
+                    root.as_ptr(),
+                    mainnet.as_ptr(),
+                    mainnet.len(),
+                    output.as_ptr(),
+                    &branch.as_ffi()
+                ),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_verify_with_root_meta(root.as_ptr(), mainnet.as_ptr(), mainnet.len(), &input, proof.as_ptr(), proof_len),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_verify_with_root_meta(root.as_ptr(), testnet.as_ptr(), testnet.len(), &input, proof.as_ptr(), proof_len),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(
+                fcmp_verify_with_root_meta(root.as_ptr(), ptr::null(), 0, &input, proof.as_ptr(), proof_len),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+
+            let empty = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_verify_with_root_meta(empty.as_ptr(), mainnet.as_ptr(), mainnet.len(), &input, proof.as_ptr(), proof_len),
+                FCMP_ERROR_EMPTY_TREE
+            );
+
+            // A bare header over the effective root carries no binding
+            let mut header = vec![FCMP_PROOF_VERSION, 0];
+            header.extend_from_slice(&root_with_meta(&root, mainnet));
+            header.extend_from_slice(&proof[PROOF_FINGERPRINT]);
+            assert_eq!(
+                fcmp_verify_with_root_meta(root.as_ptr(), mainnet.as_ptr(), mainnet.len(), &input, header.as_ptr(), header.len()),
+                FCMP_ERROR_INVALID_PARAM
+            );
+            let leafless = TestBranch::new(0, 0);
+            assert_eq!(
+                fcmp_prove_with_root_meta(
+                    proof.as_mut_ptr(),
+                    &mut proof_len,
+                    proof.len(),
+                    root.as_ptr(),
+                    mainnet.as_ptr(),
+                    mainnet.len(),
+                    output.as_ptr(),
+                    &leafless.as_ffi()
+                ),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_codes_list() {
+        // Every FCMP_ERROR_* constant; extend this with each new code
+        let defined = [
+            FCMP_ERROR_INVALID_PARAM,
+            FCMP_ERROR_PROOF_GENERATION,
+            FCMP_ERROR_PROOF_VERIFICATION,
+            FCMP_ERROR_MEMORY,
+            FCMP_ERROR_INVALID_POINT,
+            FCMP_ERROR_INVALID_SCALAR,
+            FCMP_ERROR_NOT_INITIALIZED,
+            FCMP_ERROR_EMPTY_TREE,
+            FCMP_ERROR_LIMIT_EXCEEDED,
+            FCMP_ERROR_DOUBLE_SPEND,
+            FCMP_ERROR_GENERATOR_MISMATCH,
+            FCMP_ERROR_INTERNAL,
+        ];
+
+        unsafe {
+            let mut count = 0usize;
+            assert_eq!(fcmp_error_codes(ptr::null_mut(), ptr::null_mut(), 0, &mut count), FCMP_ERROR_MEMORY);
+            assert_eq!(count, defined.len());
+
+            let mut codes = vec![0i32; count];
+            let mut messages = vec![ptr::null(); count];
+            assert_eq!(
+                fcmp_error_codes(codes.as_mut_ptr(), messages.as_mut_ptr(), count, &mut count),
+                FCMP_SUCCESS
+            );
+            assert_eq!(codes, defined);
+
+            let unknown = std::ffi::CStr::from_ptr(fcmp_error_string(1));
+            for (&code, &message) in codes.iter().zip(&messages) {
+                assert_ne!(code, FCMP_SUCCESS);
+                assert!(!message.is_null());
+                assert_ne!(std::ffi::CStr::from_ptr(message), unknown);
+            }
+        }
+    }
+
+    #[test]
+    fn test_commitments_open_total() {
+        use curve25519_dalek::scalar::Scalar;
+
+        let _guard = init_lock();
+        unsafe {
+            let values = [5u64, 7, 30];
+            let blinders = [Scalar::from(11u64), Scalar::from(13u64), Scalar::from(17u64)];
+            let mut commitments = vec![0u8; values.len() * POINT_SIZE];
+            for (i, (&v, b)) in values.iter().zip(&blinders).enumerate() {
+                let out = commitments[i * POINT_SIZE..].as_mut_ptr();
+                assert_eq!(write_commitment(out, &Scalar::from(v), b), FCMP_SUCCESS);
+            }
+            let total_blinding = blinders.iter().sum::<Scalar>().to_bytes();
+
+            let open = |commitments: &[u8], total: u64| {
+                fcmp_commitments_open_total(commitments.as_ptr(), values.len() as u32, total, total_blinding.as_ptr())
+            };
+            assert_eq!(open(&commitments, 42), FCMP_SUCCESS);
+            assert_eq!(open(&commitments, 41), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // One commitment to the wrong value
+            let out = commitments[POINT_SIZE..].as_mut_ptr();
+            assert_eq!(write_commitment(out, &Scalar::from(8u64), &blinders[1]), FCMP_SUCCESS);
+            assert_eq!(open(&commitments, 42), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(open(&commitments, 43), FCMP_SUCCESS);
+
+            let zero = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_commitments_open_total(ptr::null(), 0, 0, zero.as_ptr()), FCMP_SUCCESS);
+        }
+    }
+
+    #[test]
+    fn test_prove_deterministic() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let branch = TestBranch::new(3, 2);
+
+            let prove = |key: &[u8]| {
+                let mut proof = vec![0u8; proof_len_for_layers(3)];
+                let mut proof_len = 0usize;
+                let ret = fcmp_prove_deterministic(
+                    proof.as_mut_ptr(),
+                    &mut proof_len,
+                    proof.len(),
+                    root.as_ptr(),
+                    output.as_ptr(),
+                    &branch.as_ffi(),
+                    key.as_ptr(),
+                    key.len(),
+                );
+                assert_eq!(ret, FCMP_SUCCESS);
+                proof.truncate(proof_len);
+                proof
+            };
+
+            let key = [0x42u8; FCMP_NONCE_KEY_MIN_SIZE];
+            let first = prove(&key);
+            let second = prove(&key);
+            assert_eq!(first, second);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, first.as_ptr(), first.len()), FCMP_SUCCESS);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, second.as_ptr(), second.len()), FCMP_SUCCESS);
+
+            let other = prove(&[0x43u8; FCMP_NONCE_KEY_MIN_SIZE]);
+            assert_ne!(first, other);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, other.as_ptr(), other.len()), FCMP_SUCCESS);
+
+            let mut proof = vec![0u8; proof_len_for_layers(3)];
+            let mut proof_len = 0usize;
+            let short = [0x42u8; FCMP_NONCE_KEY_MIN_SIZE - 1];
+            assert_eq!(
+                fcmp_prove_deterministic(
+                    proof.as_mut_ptr(),
+                    &mut proof_len,
+                    proof.len(),
+                    root.as_ptr(),
+                    output.as_ptr(),
+                    &branch.as_ffi(),
+                    short.as_ptr(),
+                    short.len()
+                ),
+                FCMP_ERROR_INVALID_PARAM
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_input_rejects_torsion_r() {
+        use curve25519_dalek::constants::EIGHT_TORSION;
+
+        unsafe {
+            let mut input = test_input();
+            let mut blinders = [0u8; FCMP_BLINDERS_SIZE];
+            assert_eq!(
+                fcmp_output_rerandomize(&mut input, blinders.as_mut_ptr(), test_output(4).as_ptr(), [5u8; 32].as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert!(validate_input(&input).is_ok());
+
+            // Shift R by a point of order 8: still on the curve, no longer torsion-free
+            let r = point_from_affine(&input.r).unwrap();
+            let tainted = FcmpInput {
+                o_tilde: input.o_tilde,
+                i_tilde: input.i_tilde,
+                r: point_to_affine_bytes(&(r + EIGHT_TORSION[1])),
+                c_tilde: input.c_tilde,
+            };
+            assert!(point_from_affine(&tainted.r).is_some());
+            assert_eq!(validate_input(&tainted).err(), Some(FCMP_ERROR_INVALID_POINT));
+
+            let sig = [0u8; FCMP_SAL_SIGNATURE_SIZE];
+            let key_image = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &tainted, key_image.as_ptr(), ptr::null(), 0, 0),
+                FCMP_ERROR_INVALID_POINT
+            );
+        }
+    }
+
+    #[test]
+    fn test_generators_fingerprint() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+
+        let _guard = init_lock();
+        unsafe {
+            let mut fingerprint = [0u8; 32];
+            assert_eq!(fcmp_generators_fingerprint(fingerprint.as_mut_ptr()), FCMP_SUCCESS);
+
+            // Parameters derived independently from the same config agree
+            let derived = FcmpParams::derive().unwrap();
+            assert_eq!(derived.fingerprint(), fingerprint);
+
+            // A different H, as after a fork changes the H domain, does not
+            let forked = FcmpParams::new(derived.h + ED25519_BASEPOINT_POINT, derived.layer_generators.clone());
+            assert_ne!(forked.fingerprint(), fingerprint);
+
+            // Neither does a truncated layer generator set
+            let truncated = FcmpParams::new(derived.h, derived.layer_generators[..FCMP_MAX_TREE_DEPTH as usize].to_vec());
+            assert_ne!(truncated.fingerprint(), fingerprint);
+
+            assert_eq!(fcmp_generators_fingerprint(ptr::null_mut()), FCMP_ERROR_INVALID_PARAM);
+        }
+    }
+
+    #[test]
+    fn test_verify_limited() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let input = test_input();
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let verify = |max_steps: u64| {
+                let mut used = u64::MAX;
+                let result = fcmp_verify_limited(root.as_ptr(), &input, proof.as_ptr(), proof.len(), max_steps, &mut used);
+                (result, used)
+            };
+
+            // 3 layers: 6 decompressions and a 10-term multiscalar multiplication
+            assert_eq!(verify(1000), (FCMP_SUCCESS, 16));
+            assert_eq!(verify(16), (FCMP_SUCCESS, 16));
+
+            // Aborts before the operation that would pass the cap
+            assert_eq!(verify(15), (FCMP_ERROR_LIMIT_EXCEEDED, 6));
+            assert_eq!(verify(5), (FCMP_ERROR_LIMIT_EXCEEDED, 4));
+            assert_eq!(verify(0), (FCMP_ERROR_LIMIT_EXCEEDED, 0));
+
+            let mut tampered = proof.clone();
+            tampered[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            let mut used = 0u64;
+            assert_eq!(
+                fcmp_verify_limited(root.as_ptr(), &input, tampered.as_ptr(), tampered.len(), 1000, &mut used),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // A bare header is rejected without spending a step
+            let header = &proof[..FCMP_PROOF_HEADER_SIZE];
+            let mut bare = header.to_vec();
+            bare[1] = 0;
+            assert_eq!(
+                fcmp_verify_limited(root.as_ptr(), &input, bare.as_ptr(), bare.len(), 1000, &mut used),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(used, 0);
+        }
+    }
+
+    #[test]
+    fn test_proof_base64_round_trip() {
+        let _guard = init_lock();
+        unsafe {
+            // RFC 4648 test vectors
+            for (plain, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+                assert_eq!(base64_encode(plain.as_bytes()), encoded.as_bytes());
+                assert_eq!(base64_decode(encoded.as_bytes()).unwrap(), plain.as_bytes());
+            }
+
+            let proof = prove_with(&[3u8; POINT_SIZE], &TestBranch::new(2, 3));
+            let mut encoded = vec![0 as c_char; proof.len().div_ceil(3) * 4 + 1];
+            let mut written = 0usize;
+            assert_eq!(
+                fcmp_proof_to_base64(proof.as_ptr(), proof.len(), encoded.as_mut_ptr(), encoded.len() - 1, &mut written),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(written, encoded.len());
+            assert_eq!(
+                fcmp_proof_to_base64(proof.as_ptr(), proof.len(), encoded.as_mut_ptr(), encoded.len(), &mut written),
+                FCMP_SUCCESS
+            );
+            assert_eq!(written, encoded.len() - 1);
+            assert_eq!(encoded[written], 0);
+
+            let mut decoded = vec![0u8; proof.len()];
+            let mut decoded_len = 0usize;
+            assert_eq!(
+                fcmp_proof_from_base64(encoded.as_ptr(), written, decoded.as_mut_ptr(), decoded.len(), &mut decoded_len),
+                FCMP_SUCCESS
+            );
+            assert_eq!(decoded_len, proof.len());
+            assert_eq!(decoded, proof);
+        }
+    }
+
+    #[test]
+    fn test_proof_from_base64_rejects_invalid() {
+        unsafe {
+            let mut out = [0u8; 16];
+            let mut written = 0usize;
+            for bad in ["Zm9v!mFy", "Zm9v YmFy", "Zm9vYmF", "Zg=", "Zg===", "Z===", "Zg==Zm9v", "Zm=v", "Zh==", "Zm9=", "Zm9vYmFy\n"] {
+                assert_eq!(
+                    fcmp_proof_from_base64(bad.as_ptr() as *const c_char, bad.len(), out.as_mut_ptr(), out.len(), &mut written),
+                    FCMP_ERROR_INVALID_PARAM,
+                    "{bad:?}"
+                );
+            }
+
+            let good = "Zm9vYmFy";
+            assert_eq!(
+                fcmp_proof_from_base64(good.as_ptr() as *const c_char, good.len(), out.as_mut_ptr(), 5, &mut written),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(written, 6);
+        }
+    }
+
+    #[test]
+    fn test_view_tag() {
+        unsafe {
+            let shared = [0x5cu8; 32];
+            let mut tag = 0u8;
+            assert_eq!(fcmp_view_tag(&mut tag, shared.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_view_tag_matches(shared.as_ptr(), tag), 1);
+            assert_eq!(fcmp_view_tag_matches(shared.as_ptr(), tag.wrapping_add(1)), 0);
+
+            // Outputs for other wallets match about 1 time in 256
+            let false_positives = (0..1024u32)
+                .filter(|i| {
+                    let mut other = [0u8; 32];
+                    other[..4].copy_from_slice(&i.to_le_bytes());
+                    other[31] = 0xa5;
+                    fcmp_view_tag_matches(other.as_ptr(), tag) == 1
+                })
+                .count();
+            assert!(false_positives < 16, "{false_positives} false positives");
+
+            assert_eq!(fcmp_view_tag(ptr::null_mut(), shared.as_ptr()), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(fcmp_view_tag_matches(ptr::null(), tag), 0);
+        }
+    }
+
+    #[test]
+    fn test_nonzero_proof() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let value = Scalar::from(1_000u64).to_bytes();
+            let blinding = Scalar::from(77u64).to_bytes();
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            let mut proof = [0u8; FCMP_NONZERO_PROOF_SIZE];
+            assert_eq!(fcmp_nonzero_prove(proof.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_nonzero_verify(proof.as_ptr(), commitment.as_ptr()), FCMP_SUCCESS);
+
+            let mut tampered = proof;
+            tampered[POINT_SIZE] ^= 1;
+            assert_eq!(fcmp_nonzero_verify(tampered.as_ptr(), commitment.as_ptr()), FCMP_ERROR_PROOF_VERIFICATION);
+        }
+    }
+
+    #[test]
+    fn test_nonzero_proof_rejects_zero() {
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            let zero = [0u8; SCALAR_SIZE];
+            let blinding = Scalar::from(77u64).to_bytes();
+            let mut zero_commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(zero_commitment.as_mut_ptr(), zero.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+
+            // The prover refuses a zero value
+            let mut proof = [0u8; FCMP_NONZERO_PROOF_SIZE];
+            assert_eq!(fcmp_nonzero_prove(proof.as_mut_ptr(), zero.as_ptr(), blinding.as_ptr()), FCMP_ERROR_PROOF_GENERATION);
+
+            // A valid proof for a nonzero commitment under the same blinding does not transfer
+            let one = Scalar::ONE.to_bytes();
+            assert_eq!(fcmp_nonzero_prove(proof.as_mut_ptr(), one.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_nonzero_verify(proof.as_ptr(), zero_commitment.as_ptr()), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Nor does a proof built for the zero commitment with the inverse of a nonzero value
+            let h = pedersen_h().unwrap();
+            let c_point = decode_point(&zero_commitment).unwrap();
+            let (w, t) = (Scalar::from(5u64).invert(), Scalar::from(5u64).invert() * Scalar::from(77u64));
+            let (k_w, k_t) = (Scalar::from(11u64), Scalar::from(13u64));
+            let nonce = (k_w * c_point - k_t * h).compress();
+            let c = nonzero_challenge(&c_point.compress(), &nonce);
+            let mut forged = [0u8; FCMP_NONZERO_PROOF_SIZE];
+            forged[..POINT_SIZE].copy_from_slice(nonce.as_bytes());
+            forged[POINT_SIZE..POINT_SIZE + SCALAR_SIZE].copy_from_slice((k_w + c * w).as_bytes());
+            forged[POINT_SIZE + SCALAR_SIZE..].copy_from_slice((k_t + c * t).as_bytes());
+            assert_eq!(fcmp_nonzero_verify(forged.as_ptr(), zero_commitment.as_ptr()), FCMP_ERROR_PROOF_VERIFICATION);
+        }
+    }
+
+    #[test]
+    fn test_build_outputs_scan() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
+        unsafe {
+            // Two wallets: (view secret, view public, spend secret, spend public)
+            let wallets: Vec<[[u8; 32]; 4]> = [b"wallet-one", b"wallet-two"]
+                .iter()
+                .map(|seed| {
+                    let mut keys = [[0u8; 32]; 4];
+                    let [a, a_pub, b, b_pub] = &mut keys;
+                    assert_eq!(
+                        fcmp_derive_keys(seed.as_ptr(), seed.len(), a.as_mut_ptr(), a_pub.as_mut_ptr(), b.as_mut_ptr(), b_pub.as_mut_ptr()),
+                        FCMP_SUCCESS
+                    );
+                    keys
+                })
+                .collect();
+
+            let r = Scalar::from(0x5eed_u64);
+            let tx_pubkey = (r * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let mut recipients = Vec::new();
+            for keys in &wallets {
+                recipients.extend_from_slice(&keys[1]);
+                recipients.extend_from_slice(&keys[3]);
+            }
+            let amounts = [1_500u64, 42];
+
+            let mut outputs = vec![0u8; 2 * FCMP_BUILT_OUTPUT_SIZE];
+            assert_eq!(
+                fcmp_build_outputs(r.as_bytes().as_ptr(), recipients.as_ptr(), amounts.as_ptr(), 2, outputs.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+
+            for (i, output) in outputs.chunks_exact(FCMP_BUILT_OUTPUT_SIZE).enumerate() {
+                let (key, rest) = output.split_at(POINT_SIZE);
+                let (commitment, rest) = rest.split_at(POINT_SIZE);
+                let (encrypted, tag) = (&rest[..8], rest[8]);
+
+                for (w, keys) in wallets.iter().enumerate() {
+                    let mut owned = 0i32;
+                    let mut offset = [0u8; SCALAR_SIZE];
+                    assert_eq!(
+                        fcmp_scan_output(key.as_ptr(), tx_pubkey.as_ptr(), keys[0].as_ptr(), keys[3].as_ptr(), i as u32, &mut owned, offset.as_mut_ptr()),
+                        FCMP_SUCCESS
+                    );
+                    assert_eq!(owned, (w == i) as i32);
+                }
+
+                // The recipient recovers the amount from its side of the shared secret
+                let keys = &wallets[i];
+                let mut ss = [0u8; 32];
+                assert_eq!(fcmp_shared_secret(ss.as_mut_ptr(), keys[0].as_ptr(), tx_pubkey.as_ptr(), i as u32), FCMP_SUCCESS);
+                assert_eq!(fcmp_view_tag_matches(ss.as_ptr(), tag), 1);
+                let mut amount = 0u64;
+                assert_eq!(fcmp_decrypt_amount(&mut amount, encrypted.as_ptr(), ss.as_ptr()), FCMP_SUCCESS);
+                assert_eq!(amount, amounts[i]);
+                assert_eq!(fcmp_verify_output_amount(commitment.as_ptr(), amount, ss.as_ptr()), FCMP_SUCCESS);
+            }
+
+            // An invalid recipient key fails the whole call without writing
+            let mut bad = recipients.clone();
+            bad[POINT_SIZE..2 * POINT_SIZE].copy_from_slice(&[0xffu8; 32]);
+            let mut untouched = vec![0u8; 2 * FCMP_BUILT_OUTPUT_SIZE];
+            assert_eq!(
+                fcmp_build_outputs(r.as_bytes().as_ptr(), bad.as_ptr(), amounts.as_ptr(), 2, untouched.as_mut_ptr()),
+                FCMP_ERROR_INVALID_POINT
+            );
+            assert!(untouched.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_validate_block_outputs() {
+        unsafe {
+            // Six outputs, each with its own 16-bit range proof
+            let mut commitments = Vec::new();
+            let mut proofs = Vec::new();
+            for i in 0..6u64 {
+                let value = 1_000 * i + 7;
+                let blinding = [i as u8 + 1; SCALAR_SIZE];
+                let mut value_bytes = [0u8; SCALAR_SIZE];
+                value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+                let mut commitment = [0u8; POINT_SIZE];
+                assert_eq!(
+                    fcmp_pedersen_commit(commitment.as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()),
+                    FCMP_SUCCESS
+                );
+                let mut proof = vec![0u8; fcmp_range_proof_size(1, 16)];
+                let mut proof_len = 0usize;
+                assert_eq!(
+                    fcmp_range_prove(proof.as_mut_ptr(), &mut proof_len, proof.len(), &value, blinding.as_ptr(), 1, 16),
+                    FCMP_SUCCESS
+                );
+                commitments.extend_from_slice(&commitment);
+                proofs.push(proof);
+            }
+
+            let validate = |proofs: &[Vec<u8>], commitments: &[u8]| {
+                let ptrs: Vec<*const u8> = proofs.iter().map(|p| p.as_ptr()).collect();
+                let lens: Vec<usize> = proofs.iter().map(|p| p.len()).collect();
+                let mut failed = u32::MAX;
+                let ret = fcmp_validate_block_outputs(
+                    commitments.as_ptr(),
+                    ptrs.as_ptr(),
+                    lens.as_ptr(),
+                    16,
+                    proofs.len() as u32,
+                    &mut failed,
+                );
+                (ret, failed)
+            };
+
+            assert_eq!(validate(&proofs, &commitments), (FCMP_SUCCESS, u32::MAX));
+
+            // Output 4 carries output 3's range proof
+            let mut bad = proofs.clone();
+            bad[4] = proofs[3].clone();
+            assert_eq!(validate(&bad, &commitments), (FCMP_ERROR_PROOF_VERIFICATION, 4));
+
+            // The lowest failing index is reported
+            bad[1] = proofs[0].clone();
+            assert_eq!(validate(&bad, &commitments), (FCMP_ERROR_PROOF_VERIFICATION, 1));
+
+            // A commitment with a torsion component is rejected before its proof
+            let torsioned = (decode_point(&commitments[2 * POINT_SIZE..3 * POINT_SIZE]).unwrap() +
+                curve25519_dalek::constants::EIGHT_TORSION[1])
+                .compress()
+                .to_bytes();
+            let mut tainted = commitments.clone();
+            tainted[2 * POINT_SIZE..3 * POINT_SIZE].copy_from_slice(&torsioned);
+            assert_eq!(validate(&proofs, &tainted), (FCMP_ERROR_INVALID_POINT, 2));
+        }
+    }
+
+    #[test]
+    fn test_input_serialize() {
+        unsafe {
+            let input = test_input();
+            let size = fcmp_input_serialized_size();
+            assert_eq!(size, FCMP_INPUT_SERIALIZED_SIZE);
+
+            // One spare byte shows nothing is written past the reported size
+            let mut out = vec![0xeeu8; size + 1];
+            let mut written = 0usize;
+            assert_eq!(fcmp_input_serialize(&input, out.as_mut_ptr(), out.len(), &mut written), FCMP_SUCCESS);
+            assert_eq!(written, size);
+            assert_eq!(out[size], 0xee);
+            assert_eq!(out[..size], [[1u8; 64], [2u8; 64], [3u8; 64], [4u8; 64]].concat()[..]);
+
+            written = 0;
+            assert_eq!(fcmp_input_serialize(&input, out.as_mut_ptr(), size - 1, &mut written), FCMP_ERROR_MEMORY);
+            assert_eq!(written, size);
+        }
+    }
+
+    #[test]
+    fn test_params_swap_under_load() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let proof = prove_with(&root, &TestBranch::new(2, 2));
+            let default = FcmpConfig { pedersen_h_domain: ptr::null(), pedersen_h_domain_len: 0 };
+            let rotated_domain = b"WATTx_Pedersen_H_Rotated_v1";
+            let rotated = FcmpConfig {
+                pedersen_h_domain: rotated_domain.as_ptr(),
+                pedersen_h_domain_len: rotated_domain.len(),
+            };
+
+            let mut default_fingerprint = [0u8; 32];
+            assert_eq!(fcmp_generators_fingerprint(default_fingerprint.as_mut_ptr()), FCMP_SUCCESS);
+
+            // Under the rotated set the default-set proof no longer verifies
+            assert_eq!(fcmp_params_swap(&rotated), FCMP_SUCCESS);
+            let mut rotated_fingerprint = [0u8; 32];
+            assert_eq!(fcmp_generators_fingerprint(rotated_fingerprint.as_mut_ptr()), FCMP_SUCCESS);
+            assert_ne!(rotated_fingerprint, default_fingerprint);
+            let input = test_input();
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_ERROR_GENERATOR_MISMATCH);
+
+            // The proof names the set it was made under
+            let mut proof_fingerprint = [0u8; 32];
+            assert_eq!(
+                fcmp_proof_generators_fingerprint(proof.as_ptr(), proof.len(), proof_fingerprint.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(proof_fingerprint, default_fingerprint);
+            assert_eq!(
+                fcmp_proof_generators_fingerprint(proof.as_ptr(), FCMP_PROOF_HEADER_SIZE - 1, proof_fingerprint.as_mut_ptr()),
+                FCMP_ERROR_INVALID_PARAM
+            );
 
-    // G = base point, H = hash_to_point("WATTx_Pedersen_H")
-    let g = ED25519_BASEPOINT_POINT;
+            // Verifiers see one set or the other, never a mix that errors out
+            let done = AtomicBool::new(false);
+            let verdicts = std::thread::scope(|scope| {
+                let verifiers: Vec<_> = (0..4)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let input = test_input();
+                            let mut verdicts = [0usize; 2];
+                            while !done.load(Ordering::Relaxed) {
+                                match fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()) {
+                                    FCMP_SUCCESS => verdicts[0] += 1,
+                                    FCMP_ERROR_GENERATOR_MISMATCH => verdicts[1] += 1,
+                                    other => panic!("unexpected verify result {other}"),
+                                }
+                            }
+                            verdicts
+                        })
+                    })
+                    .collect();
 
-    // Derive H deterministically
-    let mut h_out = [0u8; POINT_SIZE];
-    let h_seed = b"WATTx_Pedersen_H_v1";
-    if fcmp_hash_to_point(h_out.as_mut_ptr(), h_seed.as_ptr(), h_seed.len()) != FCMP_SUCCESS {
-        return FCMP_ERROR_INTERNAL;
+                for i in 0..20 {
+                    let config = if i % 2 == 0 { &default } else { &rotated };
+                    assert_eq!(fcmp_params_swap(config), FCMP_SUCCESS);
+                    std::thread::sleep(std::time::Duration::from_millis(2));
+                }
+                assert_eq!(fcmp_params_swap(&default), FCMP_SUCCESS);
+                done.store(true, Ordering::Relaxed);
+
+                verifiers.into_iter().fold([0usize; 2], |total, verifier| {
+                    let verdicts = verifier.join().unwrap();
+                    [total[0] + verdicts[0], total[1] + verdicts[1]]
+                })
+            });
+            // The verifiers ran under both sets, not just one
+            assert!(verdicts[0] > 0 && verdicts[1] > 0, "verdicts {verdicts:?}");
+
+            let mut fingerprint = [0u8; 32];
+            assert_eq!(fcmp_generators_fingerprint(fingerprint.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fingerprint, default_fingerprint);
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()), FCMP_SUCCESS);
+
+            let empty = FcmpConfig { pedersen_h_domain: rotated_domain.as_ptr(), pedersen_h_domain_len: 0 };
+            assert_eq!(fcmp_params_swap(&empty), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(fcmp_params_swap(ptr::null()), FCMP_ERROR_INVALID_PARAM);
+        }
     }
 
-    use curve25519_dalek::edwards::CompressedEdwardsY;
-    let h = CompressedEdwardsY(h_out).decompress().unwrap();
+    #[test]
+    fn test_commit_elgamal_eq() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
 
-    // C = v*G + b*H
-    let commitment = v * g + b * h;
-    let result = commitment.compress().to_bytes();
+        unsafe {
+            let pubkey = (Scalar::from(0xe1_6a_u64) * ED25519_BASEPOINT_POINT).compress().to_bytes();
+            let value = Scalar::from(250_000u64).to_bytes();
+            let blinding = Scalar::from(31u64).to_bytes();
+            let randomness = Scalar::from(47u64).to_bytes();
 
-    ptr::copy_nonoverlapping(result.as_ptr(), out, POINT_SIZE);
-    FCMP_SUCCESS
-}
+            let mut commitment = [0u8; POINT_SIZE];
+            let mut ciphertext = [0u8; FCMP_ELGAMAL_CIPHERTEXT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_elgamal_encrypt(ciphertext.as_mut_ptr(), value.as_ptr(), randomness.as_ptr(), pubkey.as_ptr()),
+                FCMP_SUCCESS
+            );
 
-// ============================================================================
-// FCMP Proof Operations (Placeholder)
-// ============================================================================
+            let mut proof = [0u8; FCMP_ELGAMAL_EQ_PROOF_SIZE];
+            assert_eq!(
+                fcmp_commit_elgamal_eq_prove(proof.as_mut_ptr(), value.as_ptr(), blinding.as_ptr(), randomness.as_ptr(), pubkey.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_commit_elgamal_eq_verify(proof.as_ptr(), commitment.as_ptr(), ciphertext.as_ptr(), pubkey.as_ptr()),
+                FCMP_SUCCESS
+            );
 
-/// Estimate the proof size for given parameters
-///
-/// # Arguments
-/// - `num_inputs` - Number of inputs being proven
-/// - `num_layers` - Number of tree layers
-///
-/// # Returns
-/// - Estimated proof size in bytes, or 0 on error
-#[no_mangle]
-pub unsafe extern "C" fn fcmp_proof_size(num_inputs: u32, num_layers: u32) -> usize {
-    if num_inputs == 0 || num_layers == 0 {
-        return 0;
+            // A ciphertext of a different value under the same randomness does not match
+            let other_value = Scalar::from(250_001u64).to_bytes();
+            let mut other = [0u8; FCMP_ELGAMAL_CIPHERTEXT_SIZE];
+            assert_eq!(
+                fcmp_elgamal_encrypt(other.as_mut_ptr(), other_value.as_ptr(), randomness.as_ptr(), pubkey.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_commit_elgamal_eq_verify(proof.as_ptr(), commitment.as_ptr(), other.as_ptr(), pubkey.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // Nor does a proof for a commitment and ciphertext hiding different values
+            let mut other_commitment = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_pedersen_commit(other_commitment.as_mut_ptr(), other_value.as_ptr(), blinding.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_commit_elgamal_eq_verify(proof.as_ptr(), other_commitment.as_ptr(), ciphertext.as_ptr(), pubkey.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let identity = curve25519_dalek::edwards::EdwardsPoint::default().compress().to_bytes();
+            assert_eq!(
+                fcmp_commit_elgamal_eq_prove(proof.as_mut_ptr(), value.as_ptr(), blinding.as_ptr(), randomness.as_ptr(), identity.as_ptr()),
+                FCMP_ERROR_INVALID_POINT
+            );
+        }
     }
 
-    // Simplified estimate based on FCMP++ paper:
-    // Base: 16 elements for bulletproof components
-    // + IPA rounds: 2 * log2(rows) per curve
-    // + commitments
-    // Roughly: 32 * (16 + 2*log2(n) + inputs*layers) + 64
+    #[test]
+    fn test_bundle_binds_range_proof() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let branch = TestBranch::new(2, 2);
+            let value = 77u64;
+            let blinding = [5u8; SCALAR_SIZE];
+            let mut value_bytes = [0u8; SCALAR_SIZE];
+            value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+            let mut commitment = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
 
-    let base = 32 * 16;
-    let ipa = 32 * 2 * (32 - (num_layers as u32).leading_zeros()) as usize;
-    let commits = 32 * (num_inputs as usize) * (num_layers as usize);
+            let membership_len = proof_len_for_layers(2);
+            let bundle_len = membership_len + fcmp_range_proof_size(1, 8);
+            let make_bundle = || {
+                let mut bundle = vec![0u8; bundle_len];
+                let mut len = 0usize;
+                assert_eq!(
+                    fcmp_prove_bundle(
+                        bundle.as_mut_ptr(), &mut len, bundle.len(), root.as_ptr(), output.as_ptr(),
+                        &branch.as_ffi(), &value, blinding.as_ptr(), 1, 8,
+                    ),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(len, bundle_len);
+                bundle
+            };
+            let verify = |bundle: &[u8], bits| {
+                fcmp_verify_bundle(bundle.as_ptr(), bundle.len(), root.as_ptr(), &input, commitment.as_ptr(), 1, bits)
+            };
 
-    base + ipa + commits + 64
-}
+            let first = make_bundle();
+            let second = make_bundle();
+            assert_eq!(verify(&first, 8), FCMP_SUCCESS);
+            assert_eq!(verify(&second, 8), FCMP_SUCCESS);
+            assert_eq!(verify(&first, 16), FCMP_ERROR_PROOF_VERIFICATION);
 
-/// Generate an FCMP proof (placeholder implementation)
-///
-/// # Safety
-/// - All pointers must be valid
-/// - `proof_out` must have at least `proof_max_len` bytes available
-/// - `proof_len_out` must be writable
-///
-/// # Returns
-/// - `FCMP_SUCCESS` on success
-/// - Error code on failure
-#[no_mangle]
-pub unsafe extern "C" fn fcmp_prove(
-    proof_out: *mut u8,
-    proof_len_out: *mut usize,
-    proof_max_len: usize,
-    tree_root: *const u8,
-    output: *const u8,  // 96 bytes: O || I || C
-    branch: *const FcmpBranch,
-) -> i32 {
-    if proof_out.is_null() || proof_len_out.is_null() ||
-       tree_root.is_null() || output.is_null() || branch.is_null() {
-        return FCMP_ERROR_INVALID_PARAM;
+            // A range proof moved into another bundle no longer verifies
+            let mut swapped = first.clone();
+            swapped[membership_len..].copy_from_slice(&second[membership_len..]);
+            assert_eq!(verify(&swapped, 8), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // Neither half passes as a standalone proof
+            assert_eq!(
+                fcmp_verify(root.as_ptr(), &input, first.as_ptr(), membership_len),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(
+                fcmp_range_verify(first[membership_len..].as_ptr(), bundle_len - membership_len, commitment.as_ptr(), 1, 8),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            let mut len = 0usize;
+            let mut short = vec![0u8; bundle_len - 1];
+            assert_eq!(
+                fcmp_prove_bundle(
+                    short.as_mut_ptr(), &mut len, short.len(), root.as_ptr(), output.as_ptr(),
+                    &branch.as_ffi(), &value, blinding.as_ptr(), 1, 8,
+                ),
+                FCMP_ERROR_MEMORY
+            );
+        }
     }
 
-    if GLOBAL_PARAMS.is_none() {
-        return FCMP_ERROR_NOT_INITIALIZED;
+    #[test]
+    fn test_output_tree_index() {
+        unsafe {
+            let output = test_output(7);
+            let mut first = 0u64;
+            let mut second = 0u64;
+            assert_eq!(fcmp_output_tree_index(output.as_ptr(), 1 << 20, &mut first), FCMP_SUCCESS);
+            assert_eq!(fcmp_output_tree_index(output.as_ptr(), 1 << 20, &mut second), FCMP_SUCCESS);
+            assert_eq!(first, second);
+            assert!(first < 1 << 20);
+
+            assert_eq!(fcmp_output_tree_index(output.as_ptr(), 1, &mut first), FCMP_SUCCESS);
+            assert_eq!(first, 0);
+            assert_eq!(fcmp_output_tree_index(output.as_ptr(), 0, &mut first), FCMP_ERROR_INVALID_PARAM);
+
+            // 4096 outputs over 16 buckets: each should land near 256
+            let mut buckets = [0u32; 16];
+            for i in 0..4096u32 {
+                let mut output = [0u8; OUTPUT_TUPLE_SIZE];
+                output[..4].copy_from_slice(&i.to_le_bytes());
+                let mut index = 0u64;
+                assert_eq!(fcmp_output_tree_index(output.as_ptr(), 16, &mut index), FCMP_SUCCESS);
+                buckets[index as usize] += 1;
+            }
+            assert!(buckets.iter().all(|&n| (160..352).contains(&n)), "{:?}", buckets);
+        }
     }
 
-    // Read branch data
-    let branch_ref = &*branch;
-    if branch_ref.layers.is_null() || branch_ref.num_layers == 0 {
-        return FCMP_ERROR_INVALID_PARAM;
+    #[test]
+    fn test_external_challenge_proof() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let input = test_input();
+            let branch = TestBranch::new(3, 2);
+            let x = [7u8; SCALAR_SIZE];
+            let y = [8u8; SCALAR_SIZE];
+            let mut proof = vec![0u8; proof_len_for_layers(3)];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove_external_challenge(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch.as_ffi(), x.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(fcmp_verify_external_challenge(root.as_ptr(), &input, proof.as_ptr(), proof_len, x.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_verify_external_challenge(root.as_ptr(), &input, proof.as_ptr(), proof_len, y.as_ptr()),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof_len), FCMP_ERROR_PROOF_VERIFICATION);
+
+            let mut one = [0u8; SCALAR_SIZE];
+            one[0] = 1;
+            assert_eq!(
+                fcmp_prove_external_challenge(proof.as_mut_ptr(), &mut proof_len, proof.len(), root.as_ptr(), output.as_ptr(), &branch.as_ffi(), one.as_ptr()),
+                FCMP_ERROR_PROOF_GENERATION
+            );
+            let non_canonical = [0xffu8; SCALAR_SIZE];
+            assert_eq!(
+                fcmp_verify_external_challenge(root.as_ptr(), &input, proof.as_ptr(), proof_len, non_canonical.as_ptr()),
+                FCMP_ERROR_INVALID_SCALAR
+            );
+        }
     }
 
-    // Placeholder proof: just hash all inputs together
-    // Real implementation would use the full FCMP++ library
+    #[test]
+    fn test_commitment_diff_proof() {
+        use curve25519_dalek::scalar::Scalar;
 
-    use blake2::{Blake2b512, Digest};
+        unsafe {
+            let commit = |value: u64, blinding: &[u8; SCALAR_SIZE]| {
+                let mut value_bytes = [0u8; SCALAR_SIZE];
+                value_bytes[..8].copy_from_slice(&value.to_le_bytes());
+                let mut out = [0u8; POINT_SIZE];
+                assert_eq!(fcmp_pedersen_commit(out.as_mut_ptr(), value_bytes.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+                out
+            };
+            let (b_in, b_out) = ([9u8; SCALAR_SIZE], [4u8; SCALAR_SIZE]);
+            let c_in = commit(1000, &b_in);
+            let c_out = commit(650, &b_out);
+            let b_diff = (Scalar::from_bytes_mod_order(b_in) - Scalar::from_bytes_mod_order(b_out)).to_bytes();
 
-    let mut hasher = Blake2b512::new();
-    hasher.update(b"WATTx_FCMP_Proof_v1");
-    hasher.update(slice::from_raw_parts(tree_root, POINT_SIZE));
-    hasher.update(slice::from_raw_parts(output, OUTPUT_TUPLE_SIZE));
+            let mut proof = [0u8; FCMP_COMMITMENT_DIFF_PROOF_SIZE];
+            assert_eq!(fcmp_commitment_diff_prove(proof.as_mut_ptr(), c_in.as_ptr(), c_out.as_ptr(), 350, b_diff.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_commitment_diff_verify(proof.as_ptr(), c_in.as_ptr(), c_out.as_ptr(), 350), FCMP_SUCCESS);
 
-    // Include branch data
-    let layers = slice::from_raw_parts(branch_ref.layers, branch_ref.num_layers as usize);
-    for layer in layers {
-        if !layer.elements.is_null() && layer.num_elements > 0 {
-            let elements = slice::from_raw_parts(
-                layer.elements,
-                layer.num_elements as usize * SCALAR_SIZE
+            for wrong in [349, 351, 0] {
+                assert_eq!(
+                    fcmp_commitment_diff_verify(proof.as_ptr(), c_in.as_ptr(), c_out.as_ptr(), wrong),
+                    FCMP_ERROR_PROOF_VERIFICATION
+                );
+            }
+            assert_eq!(
+                fcmp_commitment_diff_verify(proof.as_ptr(), c_out.as_ptr(), c_in.as_ptr(), 350),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+            assert_eq!(
+                fcmp_commitment_diff_prove(proof.as_mut_ptr(), c_in.as_ptr(), c_out.as_ptr(), 351, b_diff.as_ptr()),
+                FCMP_ERROR_PROOF_GENERATION
             );
-            hasher.update(elements);
         }
     }
 
-    let hash = hasher.finalize();
-
-    // Create placeholder proof (64 bytes for now)
-    let proof_len = 64;
-    if proof_max_len < proof_len {
-        return FCMP_ERROR_MEMORY;
+    /// In-memory stream that hands out at most 7 bytes per read
+    unsafe extern "C" fn test_stream_read(buf: *mut u8, len: usize, ctx: *mut c_void) -> isize {
+        let (data, pos) = &mut *(ctx as *mut (Vec<u8>, usize));
+        let n = len.min(7).min(data.len() - *pos);
+        ptr::copy_nonoverlapping(data[*pos..].as_ptr(), buf, n);
+        *pos += n;
+        n as isize
     }
 
-    ptr::copy_nonoverlapping(hash.as_ptr(), proof_out, proof_len);
-    *proof_len_out = proof_len;
+    #[test]
+    fn test_verify_stream() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let mut stream = Vec::new();
+            for (i, layers) in [2, 3, 1, 4].into_iter().enumerate() {
+                let mut proof = prove_with(&root, &TestBranch::new(layers, 2));
+                if i == 2 {
+                    let last = proof.len() - 1;
+                    proof[last] ^= 1;
+                }
+                stream.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+                stream.extend_from_slice(&proof);
+            }
 
-    FCMP_SUCCESS
-}
+            let run = |data: &[u8]| {
+                let mut ctx = (data.to_vec(), 0usize);
+                let mut failed = u32::MAX;
+                let result = fcmp_verify_stream(
+                    Some(test_stream_read),
+                    &mut ctx as *mut (Vec<u8>, usize) as *mut c_void,
+                    root.as_ptr(),
+                    &mut failed,
+                );
+                (result, failed)
+            };
 
-/// Verify an FCMP proof (placeholder implementation)
-///
-/// # Safety
-/// - All pointers must be valid
-///
-/// # Returns
-/// - `FCMP_SUCCESS` if proof is valid
-/// - `FCMP_ERROR_PROOF_VERIFICATION` if proof is invalid
-/// - Other error codes on failure
-#[no_mangle]
-pub unsafe extern "C" fn fcmp_verify(
-    tree_root: *const u8,
-    input: *const FcmpInput,
-    proof: *const u8,
-    proof_len: usize,
-) -> i32 {
-    if tree_root.is_null() || input.is_null() || proof.is_null() {
-        return FCMP_ERROR_INVALID_PARAM;
-    }
+            let (result, failed) = run(&stream);
+            assert_ne!(result, FCMP_SUCCESS);
+            assert_eq!(failed, 2);
 
-    if GLOBAL_PARAMS.is_none() {
-        return FCMP_ERROR_NOT_INITIALIZED;
-    }
+            // The proofs before the invalid one verify on their own
+            let first_two = 2 * 4 + proof_len_for_layers(2) + proof_len_for_layers(3);
+            assert_eq!(run(&stream[..first_two]).0, FCMP_SUCCESS);
+            assert_eq!(run(&[]).0, FCMP_SUCCESS);
 
-    if proof_len < 64 {
-        return FCMP_ERROR_INVALID_PARAM;
+            // A stream ending inside a proof is malformed
+            assert_eq!(run(&stream[..first_two - 1]), (FCMP_ERROR_INVALID_PARAM, 1));
+            assert_eq!(fcmp_verify_stream(None, ptr::null_mut(), root.as_ptr(), &mut 0), FCMP_ERROR_INVALID_PARAM);
+        }
     }
 
-    // Placeholder verification: always succeeds if proof looks valid
-    // Real implementation would use the full FCMP++ library
+    #[test]
+    fn test_verify_ct_mask() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let input = test_input();
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let mut tampered = proof.clone();
+            tampered[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            let truncated = proof[..10].to_vec();
+            let other_root = [4u8; POINT_SIZE];
 
-    let proof_bytes = slice::from_raw_parts(proof, proof_len);
+            for (root, proof) in [(&root, &proof), (&root, &tampered), (&root, &truncated), (&other_root, &proof)] {
+                let expected = if fcmp_verify(root.as_ptr(), &input, proof.as_ptr(), proof.len()) == FCMP_SUCCESS {
+                    0xFF
+                } else {
+                    0x00
+                };
+                assert_eq!(fcmp_verify_ct(root.as_ptr(), &input, proof.as_ptr(), proof.len()), expected);
+            }
+            assert_eq!(fcmp_verify_ct(root.as_ptr(), &input, proof.as_ptr(), proof.len()), 0xFF);
+            assert_eq!(fcmp_verify_ct(ptr::null(), &input, proof.as_ptr(), proof.len()), 0x00);
+        }
+    }
 
-    // Check proof isn't all zeros
-    let all_zero = proof_bytes.iter().all(|&b| b == 0);
-    if all_zero {
-        return FCMP_ERROR_PROOF_VERIFICATION;
+    #[test]
+    fn test_derive_generators() {
+        unsafe {
+            let seed = b"WATTx generator test seed";
+            let expected = [
+                "5165dd7cd743f055324e2fd176e3b4d697d0f0835e147782e3be04422d2b82de",
+                "589b5381247fa98a4fa0079d91c8587f4e5752cb662945fc2fefa0d369b25578",
+                "d644b0c093659bb374898b398f2230bdb24a72adfc596316f2a37850d0efde5c",
+            ];
+            let mut out = [0u8; 3 * POINT_SIZE];
+            assert_eq!(fcmp_derive_generators(seed.as_ptr(), seed.len(), 3, out.as_mut_ptr()), FCMP_SUCCESS);
+            for (g, hex) in out.chunks_exact(POINT_SIZE).zip(expected) {
+                assert_eq!(g.iter().map(|b| format!("{:02x}", b)).collect::<String>(), hex);
+            }
+
+            // A longer run extends the shorter one, and other seeds differ
+            let mut more = [0u8; 5 * POINT_SIZE];
+            assert_eq!(fcmp_derive_generators(seed.as_ptr(), seed.len(), 5, more.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(more[..3 * POINT_SIZE], out);
+            let mut other = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_derive_generators(b"x".as_ptr(), 1, 1, other.as_mut_ptr()), FCMP_SUCCESS);
+            assert_ne!(other, out[..POINT_SIZE]);
+            assert_eq!(fcmp_derive_generators(seed.as_ptr(), seed.len(), 0, ptr::null_mut()), FCMP_SUCCESS);
+        }
     }
 
-    FCMP_SUCCESS
-}
+    #[test]
+    fn test_verify_multiversion() {
+        let _guard = init_lock();
+        unsafe {
+            let root = [3u8; POINT_SIZE];
+            let input = test_input();
+            let proof = prove_with(&root, &TestBranch::new(3, 2));
+            let verify = |proof: &[u8], accepted: &[u8]| {
+                fcmp_verify_multiversion(root.as_ptr(), &input, proof.as_ptr(), proof.len(), accepted.as_ptr(), accepted.len())
+            };
 
-// ============================================================================
-// Utility Functions
-// ============================================================================
+            assert_eq!(verify(&proof, &[2]), FCMP_SUCCESS);
+            assert_eq!(verify(&proof, &[3, 2]), FCMP_SUCCESS);
+            assert_eq!(verify(&proof, &[1]), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(verify(&proof, &[]), FCMP_ERROR_INVALID_PARAM);
 
-/// Get the library version string
-///
-/// # Returns
-/// Pointer to a null-terminated version string
-#[no_mangle]
-pub extern "C" fn fcmp_version() -> *const i8 {
-    b"0.1.0\0".as_ptr() as *const i8
-}
+            let mut tampered = proof.clone();
+            tampered[FCMP_PROOF_HEADER_SIZE + 2 * POINT_SIZE] ^= 1;
+            assert_eq!(verify(&tampered, &[2]), FCMP_ERROR_PROOF_VERIFICATION);
 
-/// Get error message for an error code
-///
-/// # Returns
-/// Pointer to a null-terminated error string
-#[no_mangle]
-pub extern "C" fn fcmp_error_string(code: i32) -> *const i8 {
-    match code {
-        FCMP_SUCCESS => b"Success\0".as_ptr() as *const i8,
-        FCMP_ERROR_INVALID_PARAM => b"Invalid parameter\0".as_ptr() as *const i8,
-        FCMP_ERROR_PROOF_GENERATION => b"Proof generation failed\0".as_ptr() as *const i8,
-        FCMP_ERROR_PROOF_VERIFICATION => b"Proof verification failed\0".as_ptr() as *const i8,
-        FCMP_ERROR_MEMORY => b"Memory allocation failed\0".as_ptr() as *const i8,
-        FCMP_ERROR_INVALID_POINT => b"Invalid curve point\0".as_ptr() as *const i8,
-        FCMP_ERROR_INVALID_SCALAR => b"Invalid scalar\0".as_ptr() as *const i8,
-        FCMP_ERROR_NOT_INITIALIZED => b"Library not initialized\0".as_ptr() as *const i8,
-        FCMP_ERROR_INTERNAL => b"Internal error\0".as_ptr() as *const i8,
-        _ => b"Unknown error\0".as_ptr() as *const i8,
+            // Accepting a version does not conjure a verifier for it
+            let mut future = proof.clone();
+            future[0] = FCMP_PROOF_VERSION + 1;
+            assert_eq!(verify(&future, &[2, 3]), FCMP_ERROR_INVALID_PARAM);
+        }
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    #[test]
+    fn test_pedersen_commit_with_h() {
+        use curve25519_dalek::constants::EIGHT_TORSION;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        unsafe {
+            let value = [42u8; SCALAR_SIZE];
+            let blinding = [7u8; SCALAR_SIZE];
+            let mut h = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_h(h.as_mut_ptr()), FCMP_SUCCESS);
+
+            let mut expected = [0u8; POINT_SIZE];
+            let mut with_h = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_pedersen_commit(expected.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_pedersen_commit_with_h(with_h.as_mut_ptr(), value.as_ptr(), blinding.as_ptr(), h.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(with_h, expected);
+
+            // Another generator gives another commitment
+            let mut h2 = [0u8; POINT_SIZE];
+            assert_eq!(fcmp_hash_to_point(h2.as_mut_ptr(), b"tx H'".as_ptr(), 5), FCMP_SUCCESS);
+            assert_eq!(
+                fcmp_pedersen_commit_with_h(with_h.as_mut_ptr(), value.as_ptr(), blinding.as_ptr(), h2.as_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_ne!(with_h, expected);
+
+            let identity = EIGHT_TORSION[0].compress().to_bytes();
+            let torsion = EIGHT_TORSION[1].compress().to_bytes();
+            let mut non_canonical = [0xffu8; POINT_SIZE];
+            non_canonical[31] = 0x7f;
+            for bad in [identity, torsion, non_canonical] {
+                assert_eq!(
+                    fcmp_pedersen_commit_with_h(with_h.as_mut_ptr(), value.as_ptr(), blinding.as_ptr(), bad.as_ptr()),
+                    FCMP_ERROR_INVALID_POINT
+                );
+            }
+        }
+    }
 
     #[test]
-    fn test_init_cleanup() {
+    fn test_prove_required_extra() {
+        let _guard = init_lock();
         unsafe {
-            assert_eq!(fcmp_init(), FCMP_SUCCESS);
-            assert_eq!(fcmp_is_initialized(), 1);
-            fcmp_cleanup();
-            assert_eq!(fcmp_is_initialized(), 0);
+            let root = [3u8; POINT_SIZE];
+            let output = test_output(1);
+            let branch = TestBranch::new(3, 2);
+            let len = proof_len_for_layers(3);
+            let mut extra = usize::MAX;
+
+            for short in [1, 7, len] {
+                assert_eq!(
+                    fcmp_prove_required_extra(len - short, root.as_ptr(), output.as_ptr(), &branch.as_ffi(), &mut extra),
+                    FCMP_SUCCESS
+                );
+                assert_eq!(extra, short);
+            }
+            assert_eq!(
+                fcmp_prove_required_extra(len + 5, root.as_ptr(), output.as_ptr(), &branch.as_ffi(), &mut extra),
+                FCMP_SUCCESS
+            );
+            assert_eq!(extra, 0);
+
+            // Growing by the reported amount is enough for fcmp_prove
+            let mut proof = vec![0u8; len];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove_required_extra(len - 7, root.as_ptr(), output.as_ptr(), &branch.as_ffi(), &mut extra),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, len - 7 + extra, root.as_ptr(), output.as_ptr(), &branch.as_ffi()),
+                FCMP_SUCCESS
+            );
+
+            assert_eq!(
+                fcmp_prove_required_extra(len, EMPTY_TREE_ROOT.as_ptr(), output.as_ptr(), &branch.as_ffi(), &mut extra),
+                FCMP_ERROR_EMPTY_TREE
+            );
+            assert_eq!(
+                fcmp_prove_required_extra(len, root.as_ptr(), output.as_ptr(), &branch.as_ffi(), ptr::null_mut()),
+                FCMP_ERROR_INVALID_PARAM
+            );
         }
     }
 
     #[test]
-    fn test_point_operations() {
+    fn test_validate_prove_args() {
+        let _guard = init_lock();
         unsafe {
-            let mut basepoint = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
-            assert_eq!(fcmp_point_is_valid(basepoint.as_ptr()), 1);
+            let root = [3u8; POINT_SIZE];
+            let output = [9u8; OUTPUT_TUPLE_SIZE];
+            let branch = TestBranch::new(3, 2);
+            let len = proof_len_for_layers(3);
+            let validate = |max_len: usize, root: *const u8, output: *const u8, branch: *const FcmpBranch| {
+                fcmp_validate_prove_args(max_len, root, output, branch)
+            };
 
-            // Scalar mul: 2 * G
-            let two = [2u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                       0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-            let mut two_g = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_point_mul(two_g.as_mut_ptr(), two.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
-            assert_eq!(fcmp_point_is_valid(two_g.as_ptr()), 1);
+            assert_eq!(validate(len, root.as_ptr(), output.as_ptr(), &branch.as_ffi()), FCMP_SUCCESS);
+            assert_eq!(validate(len, ptr::null(), output.as_ptr(), &branch.as_ffi()), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(validate(len, root.as_ptr(), ptr::null(), &branch.as_ffi()), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(validate(len, root.as_ptr(), output.as_ptr(), ptr::null()), FCMP_ERROR_INVALID_PARAM);
 
-            // G + G should equal 2*G
-            let mut g_plus_g = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_point_add(g_plus_g.as_mut_ptr(), basepoint.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
-            assert_eq!(two_g, g_plus_g);
+            let missing_layers = FcmpBranch { layers: ptr::null(), num_layers: 2, leaf_index: 0 };
+            assert_eq!(validate(len, root.as_ptr(), output.as_ptr(), &missing_layers), FCMP_ERROR_INVALID_PARAM);
+            let too_deep = TestBranch::new(FCMP_MAX_TREE_DEPTH as usize + 1, 1);
+            assert_eq!(validate(usize::MAX, root.as_ptr(), output.as_ptr(), &too_deep.as_ffi()), FCMP_ERROR_INVALID_PARAM);
+
+            assert_eq!(validate(len, EMPTY_TREE_ROOT.as_ptr(), output.as_ptr(), &branch.as_ffi()), FCMP_ERROR_EMPTY_TREE);
+            assert_eq!(validate(len - 1, root.as_ptr(), output.as_ptr(), &branch.as_ffi()), FCMP_ERROR_MEMORY);
+
+            // Each verdict matches what fcmp_prove returns for the same arguments
+            let mut proof = vec![0u8; len];
+            let mut proof_len = 0usize;
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, len - 1, root.as_ptr(), output.as_ptr(), &branch.as_ffi()),
+                FCMP_ERROR_MEMORY
+            );
+            assert_eq!(
+                fcmp_prove(proof.as_mut_ptr(), &mut proof_len, len, EMPTY_TREE_ROOT.as_ptr(), output.as_ptr(), &branch.as_ffi()),
+                FCMP_ERROR_EMPTY_TREE
+            );
+
+            fcmp_cleanup();
+            assert_eq!(validate(len, root.as_ptr(), output.as_ptr(), &branch.as_ffi()), FCMP_ERROR_NOT_INITIALIZED);
+            assert_eq!(fcmp_init(), FCMP_SUCCESS);
         }
     }
 
     #[test]
-    fn test_hash_to_point() {
+    fn test_spend_context() {
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use curve25519_dalek::scalar::Scalar;
+
         unsafe {
-            let data = b"test data";
-            let mut point = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_hash_to_point(point.as_mut_ptr(), data.as_ptr(), data.len()), FCMP_SUCCESS);
-            assert_eq!(fcmp_point_is_valid(point.as_ptr()), 1);
+            let output_for = |x: &Scalar| {
+                let o = (x * ED25519_BASEPOINT_POINT).compress().to_bytes();
+                let mut output = test_output(1);
+                output[..POINT_SIZE].copy_from_slice(&o);
+                assert_eq!(fcmp_hash_to_point(output[POINT_SIZE..].as_mut_ptr(), o.as_ptr(), o.len()), FCMP_SUCCESS);
+                output
+            };
+            let (x_a, x_b) = (Scalar::from(1111u64), Scalar::from(2222u64));
+            let (output_a, output_b) = (output_for(&x_a), output_for(&x_b));
+            let ctx_a = fcmp_spend_context_new(output_a.as_ptr(), x_a.as_bytes().as_ptr());
+            let ctx_b = fcmp_spend_context_new(output_b.as_ptr(), x_b.as_bytes().as_ptr());
+            assert!(!ctx_a.is_null() && !ctx_b.is_null());
 
-            // Same input should give same output
-            let mut point2 = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_hash_to_point(point2.as_mut_ptr(), data.as_ptr(), data.len()), FCMP_SUCCESS);
-            assert_eq!(point, point2);
+            let message = b"spend context";
+            let mut input_a = test_input();
+            assert_eq!(fcmp_spend_context_input(ctx_a, &mut input_a), FCMP_SUCCESS);
+            let mut sig = [0u8; FCMP_SAL_SIGNATURE_SIZE];
+            let mut key_image = [0u8; POINT_SIZE];
+            assert_eq!(
+                fcmp_spend_context_sign(ctx_a, message.as_ptr(), message.len(), sig.as_mut_ptr(), key_image.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &input_a, key_image.as_ptr(), message.as_ptr(), message.len(), 0),
+                FCMP_SUCCESS
+            );
 
-            // Different input should give different output
-            let data2 = b"other data";
-            let mut point3 = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_hash_to_point(point3.as_mut_ptr(), data2.as_ptr(), data2.len()), FCMP_SUCCESS);
-            assert_ne!(point, point3);
+            // A signature from another context does not verify for this input
+            assert_eq!(
+                fcmp_spend_context_sign(ctx_b, message.as_ptr(), message.len(), sig.as_mut_ptr(), key_image.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &input_a, key_image.as_ptr(), message.as_ptr(), message.len(), 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // Nor does one from a second context over the same output, whose blinders differ
+            let ctx_a2 = fcmp_spend_context_new(output_a.as_ptr(), x_a.as_bytes().as_ptr());
+            assert_eq!(
+                fcmp_spend_context_sign(ctx_a2, message.as_ptr(), message.len(), sig.as_mut_ptr(), key_image.as_mut_ptr()),
+                FCMP_SUCCESS
+            );
+            assert_eq!(
+                fcmp_sal_verify(sig.as_ptr(), &input_a, key_image.as_ptr(), message.as_ptr(), message.len(), 0),
+                FCMP_ERROR_PROOF_VERIFICATION
+            );
+
+            // The secret must open O
+            assert!(fcmp_spend_context_new(output_a.as_ptr(), x_b.as_bytes().as_ptr()).is_null());
+
+            for ctx in [ctx_a, ctx_b, ctx_a2] {
+                fcmp_spend_context_free(ctx);
+            }
+            fcmp_spend_context_free(ptr::null_mut());
         }
     }
 
     #[test]
-    fn test_pedersen_commit() {
+    fn test_verify_with_descriptor() {
+        let _guard = init_lock();
         unsafe {
-            let value = [42u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-            let blinding = [1u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let root = [3u8; POINT_SIZE];
+            let input = test_input();
+            let proof = prove_with(&root, &TestBranch::new(2, 2));
+            let verify = |depth: u32, leaf_count: u64, proof: &[u8]| {
+                let descriptor = FcmpTreeDescriptor { root, depth, leaf_count };
+                fcmp_verify_with_descriptor(&descriptor, &input, proof.as_ptr(), proof.len())
+            };
 
-            let mut commitment = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
-            assert_eq!(fcmp_point_is_valid(commitment.as_ptr()), 1);
+            assert_eq!(verify(2, 50, &proof), FCMP_SUCCESS);
+            assert_eq!(verify(2, 64, &proof), FCMP_SUCCESS);
 
-            // Same inputs should give same commitment
-            let mut commitment2 = [0u8; POINT_SIZE];
-            assert_eq!(fcmp_pedersen_commit(commitment2.as_mut_ptr(), value.as_ptr(), blinding.as_ptr()), FCMP_SUCCESS);
-            assert_eq!(commitment, commitment2);
+            // A branch deeper or shallower than the descriptor is rejected
+            let deeper = prove_with(&root, &TestBranch::new(3, 2));
+            assert_eq!(verify(2, 50, &deeper), FCMP_ERROR_PROOF_VERIFICATION);
+            assert_eq!(verify(3, 65, &proof), FCMP_ERROR_PROOF_VERIFICATION);
+
+            // So is a descriptor whose depth contradicts its leaf count
+            assert_eq!(verify(2, 65, &proof), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(verify(2, 8, &proof), FCMP_ERROR_INVALID_PARAM);
+            assert_eq!(verify(0, 0, &proof), FCMP_ERROR_EMPTY_TREE);
+
+            // A single-leaf descriptor does not take a bare header over its root
+            let mut header = vec![FCMP_PROOF_VERSION, 0];
+            header.extend_from_slice(&root);
+            header.extend_from_slice(&proof[PROOF_FINGERPRINT]);
+            assert_eq!(tree_depth_for_leaves(1), 0);
+            assert_eq!(verify(0, 1, &header), FCMP_ERROR_INVALID_PARAM);
+
+            // Depths match what a real tree reports
+            let tree = fcmp_tree_new();
+            let mut leaves = vec![0u8; 65 * SCALAR_SIZE];
+            for (i, leaf) in leaves.chunks_exact_mut(SCALAR_SIZE).enumerate() {
+                leaf[0] = i as u8;
+            }
+            let mut tree_root = [0u8; SCALAR_SIZE];
+            assert_eq!(fcmp_tree_root_after_append(tree, leaves.as_ptr(), 65, tree_root.as_mut_ptr()), FCMP_SUCCESS);
+            let (mut leaf_count, mut depth) = (0u64, 0u32);
+            assert_eq!(fcmp_tree_stats(tree, &mut leaf_count, &mut depth), FCMP_SUCCESS);
+            assert_eq!(depth, tree_depth_for_leaves(leaf_count));
+            fcmp_tree_free(tree);
         }
     }
 }
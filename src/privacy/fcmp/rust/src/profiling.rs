@@ -0,0 +1,84 @@
+//! Allocation profiling
+//!
+//! Installs a global allocator that forwards to the system allocator and,
+//! while a measurement is running on the current thread, tracks the bytes
+//! that thread has live. Only built with the `profiling` feature, since it
+//! replaces the allocator for the whole process.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Live bytes and high-water mark of the measurement running on a thread
+#[derive(Clone, Copy)]
+struct Usage {
+    live: isize,
+    peak: isize,
+}
+
+thread_local! {
+    static USAGE: Cell<Option<Usage>> = const { Cell::new(None) };
+}
+
+/// Apply an allocation (positive) or release (negative) to the current
+/// thread's measurement, if one is running
+fn track(delta: isize) {
+    // Accessing the slot never allocates; it fails only during thread teardown
+    let _ = USAGE.try_with(|slot| {
+        if let Some(mut usage) = slot.get() {
+            // Releasing memory allocated before the measurement started would
+            // otherwise push the count below its baseline and hide later peaks
+            usage.live = (usage.live + delta).max(0);
+            usage.peak = usage.peak.max(usage.live);
+            slot.set(Some(usage));
+        }
+    });
+}
+
+struct ProfilingAllocator;
+
+unsafe impl GlobalAlloc for ProfilingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        track(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ProfilingAllocator = ProfilingAllocator;
+
+/// Run `f` and return its result with the peak number of bytes it had
+/// allocated at once on this thread. The live count starts at zero and
+/// never drops below it, so freeing memory allocated before `f` cannot
+/// hide a later peak; since such frees still offset allocations made
+/// earlier in `f`, the result is a lower bound when `f` releases memory
+/// it did not allocate.
+pub(crate) fn measure_peak<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    USAGE.with(|slot| slot.set(Some(Usage { live: 0, peak: 0 })));
+    let result = f();
+    let usage = USAGE.with(|slot| slot.replace(None));
+    (result, usage.map_or(0, |u| u.peak as usize))
+}
@@ -0,0 +1,145 @@
+//! Safe Rust API
+//!
+//! Idiomatic wrappers for Rust consumers that would otherwise go through the
+//! C ABI. Everything here shares its derivations with the FFI functions, so
+//! values are interchangeable with those produced over FFI.
+
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::OsRng;
+use zeroize::Zeroizing;
+
+use crate::FcmpParams;
+
+/// A decompressed Ed25519 point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point(pub(crate) EdwardsPoint);
+
+impl Point {
+    /// Decompress a canonical 32-byte encoding
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Point> {
+        let point = CompressedEdwardsY(*bytes).decompress()?;
+        if point.compress().as_bytes() != bytes {
+            return None;
+        }
+        Some(Point(point))
+    }
+
+    /// Compressed 32-byte encoding
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+
+    /// Underlying curve25519-dalek point
+    pub fn as_edwards(&self) -> &EdwardsPoint {
+        &self.0
+    }
+}
+
+/// A secret scalar, wiped from memory when dropped
+pub struct SecretScalar(Zeroizing<Scalar>);
+
+impl SecretScalar {
+    /// Sample a uniformly random scalar; the safe counterpart of `fcmp_scalar_random`
+    pub fn random() -> SecretScalar {
+        SecretScalar(Zeroizing::new(Scalar::random(&mut OsRng)))
+    }
+
+    /// Decode a canonical (fully reduced) 32-byte scalar
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<SecretScalar> {
+        crate::decode_scalar(bytes).map(|scalar| SecretScalar(Zeroizing::new(scalar)))
+    }
+
+    /// Public point `s*G` for this secret
+    pub fn mul_base(&self) -> Point {
+        Point(EdwardsPoint::mul_base(&self.0))
+    }
+
+    /// Pedersen commitment `value*G + s*H` using this secret as the blinding
+    pub fn commit(&self, value: u64) -> Option<Point> {
+        let h = unsafe { crate::pedersen_h()? };
+        Some(Point(EdwardsPoint::mul_base(&Scalar::from(value)) + *self.0 * h))
+    }
+}
+
+/// Parameter context owning the derived generators
+#[derive(Clone)]
+pub struct Context {
+    params: FcmpParams,
+}
+
+impl Context {
+    /// Derive a context with the default generators, independent of `fcmp_init`
+    pub fn new() -> Option<Context> {
+        FcmpParams::derive().map(|params| Context { params })
+    }
+
+    /// Iterate the generators in `fcmp_get_generator` order: H, then the
+    /// per-layer proof generators
+    pub fn generators(&self) -> impl Iterator<Item = Point> + '_ {
+        std::iter::once(self.params.h)
+            .chain(self.params.layer_generators.iter().copied())
+            .map(Point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_generators_match_ffi() {
+        let _guard = crate::tests::init_lock();
+
+        let context = Context::new().unwrap();
+        let mut count = 0u32;
+        for (i, generator) in context.generators().enumerate() {
+            let mut expected = [0u8; POINT_SIZE];
+            unsafe {
+                assert_eq!(fcmp_get_generator(i as u32, expected.as_mut_ptr()), FCMP_SUCCESS);
+            }
+            assert_eq!(generator.to_bytes(), expected);
+            assert_eq!(Point::from_bytes(&expected), Some(generator));
+            count += 1;
+        }
+        assert_eq!(count, fcmp_generator_count());
+    }
+
+    #[test]
+    fn test_secret_scalar_matches_ffi() {
+        let mut bytes = [0u8; SCALAR_SIZE];
+        bytes[0] = 42;
+        let secret = SecretScalar::from_bytes(&bytes).unwrap();
+
+        let mut value = [0u8; SCALAR_SIZE];
+        value[0] = 100;
+        let mut commitment = [0u8; POINT_SIZE];
+        let mut basepoint = [0u8; POINT_SIZE];
+        let mut public = [0u8; POINT_SIZE];
+        unsafe {
+            assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), value.as_ptr(), bytes.as_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_basepoint(basepoint.as_mut_ptr()), FCMP_SUCCESS);
+            assert_eq!(fcmp_point_mul(public.as_mut_ptr(), bytes.as_ptr(), basepoint.as_ptr()), FCMP_SUCCESS);
+        }
+        assert_eq!(secret.commit(100).unwrap().to_bytes(), commitment);
+        assert_eq!(secret.mul_base().to_bytes(), public);
+
+        assert!(SecretScalar::from_bytes(&[0xff; SCALAR_SIZE]).is_none());
+        assert_ne!(SecretScalar::random().mul_base(), SecretScalar::random().mul_base());
+    }
+
+    #[cfg(feature = "memory-inspection")]
+    #[test]
+    fn test_secret_scalar_zeroed_on_drop() {
+        let mut bytes = [7u8; SCALAR_SIZE];
+        bytes[31] = 0;
+        let mut secret = std::mem::ManuallyDrop::new(SecretScalar::from_bytes(&bytes).unwrap());
+        let storage = &*secret.0 as *const Scalar as *const u8;
+        assert_ne!(unsafe { std::slice::from_raw_parts(storage, SCALAR_SIZE) }, [0u8; SCALAR_SIZE]);
+
+        // Run the destructor but keep the storage alive to inspect it
+        unsafe { std::mem::ManuallyDrop::drop(&mut secret) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(storage, SCALAR_SIZE) }, [0u8; SCALAR_SIZE]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Changing the H domain is process-wide, so this runs in its own test
+//! binary where no other test can observe the custom generator.
+
+use wattx_fcmp::*;
+
+/// commit(0, 1) = H under whatever domain is current
+unsafe fn commit_h() -> [u8; POINT_SIZE] {
+    let zero = [0u8; SCALAR_SIZE];
+    let mut one = [0u8; SCALAR_SIZE];
+    one[0] = 1;
+    let mut commitment = [0u8; POINT_SIZE];
+    assert_eq!(fcmp_pedersen_commit(commitment.as_mut_ptr(), zero.as_ptr(), one.as_ptr()), FCMP_SUCCESS);
+    commitment
+}
+
+#[test]
+fn test_set_pedersen_h_domain() {
+    unsafe {
+        let default = commit_h();
+
+        let fork = b"ExampleFork_Pedersen_H_v1";
+        assert_eq!(fcmp_set_pedersen_h_domain(fork.as_ptr(), fork.len()), FCMP_SUCCESS);
+        let forked = commit_h();
+        assert_ne!(forked, default);
+
+        let mut h = [0u8; POINT_SIZE];
+        assert_eq!(fcmp_pedersen_h(h.as_mut_ptr()), FCMP_SUCCESS);
+        assert_eq!(h, forked);
+
+        // Idempotent for the same domain
+        assert_eq!(fcmp_set_pedersen_h_domain(fork.as_ptr(), fork.len()), FCMP_SUCCESS);
+        assert_eq!(commit_h(), forked);
+
+        // The default domain restores the original generator
+        let default_domain = b"WATTx_Pedersen_H_v1";
+        assert_eq!(fcmp_set_pedersen_h_domain(default_domain.as_ptr(), default_domain.len()), FCMP_SUCCESS);
+        assert_eq!(commit_h(), default);
+
+        assert_eq!(fcmp_set_pedersen_h_domain(fork.as_ptr(), 0), FCMP_ERROR_INVALID_PARAM);
+    }
+}